@@ -0,0 +1,32 @@
+//! Measures how long capturing the CPU information takes, for the
+//! figures quoted on `Master::capture`.
+//!
+//! Run with `cargo bench --bench master`. Uses only the standard library
+//! so that it builds on stable without extra dependencies.
+
+extern crate cupid;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 1_000;
+
+fn measure<T, F: FnMut() -> T>(name: &str, mut f: F) {
+    // The first call pays for any lazily filled caches
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let per_call = start.elapsed() / ITERATIONS;
+    println!("{:<20} {:>10.2?} per call", name, per_call);
+}
+
+fn main() {
+    // Cold: every leaf is queried afresh, as on the first call
+    measure("master_uncached", cupid::master_uncached);
+    // Warm: only the leaves that differ between cores are queried
+    measure("master", cupid::master);
+    // Shared: only reads what the first call stored
+    measure("get", cupid::get);
+}
@@ -0,0 +1,14 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate cupid;
+
+use cupid::{Master, RawDump};
+
+fuzz_target!(|text: &str| {
+    if let Ok(dumps) = RawDump::all_from_cpuid_r(text) {
+        for dump in &dumps {
+            let _ = format!("{:?}", Master::from_dump(dump));
+        }
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate cupid;
+
+use cupid::{cpuinfo, Master};
+
+fuzz_target!(|text: &str| {
+    if let Ok(dump) = cpuinfo::parse(text) {
+        let _ = format!("{:?}", Master::from_dump(&dump));
+    }
+});
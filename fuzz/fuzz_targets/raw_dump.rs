@@ -0,0 +1,16 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate cupid;
+
+use cupid::{Master, RawDump};
+
+// Any dump that parses must also decode and format
+fuzz_target!(|data: &[u8]| {
+    if let Ok(dump) = RawDump::parse(data) {
+        let info = Master::from_dump(&dump);
+        let _ = format!("{:?}", info);
+        let _ = format!("{}", info);
+        assert_eq!(Ok(dump.clone()), RawDump::from_bytes(&dump.to_bytes()));
+    }
+});
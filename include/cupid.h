@@ -0,0 +1,91 @@
+/* C interface to cupid, available with the `ffi` feature. */
+
+#ifndef CUPID_H
+#define CUPID_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define CUPID_CACHE_DATA 1
+#define CUPID_CACHE_INSTRUCTION 2
+#define CUPID_CACHE_UNIFIED 3
+
+/* Leaf 1: the processor signature and the raw feature registers. */
+typedef struct CupidFeatureInformation {
+  uint32_t family_id;
+  uint32_t model_id;
+  uint32_t stepping;
+  uint32_t ecx;
+  uint32_t edx;
+} CupidFeatureInformation;
+
+/* Leaf 7, sub-leaf 0: the raw structured extended feature registers. */
+typedef struct CupidStructuredExtendedInformation {
+  uint32_t ebx;
+  uint32_t ecx;
+  uint32_t edx;
+} CupidStructuredExtendedInformation;
+
+/* Leaf 0x8000_0001: the raw extended feature registers. */
+typedef struct CupidExtendedProcessorSignature {
+  uint32_t ecx;
+  uint32_t edx;
+} CupidExtendedProcessorSignature;
+
+/* One cache, with its size in bytes. */
+typedef struct CupidCache {
+  uint32_t level;
+  /* One of the `CUPID_CACHE_*` constants, or zero if unknown. */
+  uint32_t cache_type;
+  uint64_t size;
+  uint32_t line_size;
+  uint32_t ways;
+  uint32_t sets;
+  uint32_t shared_by;
+} CupidCache;
+
+typedef struct CupidAddressSizes {
+  uint32_t physical_address_bits;
+  uint32_t linear_address_bits;
+} CupidAddressSizes;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Writes the vendor identification into `buf`, returning its length
+ * (excluding the terminator), or zero when CPUID is unavailable. */
+size_t cupid_vendor_id(char *buf, size_t len);
+
+/* Writes the brand string into `buf`, returning its length (excluding
+ * the terminator), or zero when none is reported. */
+size_t cupid_brand_string(char *buf, size_t len);
+
+/* Fills `out` with leaf 1, returning false if it is unavailable. */
+bool cupid_feature_information(CupidFeatureInformation *out);
+
+/* Fills `out` with leaf 7, returning false if it is unavailable. */
+bool cupid_structured_extended_information(CupidStructuredExtendedInformation *out);
+
+/* Fills `out` with leaf 0x8000_0001, returning false if it is
+ * unavailable. */
+bool cupid_extended_processor_signature(CupidExtendedProcessorSignature *out);
+
+/* Fills `out` with up to `len` caches, returning how many the
+ * processor reports. */
+size_t cupid_caches(CupidCache *out, size_t len);
+
+/* Fills `out` with the address sizes, returning false if they are
+ * unavailable. */
+bool cupid_address_sizes(CupidAddressSizes *out);
+
+/* Whether the processor has the named feature, using the same names
+ * as the Rust API (e.g. "sse4_2"). Unknown names are not present. */
+bool cupid_has_feature(const char *name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CUPID_H */
@@ -0,0 +1,179 @@
+//! Feature detection for 64-bit ARM processors.
+//!
+//! ARM has no CPUID instruction. Linux instead reports the features
+//! userspace may rely on through the auxiliary vector, and emulates
+//! reads of the identification registers when the `cpuid` capability
+//! is present.
+
+#[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+use core::arch::asm;
+use core::fmt;
+
+#[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+const AT_HWCAP: u64 = 16;
+#[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+const AT_HWCAP2: u64 = 26;
+
+#[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+extern "C" {
+    fn getauxval(kind: u64) -> u64;
+}
+
+/// The features and identity of an AArch64 processor, as reported by
+/// Linux.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Aarch64Information {
+    hwcap: u64,
+    hwcap2: u64,
+    midr: Option<u64>,
+}
+
+impl Aarch64Information {
+    #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+    pub(crate) fn new() -> Aarch64Information {
+        let (hwcap, hwcap2) = unsafe { (getauxval(AT_HWCAP), getauxval(AT_HWCAP2)) };
+        let mut info = Aarch64Information { hwcap, hwcap2, midr: None };
+
+        // Reading MIDR_EL1 traps unless the kernel offers to emulate it
+        if info.cpuid() {
+            let midr: u64;
+            unsafe {
+                asm!("mrs {0}, MIDR_EL1", out(reg) midr, options(nomem, nostack, preserves_flags));
+            }
+            info.midr = Some(midr);
+        }
+
+        info
+    }
+
+    fn midr_bits(self, start_bit: u32, end_bit: u32) -> Option<u32> {
+        let mask = (1u64 << (end_bit - start_bit + 1)) - 1;
+        self.midr.map(|midr| ((midr >> start_bit) & mask) as u32)
+    }
+}
+
+leaf! {
+    impl Aarch64Information {
+        raw { hwcap, hwcap2, midr }
+
+        /// The code of the company that designed the core, such as 0x41
+        /// for Arm.
+        pub fn implementer(self) -> Option<u32> {
+            self.midr_bits(24, 31)
+        }
+
+        pub fn implementer_name(self) -> Option<&'static str> {
+            self.implementer().and_then(|implementer| {
+                match implementer {
+                    0x41 => Some("Arm"),
+                    0x42 => Some("Broadcom"),
+                    0x43 => Some("Cavium"),
+                    0x46 => Some("Fujitsu"),
+                    0x48 => Some("HiSilicon"),
+                    0x4E => Some("NVIDIA"),
+                    0x50 => Some("Applied Micro"),
+                    0x51 => Some("Qualcomm"),
+                    0x53 => Some("Samsung"),
+                    0x61 => Some("Apple"),
+                    0x69 => Some("Intel"),
+                    0xC0 => Some("Ampere"),
+                    _ => None,
+                }
+            })
+        }
+
+        pub fn variant(self) -> Option<u32> {
+            self.midr_bits(20, 23)
+        }
+
+        pub fn architecture(self) -> Option<u32> {
+            self.midr_bits(16, 19)
+        }
+
+        /// The implementer-specific part number, such as 0xD0C for a
+        /// Neoverse N1.
+        pub fn part_number(self) -> Option<u32> {
+            self.midr_bits(4, 15)
+        }
+
+        pub fn revision(self) -> Option<u32> {
+            self.midr_bits(0, 3)
+        }
+
+        flags {
+            hwcap => {
+                0 => fp,
+                1 => asimd,
+                2 => evtstrm,
+                3 => aes,
+                4 => pmull,
+                5 => sha1,
+                6 => sha2,
+                7 => crc32,
+                8 => atomics,
+                9 => fphp,
+                10 => asimdhp,
+                11 => cpuid,
+                12 => asimdrdm,
+                13 => jscvt,
+                14 => fcma,
+                15 => lrcpc,
+                16 => dcpop,
+                17 => sha3,
+                18 => sm3,
+                19 => sm4,
+                20 => asimddp,
+                21 => sha512,
+                22 => sve,
+                23 => asimdfhm,
+                24 => dit,
+                25 => uscat,
+                26 => ilrcpc,
+                27 => flagm,
+                28 => ssbs,
+                29 => sb,
+                30 => paca,
+                31 => pacg
+            },
+            hwcap2 => {
+                0 => dcpodp,
+                1 => sve2,
+                2 => sveaes,
+                3 => svepmull,
+                4 => svebitperm,
+                5 => svesha3,
+                6 => svesm4,
+                7 => flagm2,
+                8 => frint,
+                9 => svei8mm,
+                10 => svef32mm,
+                11 => svef64mm,
+                12 => svebf16,
+                13 => i8mm,
+                14 => bf16,
+                15 => dgh,
+                16 => rng,
+                17 => bti,
+                18 => mte,
+                19 => ecv,
+                20 => afp,
+                21 => rpres,
+                22 => mte3,
+                23 => sme,
+                24 => sme_i16i64,
+                25 => sme_f64f64,
+                26 => sme_i8i32,
+                27 => sme_f16f32,
+                28 => sme_b16f32,
+                29 => sme_f32f32,
+                30 => sme_fa64,
+                31 => wfxt,
+                32 => ebf16,
+                33 => sve_ebf16,
+                34 => cssc,
+                35 => rprfm,
+                36 => sve2p1
+            }
+        }
+    }
+}
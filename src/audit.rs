@@ -0,0 +1,118 @@
+//! Features that CPUID reports but that are deprecated, removed from
+//! later generations, or commonly disabled after the fact.
+//!
+//! A flag says the processor implements an instruction set extension,
+//! not that anything will use it. Microcode updates disable TSX to
+//! mitigate vulnerabilities, firmware leaves SGX off, and compilers and
+//! kernels drop support for extensions that later processors removed,
+//! so code keyed on these flags alone is often surprised.
+
+use super::{Feature, Master};
+
+/// Why a supported feature may be unusable in practice.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum AuditReason {
+    /// The vendor recommends against using the feature.
+    Deprecated,
+    /// Later generations dropped the feature, and toolchains or
+    /// operating systems have followed.
+    Removed,
+    /// Microcode, firmware or the operating system often disables the
+    /// feature, whatever CPUID says.
+    OftenDisabled,
+}
+
+/// A supported feature flagged by `Master::audit`.
+// Only serialized, as the notes are borrowed from the crate
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct AuditFinding {
+    pub feature: Feature,
+    pub reason: AuditReason,
+    /// What happens to the feature in practice.
+    pub note: &'static str,
+}
+
+macro_rules! findings {
+    ($($feature:ident: $reason:ident, $note:expr;)+) => {
+        &[$(AuditFinding {
+            feature: Feature::$feature,
+            reason: AuditReason::$reason,
+            note: $note,
+        }),+]
+    }
+}
+
+static FINDINGS: &[AuditFinding] = findings! {
+    Psn: Deprecated,
+        "Only the Pentium III had a serial number, and firmware usually disables it for privacy.";
+    Hle: Deprecated,
+        "Microcode disables hardware lock elision on processors affected by TSX Asynchronous Abort; XACQUIRE and XRELEASE are then ignored.";
+    Rtm: OftenDisabled,
+        "Microcode or IA32_TSX_CTRL often disables TSX to mitigate TSX Asynchronous Abort, leaving every transaction to abort; tsx_status() tells whether it has.";
+    Mpx: Removed,
+        "Removed from Ice Lake onwards, and from GCC 9 and Linux 5.6, which no longer enable its register state.";
+    Sgx: OftenDisabled,
+        "Removed from 11th generation and later client processors, and off unless enabled in the firmware elsewhere.";
+    Avx512pf: Removed,
+        "Only Xeon Phi implemented it, and compilers have dropped it.";
+    Avx512er: Removed,
+        "Only Xeon Phi implemented it, and compilers have dropped it.";
+    Prefetchwt1: Removed,
+        "Only Xeon Phi implemented it.";
+    Avx512_4vnniw: Removed,
+        "Only Knights Mill implemented it, and compilers have dropped it.";
+    Avx512_4fmaps: Removed,
+        "Only Knights Mill implemented it, and compilers have dropped it.";
+    Xop: Removed,
+        "Only the Bulldozer family implemented it; Zen dropped it.";
+    Fma4: Removed,
+        "Only the Bulldozer family implemented it; Zen dropped it in favour of FMA3.";
+    Tbm: Removed,
+        "Only the Bulldozer family implemented it; Zen dropped it.";
+    Lwp: Removed,
+        "Only the Bulldozer family implemented it, and Linux never enabled its state.";
+};
+
+impl Master {
+    /// The supported features that are deprecated, removed from later
+    /// generations or commonly disabled, with a note on each.
+    ///
+    /// ```
+    /// if let Some(info) = cupid::master() {
+    ///     for finding in info.audit() {
+    ///         println!("{}: {}", finding.feature, finding.note);
+    ///     }
+    /// }
+    /// ```
+    pub fn audit<'a>(&'a self) -> impl Iterator<Item = AuditFinding> + 'a {
+        FINDINGS.iter().cloned().filter(move |f| self.supports(f.feature))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn audit_flags_deprecated_and_removed_features() {
+        let mut dump = genuine_intel_dump(7);
+        dump.insert(1, 0, (0x0000_0673, 0, 0, 1 << 18));
+        dump.insert(7, 0, (0, (1 << 4) | (1 << 5) | (1 << 11), 0, 0));
+        let info = Master::from_dump(&dump);
+
+        let findings: std::vec::Vec<_> = info.audit().map(|f| (f.feature, f.reason)).collect();
+        assert_eq!(vec![(Feature::Psn, AuditReason::Deprecated),
+                        (Feature::Hle, AuditReason::Deprecated),
+                        (Feature::Rtm, AuditReason::OftenDisabled)],
+                   findings);
+        assert!(info.audit().all(|f| !f.note.is_empty()));
+
+        dump.insert(7, 0, (0, 0, 0, 0));
+        dump.insert(1, 0, (0x0000_0673, 0, 0, 0));
+        assert_eq!(0, Master::from_dump(&dump).audit().count());
+    }
+}
@@ -0,0 +1,221 @@
+#![cfg_attr(test, allow(dead_code))]
+
+extern crate cupid;
+
+use std::env;
+use std::fs;
+use std::process;
+
+use cupid::{Description, Feature, FeatureSet, Master, RawDump, ReportStyle};
+
+const USAGE: &str = "Usage: cupid [--json | --metrics | --lscpu | --report | --raw | --flags-only] [--redact]
+       cupid diff [--json] <old> <new>
+       cupid check --require <features>
+
+Prints the decoded CPU information.
+
+Options:
+    --json        the description as a JSON document
+    --metrics     the description as Prometheus metrics
+    --lscpu       the description in the layout of `lscpu`
+    --report      the description as key=value lines in a stable order,
+                  for scripts and snapshot tests
+    --raw         the register dump, in the format of `cpuid -r`
+    --flags-only  the supported feature names, one per line
+    --redact      leaves out the serial number, APIC IDs and SoC vendor
+                  strings, which could identify the machine
+    -h, --help    this message
+
+Commands:
+    diff          compares two processors, each a register dump such as
+                  `cupid --raw` writes, a description written by --json,
+                  or `live` for this processor. With --json, prints the
+                  differences as JSON. Exits with 1 if they differ.
+    check         exits with 1, listing those missing, unless this
+                  processor supports every feature of --require, a
+                  comma-separated list such as `avx2,bmi2,aesni`. Features
+                  are named as by --flags-only, Linux or rustc; those
+                  whose registers the operating system has not enabled
+                  count as missing.";
+
+enum Format {
+    Human,
+    Json,
+    Metrics,
+    Lscpu,
+    Report,
+    Raw,
+    FlagsOnly,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|a| &a[..]) {
+        Some("diff") => diff(&args[1..]),
+        Some("check") => check(&args[1..]),
+        _ => describe(&args),
+    }
+}
+
+fn describe(args: &[String]) {
+    let mut format = Format::Human;
+    let mut redact = false;
+    for arg in args {
+        format = match &arg[..] {
+            "--redact" => {
+                redact = true;
+                continue;
+            },
+            "--json" => Format::Json,
+            "--metrics" => Format::Metrics,
+            "--lscpu" => Format::Lscpu,
+            "--report" => Format::Report,
+            "--raw" => Format::Raw,
+            "--flags-only" => Format::FlagsOnly,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            _ => usage_error(&format!("Unknown argument '{}'", arg)),
+        };
+    }
+
+    let info = match cupid::master() {
+        Some(info) => info,
+        None => {
+            eprintln!("CPUID is not available on this platform");
+            process::exit(1);
+        },
+    };
+    let dump = || if redact { RawDump::capture().redacted() } else { RawDump::capture() };
+    let info = if redact { Master::from_dump(&dump()) } else { info };
+
+    match format {
+        Format::Human => print!("{}", info),
+        Format::Json => println!("{}", info.to_json()),
+        Format::Metrics => print!("{}", info.to_metrics()),
+        Format::Lscpu => print!("{}", info.to_lscpu()),
+        Format::Report => {
+            let mut report = String::new();
+            let _ = info.write_report(&mut report, ReportStyle::Full);
+            print!("{}", report);
+        },
+        Format::Raw => print!("{}", dump().to_cpuid_r()),
+        Format::FlagsOnly => {
+            for (name, _) in info.flags().filter(|&(_, supported)| supported) {
+                println!("{}", name);
+            }
+        },
+    }
+}
+
+fn diff(args: &[String]) {
+    let mut json = false;
+    let mut dumps = Vec::new();
+    for arg in args {
+        match &arg[..] {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            _ if arg.starts_with("--") => usage_error(&format!("Unknown argument '{}'", arg)),
+            _ => dumps.push(&arg[..]),
+        }
+    }
+    if dumps.len() != 2 {
+        usage_error("diff takes two processors");
+    }
+
+    let diff = load(dumps[0]).diff(&load(dumps[1]));
+    if json {
+        println!("{}", diff.to_json());
+    } else {
+        print!("{}", diff);
+    }
+    process::exit(if diff.is_empty() { 0 } else { 1 });
+}
+
+fn check(args: &[String]) {
+    let mut required = FeatureSet::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let list = match &arg[..] {
+            "--require" => match args.next() {
+                Some(list) => &list[..],
+                None => usage_error("--require takes a list of features"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            _ => match arg.strip_prefix("--require=") {
+                Some(list) => list,
+                None => usage_error(&format!("Unknown argument '{}'", arg)),
+            },
+        };
+        for name in list.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            match feature(name) {
+                Some(feature) => required.insert(feature),
+                None => usage_error(&format!("Unknown feature '{}'", name)),
+            };
+        }
+    }
+
+    let info = match cupid::master() {
+        Some(info) => info,
+        None => {
+            eprintln!("CPUID is not available on this platform");
+            process::exit(1);
+        },
+    };
+    let missing = required.difference(&info.enabled_features(cupid::xcr0()));
+    if !missing.is_empty() {
+        eprintln!("the processor does not support the required features: {}", missing);
+        process::exit(1);
+    }
+}
+
+// A feature by the name `--flags-only` prints, or else by its Linux or
+// rustc name
+fn feature(name: &str) -> Option<Feature> {
+    name.parse().ok()
+        .or_else(|| Feature::from_linux_name(name))
+        .or_else(|| Feature::from_target_feature(name))
+}
+
+// Failures exit with 2, as 1 means that the processors differ
+fn load(source: &str) -> Description {
+    if source == "live" {
+        let info = cupid::master().unwrap_or_else(|| {
+            eprintln!("CPUID is not available on this platform");
+            process::exit(2);
+        });
+        return Description::from(&info);
+    }
+
+    let bytes = fs::read(source).unwrap_or_else(|e| {
+        eprintln!("Cannot read '{}': {}", source, e);
+        process::exit(2);
+    });
+    if bytes.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{') {
+        let parsed = std::str::from_utf8(&bytes).map_err(|_| cupid::json::SchemaError::Malformed)
+            .and_then(Description::from_json);
+        return parsed.unwrap_or_else(|e| {
+            eprintln!("'{}' is not a description, such as `cupid --json` writes: {}", source, e);
+            process::exit(2);
+        });
+    }
+    match RawDump::parse(&bytes) {
+        Ok(dump) => Description::from(&Master::from_dump(&dump)),
+        Err(e) => {
+            eprintln!("'{}' is not a register dump, such as `cupid --raw` writes: {}", source, e);
+            process::exit(2);
+        },
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
+}
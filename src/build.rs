@@ -0,0 +1,59 @@
+//! Support for build scripts that compile code for the build host.
+//!
+//! ```no_run
+//! // build.rs
+//! extern crate cupid;
+//!
+//! fn main() {
+//!     cupid::build::emit_cfgs();
+//! }
+//! ```
+//!
+//! The crate can then use `#[cfg(cpu_has_avx2)]`. This describes the
+//! machine running the build, so it is only correct for binaries that
+//! run where they are built.
+
+use std::io::{self, Write};
+
+use super::{master, Feature, Master};
+
+/// Prints a `cargo:rustc-cfg=cpu_has_<feature>` directive for each
+/// feature the build host supports, such as `cpu_has_avx2`.
+pub fn emit_cfgs() {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if let Some(info) = master() {
+        write_cfgs(&info, &mut out).expect("unable to write to stdout");
+    }
+}
+
+/// Writes the directives `emit_cfgs` prints for `info`. Every feature
+/// is also declared with `cargo:rustc-check-cfg`, so that checking for
+/// an unsupported feature does not warn.
+pub fn write_cfgs<W: Write>(info: &Master, out: &mut W) -> io::Result<()> {
+    for &feature in Feature::ALL {
+        writeln!(out, "cargo:rustc-check-cfg=cfg(cpu_has_{})", feature)?;
+    }
+    for &feature in Feature::ALL.iter().filter(|&&f| info.supports(f)) {
+        writeln!(out, "cargo:rustc-cfg=cpu_has_{}", feature)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn build_cfgs_name_supported_features() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+        let mut out = std::vec::Vec::new();
+        write_cfgs(&Master::from_dump(&dump), &mut out).unwrap();
+        let out = std::string::String::from_utf8(out).unwrap();
+
+        assert!(out.contains("cargo:rustc-check-cfg=cfg(cpu_has_avx2)\n"));
+        assert!(out.ends_with("cargo:rustc-cfg=cpu_has_sse4_2\ncargo:rustc-cfg=cpu_has_sse2\n"));
+    }
+}
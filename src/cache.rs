@@ -0,0 +1,48 @@
+//! Remembering CPUID results, as the instruction is slow and serializes
+//! the processor's pipeline.
+//!
+//! Sub-leaf 0 of each basic and extended leaf is queried once and then
+//! shared. Leaves that differ between cores are always queried afresh:
+//! the APIC IDs in leaves 1, 0xB, 0x1F and 0x8000_001E, and the core
+//! type and caches in leaves 0x1A and 4 of hybrid processors. Caching
+//! them would mix one core's sub-leaf 0 with another's later sub-leaves
+//! and APIC IDs. The `_uncached` functions query every leaf afresh.
+
+use std::sync::OnceLock;
+
+use super::{query, CpuidSource, Registers};
+
+// Comfortably beyond the highest leaf of either range decoded so far
+const LEAVES_PER_RANGE: usize = 0x40;
+
+// The leaves described in the module documentation
+const PER_CORE: &[u32] = &[0x1, 0x4, 0xB, 0x1A, 0x1F, 0x8000_001E];
+
+static BASIC: [OnceLock<Registers>; LEAVES_PER_RANGE] = [const { OnceLock::new() }; LEAVES_PER_RANGE];
+static EXTENDED: [OnceLock<Registers>; LEAVES_PER_RANGE] = [const { OnceLock::new() }; LEAVES_PER_RANGE];
+
+/// Queries the processor the program is running on, remembering the
+/// results as described in the [module documentation](index.html).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Cached;
+
+impl CpuidSource for Cached {
+    fn cpuid(&self, leaf: u32, subleaf: u32) -> Registers {
+        match slot(leaf, subleaf) {
+            Some(slot) => *slot.get_or_init(|| query(leaf, subleaf)),
+            None => query(leaf, subleaf),
+        }
+    }
+}
+
+fn slot(leaf: u32, subleaf: u32) -> Option<&'static OnceLock<Registers>> {
+    if subleaf != 0 || PER_CORE.contains(&leaf) {
+        return None;
+    }
+    let index = (leaf & 0xFFFF) as usize;
+    match leaf & 0xFFFF_0000 {
+        0x0000_0000 => BASIC.get(index),
+        0x8000_0000 => EXTENDED.get(index),
+        _ => None,
+    }
+}
@@ -0,0 +1,68 @@
+//! Dumps of real processors, for testing decoders against hardware
+//! that is not at hand.
+//!
+//! The dumps are in the `dumps` directory, in `cpuid -r` format, each
+//! starting with a comment naming the processor and where the dump came
+//! from. They are captured whole by `cupid --raw` or `cpuid -r`, or taken
+//! from another project's record of such a capture, and never edited, as
+//! a reduced or hand-written dump only tests what its author expected.
+//! Add a dump by capturing it on the processor and listing it in `DUMPS`.
+
+use super::{DumpError, RawDump};
+
+/// The name and `cpuid -r` text of every dump, in alphabetical order.
+pub const DUMPS: &[(&str, &str)] = &[
+    ("amd-zen2", include_str!("../dumps/amd-zen2.txt")),
+    ("intel-alder-lake", include_str!("../dumps/intel-alder-lake.txt")),
+    ("intel-cascade-lake", include_str!("../dumps/intel-cascade-lake.txt")),
+    ("kvm-emerald-rapids", include_str!("../dumps/kvm-emerald-rapids.txt")),
+];
+
+/// The dump with the given name, such as "kvm-emerald-rapids", or
+/// `None` if there is no such dump.
+pub fn load(name: &str) -> Option<Result<RawDump, DumpError>> {
+    DUMPS.iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, text)| RawDump::from_cpuid_r(text))
+}
+
+/// Every dump, by name, including any that fail to parse.
+pub fn all() -> impl Iterator<Item = (&'static str, Result<RawDump, DumpError>)> {
+    DUMPS.iter().map(|&(name, text)| (name, RawDump::from_cpuid_r(text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Master;
+
+    #[test]
+    fn corpus_dumps_decode() {
+        use level::MicroarchLevel::*;
+        use microarch::Microarchitecture::*;
+
+        let expected = [
+            ("amd-zen2", "AMD Ryzen 5 3600X 6-Core Processor", Zen2, V3),
+            ("intel-alder-lake", "12th Gen Intel(R) Core(TM) i7-12700K", GoldenCove, V3),
+            ("intel-cascade-lake", "Intel(R) Xeon(R) Gold 6252 CPU @ 2.10GHz", CascadeLake, V4),
+            ("kvm-emerald-rapids", "Intel(R) Xeon(R) Processor", RaptorCove, V4),
+        ];
+        assert_eq!(DUMPS.len(), expected.len());
+
+        for (&(name, brand, microarch, level), (dump_name, dump)) in expected.iter().zip(all()) {
+            assert_eq!(name, dump_name);
+            let dump = dump.unwrap_or_else(|e| panic!("{}: {}", name, e));
+            let info = Master::from_dump(&dump);
+            assert_eq!(Some(brand), info.brand_string(), "{}", name);
+            assert_eq!(Some(microarch), info.microarchitecture(), "{}", name);
+            assert_eq!(Some(level), info.microarch_level(), "{}", name);
+
+            // Every decoder and output format must cope with every dump
+            let _ = format!("{:?}{}", info, info);
+            info.to_json();
+            info.to_metrics();
+        }
+        assert!(load("kvm-emerald-rapids").unwrap().unwrap().get(0x4000_0000, 0).is_some());
+        assert_eq!(None, load("cyrix-6x86"));
+    }
+}
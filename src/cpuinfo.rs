@@ -0,0 +1,388 @@
+//! Reconstructing the processor description from Linux's
+//! `/proc/cpuinfo`.
+//!
+//! This is useful where CPUID cannot be executed, and for
+//! cross-checking decoded bits against what the kernel reports. The
+//! fields and flags of the first processor listed are turned back into
+//! the register values they were derived from, so only the
+//! information the kernel exposes is present; everything else reads
+//! as unsupported.
+
+use std::string::String;
+
+use super::{DumpError, Master, RawDump, RequestType};
+
+const EAX: u8 = 0;
+const EBX: u8 = 1;
+const ECX: u8 = 2;
+const EDX: u8 = 3;
+
+// The kernel's flag names, with the (leaf, register, bit) they come from.
+// Flags synthesized by the kernel have no register bit and are omitted.
+const FLAGS: &[(&str, u32, u8, u8)] = &[
+    ("fpu", 0x1, EDX, 0),
+    ("vme", 0x1, EDX, 1),
+    ("de", 0x1, EDX, 2),
+    ("pse", 0x1, EDX, 3),
+    ("tsc", 0x1, EDX, 4),
+    ("msr", 0x1, EDX, 5),
+    ("pae", 0x1, EDX, 6),
+    ("mce", 0x1, EDX, 7),
+    ("cx8", 0x1, EDX, 8),
+    ("apic", 0x1, EDX, 9),
+    ("sep", 0x1, EDX, 11),
+    ("mtrr", 0x1, EDX, 12),
+    ("pge", 0x1, EDX, 13),
+    ("mca", 0x1, EDX, 14),
+    ("cmov", 0x1, EDX, 15),
+    ("pat", 0x1, EDX, 16),
+    ("pse36", 0x1, EDX, 17),
+    ("pn", 0x1, EDX, 18),
+    ("clflush", 0x1, EDX, 19),
+    ("dts", 0x1, EDX, 21),
+    ("acpi", 0x1, EDX, 22),
+    ("mmx", 0x1, EDX, 23),
+    ("fxsr", 0x1, EDX, 24),
+    ("sse", 0x1, EDX, 25),
+    ("sse2", 0x1, EDX, 26),
+    ("ss", 0x1, EDX, 27),
+    ("ht", 0x1, EDX, 28),
+    ("tm", 0x1, EDX, 29),
+    ("pbe", 0x1, EDX, 31),
+
+    ("pni", 0x1, ECX, 0),
+    ("pclmulqdq", 0x1, ECX, 1),
+    ("dtes64", 0x1, ECX, 2),
+    ("monitor", 0x1, ECX, 3),
+    ("ds_cpl", 0x1, ECX, 4),
+    ("vmx", 0x1, ECX, 5),
+    ("smx", 0x1, ECX, 6),
+    ("est", 0x1, ECX, 7),
+    ("tm2", 0x1, ECX, 8),
+    ("ssse3", 0x1, ECX, 9),
+    ("cid", 0x1, ECX, 10),
+    ("sdbg", 0x1, ECX, 11),
+    ("fma", 0x1, ECX, 12),
+    ("cx16", 0x1, ECX, 13),
+    ("xtpr", 0x1, ECX, 14),
+    ("pdcm", 0x1, ECX, 15),
+    ("pcid", 0x1, ECX, 17),
+    ("dca", 0x1, ECX, 18),
+    ("sse4_1", 0x1, ECX, 19),
+    ("sse4_2", 0x1, ECX, 20),
+    ("x2apic", 0x1, ECX, 21),
+    ("movbe", 0x1, ECX, 22),
+    ("popcnt", 0x1, ECX, 23),
+    ("tsc_deadline_timer", 0x1, ECX, 24),
+    ("aes", 0x1, ECX, 25),
+    ("xsave", 0x1, ECX, 26),
+    ("avx", 0x1, ECX, 28),
+    ("f16c", 0x1, ECX, 29),
+    ("rdrand", 0x1, ECX, 30),
+    ("hypervisor", 0x1, ECX, 31),
+
+    ("dtherm", 0x6, EAX, 0),
+    ("ida", 0x6, EAX, 1),
+    ("arat", 0x6, EAX, 2),
+    ("pln", 0x6, EAX, 4),
+    ("pts", 0x6, EAX, 6),
+    ("hwp", 0x6, EAX, 7),
+    ("hwp_notify", 0x6, EAX, 8),
+    ("hwp_act_window", 0x6, EAX, 9),
+    ("hwp_epp", 0x6, EAX, 10),
+    ("hwp_pkg_req", 0x6, EAX, 11),
+    ("hfi", 0x6, EAX, 19),
+
+    ("fsgsbase", 0x7, EBX, 0),
+    ("tsc_adjust", 0x7, EBX, 1),
+    ("sgx", 0x7, EBX, 2),
+    ("bmi1", 0x7, EBX, 3),
+    ("hle", 0x7, EBX, 4),
+    ("avx2", 0x7, EBX, 5),
+    ("fdp_excptn_only", 0x7, EBX, 6),
+    ("smep", 0x7, EBX, 7),
+    ("bmi2", 0x7, EBX, 8),
+    ("erms", 0x7, EBX, 9),
+    ("invpcid", 0x7, EBX, 10),
+    ("rtm", 0x7, EBX, 11),
+    ("cqm", 0x7, EBX, 12),
+    ("mpx", 0x7, EBX, 14),
+    ("rdt_a", 0x7, EBX, 15),
+    ("avx512f", 0x7, EBX, 16),
+    ("avx512dq", 0x7, EBX, 17),
+    ("rdseed", 0x7, EBX, 18),
+    ("adx", 0x7, EBX, 19),
+    ("smap", 0x7, EBX, 20),
+    ("avx512ifma", 0x7, EBX, 21),
+    ("clflushopt", 0x7, EBX, 23),
+    ("clwb", 0x7, EBX, 24),
+    ("intel_pt", 0x7, EBX, 25),
+    ("avx512pf", 0x7, EBX, 26),
+    ("avx512er", 0x7, EBX, 27),
+    ("avx512cd", 0x7, EBX, 28),
+    ("sha_ni", 0x7, EBX, 29),
+    ("avx512bw", 0x7, EBX, 30),
+    ("avx512vl", 0x7, EBX, 31),
+
+    ("avx512vbmi", 0x7, ECX, 1),
+    ("umip", 0x7, ECX, 2),
+    ("pku", 0x7, ECX, 3),
+    ("ospke", 0x7, ECX, 4),
+    ("waitpkg", 0x7, ECX, 5),
+    ("avx512_vbmi2", 0x7, ECX, 6),
+    ("gfni", 0x7, ECX, 8),
+    ("vaes", 0x7, ECX, 9),
+    ("vpclmulqdq", 0x7, ECX, 10),
+    ("avx512_vnni", 0x7, ECX, 11),
+    ("avx512_bitalg", 0x7, ECX, 12),
+    ("tme", 0x7, ECX, 13),
+    ("avx512_vpopcntdq", 0x7, ECX, 14),
+    ("la57", 0x7, ECX, 16),
+    ("rdpid", 0x7, ECX, 22),
+    ("bus_lock_detect", 0x7, ECX, 24),
+    ("cldemote", 0x7, ECX, 25),
+    ("movdiri", 0x7, ECX, 27),
+    ("movdir64b", 0x7, ECX, 28),
+    ("enqcmd", 0x7, ECX, 29),
+    ("sgx_lc", 0x7, ECX, 30),
+
+    ("avx512_4vnniw", 0x7, EDX, 2),
+    ("avx512_4fmaps", 0x7, EDX, 3),
+    ("fsrm", 0x7, EDX, 4),
+    ("avx512_vp2intersect", 0x7, EDX, 8),
+    ("srbds_ctrl", 0x7, EDX, 9),
+    ("md_clear", 0x7, EDX, 10),
+    ("rtm_always_abort", 0x7, EDX, 11),
+    ("serialize", 0x7, EDX, 14),
+    ("tsxldtrk", 0x7, EDX, 16),
+    ("pconfig", 0x7, EDX, 18),
+    ("arch_lbr", 0x7, EDX, 19),
+    ("ibt", 0x7, EDX, 20),
+    ("amx_bf16", 0x7, EDX, 22),
+    ("avx512_fp16", 0x7, EDX, 23),
+    ("amx_tile", 0x7, EDX, 24),
+    ("amx_int8", 0x7, EDX, 25),
+    ("intel_stibp", 0x7, EDX, 27),
+    ("flush_l1d", 0x7, EDX, 28),
+    ("arch_capabilities", 0x7, EDX, 29),
+    ("core_capabilities", 0x7, EDX, 30),
+
+    ("syscall", 0x8000_0001, EDX, 11),
+    ("mp", 0x8000_0001, EDX, 19),
+    ("nx", 0x8000_0001, EDX, 20),
+    ("mmxext", 0x8000_0001, EDX, 22),
+    ("fxsr_opt", 0x8000_0001, EDX, 25),
+    ("pdpe1gb", 0x8000_0001, EDX, 26),
+    ("rdtscp", 0x8000_0001, EDX, 27),
+    ("lm", 0x8000_0001, EDX, 29),
+    ("3dnowext", 0x8000_0001, EDX, 30),
+    ("3dnow", 0x8000_0001, EDX, 31),
+
+    ("lahf_lm", 0x8000_0001, ECX, 0),
+    ("cmp_legacy", 0x8000_0001, ECX, 1),
+    ("svm", 0x8000_0001, ECX, 2),
+    ("extapic", 0x8000_0001, ECX, 3),
+    ("cr8_legacy", 0x8000_0001, ECX, 4),
+    ("abm", 0x8000_0001, ECX, 5),
+    ("sse4a", 0x8000_0001, ECX, 6),
+    ("misalignsse", 0x8000_0001, ECX, 7),
+    ("3dnowprefetch", 0x8000_0001, ECX, 8),
+    ("osvw", 0x8000_0001, ECX, 9),
+    ("ibs", 0x8000_0001, ECX, 10),
+    ("xop", 0x8000_0001, ECX, 11),
+    ("skinit", 0x8000_0001, ECX, 12),
+    ("wdt", 0x8000_0001, ECX, 13),
+    ("lwp", 0x8000_0001, ECX, 15),
+    ("fma4", 0x8000_0001, ECX, 16),
+    ("tce", 0x8000_0001, ECX, 17),
+    ("nodeid_msr", 0x8000_0001, ECX, 19),
+    ("tbm", 0x8000_0001, ECX, 21),
+    ("topoext", 0x8000_0001, ECX, 22),
+    ("perfctr_core", 0x8000_0001, ECX, 23),
+    ("perfctr_nb", 0x8000_0001, ECX, 24),
+    ("bpext", 0x8000_0001, ECX, 26),
+    ("ptsc", 0x8000_0001, ECX, 27),
+    ("perfctr_llc", 0x8000_0001, ECX, 28),
+    ("mwaitx", 0x8000_0001, ECX, 29),
+
+    ("nonstop_tsc", 0x8000_0007, EDX, 8),
+];
+
+/// Reads `/proc/cpuinfo` and decodes it as if it came from CPUID.
+#[cfg(target_os = "linux")]
+pub fn read() -> Option<Master> {
+    use std::fs::File;
+    use std::io::Read;
+
+    let mut text = String::new();
+    match File::open("/proc/cpuinfo").and_then(|mut f| f.read_to_string(&mut text)) {
+        Ok(_) => parse(&text).ok().map(|dump| Master::from_dump(&dump)),
+        Err(_) => None,
+    }
+}
+
+/// Converts the first processor described by `/proc/cpuinfo` text into
+/// the register values it implies. Fails when the text does not
+/// describe an x86 processor. Fields that do not parse are ignored.
+pub fn parse(text: &str) -> Result<RawDump, DumpError> {
+    let mut dump = RawDump::new();
+    let mut vendor_id = None;
+    let mut max_basic = 1;
+
+    let mut family = 0;
+    let mut model = 0;
+    let mut stepping = 0;
+    let mut clflush_size = 0;
+
+    for (key, value) in fields(text) {
+        match key {
+            "vendor_id" => vendor_id = Some(value),
+            "cpu family" => family = value.parse().unwrap_or(0),
+            "model" => model = value.parse().unwrap_or(0),
+            "stepping" => stepping = value.parse().unwrap_or(0),
+            "cpuid level" => max_basic = value.parse().unwrap_or(max_basic),
+            "clflush size" => clflush_size = value.parse().unwrap_or(0),
+            "model name" => set_brand_string(&mut dump, value),
+            "flags" => {
+                for flag in value.split_whitespace() {
+                    if let Some(&(_, leaf, register, bit)) = FLAGS.iter().find(|f| f.0 == flag) {
+                        set_bit(&mut dump, leaf, register, bit);
+                    }
+                }
+            },
+            "address sizes" => {
+                // 46 bits physical, 57 bits virtual
+                let mut sizes = value.split(',').map(|s| {
+                    s.split_whitespace().next().and_then(|n| n.parse::<u32>().ok()).unwrap_or(0)
+                });
+                let physical = sizes.next().unwrap_or(0);
+                let virt = sizes.next().unwrap_or(0);
+                let leaf = RequestType::PhysicalAddressSize as u32;
+                let (_, b, c, d) = registers(&dump, leaf);
+                dump.insert(leaf, 0, (physical | virt << 8, b, c, d));
+            },
+            _ => {},
+        }
+    }
+
+    let vendor_id = match vendor_id {
+        Some(v) => v.as_bytes(),
+        None => return Err(DumpError::NoVendor),
+    };
+    let mut vendor = [0u32; 3];
+    for (i, byte) in vendor_id.iter().take(12).enumerate() {
+        if let Some(r) = vendor.get_mut(i / 4) {
+            *r |= (*byte as u32) << (8 * (i % 4));
+        }
+    }
+    // The identification is spelled out in EBX, EDX, ECX order
+    dump.insert(RequestType::BasicInformation as u32, 0, (max_basic, vendor[0], vendor[2], vendor[1]));
+
+    let (base_family, extended_family) = if family > 0xF { (0xF, family - 0xF) } else { (family, 0) };
+    let signature = extended_family << 20 | (model >> 4) << 16 | base_family << 8 |
+        (model & 0xF) << 4 | stepping & 0xF;
+    let leaf = RequestType::VersionInformation as u32;
+    let (_, b, c, d) = registers(&dump, leaf);
+    dump.insert(leaf, 0, (signature, b | (clflush_size / 8) << 8, c, d));
+
+    let max_extended = dump.entries().iter()
+        .map(|e| e.leaf)
+        .filter(|&l| l > RequestType::ExtendedFunctionInformation as u32)
+        .max();
+    if let Some(max_extended) = max_extended {
+        dump.insert(RequestType::ExtendedFunctionInformation as u32, 0, (max_extended, 0, 0, 0));
+    }
+
+    Ok(dump)
+}
+
+/// The microcode revision of the first processor described by
+/// `/proc/cpuinfo` text, which x86 kernels list as `microcode`.
+pub fn microcode_revision(text: &str) -> Option<u32> {
+    fields(text)
+        .find(|&(key, _)| key == "microcode")
+        .and_then(|(_, value)| u32::from_str_radix(value.trim_start_matches("0x"), 16).ok())
+}
+
+// The `key : value` pairs of the first processor
+fn fields(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.lines()
+        .take_while(|l| !l.trim().is_empty())
+        .filter_map(|l| {
+            let mut parts = l.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(k), Some(v)) => Some((k.trim(), v.trim())),
+                _ => None,
+            }
+        })
+}
+
+fn registers(dump: &RawDump, leaf: u32) -> (u32, u32, u32, u32) {
+    dump.get(leaf, 0).unwrap_or((0, 0, 0, 0))
+}
+
+fn set_bit(dump: &mut RawDump, leaf: u32, register: u8, bit: u8) {
+    let (mut a, mut b, mut c, mut d) = registers(dump, leaf);
+    match register {
+        EAX => a |= 1 << bit,
+        EBX => b |= 1 << bit,
+        ECX => c |= 1 << bit,
+        _ => d |= 1 << bit,
+    }
+    dump.insert(leaf, 0, (a, b, c, d));
+}
+
+fn set_brand_string(dump: &mut RawDump, name: &str) {
+    let leaves = [
+        RequestType::BrandString1 as u32,
+        RequestType::BrandString2 as u32,
+        RequestType::BrandString3 as u32,
+    ];
+
+    // The last byte is left as the NUL terminator
+    let mut bytes = name.bytes().take(47);
+    for &leaf in &leaves {
+        let mut registers = [0u32; 4];
+        for register in registers.iter_mut() {
+            for shift in 0..4 {
+                *register |= (bytes.next().unwrap_or(0) as u32) << (8 * shift);
+            }
+        }
+        dump.insert(leaf, 0, (registers[0], registers[1], registers[2], registers[3]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proc_cpuinfo_decodes_like_cpuid() {
+        let text = "processor\t: 0
+    vendor_id\t: AuthenticAMD
+    cpu family\t: 23
+    model\t\t: 1
+    model name\t: AMD EPYC 7601 32-Core Processor
+    stepping\t: 2
+    microcode\t: 0x8001250
+    cpuid level\t: 16
+    flags\t\t: fpu sse2 pni avx avx2 syscall lm svm topoext
+    address sizes\t: 48 bits physical, 48 bits virtual
+
+    processor\t: 1
+    ";
+        let info = Master::from_dump(&parse(text).unwrap());
+
+        assert_eq!("AuthenticAMD", info.vendor_id());
+        assert_eq!(Some("AMD EPYC 7601 32-Core Processor"), info.brand_string());
+        let vi = info.version_information().unwrap();
+        assert_eq!((23, 1, 2), (vi.family_id(), vi.model_id(), vi.stepping()));
+        assert_eq!(48, info.physical_address_size().unwrap().physical_address_bits());
+        assert!(info.clone().sse3() && info.clone().avx2() && info.clone().svm());
+        assert!(!info.clone().sse4_2());
+
+        assert_eq!(Err(DumpError::NoVendor), parse("processor\t: 0\nBogoMIPS\t: 50.00\n"));
+        assert_eq!(Some(0x0800_1250), microcode_revision(text));
+        assert_eq!(None, microcode_revision("processor\t: 0\n"));
+    }
+}
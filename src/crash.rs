@@ -0,0 +1,61 @@
+//! Identifying the processor in crash reports.
+
+use std::boxed::Box;
+use std::fmt::Write;
+use std::panic;
+use std::string::String;
+
+use super::{best_simd_level, get};
+
+/// A one-line summary of the processor, such as "cpu: Intel(R) Core(TM)
+/// i7-9700K CPU @ 3.60GHz (GenuineIntel family 6 model 158 stepping
+/// 13, Skylake), x86-64-v3, simd avx2+fma".
+pub fn report_line() -> String {
+    let info = match get() {
+        Some(info) => info,
+        None => return "cpu: unknown".into(),
+    };
+
+    let mut line = String::from("cpu: ");
+    match info.brand_string() {
+        Some(brand) => write!(line, "{} ({}", brand.trim(), info.vendor_id()),
+        None => write!(line, "{} (", info.vendor_id()),
+    }.unwrap();
+    if let Some(vi) = info.version_information() {
+        write!(line, " family {} model {} stepping {}", vi.family_id(), vi.model_id(), vi.stepping()).unwrap();
+    }
+    if let Some(uarch) = info.microarchitecture() {
+        write!(line, ", {}", uarch).unwrap();
+    }
+    line.push(')');
+    if let Some(level) = info.microarch_level() {
+        write!(line, ", {}", level).unwrap();
+    }
+    write!(line, ", simd {}", best_simd_level()).unwrap();
+    line
+}
+
+/// Appends `report_line` to the output of every panic, after the
+/// previously installed hook has run.
+pub fn install_panic_hook() {
+    let line = report_line();
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        previous(panic_info);
+        eprintln!("{}", line);
+    }));
+}
+
+#[cfg(all(test, any(target_arch = "x86_64", target_arch = "x86")))]
+mod tests {
+    use super::*;
+    use master;
+
+    #[test]
+    fn report_line_names_the_processor() {
+        let line = report_line();
+        assert!(line.starts_with("cpu: "));
+        assert!(line.contains(master().unwrap().vendor_id()));
+        assert!(!line.contains('\n'));
+    }
+}
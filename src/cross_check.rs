@@ -0,0 +1,100 @@
+//! Comparing the decoded flags with the standard library's detection.
+//!
+//! A disagreement points to a decoding bug, or to a virtual machine
+//! that reports features inconsistently. The standard library reports
+//! the AVX and AVX-512 features as absent when the operating system has
+//! not enabled their register state, which CPUID alone does not show,
+//! so the comparison is with `Master::enabled_features` for the running
+//! processor's XCR0.
+
+use std::vec::Vec;
+
+use super::{Feature, Master};
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+use super::xcr0;
+
+/// A feature that the crate and `is_x86_feature_detected!` disagree on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Mismatch {
+    pub feature: Feature,
+    /// Whether this crate reports the feature as usable.
+    pub cupid: bool,
+    /// Whether `is_x86_feature_detected!` reports the feature.
+    pub std: bool,
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+macro_rules! detected {
+    ($($name:tt => $feature:ident),+) => {
+        [$((Feature::$feature, is_x86_feature_detected!($name))),+]
+    }
+}
+
+/// Compares `info` with the standard library's detection of the
+/// current processor, for every feature both know.
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+pub fn cross_check(info: &Master) -> Vec<Mismatch> {
+    let detected = detected!(
+        "aes" => Aesni,
+        "pclmulqdq" => Pclmulqdq,
+        "rdrand" => Rdrand,
+        "rdseed" => Rdseed,
+        "tsc" => Tsc,
+        "mmx" => Mmx,
+        "sse" => Sse,
+        "sse2" => Sse2,
+        "sse3" => Sse3,
+        "ssse3" => Ssse3,
+        "sse4.1" => Sse4_1,
+        "sse4.2" => Sse4_2,
+        "sse4a" => Sse4a,
+        "sha" => Sha,
+        "avx" => Avx,
+        "avx2" => Avx2,
+        "avx512f" => Avx512f,
+        "avx512cd" => Avx512cd,
+        "avx512bw" => Avx512bw,
+        "avx512dq" => Avx512dq,
+        "avx512vl" => Avx512vl,
+        "avx512ifma" => Avx512Ifma,
+        "fma" => Fma,
+        "bmi1" => Bmi1,
+        "bmi2" => Bmi2,
+        "lzcnt" => Lzcnt,
+        "tbm" => Tbm,
+        "popcnt" => Popcnt,
+        "fxsr" => Fxsr,
+        "xsave" => Xsave,
+        "cmpxchg16b" => Cmpxchg16b,
+        "adx" => Adx,
+        "rtm" => Rtm,
+        "movbe" => Movbe,
+        "f16c" => F16c,
+        "ermsb" => EnhancedRepMovsbStosb
+    );
+
+    let enabled = info.enabled_features(xcr0());
+    detected.iter()
+        .map(|&(feature, std)| Mismatch { feature, cupid: enabled.contains(feature), std })
+        .filter(|m| m.cupid != m.std)
+        .collect()
+}
+
+/// Other architectures have no `is_x86_feature_detected!`, so there is
+/// nothing to disagree with.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+pub fn cross_check(_info: &Master) -> Vec<Mismatch> {
+    Vec::new()
+}
+
+#[cfg(all(test, any(target_arch = "x86_64", target_arch = "x86")))]
+mod tests {
+    use super::*;
+    use master;
+
+    #[test]
+    fn cross_check_agrees_on_baseline_features() {
+        let mismatches = cross_check(&master().unwrap());
+        assert!(mismatches.iter().all(|m| m.feature != Feature::Sse2), "{:?}", mismatches);
+    }
+}
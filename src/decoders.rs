@@ -0,0 +1,165 @@
+//! Gathering third-party leaf decoders in one place.
+//!
+//! `decode` runs one `LeafDecoder` against the running processor. A
+//! `Decoders` registry instead holds any number of them under names,
+//! and runs them together against the processor or a `RawDump`, so
+//! that a recorded snapshot decodes as the processor did. The results
+//! continue the report of `Master::write_report` as `decoder.` lines.
+//!
+//! ```
+//! use cupid::{Decoders, LeafDecoder, ReportStyle};
+//!
+//! struct MaxLeaf;
+//!
+//! impl LeafDecoder for MaxLeaf {
+//!     type Output = u32;
+//!
+//!     fn leaf(&self) -> u32 { 0 }
+//!
+//!     fn decode(&self, eax: u32, _: u32, _: u32, _: u32) -> u32 { eax }
+//! }
+//!
+//! let mut decoders = Decoders::new();
+//! decoders.register("max_leaf", MaxLeaf);
+//!
+//! let mut report = String::new();
+//! decoders.decode().write_report(&mut report, ReportStyle::Full).unwrap();
+//! assert!(report.starts_with("decoder.max_leaf="));
+//! ```
+
+use core::fmt;
+use std::boxed::Box;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use super::{has_cpuid, leaf_supported, CpuidSource, DefaultSource, LeafDecoder};
+
+type Decode = Box<dyn Fn(&dyn CpuidSource) -> Option<String>>;
+
+/// Named third-party decoders, run together.
+#[derive(Default)]
+pub struct Decoders {
+    decoders: Vec<(&'static str, Decode)>,
+}
+
+impl Decoders {
+    pub fn new() -> Decoders {
+        Decoders { decoders: Vec::new() }
+    }
+
+    /// Adds a decoder, whose output is recorded as it displays. Names
+    /// should be unique, as they become the keys of the report.
+    pub fn register<D>(&mut self, name: &'static str, decoder: D) -> &mut Decoders
+        where D: LeafDecoder + 'static,
+              D::Output: fmt::Display
+    {
+        self.decoders.push((name, Box::new(move |source: &dyn CpuidSource| {
+            if !leaf_supported(source, decoder.leaf()) {
+                return None;
+            }
+            let (a, b, c, d) = source.cpuid(decoder.leaf(), decoder.subleaf());
+            Some(decoder.decode(a, b, c, d).to_string())
+        })));
+        self
+    }
+
+    /// Runs every decoder against the running processor. Each result is
+    /// `None` if the processor cannot be queried or reports that the
+    /// decoder's leaf is beyond the highest one it supports.
+    pub fn decode(&self) -> Decoded {
+        if has_cpuid() {
+            self.decode_from(&DefaultSource::default())
+        } else {
+            Decoded { values: self.decoders.iter().map(|&(name, _)| (name, None)).collect() }
+        }
+    }
+
+    /// Runs every decoder against `source`, such as a `RawDump`.
+    pub fn decode_from<S: CpuidSource>(&self, source: &S) -> Decoded {
+        Decoded {
+            values: self.decoders.iter().map(|(name, decode)| (*name, decode(source))).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.decoders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decoders.is_empty()
+    }
+}
+
+impl fmt::Debug for Decoders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.decoders.iter().map(|&(name, _)| name)).finish()
+    }
+}
+
+/// The results of a `Decoders`, in the order the decoders were
+/// registered.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Decoded {
+    values: Vec<(&'static str, Option<String>)>,
+}
+
+impl Decoded {
+    /// The result of the decoder registered as `name`, if it ran.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.iter().find(|v| v.0 == name).and_then(|v| v.1.as_deref())
+    }
+
+    /// Every decoder by name, with its result if it ran.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Option<&str>)> {
+        self.values.iter().map(|(name, value)| (*name, value.as_deref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::genuine_intel_dump;
+    use ReportStyle;
+
+    struct Vendor;
+
+    impl LeafDecoder for Vendor {
+        type Output = String;
+
+        fn leaf(&self) -> u32 { 0 }
+
+        fn decode(&self, _: u32, b: u32, c: u32, d: u32) -> String {
+            [b, d, c].iter().flat_map(|r| r.to_le_bytes()).map(char::from).collect()
+        }
+    }
+
+    struct Beyond;
+
+    impl LeafDecoder for Beyond {
+        type Output = u32;
+
+        fn leaf(&self) -> u32 { 0x8000_0008 }
+
+        fn decode(&self, a: u32, _: u32, _: u32, _: u32) -> u32 { a }
+    }
+
+    #[test]
+    fn decoders_run_against_a_snapshot_and_continue_the_report() {
+        let mut decoders = Decoders::new();
+        decoders.register("vendor", Vendor).register("address_sizes", Beyond);
+        assert_eq!(2, decoders.len());
+
+        let decoded = decoders.decode_from(&genuine_intel_dump(1));
+        assert_eq!(Some("GenuineIntel"), decoded.get("vendor"));
+        assert_eq!(None, decoded.get("address_sizes"));
+        assert_eq!(vec![("vendor", Some("GenuineIntel")), ("address_sizes", None)], decoded.iter().collect::<Vec<_>>());
+
+        let mut full = String::new();
+        decoded.write_report(&mut full, ReportStyle::Full).unwrap();
+        assert_eq!("decoder.vendor=GenuineIntel\ndecoder.address_sizes=\n", full);
+
+        let mut compact = String::new();
+        decoded.write_report(&mut compact, ReportStyle::Compact).unwrap();
+        assert_eq!("decoder.vendor=GenuineIntel\n", compact);
+    }
+}
@@ -0,0 +1,48 @@
+//! Checks for the instructions this crate executes itself.
+//!
+//! These always ask the processor, never a dump standing in for it
+//! under the `testing` feature, as executing an instruction the
+//! processor lacks faults. Each answer is remembered, as CPUID is far
+//! slower than the instructions it guards.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use super::{cpuid_count, has_cpuid, Register};
+
+const UNKNOWN: u8 = 0;
+const ABSENT: u8 = 1;
+const PRESENT: u8 = 2;
+
+/// The remembered answer of one `detect_once` check.
+pub struct Detected(AtomicU8);
+
+impl Detected {
+    pub const fn new() -> Detected {
+        Detected(AtomicU8::new(UNKNOWN))
+    }
+}
+
+/// Whether `bit` of `reg` is set in the processor's own result for
+/// `leaf` and `subleaf`, asking only the first time.
+pub fn detect_once(cache: &Detected, leaf: u32, subleaf: u32, reg: Register, bit: u32) -> bool {
+    match cache.0.load(Ordering::Relaxed) {
+        UNKNOWN => {
+            let present = detect(leaf, subleaf, reg, bit);
+            cache.0.store(if present { PRESENT } else { ABSENT }, Ordering::Relaxed);
+            present
+        },
+        state => state == PRESENT,
+    }
+}
+
+fn detect(leaf: u32, subleaf: u32, reg: Register, bit: u32) -> bool {
+    if !has_cpuid() {
+        return false;
+    }
+    // The basic and extended ranges each report their highest leaf
+    let (max_value, _, _, _) = cpuid_count(leaf & 0x8000_0000, 0);
+    if max_value < leaf {
+        return false;
+    }
+    reg.of(cpuid_count(leaf, subleaf)) & (1 << bit) != 0
+}
@@ -0,0 +1,364 @@
+//! Comparing two processor descriptions.
+//!
+//! Either side may be decoded from the registers, or read back from a
+//! document written by `Master::to_json`:
+//!
+//! ```
+//! use cupid::Description;
+//!
+//! if let Some(info) = cupid::master() {
+//!     let saved = Description::from_json(&info.to_json()).unwrap();
+//!     assert!(saved.diff(&Description::from(&info)).is_empty());
+//! }
+//! ```
+
+use std::fmt;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use super::json::{parse, schema_version, string, SchemaError, Value};
+#[cfg(feature = "caches")]
+use super::CacheType;
+use super::Master;
+
+/// A field whose value differs between two descriptions. Values that
+/// are not reported are shown as "none".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Change {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// The differences between two descriptions, from `Master::diff`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Diff {
+    identity: Vec<Change>,
+    added_features: Vec<&'static str>,
+    removed_features: Vec<&'static str>,
+    caches: Vec<Change>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.identity.is_empty() && self.added_features.is_empty() &&
+            self.removed_features.is_empty() && self.caches.is_empty()
+    }
+
+    /// Changes to the vendor, brand, signature and address sizes.
+    pub fn identity(&self) -> &[Change] {
+        &self.identity
+    }
+
+    /// Features only the other description has.
+    pub fn added_features(&self) -> &[&'static str] {
+        &self.added_features
+    }
+
+    /// Features only this description has.
+    pub fn removed_features(&self) -> &[&'static str] {
+        &self.removed_features
+    }
+
+    /// Caches that differ in size, or exist on only one side.
+    pub fn caches(&self) -> &[Change] {
+        &self.caches
+    }
+
+    /// Renders the differences as a JSON document, for scripts:
+    ///
+    /// ```text
+    /// {
+    ///   "identity": [{ "field": "model", "old": "158", "new": "165" }],
+    ///   "added_features": ["avx512f"],
+    ///   "removed_features": [],
+    ///   "caches": [{ "field": "L3 cache", "old": "12582912 bytes", "new": "none" }]
+    /// }
+    /// ```
+    pub fn to_json(&self) -> String {
+        let changes = |out: &mut String, changes: &[Change]| {
+            out.push('[');
+            for (i, c) in changes.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                out.push_str("{\"field\":");
+                string(out, &c.field);
+                out.push_str(",\"old\":");
+                string(out, &c.old);
+                out.push_str(",\"new\":");
+                string(out, &c.new);
+                out.push('}');
+            }
+            out.push(']');
+        };
+        let names = |out: &mut String, names: &[&str]| {
+            out.push('[');
+            for (i, name) in names.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                string(out, name);
+            }
+            out.push(']');
+        };
+
+        let mut out = String::from("{\"identity\":");
+        changes(&mut out, &self.identity);
+        out.push_str(",\"added_features\":");
+        names(&mut out, &self.added_features);
+        out.push_str(",\"removed_features\":");
+        names(&mut out, &self.removed_features);
+        out.push_str(",\"caches\":");
+        changes(&mut out, &self.caches);
+        out.push('}');
+        out
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for c in self.identity.iter().chain(&self.caches) {
+            writeln!(f, "{}: {} -> {}", c.field, c.old, c.new)?;
+        }
+        for name in &self.added_features {
+            writeln!(f, "+ {}", name)?;
+        }
+        for name in &self.removed_features {
+            writeln!(f, "- {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// What a diff compares of a processor: its identity, features and
+/// caches, as recorded by a `Master` or a JSON document.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Description {
+    vendor: String,
+    brand: Option<String>,
+    version: Option<(u64, u64, u64)>,
+    address_sizes: Option<(u64, u64)>,
+    // Each of `Master::FLAGS`, unknown if a document from an earlier
+    // release does not list it
+    features: Vec<(&'static str, Option<bool>)>,
+    caches: Vec<(String, u64)>,
+}
+
+impl<'a> From<&'a Master> for Description {
+    fn from(m: &'a Master) -> Description {
+        Description {
+            vendor: m.vendor_id().to_string(),
+            brand: m.brand_string().map(str::to_string),
+            version: m.version_information()
+                .map(|v| (v.family_id() as u64, v.model_id() as u64, v.stepping() as u64)),
+            // As `to_json` records them
+            address_sizes: m.physical_address_size().and_then(|p| {
+                m.linear_address_bits().map(|l| (p.physical_address_bits() as u64, l as u64))
+            }),
+            features: m.flags().map(|(name, has)| (name, Some(has))).collect(),
+            caches: caches(m),
+        }
+    }
+}
+
+impl Description {
+    /// Reads back a document written by `Master::to_json` of this or
+    /// any earlier release. Features the document does not list are
+    /// not compared.
+    pub fn from_json(document: &str) -> Result<Description, SchemaError> {
+        schema_version(document)?;
+        let doc = parse(document)?;
+        let malformed = SchemaError::Malformed;
+
+        let vendor = doc.get("vendor").and_then(Value::as_str).ok_or(malformed)?.to_string();
+        let brand = match doc.get("brand") {
+            Some(&Value::Null) => None,
+            brand => Some(brand.and_then(Value::as_str).ok_or(malformed)?.to_string()),
+        };
+        let number = |v: &Value, key: &str| v.get(key).and_then(Value::as_u64).ok_or(malformed);
+        let version = match doc.get("version") {
+            Some(&Value::Null) => None,
+            Some(v) => Some((number(v, "family")?, number(v, "model")?, number(v, "stepping")?)),
+            None => return Err(malformed),
+        };
+        let address_sizes = match doc.get("address_sizes") {
+            Some(&Value::Null) => None,
+            Some(a) => Some((number(a, "physical")?, number(a, "linear")?)),
+            None => return Err(malformed),
+        };
+
+        let listed = match doc.get("features") {
+            Some(listed @ &Value::Object(_)) => listed,
+            _ => return Err(malformed),
+        };
+        let mut features = Vec::new();
+        for &(name, _) in Master::FLAGS {
+            let has = match listed.get(name) {
+                Some(&Value::Bool(has)) => Some(has),
+                Some(_) => return Err(malformed),
+                None => None,
+            };
+            features.push((name, has));
+        }
+
+        let mut caches = Vec::new();
+        match doc.get("caches") {
+            Some(Value::Array(listed)) => {
+                for cache in listed {
+                    let kind = match cache.get("type") {
+                        Some(&Value::Null) => "",
+                        Some(kind) => match kind.as_str() {
+                            Some("data") => "d",
+                            Some("instruction") => "i",
+                            Some("unified") => "",
+                            _ => return Err(malformed),
+                        },
+                        None => return Err(malformed),
+                    };
+                    caches.push((format!("L{}{} cache", number(cache, "level")?, kind), number(cache, "size")?));
+                }
+            },
+            _ => return Err(malformed),
+        }
+
+        Ok(Description { vendor, brand, version, address_sizes, features, caches })
+    }
+
+    /// Reports how `other` differs from this description.
+    pub fn diff(&self, other: &Description) -> Diff {
+        let mut diff = Diff::default();
+
+        {
+            let mut compare = |field: &str, old: Option<String>, new: Option<String>| {
+                if old != new {
+                    diff.identity.push(Change {
+                        field: field.to_string(),
+                        old: old.unwrap_or_else(|| "none".to_string()),
+                        new: new.unwrap_or_else(|| "none".to_string()),
+                    });
+                }
+            };
+
+            compare("vendor", Some(self.vendor.clone()), Some(other.vendor.clone()));
+            compare("brand", self.brand.clone(), other.brand.clone());
+
+            let family = |d: &Description| d.version.map(|v| v.0.to_string());
+            let model = |d: &Description| d.version.map(|v| v.1.to_string());
+            let stepping = |d: &Description| d.version.map(|v| v.2.to_string());
+            let address_sizes = |d: &Description| {
+                d.address_sizes.map(|(physical, linear)| {
+                    format!("{} bits physical, {} bits virtual", physical, linear)
+                })
+            };
+            compare("family", family(self), family(other));
+            compare("model", model(self), model(other));
+            compare("stepping", stepping(self), stepping(other));
+            compare("address sizes", address_sizes(self), address_sizes(other));
+        }
+
+        for (&(name, old), &(_, new)) in self.features.iter().zip(&other.features) {
+            match (old, new) {
+                (Some(false), Some(true)) => diff.added_features.push(name),
+                (Some(true), Some(false)) => diff.removed_features.push(name),
+                _ => {},
+            }
+        }
+
+        let (old, new) = (&self.caches, &other.caches);
+        for (label, _) in old.iter().chain(new) {
+            if diff.caches.iter().any(|c| &c.field == label) {
+                continue;
+            }
+            let size = |caches: &[(String, u64)]| {
+                caches.iter().find(|c| &c.0 == label).map(|c| c.1)
+            };
+            let (old_size, new_size) = (size(old), size(new));
+            if old_size != new_size {
+                let show = |s: Option<u64>| s.map(|s| format!("{} bytes", s)).unwrap_or_else(|| "none".to_string());
+                diff.caches.push(Change { field: label.clone(), old: show(old_size), new: show(new_size) });
+            }
+        }
+
+        diff
+    }
+}
+
+impl Master {
+    /// Reports how `other` differs from this description.
+    pub fn diff(&self, other: &Master) -> Diff {
+        Description::from(self).diff(&Description::from(other))
+    }
+}
+
+// Each cache by a label such as "L1d cache", with its size in bytes
+#[cfg(feature = "caches")]
+fn caches(m: &Master) -> Vec<(String, u64)> {
+    let caches = m.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]);
+    caches.iter().map(|c| {
+        let kind = match c.cache_type() {
+            Some(CacheType::Data) => "d",
+            Some(CacheType::Instruction) => "i",
+            Some(CacheType::Unified) | None => "",
+        };
+        (format!("L{}{} cache", c.level(), kind), c.size())
+    }).collect()
+}
+
+#[cfg(not(feature = "caches"))]
+fn caches(_m: &Master) -> Vec<(String, u64)> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn diff_reports_changed_identity_and_features() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+        let old = Master::from_dump(&dump);
+        assert!(old.diff(&old).is_empty());
+
+        dump.insert(1, 0, (0x0006_06A7, 0, 1 << 0, 1 << 26));
+        let diff = old.diff(&Master::from_dump(&dump));
+        assert_eq!(1, diff.identity().len());
+        assert_eq!("stepping", diff.identity()[0].field);
+        assert_eq!(&["sse3"], diff.added_features());
+        assert_eq!(&["sse4_2"], diff.removed_features());
+        assert_eq!("stepping: 6 -> 7\n+ sse3\n- sse4_2\n", format!("{}", diff));
+        assert_eq!("{\"identity\":[{\"field\":\"stepping\",\"old\":\"6\",\"new\":\"7\"}],\
+                    \"added_features\":[\"sse3\"],\"removed_features\":[\"sse4_2\"],\"caches\":[]}",
+                   diff.to_json());
+    }
+
+    #[test]
+    fn json_descriptions_compare_with_dumps_and_each_other() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+        let old = Master::from_dump(&dump);
+        let saved = Description::from_json(&old.to_json()).unwrap();
+        assert_eq!(Description::from(&old), saved);
+
+        dump.insert(1, 0, (0x0006_06A7, 0, 1 << 0, 1 << 26));
+        let new = Master::from_dump(&dump);
+        assert_eq!(old.diff(&new), saved.diff(&Description::from(&new)));
+        let both = saved.diff(&Description::from_json(&new.to_json()).unwrap());
+        assert_eq!("stepping: 6 -> 7\n+ sse3\n- sse4_2\n", format!("{}", both));
+
+        // An earlier release that did not list sse3 yet
+        let earlier = new.to_json().replace("\"sse3\":true,", "");
+        assert_eq!("stepping: 6 -> 7\n- sse4_2\n",
+                   format!("{}", saved.diff(&Description::from_json(&earlier).unwrap())));
+
+        assert_eq!(Err(SchemaError::MissingSchema), Description::from_json("{\"vendor\":\"GenuineIntel\"}"));
+        assert_eq!(Err(SchemaError::Malformed), Description::from_json("{\"schema\":1,\"vendor\":\"GenuineIntel\"}"));
+        let truncated = old.to_json();
+        assert_eq!(Err(SchemaError::Malformed), Description::from_json(&truncated[..truncated.len() - 1]));
+    }
+}
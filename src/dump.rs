@@ -0,0 +1,569 @@
+//! Raw register snapshots, for offline analysis and bug reports.
+//!
+//! Both encodings are independent of the host's byte order: the binary
+//! one is little-endian throughout, and `cpuid -r` text writes the
+//! registers as numbers. A dump captured on x86 decodes identically on
+//! a big-endian or non-x86 machine, so the strings spelled out in the
+//! registers, such as the vendor and brand, are always read as the
+//! processor laid them out.
+
+use std::collections::BTreeMap;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+use super::{has_cpuid, query, RequestType, XEN_LEAF_BASE_LIMIT};
+
+// Guards against processors (or hypervisors) reporting absurd maxima
+const MAX_LEAVES_PER_RANGE: u32 = 0x100;
+const MAX_SUBLEAVES: u32 = 0x20;
+
+// leaf + subleaf + 4 registers, each a little-endian u32
+const ENTRY_BYTES: usize = 6 * 4;
+
+// Starts the binary encoding, followed by the format version
+const MAGIC: &[u8; 4] = b"CPUD";
+const HEADER_BYTES: usize = 8;
+
+/// The version of the binary encoding `RawDump::to_bytes` writes, and of
+/// the serde form, which records it as `version`.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The registers returned by one query.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RawEntry {
+    pub leaf: u32,
+    pub subleaf: u32,
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+/// The error from decoding a malformed dump, or one written by a later
+/// release in a newer format.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DumpError {
+    /// A binary dump whose length is not a whole number of entries.
+    Truncated,
+    /// A binary dump that does not start with the header.
+    MissingHeader,
+    /// A dump in a format version this release does not know, such as
+    /// one written by a later release.
+    UnknownVersion(u32),
+    /// A line of `cpuid -r` output that is not a register line, by its
+    /// number, starting from 1.
+    MalformedLine(usize),
+    /// Text without a single register line.
+    Empty,
+    /// `/proc/cpuinfo` text that names no vendor, so does not describe
+    /// an x86 processor.
+    NoVendor,
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DumpError::Truncated => f.write_str("truncated dump"),
+            DumpError::MissingHeader => f.write_str("missing dump header"),
+            DumpError::UnknownVersion(v) => write!(f, "unknown dump format version {}", v),
+            DumpError::MalformedLine(line) => write!(f, "malformed register line {}", line),
+            DumpError::Empty => f.write_str("no register lines"),
+            DumpError::NoVendor => f.write_str("no x86 vendor"),
+        }
+    }
+}
+
+impl std::error::Error for DumpError {}
+
+/// Every (leaf, subleaf) pair this crate knows how to decode, with the
+/// registers the processor returned for it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "VersionedDump", into = "VersionedDump"))]
+pub struct RawDump {
+    entries: Vec<RawEntry>,
+    // The position of each query in `entries`, so that inserting and
+    // looking up stay cheap in dumps of many leaves
+    index: BTreeMap<(u32, u32), usize>,
+}
+
+// The serde form of a dump, which records the format version as the
+// binary encoding does
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct VersionedDump {
+    version: u32,
+    entries: Vec<RawEntry>,
+}
+
+#[cfg(feature = "serde")]
+impl From<RawDump> for VersionedDump {
+    fn from(dump: RawDump) -> VersionedDump {
+        VersionedDump { version: FORMAT_VERSION, entries: dump.entries }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<VersionedDump> for RawDump {
+    type Error = DumpError;
+
+    fn try_from(versioned: VersionedDump) -> Result<RawDump, DumpError> {
+        if versioned.version != FORMAT_VERSION {
+            return Err(DumpError::UnknownVersion(versioned.version));
+        }
+        let mut dump = RawDump::new();
+        for e in versioned.entries {
+            dump.insert(e.leaf, e.subleaf, (e.eax, e.ebx, e.ecx, e.edx));
+        }
+        Ok(dump)
+    }
+}
+
+impl RawDump {
+    /// An empty dump, to be filled with `insert`.
+    pub fn new() -> RawDump {
+        RawDump::default()
+    }
+
+    /// Queries every supported leaf of the current processor, with the
+    /// sub-leaves of those leaves that are known to have them. The dump
+    /// is empty on processors without CPUID.
+    pub fn capture() -> RawDump {
+        let mut dump = RawDump::new();
+        if !has_cpuid() {
+            return dump;
+        }
+
+        let (max_basic, _, _, _) = dump.query(RequestType::BasicInformation as u32, 0);
+        for leaf in 1..max_basic.saturating_add(1).min(MAX_LEAVES_PER_RANGE) {
+            dump.capture_leaf(leaf);
+        }
+
+        let hypervisor = dump.get(RequestType::VersionInformation as u32, 0)
+            .map(|(_, _, c, _)| c & (1 << 31) != 0)
+            .unwrap_or(false);
+        if hypervisor {
+            // Xen may relocate its range, so look at every candidate base
+            let mut base = RequestType::HypervisorInformation as u32;
+            while base < XEN_LEAF_BASE_LIMIT {
+                let (max_leaf, _, _, _) = query(base, 0);
+                if max_leaf >= base && max_leaf < base + MAX_LEAVES_PER_RANGE {
+                    for leaf in base..max_leaf + 1 {
+                        dump.query(leaf, 0);
+                    }
+                } else if base == RequestType::HypervisorInformation as u32 {
+                    dump.query(base, 0);
+                }
+                base += 0x100;
+            }
+        }
+
+        let extended = RequestType::ExtendedFunctionInformation as u32;
+        let (max_extended, _, _, _) = dump.query(extended, 0);
+        if max_extended > extended {
+            let last = max_extended.min(extended + MAX_LEAVES_PER_RANGE - 1);
+            for leaf in extended + 1..last + 1 {
+                dump.capture_leaf(leaf);
+            }
+        }
+
+        dump
+    }
+
+    fn capture_leaf(&mut self, leaf: u32) {
+        let (a, _, _, _) = self.query(leaf, 0);
+
+        // Caches: sub-leaves run until a cache type of zero. They are
+        // captured whether or not the `caches` feature decodes them.
+        if leaf == 0x4 || leaf == 0x8000_001D {
+            let mut subleaf = 1;
+            let mut cache_type = a & 0x1F;
+            while cache_type != 0 && subleaf < MAX_SUBLEAVES {
+                cache_type = self.query(leaf, subleaf).0 & 0x1F;
+                subleaf += 1;
+            }
+            return;
+        }
+
+        // Topology: sub-leaves run until a level type of zero
+        if leaf == 0xB || leaf == 0x1F {
+            let mut subleaf = 1;
+            let mut level_type = (self.get(leaf, 0).unwrap_or((0, 0, 0, 0)).2 >> 8) & 0xFF;
+            while level_type != 0 && subleaf < MAX_SUBLEAVES {
+                level_type = (self.query(leaf, subleaf).2 >> 8) & 0xFF;
+                subleaf += 1;
+            }
+            return;
+        }
+
+        // XSAVE: one sub-leaf per user (XCR0) or supervisor (IA32_XSS) state component
+        if leaf == 0xD {
+            let (_, _, xss, _) = self.query(leaf, 1);
+            let components = a | xss;
+            for subleaf in 2..MAX_SUBLEAVES {
+                if components & (1 << subleaf) != 0 {
+                    self.query(leaf, subleaf);
+                }
+            }
+            return;
+        }
+
+        let max_subleaf = match leaf {
+            l if l == RequestType::StructuredExtendedInformation as u32 => a,
+            l if l == RequestType::TileInformation as u32 => a,
+            l if l == RequestType::RdtMonitoring as u32 => 1,
+            l if l == RequestType::RdtAllocation as u32 => 3,
+            l if l == RequestType::Pconfig as u32 => 3,
+            // Processor trace, SoC vendor, TLB and PMU enumeration
+            // report their highest sub-leaf in EAX
+            0x14 | 0x17 | 0x18 | 0x20 => a,
+            _ => 0,
+        };
+        for subleaf in 1..max_subleaf.saturating_add(1).min(MAX_SUBLEAVES) {
+            self.query(leaf, subleaf);
+        }
+    }
+
+    fn query(&mut self, leaf: u32, subleaf: u32) -> (u32, u32, u32, u32) {
+        let registers = query(leaf, subleaf);
+        self.insert(leaf, subleaf, registers);
+        registers
+    }
+
+    /// Records the registers for a query, replacing any existing entry.
+    pub fn insert(&mut self, leaf: u32, subleaf: u32, registers: (u32, u32, u32, u32)) {
+        let (a, b, c, d) = registers;
+        let entry = RawEntry { leaf, subleaf, eax: a, ebx: b, ecx: c, edx: d };
+
+        let len = self.entries.len();
+        let i = *self.index.entry((leaf, subleaf)).or_insert(len);
+        if i == len {
+            self.entries.push(entry);
+        } else {
+            self.entries[i] = entry;
+        }
+    }
+
+    /// The registers recorded for a query, if any.
+    pub fn get(&self, leaf: u32, subleaf: u32) -> Option<(u32, u32, u32, u32)> {
+        self.index.get(&(leaf, subleaf))
+            .map(|&i| self.entries[i])
+            .map(|e| (e.eax, e.ebx, e.ecx, e.edx))
+    }
+
+    /// The recorded queries, in the order they were made.
+    pub fn entries(&self) -> &[RawEntry] {
+        &self.entries
+    }
+
+    /// A compact binary encoding: a header of "CPUD" and the format
+    /// version, followed by 24 bytes per entry, holding the leaf,
+    /// subleaf and the four registers. Integers are little-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_BYTES + self.entries.len() * ENTRY_BYTES);
+        bytes.extend_from_slice(MAGIC);
+        push_u32(&mut bytes, FORMAT_VERSION);
+        for e in &self.entries {
+            for &v in &[e.leaf, e.subleaf, e.eax, e.ebx, e.ecx, e.edx] {
+                push_u32(&mut bytes, v);
+            }
+        }
+        bytes
+    }
+
+    /// Decodes the output of `to_bytes`. Fails if the input lacks the
+    /// header, is truncated, or is in a format version this release
+    /// does not know.
+    pub fn from_bytes(bytes: &[u8]) -> Result<RawDump, DumpError> {
+        let entries = match bytes.strip_prefix(&MAGIC[..]) {
+            Some(rest) if rest.len() >= HEADER_BYTES - MAGIC.len() => {
+                let (version, entries) = rest.split_at(HEADER_BYTES - MAGIC.len());
+                let version = read_u32(version);
+                if version != FORMAT_VERSION {
+                    return Err(DumpError::UnknownVersion(version));
+                }
+                entries
+            },
+            Some(_) => return Err(DumpError::Truncated),
+            None => return Err(DumpError::MissingHeader),
+        };
+
+        let chunks = entries.chunks_exact(ENTRY_BYTES);
+        if !chunks.remainder().is_empty() {
+            return Err(DumpError::Truncated);
+        }
+
+        let mut dump = RawDump::new();
+        for chunk in chunks {
+            let mut v = [0u32; 6];
+            for (v, b) in v.iter_mut().zip(chunk.chunks(4)) {
+                *v = read_u32(b);
+            }
+            dump.insert(v[0], v[1], (v[2], v[3], v[4], v[5]));
+        }
+        Ok(dump)
+    }
+
+    /// Parses a dump in any format the crate writes or reads: the output
+    /// of `to_bytes` or of `cpuid -r`. Any input, however malformed,
+    /// fails with an error rather than a panic. The error is that of
+    /// the format the input most resembles.
+    pub fn parse(bytes: &[u8]) -> Result<RawDump, DumpError> {
+        // A binary dump can happen to be valid UTF-8, but not `cpuid -r`
+        let text = ::core::str::from_utf8(bytes).map(RawDump::from_cpuid_r);
+        if let Ok(Ok(dump)) = text {
+            return Ok(dump);
+        }
+        match RawDump::from_bytes(bytes) {
+            Ok(dump) => Ok(dump),
+            Err(e) if bytes.starts_with(&MAGIC[..]) => Err(e),
+            Err(e) => text.unwrap_or(Err(e)),
+        }
+    }
+
+    /// Parses the raw output of Todd Allen's `cpuid -r`, returning the
+    /// dump of the first processor listed. Fails if any register line
+    /// is malformed, or if there are none. Lines starting with `#` are
+    /// comments.
+    pub fn from_cpuid_r(text: &str) -> Result<RawDump, DumpError> {
+        RawDump::all_from_cpuid_r(text)?.into_iter()
+            .find(|dump| !dump.entries().is_empty())
+            .ok_or(DumpError::Empty)
+    }
+
+    /// Parses the raw output of `cpuid -r`, with one dump per processor.
+    pub fn all_from_cpuid_r(text: &str) -> Result<Vec<RawDump>, DumpError> {
+        let mut dumps = Vec::new();
+
+        for (number, line) in text.lines().map(str::trim).enumerate() {
+            let malformed = DumpError::MalformedLine(number + 1);
+            if line.starts_with("CPU") {
+                dumps.push(RawDump::new());
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // 0x00000001 0x00: eax=0x000306c3 ebx=0x00100800 ecx=0x7ffafbff edx=0xbfebfbff
+            let mut parts = line.split(|c: char| c == ':' || c.is_whitespace())
+                .filter(|p| !p.is_empty());
+            let leaf = parts.next().and_then(hex).ok_or(malformed)?;
+            let subleaf = parts.next().and_then(hex).ok_or(malformed)?;
+            let mut registers = [0; 4];
+            for (register, name) in registers.iter_mut().zip(&["eax=", "ebx=", "ecx=", "edx="]) {
+                *register = parts.next()
+                    .and_then(|p| p.strip_prefix(name))
+                    .and_then(hex)
+                    .ok_or(malformed)?;
+            }
+
+            // Single-processor output (`cpuid -1 -r`) may lack a header
+            if dumps.is_empty() {
+                dumps.push(RawDump::new());
+            }
+            if let Some(dump) = dumps.last_mut() {
+                dump.insert(leaf, subleaf, (registers[0], registers[1], registers[2], registers[3]));
+            }
+        }
+
+        Ok(dumps)
+    }
+
+    /// A copy of the dump without the values that could identify the
+    /// machine or the logical processor it was captured on: the
+    /// processor serial number (leaf 3), the APIC IDs of leaves 1, 0xB,
+    /// 0x1F and 0x8000_001E, and the SoC vendor, project, stepping and
+    /// brand of leaf 0x17. These are zeroed in place, leaving every
+    /// capability bit and the shape of the dump as captured, so the
+    /// copy still decodes to the same features.
+    pub fn redacted(&self) -> RawDump {
+        let mut dump = self.clone();
+        for e in &mut dump.entries {
+            match e.leaf {
+                0x3 => {
+                    e.eax = 0;
+                    e.ebx = 0;
+                    e.ecx = 0;
+                    e.edx = 0;
+                },
+                // Initial APIC ID in EBX[31:24]
+                0x1 => e.ebx &= 0x00FF_FFFF,
+                0xB | 0x1F => e.edx = 0,
+                // Sub-leaf 0 keeps only the highest sub-leaf in EAX;
+                // the rest spell out the brand
+                0x17 => {
+                    if e.subleaf != 0 {
+                        e.eax = 0;
+                    }
+                    e.ebx = 0;
+                    e.ecx = 0;
+                    e.edx = 0;
+                },
+                0x8000_001E => e.eax = 0,
+                _ => (),
+            }
+        }
+        dump
+    }
+
+    /// Formats the dump as `cpuid -r` does for a single processor.
+    pub fn to_cpuid_r(&self) -> String {
+        let mut text = String::from("CPU 0:\n");
+        for e in &self.entries {
+            text.push_str(&format!("   0x{:08x} 0x{:02x}: eax=0x{:08x} ebx=0x{:08x} ecx=0x{:08x} edx=0x{:08x}\n",
+                                   e.leaf, e.subleaf, e.eax, e.ebx, e.ecx, e.edx));
+        }
+        text
+    }
+}
+
+impl IntoIterator for RawDump {
+    type Item = RawEntry;
+    type IntoIter = ::std::vec::IntoIter<RawEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+fn push_u32(bytes: &mut Vec<u8>, v: u32) {
+    bytes.extend_from_slice(&v.to_le_bytes());
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u32)
+}
+
+fn hex(s: &str) -> Option<u32> {
+    u32::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {cpuid, genuine_intel_dump, Feature, Master};
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    fn raw_dump_round_trips_through_bytes() {
+        let dump = RawDump::capture();
+
+        assert_eq!(Some(cpuid(RequestType::BasicInformation)), dump.get(0, 0));
+        assert_eq!(Ok(dump.clone()), RawDump::from_bytes(&dump.to_bytes()));
+        let bytes = dump.to_bytes();
+        assert_eq!(Err(DumpError::Truncated), RawDump::from_bytes(&bytes[..bytes.len() - 1]));
+    }
+
+    #[test]
+    fn raw_dump_bytes_are_versioned() {
+        let dump = genuine_intel_dump(1);
+        let bytes = dump.to_bytes();
+        assert_eq!(b"CPUD\x01\0\0\0", &bytes[..8]);
+        assert_eq!(Err(DumpError::MissingHeader), RawDump::from_bytes(&bytes[8..]));
+
+        let mut newer = bytes.clone();
+        newer[4] = FORMAT_VERSION as u8 + 1;
+        assert_eq!(Err(DumpError::UnknownVersion(2)), RawDump::from_bytes(&newer));
+        newer[4] = 0;
+        assert_eq!(Err(DumpError::UnknownVersion(0)), RawDump::from_bytes(&newer));
+        assert_eq!(Err(DumpError::Truncated), RawDump::from_bytes(b"CPUD"));
+    }
+
+    #[test]
+    fn raw_dump_bytes_are_little_endian() {
+        let mut dump = RawDump::new();
+        let vendor = |s: &[u8; 4]| u32::from_le_bytes(*s);
+        dump.insert(0, 0, (0x0102_0304, vendor(b"Auth"), vendor(b"cAMD"), vendor(b"enti")));
+
+        let bytes = dump.to_bytes();
+        assert_eq!([4, 3, 2, 1], bytes[16..20]);
+        assert_eq!(b"AuthcAMDenti", &bytes[20..32]);
+        assert_eq!(Ok(dump.clone()), RawDump::from_bytes(&bytes));
+        assert_eq!("AuthenticAMD", Master::from_dump(&dump).vendor_id());
+    }
+
+    #[test]
+    fn raw_dump_parse_reports_malformed_input() {
+        let dump = genuine_intel_dump(1);
+
+        let bytes = dump.to_bytes();
+        assert_eq!(Err(DumpError::Truncated), RawDump::parse(&bytes[..bytes.len() - 1]));
+        let text = dump.to_cpuid_r() + "   0x00000001 0x00: eax=junk\n";
+        assert_eq!(Err(DumpError::MalformedLine(3)), RawDump::parse(text.as_bytes()));
+        assert_eq!(Err(DumpError::MissingHeader), RawDump::parse(&[0xff; 7]));
+        assert_eq!(Err(DumpError::Empty), RawDump::parse(b""));
+
+        let mut junk = bytes[..8].to_vec();
+        junk.extend_from_slice(&[0xff; 24]);
+        let info = Master::from_dump(&RawDump::parse(&junk).unwrap());
+        let _ = format!("{:?}", info);
+    }
+
+    #[test]
+    fn raw_dump_inserts_stay_cheap_and_in_order() {
+        // Quadratic insertion would take minutes over this many leaves
+        let mut bytes = genuine_intel_dump(1).to_bytes();
+        for leaf in 0..200_000u32 {
+            for v in &[leaf, 0, leaf, 0, 0, 0] {
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        let mut dump = RawDump::from_bytes(&bytes).unwrap();
+        assert_eq!(200_000, dump.entries().len());
+        assert_eq!(Some((199_999, 0, 0, 0)), dump.get(199_999, 0));
+
+        dump.insert(0, 0, (1, 2, 3, 4));
+        dump.insert(7, 1, (5, 6, 7, 8));
+        assert_eq!(200_001, dump.entries().len());
+        assert_eq!((0, 1), (dump.entries()[0].leaf, dump.entries()[0].eax));
+        assert_eq!((7, 1), (dump.entries()[200_000].leaf, dump.entries()[200_000].subleaf));
+        assert_eq!(Some((5, 6, 7, 8)), dump.get(7, 1));
+    }
+
+    #[test]
+    fn cpuid_r_format_round_trips() {
+        let text = "CPU 0:
+       0x00000000 0x00: eax=0x0000000d ebx=0x756e6547 ecx=0x6c65746e edx=0x49656e69
+       0x00000001 0x00: eax=0x000306c3 ebx=0x00100800 ecx=0x7ffafbff edx=0xbfebfbff
+    CPU 1:
+       0x00000000 0x00: eax=0x0000000d ebx=0x756e6547 ecx=0x6c65746e edx=0x49656e69
+    ";
+        let dumps = RawDump::all_from_cpuid_r(text).unwrap();
+        assert_eq!(2, dumps.len());
+        assert_eq!(Some((0x000306c3, 0x00100800, 0x7ffafbff, 0xbfebfbff)), dumps[0].get(1, 0));
+
+        let dump = RawDump::from_cpuid_r(text).unwrap();
+        assert_eq!(Ok(dump.clone()), RawDump::from_cpuid_r(&dump.to_cpuid_r()));
+        assert_eq!(Err(DumpError::MalformedLine(2)),
+                   RawDump::from_cpuid_r("CPU 0:\n   0x00000000 0x00: eax=0x0000000d"));
+        assert_eq!(Err(DumpError::Empty), RawDump::from_cpuid_r("# no registers\n"));
+    }
+
+    #[test]
+    fn redacted_dumps_keep_the_features_but_not_the_ids() {
+        let mut dump = genuine_intel_dump(0x1F);
+        dump.insert(1, 0, (0x0000_0673, 0x0B10_0800, 1 << 21, (1 << 18) | (1 << 26)));
+        dump.insert(3, 0, (0, 0, 0x89AB_CDEF, 0x0123_4567));
+        dump.insert(0xB, 0, (1, 2, 0x100, 11));
+        dump.insert(0x17, 0, (3, 0x1_0123, 0x45, 0x67));
+        dump.insert(0x17, 1, (0x6F43_4F53, 0x6F43_4F53, 0x6F43_4F53, 0x6F43_4F53));
+
+        let redacted = dump.redacted();
+        assert_eq!(Some((0x0000_0673, 0x0010_0800, 1 << 21, (1 << 18) | (1 << 26))), redacted.get(1, 0));
+        assert_eq!(Some((0, 0, 0, 0)), redacted.get(3, 0));
+        assert_eq!(Some((1, 2, 0x100, 0)), redacted.get(0xB, 0));
+        assert_eq!(Some((3, 0, 0, 0)), redacted.get(0x17, 0));
+        assert_eq!(Some((0, 0, 0, 0)), redacted.get(0x17, 1));
+        assert_eq!(dump.entries().len(), redacted.entries().len());
+
+        let (info, redacted) = (Master::from_dump(&dump), Master::from_dump(&redacted));
+        assert!(Feature::ALL.iter().all(|&f| info.supports(f) == redacted.supports(f)));
+        assert_eq!(0, redacted.version_information().unwrap().initial_apic_id());
+    }
+}
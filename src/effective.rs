@@ -0,0 +1,154 @@
+//! The features code may actually execute, combining what the compile
+//! target assumes, what CPUID reports and what the operating system
+//! has enabled.
+//!
+//! The three sources are combined in this order:
+//!
+//! 1. The features CPUID reports are the starting point.
+//! 2. Features whose register state the operating system has not
+//!    enabled in XCR0 are removed, as their instructions fault even
+//!    though the processor implements them. Without OSXSAVE, none of
+//!    that state is enabled.
+//! 3. The features the binary was compiled to assume are added, as the
+//!    compiler already uses them throughout the code, so the binary is
+//!    only running at all where they work. This covers a hypervisor
+//!    that hides a flag its host implements.
+//!
+//! ```
+//! let features = cupid::effective_features!();
+//! if features.contains(cupid::Feature::Avx2) {
+//!     // safe to call a `#[target_feature(enable = "avx2")]` function
+//! }
+//! ```
+
+use super::{master, xcr0, Feature, FeatureSet, Master};
+
+// The XCR0 state components the features below need enabled
+const XCR0_SSE: u64 = 1 << 1;
+const XCR0_AVX: u64 = 1 << 2;
+const XCR0_BNDREGS: u64 = 1 << 3;
+const XCR0_BNDCSR: u64 = 1 << 4;
+const XCR0_OPMASK: u64 = 1 << 5;
+const XCR0_ZMM_HI256: u64 = 1 << 6;
+const XCR0_HI16_ZMM: u64 = 1 << 7;
+const XCR0_TILECFG: u64 = 1 << 17;
+const XCR0_TILEDATA: u64 = 1 << 18;
+const XCR0_APX: u64 = 1 << 19;
+
+const AVX_STATE: u64 = XCR0_SSE | XCR0_AVX;
+const AVX512_STATE: u64 = AVX_STATE | XCR0_OPMASK | XCR0_ZMM_HI256 | XCR0_HI16_ZMM;
+
+// Each feature using XSAVE-managed registers, with the state it needs
+const STATE: &[(Feature, u64)] = &[
+    (Feature::Avx, AVX_STATE),
+    (Feature::Avx2, AVX_STATE),
+    (Feature::Fma, AVX_STATE),
+    (Feature::F16c, AVX_STATE),
+    (Feature::Fma4, AVX_STATE),
+    (Feature::Xop, AVX_STATE),
+    (Feature::AvxVnni, AVX_STATE),
+    (Feature::AvxIfma, AVX_STATE),
+    (Feature::AvxVnniInt8, AVX_STATE),
+    (Feature::AvxVnniInt16, AVX_STATE),
+    (Feature::AvxNeConvert, AVX_STATE),
+    (Feature::Sha512, AVX_STATE),
+    (Feature::Sm3, AVX_STATE),
+    (Feature::Sm4, AVX_STATE),
+    (Feature::Vaes, AVX_STATE),
+    (Feature::Vpclmulqdq, AVX_STATE),
+    (Feature::Mpx, XCR0_BNDREGS | XCR0_BNDCSR),
+    (Feature::Avx512f, AVX512_STATE),
+    (Feature::Avx512dq, AVX512_STATE),
+    (Feature::Avx512Ifma, AVX512_STATE),
+    (Feature::Avx512pf, AVX512_STATE),
+    (Feature::Avx512er, AVX512_STATE),
+    (Feature::Avx512cd, AVX512_STATE),
+    (Feature::Avx512bw, AVX512_STATE),
+    (Feature::Avx512vl, AVX512_STATE),
+    (Feature::Avx512Vbmi, AVX512_STATE),
+    (Feature::Avx512Vbmi2, AVX512_STATE),
+    (Feature::Avx512Vnni, AVX512_STATE),
+    (Feature::Avx512Bitalg, AVX512_STATE),
+    (Feature::Avx512Vpopcntdq, AVX512_STATE),
+    (Feature::Avx512_4vnniw, AVX512_STATE),
+    (Feature::Avx512_4fmaps, AVX512_STATE),
+    (Feature::Avx512Vp2intersect, AVX512_STATE),
+    (Feature::Avx512Fp16, AVX512_STATE),
+    (Feature::Avx512Bf16, AVX512_STATE),
+    (Feature::Avx10, AVX512_STATE),
+    (Feature::AmxTile, XCR0_TILECFG | XCR0_TILEDATA),
+    (Feature::AmxInt8, XCR0_TILECFG | XCR0_TILEDATA),
+    (Feature::AmxBf16, XCR0_TILECFG | XCR0_TILEDATA),
+    (Feature::AmxFp16, XCR0_TILECFG | XCR0_TILEDATA),
+    (Feature::AmxComplex, XCR0_TILECFG | XCR0_TILEDATA),
+    (Feature::ApxF, XCR0_APX),
+];
+
+impl Master {
+    /// The features that may be executed given `xcr0`, the register
+    /// state the operating system has enabled, or `None` if it has not
+    /// enabled XSAVE: steps 1 and 2 of the module documentation.
+    pub fn enabled_features(&self, xcr0: Option<u64>) -> FeatureSet {
+        let enabled = xcr0.unwrap_or(0);
+        let mut features = FeatureSet::from(self);
+        for &(feature, state) in STATE {
+            if enabled & state != state {
+                features.remove(feature);
+            }
+        }
+        features
+    }
+
+    /// The features that may be executed by a binary compiled to
+    /// assume `statically_known`, as described in the module
+    /// documentation.
+    pub fn effective_features(&self, xcr0: Option<u64>, statically_known: &FeatureSet) -> FeatureSet {
+        self.enabled_features(xcr0).union(statically_known)
+    }
+}
+
+/// The features the running code may execute, as described in the
+/// module documentation, given the features it was compiled to assume.
+/// `effective_features!` passes those of the calling crate.
+pub fn effective_features(statically_known: &FeatureSet) -> FeatureSet {
+    match master() {
+        Some(info) => info.effective_features(xcr0(), statically_known),
+        None => *statically_known,
+    }
+}
+
+/// The features the calling crate may execute, as described in the
+/// [`effective`](effective/index.html) module, with the features it is
+/// compiled to assume from `statically_known!`.
+#[macro_export]
+macro_rules! effective_features {
+    () => {
+        $crate::effective::effective_features(&$crate::statically_known!())
+    };
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn effective_features_need_os_state_unless_assumed() {
+        let mut dump = genuine_intel_dump(7);
+        dump.insert(1, 0, (0, 0, (1 << 28) | (1 << 27), 1 << 26));
+        dump.insert(7, 0, (0, (1 << 5) | (1 << 16), 0, 0));
+        let info = Master::from_dump(&dump);
+
+        let avx_only = info.enabled_features(Some(0x7));
+        assert!(avx_only.contains(Feature::Avx2) && !avx_only.contains(Feature::Avx512f));
+        assert!(avx_only.contains(Feature::Sse2));
+
+        let no_xsave = info.enabled_features(None);
+        assert!(!no_xsave.contains(Feature::Avx) && no_xsave.contains(Feature::Sse2));
+
+        let assumed: FeatureSet = [Feature::Avx, Feature::Avx2].iter().cloned().collect();
+        let effective = info.effective_features(None, &assumed);
+        assert!(effective.contains(Feature::Avx2) && !effective.contains(Feature::Avx512f));
+        assert_eq!(info.enabled_features(Some(0xE7)), info.effective_features(Some(0xE7), &assumed));
+    }
+}
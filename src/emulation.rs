@@ -0,0 +1,115 @@
+//! Detecting a binary translator, which runs x86 code on another
+//! architecture and synthesizes the CPUID results.
+//!
+//! Under translation, CPUID describes a processor that does not exist:
+//! the features are those the translator implements, often slowly,
+//! and timings bear no relation to the host. Benchmarks and installers
+//! choosing a build for the machine should look past it.
+
+#[cfg(feature = "hypervisor")]
+use super::Hypervisor;
+use super::{master, Master};
+
+/// A known binary translator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Translator {
+    /// Apple's Rosetta 2, on Apple silicon Macs.
+    Rosetta,
+    /// QEMU's Tiny Code Generator, when QEMU runs without hardware
+    /// virtualization.
+    Tcg,
+}
+
+// Rosetta 2 reports a brand string of "VirtualApple @ 2.50GHz processor"
+const ROSETTA_BRAND: &str = "VirtualApple";
+
+impl Master {
+    /// The translator synthesizing the CPUID results, as far as they
+    /// reveal it. Rosetta 2 is only recognized by its brand string;
+    /// `is_emulated` also asks macOS.
+    pub fn translator(&self) -> Option<Translator> {
+        #[cfg(feature = "hypervisor")]
+        let tcg = self.hypervisor_information()
+            .is_some_and(|hi| hi.hypervisor() == Hypervisor::Tcg);
+        #[cfg(not(feature = "hypervisor"))]
+        let tcg = false;
+        if self.brand_string().is_some_and(|b| b.trim_start().starts_with(ROSETTA_BRAND)) {
+            Some(Translator::Rosetta)
+        } else if tcg {
+            Some(Translator::Tcg)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the CPUID results come from a binary translator rather
+    /// than the processor.
+    pub fn is_emulated(&self) -> bool {
+        self.translator().is_some()
+    }
+}
+
+/// Whether the running code is being translated from x86. On macOS,
+/// this also asks the kernel whether the process runs under Rosetta,
+/// which catches translators that disguise their CPUID results.
+pub fn is_emulated() -> bool {
+    process_translated() || master().is_some_and(|m| m.is_emulated())
+}
+
+#[cfg(target_os = "macos")]
+fn process_translated() -> bool {
+    extern "C" {
+        fn sysctlbyname(name: *const u8, oldp: *mut i32, oldlenp: *mut usize,
+                        newp: *const u8, newlen: usize) -> i32;
+    }
+
+    // 1 when translated, 0 when native, and absent before Rosetta 2
+    let mut translated = 0i32;
+    let mut size = ::core::mem::size_of::<i32>();
+    let found = unsafe {
+        sysctlbyname(b"sysctl.proc_translated\0".as_ptr(), &mut translated, &mut size,
+                     ::core::ptr::null(), 0)
+    };
+    found == 0 && translated == 1
+}
+
+#[cfg(not(target_os = "macos"))]
+fn process_translated() -> bool {
+    false
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn rosetta_and_tcg_are_recognized_as_translators() {
+        let mut dump = genuine_intel_dump(0xD);
+        dump.insert(1, 0, (0x0006_06A4, 0, 0x0000_0201, 0x0780_8141));
+        dump.insert(0x8000_0000, 0, (0x8000_0004, 0, 0, 0));
+        let brand = b"VirtualApple @ 2.50GHz processor\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0";
+        let words: std::vec::Vec<u32> = brand.chunks(4)
+            .map(|c| c[0] as u32 | (c[1] as u32) << 8 | (c[2] as u32) << 16 | (c[3] as u32) << 24)
+            .collect();
+        for (i, regs) in words.chunks(4).enumerate() {
+            dump.insert(0x8000_0002 + i as u32, 0, (regs[0], regs[1], regs[2], regs[3]));
+        }
+        let info = Master::from_dump(&dump);
+        assert_eq!(Some(Translator::Rosetta), info.translator());
+        assert!(info.is_emulated());
+
+        // QEMU without KVM, whose signature is only read with the
+        // `hypervisor` feature
+        dump.insert(0x8000_0002, 0, (0x554D_4551, 0x7269_5620, 0x6C61_7574, 0x5043_2020));
+        dump.insert(1, 0, (0x0006_06A4, 0, 0x8000_0201, 0x0780_8141));
+        dump.insert(0x4000_0000, 0, (0x4000_0001, 0x5447_4354, 0x4354_4743, 0x4743_5447));
+        let expected = if cfg!(feature = "hypervisor") { Some(Translator::Tcg) } else { None };
+        assert_eq!(expected, Master::from_dump(&dump).translator());
+
+        dump.insert(0x4000_0000, 0, (0x4000_0001, 0x4B4D_564B, 0x564B_4D56, 0x0000_004D));
+        assert!(!Master::from_dump(&dump).is_emulated());
+    }
+}
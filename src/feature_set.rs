@@ -0,0 +1,232 @@
+//! A set of features, such as those an application requires.
+
+use core::fmt;
+use core::iter::FromIterator;
+
+use super::{Feature, Master};
+
+const WORDS: usize = Feature::ALL.len().div_ceil(64);
+
+/// A set of `Feature`s, stored as a bitset.
+///
+/// ```
+/// use cupid::{Feature, FeatureSet};
+///
+/// let required: FeatureSet = [Feature::Sse4_2, Feature::Avx2].iter().cloned().collect();
+/// if let Some(info) = cupid::master() {
+///     let missing = required.difference(&FeatureSet::from(&info));
+///     for feature in missing.iter() {
+///         println!("missing {}", feature);
+///     }
+/// }
+/// ```
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FeatureSet {
+    words: [u64; WORDS],
+}
+
+impl FeatureSet {
+    pub fn new() -> FeatureSet {
+        FeatureSet::default()
+    }
+
+    /// Every feature the crate knows.
+    pub fn all() -> FeatureSet {
+        Feature::ALL.iter().cloned().collect()
+    }
+
+    /// Adds `feature`, returning whether it was absent.
+    pub fn insert(&mut self, feature: Feature) -> bool {
+        let present = self.contains(feature);
+        let (word, bit) = position(feature);
+        self.words[word] |= bit;
+        !present
+    }
+
+    /// Removes `feature`, returning whether it was present.
+    pub fn remove(&mut self, feature: Feature) -> bool {
+        let present = self.contains(feature);
+        let (word, bit) = position(feature);
+        self.words[word] &= !bit;
+        present
+    }
+
+    pub fn contains(&self, feature: Feature) -> bool {
+        let (word, bit) = position(feature);
+        self.words[word] & bit != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Whether every feature of `self` is also in `other`.
+    pub fn is_subset(&self, other: &FeatureSet) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    pub fn union(&self, other: &FeatureSet) -> FeatureSet {
+        self.combine(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &FeatureSet) -> FeatureSet {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// The features of `self` that are not in `other`.
+    pub fn difference(&self, other: &FeatureSet) -> FeatureSet {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    /// The features in the set, in the order of `Feature::ALL`.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = Feature> + 'a {
+        Feature::ALL.iter().cloned().filter(move |&f| self.contains(f))
+    }
+
+    fn combine<F>(&self, other: &FeatureSet, f: F) -> FeatureSet
+        where F: Fn(u64, u64) -> u64
+    {
+        let mut set = *self;
+        for (a, &b) in set.words.iter_mut().zip(&other.words) {
+            *a = f(*a, b);
+        }
+        set
+    }
+}
+
+fn position(feature: Feature) -> (usize, u64) {
+    let i = feature as usize;
+    (i / 64, 1 << (i % 64))
+}
+
+impl<'a> From<&'a Master> for FeatureSet {
+    /// The features the processor supports.
+    fn from(master: &'a Master) -> FeatureSet {
+        Feature::ALL.iter().cloned().filter(|&f| master.supports(f)).collect()
+    }
+}
+
+impl FromIterator<Feature> for FeatureSet {
+    fn from_iter<I: IntoIterator<Item = Feature>>(iter: I) -> FeatureSet {
+        let mut set = FeatureSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Feature> for FeatureSet {
+    fn extend<I: IntoIterator<Item = Feature>>(&mut self, iter: I) {
+        for feature in iter {
+            self.insert(feature);
+        }
+    }
+}
+
+impl fmt::Display for FeatureSet {
+    /// The feature names on one line, separated by commas, such as
+    /// "sse3, ssse3, sse4_1".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, feature) in self.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(feature.name())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for FeatureSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// The error from `Master::requires`, listing the required features
+/// that the processor lacks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MissingFeatures(pub(crate) FeatureSet);
+
+impl MissingFeatures {
+    pub fn features(&self) -> &FeatureSet {
+        &self.0
+    }
+}
+
+impl fmt::Display for MissingFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the processor does not support the required features: {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MissingFeatures {}
+
+impl Master {
+    /// Checks that every one of `features` is supported, such as at
+    /// startup by an application with minimum requirements.
+    ///
+    /// ```
+    /// use cupid::Feature;
+    ///
+    /// if let Some(info) = cupid::master() {
+    ///     if let Err(e) = info.requires(&[Feature::Avx2, Feature::Aesni]) {
+    ///         println!("{}", e);
+    ///     }
+    /// }
+    /// ```
+    pub fn requires(&self, features: &[Feature]) -> Result<(), MissingFeatures> {
+        let missing: FeatureSet = features.iter().cloned().filter(|&f| !self.supports(f)).collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(MissingFeatures(missing))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use genuine_intel_dump;
+
+    #[test]
+    fn feature_sets_combine() {
+        let a: FeatureSet = [Feature::Sse2, Feature::Avx].iter().cloned().collect();
+        let b: FeatureSet = [Feature::Avx, Feature::Rapl].iter().cloned().collect();
+
+        assert_eq!(3, a.union(&b).len());
+        assert_eq!(Some(Feature::Avx), a.intersection(&b).iter().next());
+        assert!(a.difference(&b).contains(Feature::Sse2));
+        assert!(!a.difference(&b).contains(Feature::Avx));
+        assert!(a.intersection(&b).is_subset(&a));
+        assert!(a.difference(&a).is_empty());
+        assert_eq!(Feature::ALL.len(), FeatureSet::all().len());
+
+        let mut c = a;
+        assert!(c.remove(Feature::Sse2));
+        assert!(!c.remove(Feature::Sse2));
+        assert!(c.insert(Feature::Rapl));
+        assert_eq!(Some(Feature::Rapl), c.iter().last());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn requires_lists_missing_features() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+        let info = Master::from_dump(&dump);
+
+        assert_eq!(Ok(()), info.requires(&[Feature::Sse2, Feature::Sse4_2]));
+        let missing = info.requires(&[Feature::Sse2, Feature::Avx2, Feature::Aesni]).unwrap_err();
+        assert_eq!(2, missing.features().len());
+        assert_eq!("the processor does not support the required features: aesni, avx2",
+                   format!("{}", missing));
+    }
+}
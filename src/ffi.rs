@@ -0,0 +1,271 @@
+//! A C interface to the decoded information.
+//!
+//! Every function queries the processor afresh, so callers wanting
+//! many values should fetch each structure once. The matching header
+//! is `include/cupid.h`; to link from C, build the crate as a static
+//! or dynamic library, e.g. `cargo rustc --features ffi --crate-type
+//! staticlib`.
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+#[cfg(feature = "caches")]
+use std::slice;
+
+#[cfg(feature = "caches")]
+use super::CacheType;
+use super::{master, Master};
+
+/// Leaf 1: the processor signature and the raw feature registers.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CupidFeatureInformation {
+    pub family_id: u32,
+    pub model_id: u32,
+    pub stepping: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+/// Leaf 7, sub-leaf 0: the raw structured extended feature registers.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CupidStructuredExtendedInformation {
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+/// Leaf 0x8000_0001: the raw extended feature registers.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CupidExtendedProcessorSignature {
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+pub const CUPID_CACHE_DATA: u32 = 1;
+pub const CUPID_CACHE_INSTRUCTION: u32 = 2;
+pub const CUPID_CACHE_UNIFIED: u32 = 3;
+
+/// One cache, with its size in bytes.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CupidCache {
+    pub level: u32,
+    /// One of the `CUPID_CACHE_*` constants, or zero if unknown.
+    pub cache_type: u32,
+    pub size: u64,
+    pub line_size: u32,
+    pub ways: u32,
+    pub sets: u32,
+    pub shared_by: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CupidAddressSizes {
+    pub physical_address_bits: u32,
+    pub linear_address_bits: u32,
+}
+
+// Copies `src` into `buf` as a NUL-terminated string, returning the
+// length of `src` so callers can detect truncation.
+unsafe fn copy_str(src: &str, buf: *mut c_char, len: usize) -> usize {
+    if !buf.is_null() && len > 0 {
+        let n = src.len().min(len - 1);
+        ptr::copy_nonoverlapping(src.as_ptr() as *const c_char, buf, n);
+        *buf.add(n) = 0;
+    }
+    src.len()
+}
+
+unsafe fn write<T>(out: *mut T, value: Option<T>) -> bool {
+    match value {
+        Some(value) if !out.is_null() => {
+            *out = value;
+            true
+        },
+        _ => false,
+    }
+}
+
+/// Writes the vendor identification into `buf`, returning its length
+/// (excluding the terminator), or zero when CPUID is unavailable.
+///
+/// # Safety
+///
+/// `buf` must be null or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cupid_vendor_id(buf: *mut c_char, len: usize) -> usize {
+    match master() {
+        Some(m) => copy_str(m.vendor_id(), buf, len),
+        None => 0,
+    }
+}
+
+/// Writes the brand string into `buf`, returning its length (excluding
+/// the terminator), or zero when none is reported.
+///
+/// # Safety
+///
+/// `buf` must be null or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cupid_brand_string(buf: *mut c_char, len: usize) -> usize {
+    match master().as_ref().and_then(Master::brand_string) {
+        Some(brand) => copy_str(brand, buf, len),
+        None => 0,
+    }
+}
+
+/// Fills `out` with leaf 1, returning false if it is unavailable.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cupid_feature_information(out: *mut CupidFeatureInformation) -> bool {
+    let info = master().and_then(|m| m.version_information).map(|vi| {
+        CupidFeatureInformation {
+            family_id: vi.family_id(),
+            model_id: vi.model_id(),
+            stepping: vi.stepping(),
+            ecx: vi.ecx,
+            edx: vi.edx,
+        }
+    });
+    write(out, info)
+}
+
+/// Fills `out` with leaf 7, returning false if it is unavailable.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cupid_structured_extended_information(out: *mut CupidStructuredExtendedInformation) -> bool {
+    let info = master().and_then(|m| m.structured_extended_information).map(|sei| {
+        CupidStructuredExtendedInformation { ebx: sei.ebx, ecx: sei.ecx, edx: sei.edx }
+    });
+    write(out, info)
+}
+
+/// Fills `out` with leaf 0x8000_0001, returning false if it is
+/// unavailable.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cupid_extended_processor_signature(out: *mut CupidExtendedProcessorSignature) -> bool {
+    let info = master().and_then(|m| m.extended_processor_signature).map(|eps| {
+        CupidExtendedProcessorSignature { ecx: eps.ecx, edx: eps.edx }
+    });
+    write(out, info)
+}
+
+/// Fills `out` with up to `len` caches, returning how many the
+/// processor reports.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes of `len` elements.
+#[cfg(feature = "caches")]
+#[no_mangle]
+pub unsafe extern "C" fn cupid_caches(out: *mut CupidCache, len: usize) -> usize {
+    let m = match master() {
+        Some(m) => m,
+        None => return 0,
+    };
+    let caches = m.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]);
+
+    if !out.is_null() {
+        let out = slice::from_raw_parts_mut(out, len);
+        for (o, cache) in out.iter_mut().zip(caches) {
+            *o = CupidCache {
+                level: cache.level(),
+                cache_type: match cache.cache_type() {
+                    Some(CacheType::Data) => CUPID_CACHE_DATA,
+                    Some(CacheType::Instruction) => CUPID_CACHE_INSTRUCTION,
+                    Some(CacheType::Unified) => CUPID_CACHE_UNIFIED,
+                    None => 0,
+                },
+                size: cache.size(),
+                line_size: cache.line_size(),
+                ways: cache.ways(),
+                sets: cache.sets(),
+                shared_by: cache.max_logical_processors_sharing(),
+            };
+        }
+    }
+    caches.len()
+}
+
+/// Without the `caches` feature no caches are decoded, so this always
+/// returns zero.
+///
+/// # Safety
+///
+/// Always safe to call; `out` is never written.
+#[cfg(not(feature = "caches"))]
+#[no_mangle]
+pub unsafe extern "C" fn cupid_caches(_out: *mut CupidCache, _len: usize) -> usize {
+    0
+}
+
+/// Fills `out` with the address sizes, returning false if they are
+/// unavailable.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cupid_address_sizes(out: *mut CupidAddressSizes) -> bool {
+    let sizes = master().and_then(|m| {
+        Some(CupidAddressSizes {
+            physical_address_bits: m.physical_address_size?.physical_address_bits(),
+            linear_address_bits: m.linear_address_bits()?,
+        })
+    });
+    write(out, sizes)
+}
+
+/// Whether the processor has the named feature, using the same names
+/// as the Rust API (e.g. "sse4_2"). Unknown names are not present.
+///
+/// # Safety
+///
+/// `name` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cupid_has_feature(name: *const c_char) -> bool {
+    if name.is_null() {
+        return false;
+    }
+    let name = match CStr::from_ptr(name).to_str() {
+        Ok(name) => name,
+        Err(_) => return false,
+    };
+    master().and_then(|m| m.has_feature(name)).unwrap_or(false)
+}
+
+#[cfg(all(test, any(target_arch = "x86_64", target_arch = "x86")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffi_reports_features_and_strings() {
+        use std::os::raw::c_char;
+
+        let mut buf = [0 as c_char; 8];
+        unsafe {
+            assert!(cupid_has_feature(b"sse2\0".as_ptr() as *const c_char));
+            assert!(!cupid_has_feature(b"no_such_feature\0".as_ptr() as *const c_char));
+
+            assert_eq!(12, cupid_vendor_id(buf.as_mut_ptr(), buf.len()));
+        }
+        let truncated: std::vec::Vec<u8> = buf.iter().map(|&c| c as u8).collect();
+        let info = master().unwrap();
+        assert_eq!(&info.vendor_id().as_bytes()[..7], &truncated[..7]);
+        assert_eq!(0, truncated[7]);
+    }
+}
@@ -0,0 +1,110 @@
+//! A stable identity for a processor model and configuration.
+//!
+//! The fingerprint covers the vendor, the signature (family, model and
+//! stepping), the feature bits of leaves 1, 7 and 0x8000_0001, and the
+//! layout of the caches. It leaves out whatever differs between cores
+//! or boots of the same machine, such as APIC IDs, logical processor
+//! counts and frequencies, so that identical machines share a
+//! fingerprint and a changed one does not. The caches are only covered
+//! with the `caches` feature, which is needed to query them.
+//!
+//! The registers are hashed with 64-bit FNV-1a, which is the same on
+//! every platform and in every release.
+
+use super::Master;
+
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+// The cache type, level and associativity bits of leaf 4's EAX; the
+// rest counts the logical processors sharing the cache
+#[cfg(feature = "caches")]
+const CACHE_DESCRIPTION_MASK: u32 = 0x3FFF;
+
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+impl Master {
+    /// A hash identifying the processor model and configuration, as
+    /// described in the [`fingerprint`](fingerprint/index.html) module.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash = Fnv1a(FNV_OFFSET_BASIS);
+        hash.write(self.vendor_id().as_bytes());
+
+        // Each leaf is preceded by its number, so that a missing leaf
+        // cannot be confused with one reporting zeros
+        if let Some(v) = self.version_information() {
+            hash.write_u32(0x1);
+            hash.write_u32(v.eax());
+            hash.write_u32(v.ecx());
+            hash.write_u32(v.edx());
+        }
+        if let Some(s) = self.structured_extended_information() {
+            hash.write_u32(0x7);
+            hash.write_u32(s.ebx());
+            hash.write_u32(s.ecx());
+            hash.write_u32(s.edx());
+        }
+        if let Some(s) = self.structured_extended_information_1() {
+            hash.write_u32(0x7_0001);
+            hash.write_u32(s.eax());
+            hash.write_u32(s.edx());
+        }
+        if let Some(e) = self.extended_processor_signature() {
+            hash.write_u32(0x8000_0001);
+            hash.write_u32(e.ecx());
+            hash.write_u32(e.edx());
+        }
+        #[cfg(feature = "caches")]
+        if let Some(p) = self.deterministic_cache_parameters() {
+            for cache in p.caches() {
+                hash.write_u32(0x4);
+                hash.write_u32(cache.eax() & CACHE_DESCRIPTION_MASK);
+                hash.write_u32(cache.ebx());
+                hash.write_u32(cache.ecx());
+                hash.write_u32(cache.edx());
+            }
+        }
+
+        hash.0
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use corpus;
+
+    #[test]
+    fn fingerprint_ignores_volatile_registers() {
+        let xeon = corpus::load("kvm-emerald-rapids").unwrap().unwrap();
+        let fingerprint = Master::from_dump(&xeon).fingerprint();
+        // The hash must not change between releases
+        #[cfg(feature = "caches")]
+        assert_eq!(0x7153_ABE1_A234_56DE, fingerprint);
+
+        let mut other_core = xeon.clone();
+        let (a, b, c, d) = xeon.get(1, 0).unwrap();
+        other_core.insert(1, 0, (a, b ^ 0x0F00_0000, c, d));
+        assert_eq!(fingerprint, Master::from_dump(&other_core).fingerprint());
+
+        let mut other_stepping = xeon.clone();
+        other_stepping.insert(1, 0, (a + 1, b, c, d));
+        assert_ne!(fingerprint, Master::from_dump(&other_stepping).fingerprint());
+
+        let fingerprints: std::collections::BTreeSet<_> =
+            corpus::all().map(|(_, dump)| Master::from_dump(&dump.unwrap()).fingerprint()).collect();
+        assert_eq!(corpus::DUMPS.len(), fingerprints.len());
+    }
+}
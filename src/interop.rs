@@ -0,0 +1,234 @@
+//! Conversions to and from the types of the `raw-cpuid` crate, for
+//! projects that use both crates or are moving from one to the other.
+//!
+//! A `raw-cpuid` reader can be decoded by cupid, and a `RawDump` can be
+//! decoded by `raw-cpuid`. Neither queries the processor again.
+//!
+//! The feature information of leaf 1, the cache parameters of leaf 4
+//! and the topology levels of leaf 0xB also convert one structure at a
+//! time. The decoded types of `raw-cpuid` cannot be built from register
+//! values, so cupid's are decoded by `raw-cpuid` from their registers,
+//! and theirs are encoded back into registers from what they report.
+//! What `raw-cpuid` does not report is lost on the way back: the
+//! processor type of leaf 1, and the SDBG and xTPR bits of its ECX.
+
+use raw_cpuid::{self, CpuId, CpuIdReader, CpuIdResult};
+
+use super::{CacheParameters, CpuidSource, Hypervisor, Registers, TopologyLevel, VersionInformation};
+#[cfg(feature = "std")]
+use super::RawDump;
+
+/// Reads from a `raw-cpuid` reader, such as one replaying recorded
+/// registers, so that `Master::from_source` can decode it.
+#[derive(Debug, Copy, Clone)]
+pub struct FromReader<R>(pub R);
+
+impl<R: CpuIdReader> CpuidSource for FromReader<R> {
+    fn cpuid(&self, leaf: u32, subleaf: u32) -> Registers {
+        let r = self.0.cpuid2(leaf, subleaf);
+        (r.eax, r.ebx, r.ecx, r.edx)
+    }
+}
+
+// Answers leaf 0 as a GenuineIntel processor whose highest leaf is
+// `leaf`, and sub-leaf 0 of `leaf` with `registers`, which is all that
+// `raw-cpuid` reads to decode a single structure
+#[derive(Copy, Clone)]
+struct Single {
+    leaf: u32,
+    registers: Registers,
+}
+
+impl CpuIdReader for Single {
+    fn cpuid2(&self, leaf: u32, subleaf: u32) -> CpuIdResult {
+        let (eax, ebx, ecx, edx) = match (leaf, subleaf) {
+            (0, _) => (self.leaf, 0x756E_6547, 0x6C65_746E, 0x4965_6E69),
+            (l, 0) if l == self.leaf => self.registers,
+            // Ends the sub-leaves of leaves 4 and 0xB after the first
+            _ => (0, 0, 0, 0),
+        };
+        CpuIdResult { eax, ebx, ecx, edx }
+    }
+}
+
+fn decode(leaf: u32, registers: Registers) -> CpuId<Single> {
+    CpuId::with_cpuid_reader(Single { leaf, registers })
+}
+
+// Sets each bit whose flag is set
+fn encode(flags: &[(u32, bool)]) -> u32 {
+    flags.iter().filter(|&&(_, set)| set).fold(0, |bits, &(bit, _)| bits | (1 << bit))
+}
+
+impl From<VersionInformation> for raw_cpuid::FeatureInfo {
+    fn from(vi: VersionInformation) -> raw_cpuid::FeatureInfo {
+        decode(1, (vi.eax, vi.ebx, vi.ecx, vi.edx)).get_feature_info()
+            .expect("leaf 1 is within the leaves reported")
+    }
+}
+
+impl From<raw_cpuid::FeatureInfo> for VersionInformation {
+    fn from(f: raw_cpuid::FeatureInfo) -> VersionInformation {
+        let eax = f.stepping_id() as u32 |
+            (f.base_model_id() as u32) << 4 |
+            (f.base_family_id() as u32) << 8 |
+            (f.extended_model_id() as u32) << 16 |
+            (f.extended_family_id() as u32) << 20;
+        let ebx = f.brand_index() as u32 |
+            (f.cflush_cache_line_size() as u32) << 8 |
+            (f.max_logical_processor_ids() as u32) << 16 |
+            (f.initial_local_apic_id() as u32) << 24;
+        let ecx = encode(&[
+            (0, f.has_sse3()), (1, f.has_pclmulqdq()), (2, f.has_ds_area()),
+            (3, f.has_monitor_mwait()), (4, f.has_cpl()), (5, f.has_vmx()),
+            (6, f.has_smx()), (7, f.has_eist()), (8, f.has_tm2()),
+            (9, f.has_ssse3()), (10, f.has_cnxtid()), (12, f.has_fma()),
+            (13, f.has_cmpxchg16b()), (15, f.has_pdcm()), (17, f.has_pcid()),
+            (18, f.has_dca()), (19, f.has_sse41()), (20, f.has_sse42()),
+            (21, f.has_x2apic()), (22, f.has_movbe()), (23, f.has_popcnt()),
+            (24, f.has_tsc_deadline()), (25, f.has_aesni()), (26, f.has_xsave()),
+            (27, f.has_oxsave()), (28, f.has_avx()), (29, f.has_f16c()),
+            (30, f.has_rdrand()), (31, f.has_hypervisor()),
+        ]);
+        let edx = encode(&[
+            (0, f.has_fpu()), (1, f.has_vme()), (2, f.has_de()),
+            (3, f.has_pse()), (4, f.has_tsc()), (5, f.has_msr()),
+            (6, f.has_pae()), (7, f.has_mce()), (8, f.has_cmpxchg8b()),
+            (9, f.has_apic()), (11, f.has_sysenter_sysexit()), (12, f.has_mtrr()),
+            (13, f.has_pge()), (14, f.has_mca()), (15, f.has_cmov()),
+            (16, f.has_pat()), (17, f.has_pse36()), (18, f.has_psn()),
+            (19, f.has_clflush()), (21, f.has_ds()), (22, f.has_acpi()),
+            (23, f.has_mmx()), (24, f.has_fxsave_fxstor()), (25, f.has_sse()),
+            (26, f.has_sse2()), (27, f.has_ss()), (28, f.has_htt()),
+            (29, f.has_tm()), (31, f.has_pbe()),
+        ]);
+        VersionInformation { eax, ebx, ecx, edx }
+    }
+}
+
+impl From<CacheParameters> for raw_cpuid::CacheParameter {
+    fn from(cache: CacheParameters) -> raw_cpuid::CacheParameter {
+        decode(4, (cache.eax, cache.ebx, cache.ecx, cache.edx)).get_cache_parameters()
+            .and_then(|mut caches| caches.next())
+            .expect("only caches of a known type are enumerated")
+    }
+}
+
+impl From<raw_cpuid::CacheParameter> for CacheParameters {
+    fn from(c: raw_cpuid::CacheParameter) -> CacheParameters {
+        // Counts are reported as one less than they are
+        let eax = c.cache_type() as u32 |
+            (c.level() as u32) << 5 |
+            (c.is_self_initializing() as u32) << 8 |
+            (c.is_fully_associative() as u32) << 9 |
+            (c.max_cores_for_cache() as u32 - 1) << 14 |
+            (c.max_cores_for_package() as u32 - 1) << 26;
+        let ebx = (c.coherency_line_size() as u32 - 1) |
+            (c.physical_line_partitions() as u32 - 1) << 12 |
+            (c.associativity() as u32 - 1) << 22;
+        let ecx = c.sets() as u32 - 1;
+        let edx = c.is_write_back_invalidate() as u32 |
+            (c.is_inclusive() as u32) << 1 |
+            (c.has_complex_indexing() as u32) << 2;
+        CacheParameters { eax, ebx, ecx, edx }
+    }
+}
+
+impl From<TopologyLevel> for raw_cpuid::ExtendedTopologyLevel {
+    fn from(level: TopologyLevel) -> raw_cpuid::ExtendedTopologyLevel {
+        decode(0xB, (level.eax, level.ebx, level.ecx, level.edx)).get_extended_topology_info()
+            .and_then(|mut levels| levels.next())
+            .expect("only levels of a known type are enumerated")
+    }
+}
+
+impl From<raw_cpuid::ExtendedTopologyLevel> for TopologyLevel {
+    fn from(l: raw_cpuid::ExtendedTopologyLevel) -> TopologyLevel {
+        TopologyLevel {
+            eax: l.shift_right_for_next_apic_id(),
+            ebx: l.processors() as u32,
+            ecx: l.level_number() as u32 | (l.level_type() as u32) << 8,
+            edx: l.x2apic_id(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl CpuIdReader for RawDump {
+    fn cpuid2(&self, leaf: u32, subleaf: u32) -> CpuIdResult {
+        let (eax, ebx, ecx, edx) = CpuidSource::cpuid(self, leaf, subleaf);
+        CpuIdResult { eax, ebx, ecx, edx }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<RawDump> for CpuId<RawDump> {
+    fn from(dump: RawDump) -> CpuId<RawDump> {
+        CpuId::with_cpuid_reader(dump)
+    }
+}
+
+impl From<raw_cpuid::Hypervisor> for Hypervisor {
+    fn from(hypervisor: raw_cpuid::Hypervisor) -> Hypervisor {
+        match hypervisor {
+            raw_cpuid::Hypervisor::Xen => Hypervisor::Xen,
+            raw_cpuid::Hypervisor::VMware => Hypervisor::VMware,
+            raw_cpuid::Hypervisor::HyperV => Hypervisor::HyperV,
+            raw_cpuid::Hypervisor::KVM => Hypervisor::Kvm,
+            raw_cpuid::Hypervisor::QEMU => Hypervisor::Tcg,
+            raw_cpuid::Hypervisor::Bhyve => Hypervisor::Bhyve,
+            raw_cpuid::Hypervisor::ACRN => Hypervisor::Acrn,
+            _ => Hypervisor::Unknown,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use {genuine_intel_dump, Master};
+
+    #[test]
+    fn raw_cpuid_reads_dumps_and_cupid_reads_raw_cpuid() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+
+        let cpuid = raw_cpuid::CpuId::from(dump.clone());
+        assert_eq!("GenuineIntel", cpuid.get_vendor_info().unwrap().as_str());
+        assert!(cpuid.get_feature_info().unwrap().has_sse42());
+
+        let info = Master::from_source(&FromReader(dump.clone()));
+        assert_eq!(Master::from_dump(&dump), info);
+    }
+
+    #[test]
+    fn raw_cpuid_structs_convert_both_ways() {
+        // An i7-8700K, without the bits raw-cpuid leaves out
+        let mut dump = genuine_intel_dump(0x16);
+        dump.insert(1, 0, (0x0009_06EA, 0x0C10_0800, 0x7FFA_B3BF, 0xBFEB_FBFF));
+        dump.insert(4, 0, (0x1C00_4121, 0x01C0_003F, 0x0000_003F, 0));
+        dump.insert(4, 1, (0x1C00_4143, 0x03C0_003F, 0x0000_2FFF, 6));
+        dump.insert(0xB, 0, (1, 2, 0x100, 8));
+        dump.insert(0xB, 1, (4, 12, 0x201, 8));
+        let info = Master::from_dump(&dump);
+
+        let vi = info.version_information().unwrap();
+        let feature_info = raw_cpuid::FeatureInfo::from(*vi);
+        assert_eq!((6, 0x9E, 0xA), (feature_info.family_id(), feature_info.model_id(), feature_info.stepping_id()));
+        assert!(feature_info.has_avx() && !feature_info.has_hypervisor());
+        assert_eq!(*vi, VersionInformation::from(feature_info));
+
+        for &cache in info.deterministic_cache_parameters().unwrap().caches() {
+            let raw = raw_cpuid::CacheParameter::from(cache);
+            assert_eq!((cache.level() as u8, cache.sets() as usize), (raw.level(), raw.sets()));
+            assert_eq!(cache, CacheParameters::from(raw));
+        }
+        assert_eq!(2, info.deterministic_cache_parameters().unwrap().caches().len());
+
+        for &level in info.extended_topology().unwrap().levels() {
+            let raw = raw_cpuid::ExtendedTopologyLevel::from(level);
+            assert_eq!(level.x2apic_id(), raw.x2apic_id());
+            assert_eq!(level, TopologyLevel::from(raw));
+        }
+    }
+}
@@ -0,0 +1,412 @@
+//! A JSON rendering of the processor description.
+//!
+//! The document has the following layout. Keys are never renamed or
+//! removed; values the processor does not report are `null`, and new
+//! features are only ever added to `features`.
+//!
+//! ```text
+//! {
+//!   "schema": 1,
+//!   "vendor": "GenuineIntel",
+//!   "brand": "Intel(R) Core(TM) i7-8700K CPU @ 3.70GHz",
+//!   "version": { "family": 6, "model": 158, "stepping": 10 },
+//!   "features": { "sse3": true, "pclmulqdq": true, ... },
+//!   "caches": [
+//!     { "level": 1, "type": "data", "size": 32768, "line_size": 64,
+//!       "ways": 8, "sets": 64, "shared_by": 2 },
+//!     ...
+//!   ],
+//!   "address_sizes": { "physical": 39, "linear": 48 }
+//! }
+//! ```
+//!
+//! Cache sizes are in bytes and `shared_by` is the maximum number of
+//! logical processors sharing the cache.
+//!
+//! `schema` is the version of the layout, `SCHEMA_VERSION`, and always
+//! comes first. It changes whenever a key is added outside `features`,
+//! so that documents from different releases can be told apart.
+
+use std::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+#[cfg(feature = "caches")]
+use super::CacheType;
+use super::Master;
+
+/// The version of the layout `Master::to_json` writes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Why `schema_version` or `Description::from_json` rejected a document.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SchemaError {
+    /// The document is not a JSON object starting with `schema`.
+    MissingSchema,
+    /// A version this release does not know, such as one written by a
+    /// later release.
+    UnknownVersion(u32),
+    /// The document is not valid JSON, or does not have the layout its
+    /// schema version describes.
+    Malformed,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SchemaError::MissingSchema => f.write_str("not a cupid JSON document: missing schema"),
+            SchemaError::UnknownVersion(v) => write!(f, "unknown JSON schema version {}", v),
+            SchemaError::Malformed => f.write_str("malformed cupid JSON document"),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// The schema version of a document written by `Master::to_json` of
+/// this or any earlier release.
+///
+/// ```
+/// use cupid::json::{schema_version, SchemaError};
+///
+/// assert_eq!(Ok(1), schema_version("{\"schema\":1,\"vendor\":\"AuthenticAMD\"}"));
+/// assert_eq!(Err(SchemaError::MissingSchema), schema_version("{\"vendor\":\"AuthenticAMD\"}"));
+/// assert_eq!(Err(SchemaError::UnknownVersion(9)), schema_version("{\"schema\":9}"));
+/// ```
+pub fn schema_version(document: &str) -> Result<u32, SchemaError> {
+    let value = document.trim_start()
+        .strip_prefix('{')
+        .and_then(|body| body.trim_start().strip_prefix("\"schema\""))
+        .and_then(|rest| rest.trim_start().strip_prefix(':'))
+        .map(str::trim_start)
+        .ok_or(SchemaError::MissingSchema)?;
+    let digits = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let version = value[..digits].parse().map_err(|_| SchemaError::MissingSchema)?;
+    if version == 0 || version > SCHEMA_VERSION {
+        return Err(SchemaError::UnknownVersion(version));
+    }
+    Ok(version)
+}
+
+impl Master {
+    /// Renders the processor description as a single JSON document.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str(&format!("{{\"schema\":{},\"vendor\":", SCHEMA_VERSION));
+        string(&mut out, self.vendor_id());
+
+        out.push_str(",\"brand\":");
+        match self.brand_string() {
+            Some(brand) => string(&mut out, brand),
+            None => out.push_str("null"),
+        }
+
+        out.push_str(",\"version\":");
+        match self.version_information() {
+            Some(vi) => {
+                out.push_str(&format!("{{\"family\":{},\"model\":{},\"stepping\":{}}}",
+                                      vi.family_id(), vi.model_id(), vi.stepping()));
+            },
+            None => out.push_str("null"),
+        }
+
+        out.push_str(",\"features\":{");
+        for (i, &(name, has)) in Master::FLAGS.iter().enumerate() {
+            if i != 0 {
+                out.push(',');
+            }
+            string(&mut out, name);
+            out.push_str(if has(self) { ":true" } else { ":false" });
+        }
+        out.push('}');
+
+        out.push_str(",\"caches\":[");
+        #[cfg(feature = "caches")]
+        {
+            let caches = self.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]);
+            for (i, cache) in caches.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                let cache_type = match cache.cache_type() {
+                    Some(CacheType::Data) => "\"data\"",
+                    Some(CacheType::Instruction) => "\"instruction\"",
+                    Some(CacheType::Unified) => "\"unified\"",
+                    None => "null",
+                };
+                out.push_str(&format!("{{\"level\":{},\"type\":{},\"size\":{},\"line_size\":{},\
+                                       \"ways\":{},\"sets\":{},\"shared_by\":{}}}",
+                                      cache.level(), cache_type, cache.size(), cache.line_size(),
+                                      cache.ways(), cache.sets(),
+                                      cache.max_logical_processors_sharing()));
+            }
+        }
+        out.push(']');
+
+        out.push_str(",\"address_sizes\":");
+        match (self.physical_address_size(), self.linear_address_bits()) {
+            (Some(pas), Some(linear)) => {
+                out.push_str(&format!("{{\"physical\":{},\"linear\":{}}}",
+                                      pas.physical_address_bits(), linear));
+            },
+            _ => out.push_str("null"),
+        }
+
+        out.push('}');
+        out
+    }
+}
+
+// A parsed JSON value, for reading documents back. Numbers keep their
+// text, as only the reader knows what type they should be.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match *self {
+            Value::Object(ref members) => members.iter().find(|m| m.0 == key).map(|m| &m.1),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Number(ref n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+// Deeper documents are rejected rather than risking the stack, as
+// documents may come from anywhere
+const MAX_DEPTH: usize = 32;
+
+pub(crate) fn parse(document: &str) -> Result<Value, SchemaError> {
+    let mut parser = Parser { bytes: document.as_bytes(), pos: 0 };
+    let value = parser.value(0)?;
+    parser.whitespace();
+    if parser.pos != parser.bytes.len() {
+        return Err(SchemaError::Malformed);
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Result<u8, SchemaError> {
+        let b = self.peek().ok_or(SchemaError::Malformed)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn literal(&mut self, text: &str, value: Value) -> Result<Value, SchemaError> {
+        if self.bytes[self.pos..].starts_with(text.as_bytes()) {
+            self.pos += text.len();
+            Ok(value)
+        } else {
+            Err(SchemaError::Malformed)
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, SchemaError> {
+        if depth > MAX_DEPTH {
+            return Err(SchemaError::Malformed);
+        }
+        self.whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                }
+                loop {
+                    self.whitespace();
+                    if self.next()? != b'"' {
+                        return Err(SchemaError::Malformed);
+                    }
+                    let key = self.string()?;
+                    self.whitespace();
+                    if self.next()? != b':' {
+                        return Err(SchemaError::Malformed);
+                    }
+                    members.push((key, self.value(depth + 1)?));
+                    self.whitespace();
+                    match self.next()? {
+                        b',' => {},
+                        b'}' => return Ok(Value::Object(members)),
+                        _ => return Err(SchemaError::Malformed),
+                    }
+                }
+            },
+            Some(b'[') => {
+                self.pos += 1;
+                let mut elements = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Value::Array(elements));
+                }
+                loop {
+                    elements.push(self.value(depth + 1)?);
+                    self.whitespace();
+                    match self.next()? {
+                        b',' => {},
+                        b']' => return Ok(Value::Array(elements)),
+                        _ => return Err(SchemaError::Malformed),
+                    }
+                }
+            },
+            Some(b'"') => {
+                self.pos += 1;
+                self.string().map(Value::String)
+            },
+            Some(b'-') | Some(b'0'..=b'9') => {
+                let start = self.pos;
+                while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E') | Some(b'0'..=b'9') = self.peek() {
+                    self.pos += 1;
+                }
+                let text = String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned();
+                Ok(Value::Number(text))
+            },
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            _ => Err(SchemaError::Malformed),
+        }
+    }
+
+    // The rest of a string whose opening quote has been read
+    fn string(&mut self) -> Result<String, SchemaError> {
+        let mut out = Vec::new();
+        loop {
+            match self.next()? {
+                b'"' => return String::from_utf8(out).map_err(|_| SchemaError::Malformed),
+                b'\\' => {
+                    let escaped = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let high = self.hex4()?;
+                            let code = if (0xD800..0xDC00).contains(&high) {
+                                if self.next()? != b'\\' || self.next()? != b'u' {
+                                    return Err(SchemaError::Malformed);
+                                }
+                                let low = self.hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(SchemaError::Malformed);
+                                }
+                                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                            } else {
+                                high
+                            };
+                            ::core::char::from_u32(code).ok_or(SchemaError::Malformed)?
+                        },
+                        _ => return Err(SchemaError::Malformed),
+                    };
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                },
+                b if b < 0x20 => return Err(SchemaError::Malformed),
+                b => out.push(b),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, SchemaError> {
+        let digits = self.bytes.get(self.pos..self.pos + 4).ok_or(SchemaError::Malformed)?;
+        let mut code = 0;
+        for &d in digits {
+            code = code << 4 | (d as char).to_digit(16).ok_or(SchemaError::Malformed)?;
+        }
+        self.pos += 4;
+        Ok(code)
+    }
+}
+
+// TOML basic strings and YAML double-quoted scalars escape the same way
+pub(crate) fn string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn json_lists_every_flag() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0009_06EA, 0, 0, 1 << 26));
+        let json = Master::from_dump(&dump).to_json();
+
+        assert!(json.starts_with("{\"schema\":1,\"vendor\":\"GenuineIntel\""));
+        assert_eq!(Ok(SCHEMA_VERSION), schema_version(&json));
+        assert!(json.contains("\"sse2\":true"));
+        assert_eq!(json.matches(":true").count() + json.matches(":false").count(),
+                   Master::FLAGS.len());
+    }
+
+    #[test]
+    fn parse_reads_back_any_json() {
+        let value = parse(" {\"a\": [1, -2.5e3, null, true], \"b\": \"\\u00e9\\ud83d\\ude00\\n\\\"\"} ").unwrap();
+        assert_eq!(Some(&Value::Array(vec![Value::Number("1".into()), Value::Number("-2.5e3".into()),
+                                            Value::Null, Value::Bool(true)])),
+                   value.get("a"));
+        assert_eq!(Some("\u{e9}\u{1F600}\n\""), value.get("b").and_then(Value::as_str));
+
+        for bad in &["", "{", "{\"a\" 1}", "[1,]", "\"\\ud83d\"", "\"\\x\"", "nul", "{} {}"] {
+            assert_eq!(Err(SchemaError::Malformed), parse(bad), "{}", bad);
+        }
+        let deep = "[".repeat(MAX_DEPTH + 2) + &"]".repeat(MAX_DEPTH + 2);
+        assert_eq!(Err(SchemaError::Malformed), parse(&deep));
+    }
+}
@@ -0,0 +1,214 @@
+//! CPUID tables for KVM's `KVM_SET_CPUID2` ioctl, for virtual machine
+//! monitors that expose a recorded or live processor to their guests.
+//!
+//! KVM takes a `struct kvm_cpuid2`, a count followed by a flat array of
+//! `struct kvm_cpuid_entry2`, one per (leaf, sub-leaf) pair. Leaves
+//! whose output depends on the sub-leaf in ECX must be marked with
+//! `KVM_CPUID_FLAG_SIGNIFCANT_INDEX`, or KVM answers every sub-leaf
+//! with the first.
+//!
+//! Masking by a baseline `Profile` clears the instruction set
+//! extensions the profile lacks, so that guests started on different
+//! hosts see the same processor and can migrate between them. As with
+//! `Master::compare_to_profile`, only the features some profile
+//! mentions are masked; platform features are left as recorded.
+//!
+//! Every other field is copied verbatim, including those that differ
+//! from one processor to the next: the initial APIC ID in leaf 1 EBX,
+//! the x2APIC ID in EDX of leaves 0xB and 0x1F, and AMD's extended APIC
+//! ID in leaf 0x8000_001E EAX. KVM does not rewrite them, so a monitor
+//! should set each vCPU's own with `set_apic_id` before passing it the
+//! table.
+//!
+//! ```no_run
+//! use cupid::kvm::KvmCpuid2;
+//! use cupid::{Profile, RawDump};
+//!
+//! let mut table = KvmCpuid2::new(&RawDump::capture(), Profile::named("Haswell"));
+//! table.set_apic_id(0);
+//! let bytes = table.to_bytes();
+//! // ioctl(vcpu_fd, KVM_SET_CPUID2, bytes.as_ptr())
+//! ```
+
+use std::vec::Vec;
+
+use super::{FeatureSet, Profile, RawDump};
+use super::profile::profiled;
+
+/// `KVM_CPUID_FLAG_SIGNIFCANT_INDEX`, spelt as in the kernel headers:
+/// the entry applies only to its own sub-leaf.
+pub const KVM_CPUID_FLAG_SIGNIFCANT_INDEX: u32 = 1 << 0;
+
+/// `KVM_MAX_CPUID_ENTRIES`, the most entries KVM accepts in one table.
+pub const KVM_MAX_CPUID_ENTRIES: usize = 256;
+
+// The leaves KVM treats as indexed, as in the kernel's
+// `cpuid_function_is_indexed`
+const INDEXED: &[u32] = &[
+    0x4, 0x7, 0xB, 0xD, 0xF, 0x10, 0x12, 0x14, 0x17, 0x18, 0x1D, 0x1E, 0x1F, 0x24,
+    0x8000_001D,
+];
+
+/// `struct kvm_cpuid_entry2`, the registers KVM returns for one
+/// (leaf, sub-leaf) pair, laid out as the kernel expects.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KvmCpuidEntry2 {
+    /// The leaf, in EAX.
+    pub function: u32,
+    /// The sub-leaf, in ECX.
+    pub index: u32,
+    pub flags: u32,
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+    pub padding: [u32; 3],
+}
+
+/// The entries of a `struct kvm_cpuid2`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KvmCpuid2 {
+    entries: Vec<KvmCpuidEntry2>,
+}
+
+impl KvmCpuid2 {
+    /// A table of every leaf in `dump`, with the features `baseline`
+    /// lacks cleared as described in the module documentation.
+    ///
+    /// The host's hypervisor leaves, present when it is itself a guest,
+    /// are left out, as the monitor describes itself there.
+    pub fn new(dump: &RawDump, baseline: Option<&Profile>) -> KvmCpuid2 {
+        let masked = match baseline {
+            Some(profile) => profiled().difference(&profile.features()),
+            None => FeatureSet::new(),
+        };
+
+        let entries = dump.entries().iter()
+            .filter(|e| e.leaf & 0xF000_0000 != 0x4000_0000)
+            .map(|e| {
+                let indexed = INDEXED.contains(&e.leaf) ||
+                    dump.entries().iter().any(|o| o.leaf == e.leaf && o.subleaf != 0);
+                let mut registers = [e.eax, e.ebx, e.ecx, e.edx];
+                for (leaf, subleaf, register, bit) in masked.iter().filter_map(|f| f.location()) {
+                    if leaf == e.leaf && subleaf == e.subleaf {
+                        registers[register as usize] &= !(1 << bit);
+                    }
+                }
+                KvmCpuidEntry2 {
+                    function: e.leaf,
+                    index: if indexed { e.subleaf } else { 0 },
+                    flags: if indexed { KVM_CPUID_FLAG_SIGNIFCANT_INDEX } else { 0 },
+                    eax: registers[0],
+                    ebx: registers[1],
+                    ecx: registers[2],
+                    edx: registers[3],
+                    padding: [0; 3],
+                }
+            })
+            .collect();
+
+        KvmCpuid2 { entries }
+    }
+
+    /// The entries, in the order `dump` recorded them. KVM rejects
+    /// tables of more than `KVM_MAX_CPUID_ENTRIES`.
+    pub fn entries(&self) -> &[KvmCpuidEntry2] {
+        &self.entries
+    }
+
+    /// Sets the APIC IDs that CPUID reports to those of one vCPU, in
+    /// leaf 1, leaves 0xB and 0x1F, and leaf 0x8000_001E. Leaf 1 holds
+    /// only the low eight bits.
+    pub fn set_apic_id(&mut self, apic_id: u32) {
+        for e in &mut self.entries {
+            match e.function {
+                0x1 => e.ebx = (e.ebx & 0x00FF_FFFF) | (apic_id & 0xFF) << 24,
+                0xB | 0x1F => e.edx = apic_id,
+                0x8000_001E => e.eax = apic_id,
+                _ => {},
+            }
+        }
+    }
+
+    /// The table as a `struct kvm_cpuid2`, in the host's byte order,
+    /// ready to pass to the ioctl.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.entries.len() * 40);
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_ne_bytes());
+        bytes.extend_from_slice(&0u32.to_ne_bytes());
+        for e in &self.entries {
+            let words = [e.function, e.index, e.flags, e.eax, e.ebx, e.ecx, e.edx,
+                         e.padding[0], e.padding[1], e.padding[2]];
+            for word in &words {
+                bytes.extend_from_slice(&word.to_ne_bytes());
+            }
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {genuine_intel_dump, Feature, Master, Register};
+
+    #[test]
+    fn kvm_cpuid2_masks_to_a_profile() {
+        let all = !0;
+        let mut dump = genuine_intel_dump(0xD);
+        dump.insert(1, 0, (0x0009_06EA, 0, all, all));
+        dump.insert(7, 0, (1, all, all, all));
+        dump.insert(7, 1, (all, 0, 0, 0));
+        dump.insert(0xD, 0, (7, 0, 0, 0));
+        dump.insert(0xD, 1, (all, 0, 0, 0));
+        dump.insert(0x4000_0000, 0, (0x4000_0001, 0x4B4D_564B, 0x564B_4D56, 0x4D));
+        dump.insert(0x8000_0000, 0, (0x8000_0008, 0, 0, 0));
+        dump.insert(0x8000_0001, 0, (0, 0, all, all));
+        dump.insert(0x8000_0008, 0, (0x3027, all, 0, 0));
+
+        let table = KvmCpuid2::new(&dump, None);
+        assert_eq!(dump.entries().len() - 1, table.entries().len());
+        assert!(table.entries().iter().all(|e| e.function != 0x4000_0000));
+        let seven_one = table.entries().iter().find(|e| e.function == 7 && e.index == 1).unwrap();
+        assert_eq!(KVM_CPUID_FLAG_SIGNIFCANT_INDEX, seven_one.flags);
+        assert_eq!(0, table.entries()[1].flags);
+
+        let bytes = table.to_bytes();
+        assert_eq!(8 + 40 * table.entries().len(), bytes.len());
+        assert_eq!(table.entries().len() as u32, u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+
+        let baseline = Profile::named("x86-64").unwrap();
+        let mut masked = RawDump::new();
+        for e in KvmCpuid2::new(&dump, Some(baseline)).entries() {
+            masked.insert(e.function, e.index, (e.eax, e.ebx, e.ecx, e.edx));
+        }
+        let host = Master::from_dump(&dump);
+        let guest = Master::from_dump(&masked);
+        assert!(host.supports(Feature::Avx512f));
+        assert_eq!(Some(0), guest.compare_to_profile("x86-64").map(|c| c.extra.len()));
+        assert!(guest.compare_to_profile("x86-64").unwrap().meets_baseline());
+        assert!(guest.supports(Feature::Hypervisor));
+        assert!(guest.supports(Feature::Vmx));
+        assert_eq!(Some((0x7, 0, Register::Ebx, 16)), Feature::Avx512f.location());
+        assert_eq!(Some((0xD, 1, Register::Eax, 3)), Feature::Xsaves.location());
+        // Leaves left out by a Cargo feature have no location
+        #[cfg(all(feature = "amd", feature = "hypervisor"))]
+        assert!(Feature::ALL.iter().all(|&f| f.location().is_some() == (f != Feature::Mktme)));
+    }
+
+    #[test]
+    fn kvm_cpuid2_sets_apic_ids_per_vcpu() {
+        let mut dump = genuine_intel_dump(0xB);
+        dump.insert(1, 0, (0x0009_06EA, 0x0310_0800, 0, 0));
+        dump.insert(0xB, 0, (1, 2, 0x100, 3));
+        dump.insert(0xB, 1, (4, 12, 0x201, 3));
+
+        let mut table = KvmCpuid2::new(&dump, None);
+        table.set_apic_id(0x105);
+        let ids: std::vec::Vec<_> = table.entries().iter().map(|e| (e.function, e.ebx, e.edx)).collect();
+        assert_eq!(vec![(0, 0x756E_6547, 0x4965_6E69), (1, 0x0510_0800, 0), (0xB, 2, 0x105), (0xB, 12, 0x105)], ids);
+    }
+}
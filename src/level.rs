@@ -0,0 +1,119 @@
+//! The x86-64 microarchitecture levels of the psABI.
+
+use core::fmt;
+
+use super::{Feature, Master};
+
+/// A microarchitecture level, the baseline feature sets that the x86-64
+/// psABI defines for targeting groups of processors. Each level
+/// includes those below it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum MicroarchLevel {
+    V1,
+    V2,
+    V3,
+    V4,
+}
+
+impl MicroarchLevel {
+    pub const ALL: &'static [MicroarchLevel] = &[
+        MicroarchLevel::V1,
+        MicroarchLevel::V2,
+        MicroarchLevel::V3,
+        MicroarchLevel::V4,
+    ];
+
+    /// The features this level adds to the one below it.
+    pub const fn features(self) -> &'static [Feature] {
+        match self {
+            MicroarchLevel::V1 => &[
+                Feature::Intel64BitArchitecture,
+                Feature::Cmov,
+                Feature::Cx8,
+                Feature::Fpu,
+                Feature::Fxsr,
+                Feature::Mmx,
+                Feature::SyscallSysretIn64Bit,
+                Feature::Sse,
+                Feature::Sse2,
+            ],
+            MicroarchLevel::V2 => &[
+                Feature::Cmpxchg16b,
+                Feature::LahfSahfIn64Bit,
+                Feature::Popcnt,
+                Feature::Sse3,
+                Feature::Sse4_1,
+                Feature::Sse4_2,
+                Feature::Ssse3,
+            ],
+            MicroarchLevel::V3 => &[
+                Feature::Avx,
+                Feature::Avx2,
+                Feature::Bmi1,
+                Feature::Bmi2,
+                Feature::F16c,
+                Feature::Fma,
+                Feature::Lzcnt,
+                Feature::Movbe,
+                Feature::Osxsave,
+            ],
+            MicroarchLevel::V4 => &[
+                Feature::Avx512f,
+                Feature::Avx512bw,
+                Feature::Avx512cd,
+                Feature::Avx512dq,
+                Feature::Avx512vl,
+            ],
+        }
+    }
+}
+
+impl fmt::Display for MicroarchLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let level = match *self {
+            MicroarchLevel::V1 => "x86-64",
+            MicroarchLevel::V2 => "x86-64-v2",
+            MicroarchLevel::V3 => "x86-64-v3",
+            MicroarchLevel::V4 => "x86-64-v4",
+        };
+        f.write_str(level)
+    }
+}
+
+impl Master {
+    /// The highest microarchitecture level whose features are all
+    /// supported, or `None` if even the x86-64 baseline is not. Only the
+    /// CPUID bits are checked, not whether the operating system has
+    /// enabled the AVX and AVX-512 register state.
+    pub fn microarch_level(&self) -> Option<MicroarchLevel> {
+        MicroarchLevel::ALL.iter()
+            .cloned()
+            .take_while(|level| level.features().iter().all(|&f| self.supports(f)))
+            .last()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn microarch_level_requires_every_feature() {
+        // Nehalem: SSE4.2 and POPCNT, but no AVX
+        let mut dump = genuine_intel_dump(0xB);
+        dump.insert(1, 0, (0x0001_06A5, 0, 0x0098_E3BD, 0xBFEB_FBFF));
+        dump.insert(0x8000_0000, 0, (0x8000_0008, 0, 0, 0));
+        dump.insert(0x8000_0001, 0, (0, 0, 0x0000_0001, 0x2810_0800));
+        assert_eq!(Some(MicroarchLevel::V2), Master::from_dump(&dump).microarch_level());
+
+        dump.insert(0x8000_0001, 0, (0, 0, 0, 0x2810_0800));
+        assert_eq!(Some(MicroarchLevel::V1), Master::from_dump(&dump).microarch_level());
+
+        dump.insert(0x8000_0001, 0, (0, 0, 0, 0));
+        assert_eq!(None, Master::from_dump(&dump).microarch_level());
+        assert_eq!("x86-64-v3", format!("{}", MicroarchLevel::V3));
+    }
+}
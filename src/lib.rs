@@ -1,4 +1,4 @@
-#![feature(asm)]
+#![no_std]
 
 //! ```
 //! extern crate cupid;
@@ -14,913 +14,7599 @@
 //! }
 //! ```
 
-use std::{fmt, slice, str};
-use std::ops::Deref;
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate std;
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
+#[cfg(feature = "raw-cpuid")]
+extern crate raw_cpuid;
+
+#[cfg(feature = "python")]
+extern crate pyo3;
+
+#[cfg(feature = "tracing")]
+extern crate tracing;
+
+use core::{array, fmt, hash, str};
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), not(target_env = "msvc")))]
+use core::arch::asm;
+use core::cell::Cell;
+use core::ops::Deref;
+
+// The query and decode paths must never panic, as they are used in
+// contexts where unwinding is not an option. Optimized test builds
+// fail to link if the compiler cannot prove that.
+#[cfg(all(test, not(debug_assertions)))]
+extern crate no_panic;
+#[cfg(all(test, not(debug_assertions)))]
+use no_panic::no_panic;
+
+#[repr(u32)]
 enum RequestType {
     BasicInformation                  = 0x00000000,
     VersionInformation                = 0x00000001,
+    ProcessorSerialNumber             = 0x00000003,
+    #[cfg(feature = "caches")]
+    DeterministicCacheParameters      = 0x00000004,
     ThermalPowerManagementInformation = 0x00000006,
     StructuredExtendedInformation     = 0x00000007,
+    PerformanceMonitoring             = 0x0000000A,
+    ExtendedTopology                  = 0x0000000B,
+    ExtendedState                     = 0x0000000D,
+    RdtMonitoring                     = 0x0000000F,
+    RdtAllocation                     = 0x00000010,
+    TscFrequency                      = 0x00000015,
+    ProcessorFrequency                = 0x00000016,
+    KeyLocker                         = 0x00000019,
+    NativeModelIdentification         = 0x0000001A,
+    Pconfig                           = 0x0000001B,
+    LastBranchRecords                 = 0x0000001C,
+    TileInformation                   = 0x0000001D,
+    TmulInformation                   = 0x0000001E,
+    V2ExtendedTopology                = 0x0000001F,
+    TdxGuest                          = 0x00000021,
+    HypervisorInformation             = 0x40000000,
     ExtendedFunctionInformation       = 0x80000000,
     ExtendedProcessorSignature        = 0x80000001,
     BrandString1                      = 0x80000002,
     BrandString2                      = 0x80000003,
     BrandString3                      = 0x80000004,
-    // reserved                       = 0x80000005,
+    #[cfg(feature = "caches")]
+    L1CacheAndTlb                     = 0x80000005,
+    #[cfg(feature = "caches")]
     CacheLine                         = 0x80000006,
     TimeStampCounter                  = 0x80000007,
     PhysicalAddressSize               = 0x80000008,
+    #[cfg(feature = "amd")]
+    SvmFeatures                       = 0x8000000A,
+    #[cfg(feature = "amd")]
+    GigabytePageTlb                   = 0x80000019,
+    #[cfg(feature = "amd")]
+    PerformanceOptimization           = 0x8000001A,
+    #[cfg(feature = "amd")]
+    InstructionBasedSampling          = 0x8000001B,
+    #[cfg(all(feature = "caches", feature = "amd"))]
+    CacheProperties                   = 0x8000001D,
+    #[cfg(feature = "amd")]
+    ProcessorTopology                 = 0x8000001E,
+    #[cfg(feature = "amd")]
+    EncryptedMemory                   = 0x8000001F,
+    #[cfg(feature = "amd")]
+    ExtendedFeatureIdentification2    = 0x80000021,
 }
 
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+#[cfg_attr(all(test, not(debug_assertions), not(feature = "testing")), no_panic)]
 fn cpuid(code: RequestType) -> (u32, u32, u32, u32) {
+    query(code as u32, 0)
+}
+
+// Every query goes through here, so that the `testing` feature can
+// stand a recorded processor in for the real one
+#[cfg(feature = "testing")]
+fn query(leaf: u32, subleaf: u32) -> (u32, u32, u32, u32) {
+    match testing::fake_dump() {
+        Some(dump) => dump.cpuid(leaf, subleaf),
+        None => cpuid_count(leaf, subleaf),
+    }
+}
+
+#[cfg(not(feature = "testing"))]
+#[inline]
+fn query(leaf: u32, subleaf: u32) -> (u32, u32, u32, u32) {
+    cpuid_count(leaf, subleaf)
+}
+
+#[cfg(all(target_arch = "x86_64", not(target_env = "msvc")))]
+#[cfg_attr(all(test, not(debug_assertions)), no_panic)]
+fn cpuid_count(leaf: u32, subleaf: u32) -> (u32, u32, u32, u32) {
     let res1;
     let res2;
     let res3;
     let res4;
 
+    // LLVM reserves RBX, so CPUID's EBX is swapped out through another
+    // register instead, leaving RBX as it was.
     unsafe {
-        asm!("cpuid"
-             : // output operands
-             "={eax}"(res1),
-             "={ebx}"(res2),
-             "={ecx}"(res3),
-             "={edx}"(res4)
-             : // input operands
-             "{eax}"(code as u32),
-             "{ecx}"(0 as u32)
-             : // clobbers
-             : // options
-        );
+        asm!("mov {0:r}, rbx",
+             "cpuid",
+             "xchg {0:r}, rbx",
+             out(reg) res2,
+             inout("eax") leaf => res1,
+             inout("ecx") subleaf => res3,
+             out("edx") res4,
+             options(nostack, preserves_flags));
     }
 
     (res1, res2, res3, res4)
 }
 
-// This matches the Intel Architecture guide, with bits 31 -> 0.
-// The bit positions are inclusive.
-fn bits_of(val: u32, start_bit: u8, end_bit: u8) -> u32 {
-    let mut silly = 0;
+// Position-independent 32-bit code keeps the GOT pointer in EBX, so
+// it cannot be named as an output. CPUID's EBX is swapped out through
+// another register instead, leaving EBX as it was.
+#[cfg(all(target_arch = "x86", not(target_env = "msvc")))]
+#[cfg_attr(all(test, not(debug_assertions)), no_panic)]
+fn cpuid_count(leaf: u32, subleaf: u32) -> (u32, u32, u32, u32) {
+    let res1;
+    let res2;
+    let res3;
+    let res4;
 
-    for _ in start_bit..end_bit+1 {
-        silly <<= 1;
-        silly |= 1;
+    unsafe {
+        asm!("mov {0}, ebx",
+             "cpuid",
+             "xchg {0}, ebx",
+             out(reg) res2,
+             inout("eax") leaf => res1,
+             inout("ecx") subleaf => res3,
+             out("edx") res4,
+             options(nostack, preserves_flags));
     }
 
-    (val >> start_bit) & silly
+    (res1, res2, res3, res4)
 }
 
-fn as_bytes(v: &u32) -> &[u8] {
-    let start = v as *const u32 as *const u8;
-    // TODO: use u32::BYTES
-    unsafe { slice::from_raw_parts(start, 4) }
-}
+// Reads an extended control register. Faults unless OSXSAVE is set.
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), not(target_env = "msvc")))]
+fn xgetbv(xcr: u32) -> u64 {
+    let low: u32;
+    let high: u32;
 
-macro_rules! bit {
-    ($reg:ident, {$($idx:expr => $name:ident),+}) => {
-        $(pub fn $name(self) -> bool {
-            ((self.$reg >> $idx) & 1) != 0
-        })+
+    unsafe {
+        asm!("xgetbv",
+             in("ecx") xcr,
+             out("eax") low,
+             out("edx") high,
+             options(nomem, nostack, preserves_flags));
     }
+
+    (high as u64) << 32 | low as u64
 }
 
-macro_rules! dump {
-    ($me:expr, $f: expr, $sname:expr, {$($name:ident),+}) => {
-        $f.debug_struct($sname)
-            $(.field(stringify!($name), &$me.$name()))+
-            .finish()
+// The ID flag (bit 21) of EFLAGS can only be toggled on processors
+// with CPUID, which some i486 and earlier processors lack.
+#[cfg(all(target_arch = "x86", not(target_env = "msvc")))]
+fn has_cpuid() -> bool {
+    let original: u32;
+    let toggled: u32;
+
+    unsafe {
+        asm!("pushfd",
+             "pop {original}",
+             "mov {toggled}, {original}",
+             "xor {toggled}, 0x200000",
+             "push {toggled}",
+             "popfd",
+             "pushfd",
+             "pop {toggled}",
+             "push {original}",
+             "popfd",
+             original = out(reg) original,
+             toggled = out(reg) toggled);
     }
+
+    (original ^ toggled) & (1 << 21) != 0
 }
 
-macro_rules! delegate_flag {
-    ($item:ident, {$($name:ident),+}) => {
-        $(pub fn $name(self) -> bool {
-            self.$item.map(|i| i.$name()).unwrap_or(false)
-        })+
-    }
+// Every x86_64 processor has CPUID.
+#[cfg(target_arch = "x86_64")]
+fn has_cpuid() -> bool {
+    true
 }
 
-macro_rules! master_attr_reader {
-    ($name:ident, $kind:ty) => {
-        pub fn $name(&self) -> Option<&$kind> {
-            self.$name.as_ref()
+#[cfg(all(target_env = "msvc", any(target_arch = "x86_64", target_arch = "x86")))]
+use msvc::{cpuid_count, xgetbv};
+#[cfg(all(target_env = "msvc", target_arch = "x86"))]
+use msvc::has_cpuid;
+
+// Other architectures have no CPUID; every leaf reads as unsupported.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+fn cpuid_count(_leaf: u32, _subleaf: u32) -> (u32, u32, u32, u32) {
+    (0, 0, 0, 0)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+fn has_cpuid() -> bool {
+    false
+}
+
+/// The values of EAX, EBX, ECX and EDX, in that order, after CPUID.
+pub type Registers = (u32, u32, u32, u32);
+
+/// One of the registers CPUID reports in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Register {
+    Eax,
+    Ebx,
+    Ecx,
+    Edx,
+}
+
+impl Register {
+    /// This register's value among `registers`.
+    pub fn of(self, registers: Registers) -> u32 {
+        match self {
+            Register::Eax => registers.0,
+            Register::Ebx => registers.1,
+            Register::Ecx => registers.2,
+            Register::Edx => registers.3,
         }
     }
 }
 
+/// Where register values come from: the processor itself (`Native`), a
+/// recording of one (`RawDump`), or any function of the leaf and
+/// sub-leaf, such as a test fake.
+///
+/// ```
+/// use cupid::Master;
+///
+/// // A processor reporting only SSE2
+/// let fake = |leaf, _subleaf| match leaf {
+///     0 => (1, 0x756E_6547, 0x6C65_746E, 0x4965_6E69),
+///     1 => (0, 0, 0, 1 << 26),
+///     _ => (0, 0, 0, 0),
+/// };
+/// let info = Master::from_source(&fake);
+/// assert!(info.clone().sse2() && !info.avx());
+/// ```
+pub trait CpuidSource {
+    /// The registers for `leaf` and `subleaf`; unsupported leaves should
+    /// read as zero.
+    fn cpuid(&self, leaf: u32, subleaf: u32) -> Registers;
+}
+
+/// Queries the processor the program is running on.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Native;
+
+impl CpuidSource for Native {
+    fn cpuid(&self, leaf: u32, subleaf: u32) -> Registers {
+        query(leaf, subleaf)
+    }
+}
+
+// The processor itself, even when the `testing` feature stands a dump
+// in for it, for callers that go on to execute what it reports.
 #[derive(Copy, Clone)]
-pub struct VersionInformation {
-    eax: u32,
-    ebx: u32,
-    ecx: u32,
-    edx: u32,
+struct Processor;
+
+impl CpuidSource for Processor {
+    fn cpuid(&self, leaf: u32, subleaf: u32) -> Registers {
+        cpuid_count(leaf, subleaf)
+    }
 }
 
-impl VersionInformation {
-    fn new() -> VersionInformation {
-        let (a, b, c, d) = cpuid(RequestType::VersionInformation);
-        VersionInformation { eax: a, ebx: b, ecx: c, edx: d }
+impl<F> CpuidSource for F
+    where F: Fn(u32, u32) -> Registers
+{
+    fn cpuid(&self, leaf: u32, subleaf: u32) -> Registers {
+        self(leaf, subleaf)
     }
+}
 
-    pub fn family_id(self) -> u32 {
-        let family_id = bits_of(self.eax, 8, 11);
-        let extended_family_id = bits_of(self.eax, 20, 27);
+// More than every query of a single decoding pass on current processors
+const PASS_CAPACITY: usize = 64;
 
-        if family_id != 0x0F {
-            family_id
-        } else {
-            extended_family_id + family_id
+// Remembers each query for the length of one decoding pass, so that a
+// leaf read by several decoders is only queried once. Queries past the
+// capacity go straight to the source.
+struct OncePerPass<'a, S: 'a> {
+    source: &'a S,
+    seen: [Cell<(u32, u32, Registers)>; PASS_CAPACITY],
+    len: Cell<usize>,
+}
+
+impl<'a, S: CpuidSource> OncePerPass<'a, S> {
+    fn new(source: &'a S) -> OncePerPass<'a, S> {
+        OncePerPass {
+            source,
+            seen: [const { Cell::new((0, 0, (0, 0, 0, 0))) }; PASS_CAPACITY],
+            len: Cell::new(0),
         }
     }
+}
 
-    pub fn model_id(self) -> u32 {
-        let family_id = self.family_id();
-        let model_id = bits_of(self.eax, 4, 7);
-        let extended_model_id = bits_of(self.eax, 16, 19);
+impl<'a, S: CpuidSource> CpuidSource for OncePerPass<'a, S> {
+    fn cpuid(&self, leaf: u32, subleaf: u32) -> Registers {
+        let len = self.len.get();
+        let seen = self.seen.get(..len).unwrap_or(&[]);
+        if let Some(entry) = seen.iter().map(Cell::get).find(|e| e.0 == leaf && e.1 == subleaf) {
+            return entry.2;
+        }
 
-        if family_id == 0x06 || family_id == 0x0F {
-            (extended_model_id << 4) + model_id
-        } else {
-            model_id
+        let registers = self.source.cpuid(leaf, subleaf);
+        if let Some(slot) = self.seen.get(len) {
+            slot.set((leaf, subleaf, registers));
+            self.len.set(len + 1);
         }
+        registers
     }
+}
 
-    pub fn stepping(self) -> u32 {
-        bits_of(self.eax, 0, 3)
+// Leaves missing from the dump read as zero, as unsupported leaves do
+#[cfg(feature = "std")]
+impl CpuidSource for RawDump {
+    fn cpuid(&self, leaf: u32, subleaf: u32) -> Registers {
+        self.get(leaf, subleaf).unwrap_or((0, 0, 0, 0))
     }
+}
 
-    fn processor_signature(self) -> u32 {
-        self.eax
+// Repeated queries are served from the cache where there is one
+#[cfg(feature = "std")]
+type DefaultSource = cache::Cached;
+#[cfg(not(feature = "std"))]
+type DefaultSource = Native;
+
+// The basic and extended ranges each report their highest leaf in
+// their first leaf. Other ranges (e.g. hypervisor) are not checked.
+#[cfg(any(target_arch = "x86_64", target_arch = "x86", feature = "std"))]
+fn leaf_supported<S: CpuidSource + ?Sized>(source: &S, leaf: u32) -> bool {
+    let base = leaf & 0xFFFF_0000;
+    match base {
+        0x0000_0000 => source.cpuid(base, 0).0 >= leaf,
+        0x8000_0000 => {
+            let max = source.cpuid(base, 0).0;
+            in_extended_range(max) && max >= leaf
+        },
+        _ => true,
     }
+}
 
-    pub fn brand_string(self) -> Option<&'static str> {
-        let brand_index = bits_of(self.ebx, 0, 7);
-        let processor_signature = self.processor_signature();
+// This matches the Intel Architecture guide, with bits 31 -> 0.
+// The bit positions are inclusive. Working in 64 bits means neither a
+// full-width field nor one starting past bit 31 needs a branch; the
+// mask keeps the low `width` bits, up to all 64.
+const fn bits_of(val: u32, start_bit: u8, end_bit: u8) -> u32 {
+    let width = end_bit.saturating_sub(start_bit) as u32 + 1;
+    debug_assert!(width <= 64);
+    let mask = u64::MAX.wrapping_shr(64u32.wrapping_sub(width));
 
-        match brand_index {
-            0x00 => None,
-            0x01 => Some("Intel(R) Celeron(R)"),
-            0x02 => Some("Intel(R) Pentium(R) III"),
-            0x03 => {
-                if processor_signature == 0x06B1 {
-                    Some("Intel(R) Celeron(R)")
-                } else {
-                    Some("Intel(R) Pentium(R) III Xeon(R)")
-                }
-            },
-            0x04 => Some("Intel(R) Pentium(R) III"),
-            0x06 => Some("Mobile Intel(R) Pentium(R) III-M"),
-            0x07 => Some("Mobile Intel(R) Celeron(R)"),
-            0x08 => Some("Intel(R) Pentium(R) 4"),
-            0x09 => Some("Intel(R) Pentium(R) 4"),
-            0x0A => Some("Intel(R) Celeron(R)"),
-            0x0B => {
-                if processor_signature == 0x0F13 {
-                    Some("Intel(R) Xeon(R) MP")
-                } else {
-                    Some("Intel(R) Xeon(R)")
-                }
-            },
-            0x0C => Some("Intel(R) Xeon(R) MP"),
-            0x0E => {
-                if processor_signature == 0x0F13 {
-                    Some("Intel(R) Xeon(R)")
-                } else {
-                    Some("Mobile Intel(R) Pentium(R) 4-M")
-                }
-            },
-            0x0F => Some("Mobile Intel(R) Celeron(R)"),
-            0x11 => Some("Mobile Genuine Intel(R)"),
-            0x12 => Some("Intel(R) Celeron(R) M"),
-            0x13 => Some("Mobile Intel(R) Celeron(R)"),
-            0x14 => Some("Intel(R) Celeron(R)"),
-            0x15 => Some("Mobile Genuine Intel(R)"),
-            0x16 => Some("Intel(R) Pentium(R) M"),
-            0x17 => Some("Mobile Intel(R) Celeron(R)"),
-            _ => None,
+    ((val as u64).wrapping_shr(start_bit as u32) & mask) as u32
+}
+
+// The characters of a register holding part of a string. CPUID strings
+// are little-endian whatever the host, which matters when decoding a
+// dump on a big-endian machine.
+fn le_bytes(v: u32) -> array::IntoIter<u8, 4> {
+    IntoIterator::into_iter(v.to_le_bytes())
+}
+
+macro_rules! bit {
+    ($reg:ident, {$($idx:expr => $name:ident),+}) => {
+        $(pub const fn $name(self) -> bool {
+            ((self.$reg >> $idx) & 1) != 0
+        })+
+    }
+}
+
+// Where CPUID reports a flag kept in the field, as the sub-leaf after
+// the leaf's first, the register and the bit. Fields that are not
+// registers of a CPUID leaf, or are of hypervisor leaves whose numbers
+// vary, have no location.
+macro_rules! flag_location {
+    (eax, $idx:expr) => (Some((0, $crate::Register::Eax, $idx)));
+    (ebx, $idx:expr) => (Some((0, $crate::Register::Ebx, $idx)));
+    (ecx, $idx:expr) => (Some((0, $crate::Register::Ecx, $idx)));
+    (edx, $idx:expr) => (Some((0, $crate::Register::Edx, $idx)));
+    (eax_1, $idx:expr) => (Some((1, $crate::Register::Eax, $idx)));
+    (resources, $idx:expr) => (Some((0, $crate::Register::Edx, $idx)));
+    (l3_edx, $idx:expr) => (Some((1, $crate::Register::Edx, $idx)));
+    ($field:ident, $idx:expr) => (None);
+}
+
+// Defines the accessors for each register's flags, and records them by
+// name, with where CPUID reports them, so that they may be enumerated.
+macro_rules! flags {
+    ({$($reg:ident => {$($idx:expr => $name:ident),+}),+}) => {
+        $(bit!($reg, {$($idx => $name),+});)+
+
+        const FLAGS: &'static [$crate::LeafFlag<Self>] = &[
+            $($((stringify!($name), Self::$name, flag_location!($reg, $idx))),+),+
+        ];
+
+        /// Every flag by name, with whether it is set.
+        pub fn iter(self) -> impl Iterator<Item = (&'static str, bool)> {
+            Self::FLAGS.iter().map(move |&(name, has, _)| (name, has(self)))
         }
     }
+}
 
-    bit!(ecx, {
-         0 => sse3,
-         1 => pclmulqdq,
-         2 => dtes64,
-         3 => monitor,
-         4 => ds_cpl,
-         5 => vmx,
-         6 => smx,
-         7 => eist,
-         8 => tm2,
-         9 => ssse3,
-        10 => cnxt_id,
-        11 => sdbg,
-        12 => fma,
-        13 => cmpxchg16b,
-        14 => xtpr_update_control,
-        15 => pdcm,
-        // 16 - reserved
-        17 => pcid,
-        18 => dca,
-        19 => sse4_1,
-        20 => sse4_2,
-        21 => x2apic,
-        22 => movbe,
-        23 => popcnt,
-        24 => tsc_deadline,
-        25 => aesni,
-        26 => xsave,
-        27 => osxsave,
-        28 => avx,
-        29 => f16c,
-        30 => rdrand
-        // 31 - unused
-    });
-
-    bit!(edx, {
-        0 => fpu,
-        1 => vme,
-        2 => de,
-        3 => pse,
-        4 => tsc,
-        5 => msr,
-        6 => pae,
-        7 => mce,
-        8 => cx8,
-        9 => apic,
-        // 10 - reserved
-        11 => sep,
-        12 => mtrr,
-        13 => pge,
-        14 => mca,
-        15 => cmov,
-        16 => pat,
-        17 => pse_36,
-        18 => psn,
-        19 => clfsh,
-        // 20 - reserved
-        21 => ds,
-        22 => acpi,
-        23 => mmx,
-        24 => fxsr,
-        25 => sse,
-        26 => sse2,
-        27 => ss,
-        28 => htt,
-        29 => tm,
-        // 30 -reserved
-        31 => pbe
-    });
-}
-
-impl fmt::Debug for VersionInformation {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        dump!(self, f, "VersionInformation", {
-            family_id,
-            model_id,
-            stepping,
-            brand_string,
-            sse3,
-            pclmulqdq,
-            dtes64,
-            monitor,
-            ds_cpl,
-            vmx,
-            smx,
-            eist,
-            tm2,
-            ssse3,
-            cnxt_id,
-            sdbg,
-            fma,
-            cmpxchg16b,
-            xtpr_update_control,
-            pdcm,
-            pcid,
-            dca,
-            sse4_1,
-            sse4_2,
-            x2apic,
-            movbe,
-            popcnt,
-            tsc_deadline,
-            aesni,
-            xsave,
-            osxsave,
-            avx,
-            f16c,
-            rdrand,
-            fpu,
-            vme,
-            de,
-            pse,
-            tsc,
-            msr,
-            pae,
-            mce,
-            cx8,
-            apic,
-            sep,
-            mtrr,
-            pge,
-            mca,
-            cmov,
-            pat,
-            pse_36,
-            psn,
-            clfsh,
-            ds,
-            acpi,
-            mmx,
-            fxsr,
-            sse,
-            sse2,
-            ss,
-            htt,
-            tm,
-            pbe
-        })
+// Exposes the raw register values, for the bits not yet decoded.
+macro_rules! registers {
+    ($($reg:ident),+) => {
+        $(pub const fn $reg(self) -> u32 {
+            self.$reg
+        })+
     }
 }
 
-#[derive(Copy,Clone)]
-pub struct ExtendedProcessorSignature {
-    ecx: u32,
-    edx: u32,
+macro_rules! dump {
+    ($me:expr, $f: expr, $sname:expr, {$($name:ident),+}) => {
+        $f.debug_struct($sname)
+            $(.field(stringify!($name), &$me.$name()))+
+            .finish()
+    };
+    ($me:expr, $f: expr, $sname:expr, flags) => {
+        dump!($me, $f, $sname, {}, flags)
+    };
+    // The alternate form (`{:#?}`) lists only the flags that are set
+    ($me:expr, $f: expr, $sname:expr, {$($name:ident),*}, flags) => {{
+        let alternate = $f.alternate();
+        let mut s = $f.debug_struct($sname);
+        $(s.field(stringify!($name), &$me.$name());)*
+        for (name, set) in $me.iter().filter(|&(_, set)| set || !alternate) {
+            s.field(name, &set);
+        }
+        s.finish()
+    }};
 }
 
-impl ExtendedProcessorSignature {
-    fn new() -> ExtendedProcessorSignature {
-        let (_, _, c, d) = cpuid(RequestType::ExtendedProcessorSignature);
-        ExtendedProcessorSignature { ecx: c, edx: d }
-    }
-
-    bit!(ecx, {
-        0 => lahf_sahf_in_64_bit,
-        // 1-4 reserved
-        5 => lzcnt,
-        // 6-7 reserved
-        8 => prefetchw
-        // 9-31 reserved
-    });
-
-    bit!(edx, {
-        // 0-10 reserved
-        11 => syscall_sysret_in_64_bit,
-        // 12-19 reserved
-        20 => execute_disable,
-        // 21-25 reserved
-        26 => gigabyte_pages,
-        27 => rdtscp_and_ia32_tsc_aux,
-        // 28 reserved
-        29 => intel_64_bit_architecture
-        // 30-31 reserved
-    });
-}
-
-impl fmt::Debug for ExtendedProcessorSignature {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        dump!(self, f, "ThermalPowerManagementInformation", {
-            lahf_sahf_in_64_bit,
-            lzcnt,
-            prefetchw,
-            syscall_sysret_in_64_bit,
-            execute_disable,
-            gigabyte_pages,
-            rdtscp_and_ia32_tsc_aux,
-            intel_64_bit_architecture
-        })
+macro_rules! delegate_flag {
+    ($item:ident, {$($name:ident),+}) => {
+        $(pub fn $name(self) -> bool {
+            self.$item.map(|i| i.$name()).unwrap_or(false)
+        })+
+    };
+    ({$($name:ident),+}) => {
+        $(pub fn $name(self) -> bool {
+            false
+        })+
+    };
+}
+
+// Delegates each flag to its leaf and records every flag by name, so
+// that consumers can enumerate them. Each flag is also a variant of
+// `Feature`, in the same order, so a variant indexes `Master::FLAGS`.
+// Flags may carry their name in `-C target-feature`, as in
+// `20: sse4_2 => Sse4_2 ("sse4.2")`, or as in `129: movdiri => Movdiri
+// (llvm "movdiri")` where only LLVM knows the name and rustc warns.
+// Leaves behind a Cargo feature carry its `#[cfg]`, which is first
+// turned into a predicate for `all`, so that each flag can repeat it.
+// Without the feature their flags still exist, but always read as unset.
+macro_rules! master_flags {
+    ({$($(#[cfg($($gate:tt)*)])? $item:ident $location:tt => $flags:tt),+}) => {
+        master_flags!(@gated {$(($($($gate)*)?) $item $location => $flags),+});
+    };
+    (@gated {$($gate:tt $item:ident $location:tt => {$($id:literal: $name:ident => $variant:ident
+                          $(($target:literal))? $((llvm $llvm:literal))?),+}),+}) => {
+        impl Master {
+            $(
+                #[cfg(all $gate)]
+                delegate_flag!($item, {$($name),+});
+                #[cfg(not(all $gate))]
+                delegate_flag!({$($name),+});
+            )+
+
+            const FLAGS: &'static [(&'static str, fn(&Master) -> bool)] = &[
+                $($((stringify!($name), {
+                    #[cfg(all $gate)]
+                    fn $name(m: &Master) -> bool {
+                        m.$item.map(|i| i.$name()).unwrap_or(false)
+                    }
+                    #[cfg(not(all $gate))]
+                    fn $name(_: &Master) -> bool {
+                        false
+                    }
+                    $name
+                })),+),+
+            ];
+
+            // Each leaf and how many flags it contributes, in turn, to `FLAGS`
+            const FLAG_LEAVES: &'static [(&'static str, usize)] = &[
+                $((stringify!($item), [$(stringify!($name)),+].len())),+
+            ];
+        }
+
+        /// A feature flag of `Master`, for naming features in code rather
+        /// than by string. It displays and parses as the name of the
+        /// corresponding method, such as "sse4_2".
+        ///
+        /// Features are added as processors gain them, so matches on it
+        /// need a wildcard arm.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+        #[non_exhaustive]
+        pub enum Feature {
+            $($($variant),+),+
+        }
+
+        impl Feature {
+            /// Every feature, in the order of `Master::flags`.
+            pub const ALL: &'static [Feature] = &[$($(Feature::$variant),+),+];
+
+            // The stable ID of each feature, in the order of `ALL`
+            const IDS: &'static [u16] = &[$($($id),+),+];
+
+            // Where CPUID reports each feature, in the order of `ALL`
+            const LOCATIONS: &'static [Option<(u32, u32, Register, u32)>] = &[
+                $($(
+                    #[cfg(all $gate)]
+                    feature_location!($location, $name),
+                    #[cfg(not(all $gate))]
+                    None,
+                )+)+
+            ];
+
+            // Features by their `-C target-feature` name, with whether
+            // rustc knows the name
+            const TARGET_FEATURES: &'static [(Feature, &'static str, bool)] = &[
+                $($($((Feature::$variant, $target, true),)? $((Feature::$variant, $llvm, false),)?)+)+
+            ];
+        }
     }
 }
 
-// 3 calls of 4 registers of 4 bytes
-const BRAND_STRING_LENGTH: usize = 3 * 4 * 4;
+// Where CPUID reports a flag of `Master`, from the leaf's type and its
+// (leaf, sub-leaf) in the feature table
+macro_rules! feature_location {
+    ((), $name:ident) => (None);
+    (($ty:ident, $leaf:expr, $subleaf:expr), $name:ident) => {
+        locate_flag($ty::FLAGS, $leaf, $subleaf, stringify!($name))
+    };
+}
 
-pub struct BrandString {
-    bytes: [u8; BRAND_STRING_LENGTH],
+// Serializes a leaf as its decoded fields and flags, by the names of
+// their accessors, after the fields of the struct itself under `raw`.
+// Only `raw` is read back. Each of the struct's fields must be listed,
+// as it is destructured in full.
+macro_rules! leaf_serde {
+    ($leaf:ident, {$($raw:ident),+}, {$($name:ident),*} $(, $flags:ident)?) => {
+        // The struct's fields double as the type parameters of `Raw`
+        #[cfg(feature = "serde")]
+        #[allow(non_camel_case_types)]
+        const _: () = {
+            #[derive(Serialize, Deserialize)]
+            struct Raw<$($raw),+> {
+                $($raw: $raw),+
+            }
+
+            #[derive(Deserialize)]
+            struct Form<R> {
+                raw: R,
+            }
+
+            impl ::serde::Serialize for $leaf {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                    where S: ::serde::Serializer
+                {
+                    use ::serde::ser::SerializeStruct;
+
+                    let $leaf { $(ref $raw),+ } = *self;
+                    let names: &[&str] = &[$(stringify!($name)),*];
+                    let len = 1 + names.len() $(+ leaf_serde!(@len $flags))?;
+                    let mut state = serializer.serialize_struct(stringify!($leaf), len)?;
+                    state.serialize_field("raw", &Raw { $($raw),+ })?;
+                    $(state.serialize_field(stringify!($name), &self.$name())?;)*
+                    $(leaf_serde!(@fields $flags, self, state);)?
+                    state.end()
+                }
+            }
+
+            impl<'de> ::serde::Deserialize<'de> for $leaf {
+                fn deserialize<D>(deserializer: D) -> Result<$leaf, D::Error>
+                    where D: ::serde::Deserializer<'de>
+                {
+                    let form: Form<Raw<$(leaf_serde!(@infer $raw)),+>> = Form::deserialize(deserializer)?;
+                    let Raw { $($raw),+ } = form.raw;
+                    Ok($leaf { $($raw),+ })
+                }
+            }
+        };
+    };
+    (@infer $raw:ident) => (_);
+    (@len flags) => (Self::FLAGS.len());
+    (@fields flags, $me:expr, $state:ident) => {
+        for (name, set) in $me.iter() {
+            $state.serialize_field(name, &set)?;
+        }
+    };
 }
 
-impl BrandString {
-    fn new() -> BrandString {
-        fn append_bytes(a: RequestType, bytes: &mut [u8]) {
-            let (a, b, c, d) = cpuid(a);
+// Declares a leaf's decoded fields and flags in one place, generating
+// the accessors, the flag enumeration, and `Debug` and serde
+// implementations that list them all. Methods not meant for those go
+// in a plain `impl`. `raw` names the fields of the struct, which serde
+// stores as they are.
+macro_rules! leaf {
+    (impl $leaf:ident {
+        raw $raw:tt
+        $($(#[$attr:meta])* pub fn $name:ident($($args:tt)*) -> $ty:ty $body:block)*
+        flags $flags:tt
+    }) => {
+        impl $leaf {
+            $($(#[$attr])* pub fn $name($($args)*) -> $ty $body)*
 
-            let result_bytes =
-                as_bytes(&a).iter()
-                .chain(as_bytes(&b).iter())
-                .chain(as_bytes(&c).iter())
-                .chain(as_bytes(&d).iter());
+            flags!($flags);
+        }
 
-            for (output, input) in bytes.iter_mut().zip(result_bytes) {
-                *output = *input
+        impl fmt::Debug for $leaf {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                dump!(self, f, stringify!($leaf), {$($name),*}, flags)
             }
         }
 
-        let mut brand_string = BrandString { bytes: [0; BRAND_STRING_LENGTH] };
-        append_bytes(RequestType::BrandString1, &mut brand_string.bytes[0..]);
-        append_bytes(RequestType::BrandString2, &mut brand_string.bytes[16..]);
-        append_bytes(RequestType::BrandString3, &mut brand_string.bytes[32..]);
-        brand_string
+        leaf_serde!($leaf, $raw, {$($name),*}, flags);
+    };
+    (impl $leaf:ident {
+        raw $raw:tt
+        $($(#[$attr:meta])* pub fn $name:ident($($args:tt)*) -> $ty:ty $body:block)+
+    }) => {
+        impl $leaf {
+            $($(#[$attr])* pub fn $name($($args)*) -> $ty $body)+
+        }
+
+        impl fmt::Debug for $leaf {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                dump!(self, f, stringify!($leaf), {$($name),+})
+            }
+        }
+
+        leaf_serde!($leaf, $raw, {$($name),+});
+    };
+}
+
+macro_rules! master_attr_reader {
+    ($name:ident, $kind:ty) => {
+        pub fn $name(&self) -> Option<&$kind> {
+            self.$name.as_ref()
+        }
     }
 }
 
-impl Clone for BrandString {
-    fn clone(&self) -> Self {
-        let mut bytes = [0; BRAND_STRING_LENGTH];
-        for (d, s) in bytes.iter_mut().zip(self.bytes.iter()) {
-            *d = *s;
+// Declared after the macros above so that the modules may use them
+pub mod aarch64;
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod build;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod corpus;
+#[cfg(feature = "std")]
+pub mod cpuinfo;
+#[cfg(feature = "std")]
+pub mod cross_check;
+#[cfg(feature = "std")]
+pub mod crash;
+#[cfg(feature = "std")]
+pub mod decoders;
+mod detect;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod dump;
+pub mod effective;
+pub mod emulation;
+pub mod feature_set;
+pub mod fingerprint;
+#[cfg(feature = "raw-cpuid")]
+pub mod interop;
+#[cfg(feature = "std")]
+pub mod kvm;
+pub mod level;
+mod linux;
+#[cfg(feature = "tracing")]
+pub mod logging;
+#[cfg(feature = "std")]
+pub mod lscpu;
+#[cfg(feature = "std")]
+pub mod metrics;
+pub mod microarch;
+#[cfg(feature = "std")]
+pub mod microcode;
+#[cfg(all(feature = "msr", target_os = "linux"))]
+pub mod msr;
+#[cfg(all(target_env = "msvc", any(target_arch = "x86_64", target_arch = "x86")))]
+mod msvc;
+#[cfg(all(feature = "numa", any(target_os = "linux", windows)))]
+pub mod numa;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod per_core;
+pub mod power;
+pub mod profile;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+pub mod registry;
+mod report;
+#[cfg(feature = "security")]
+pub mod security;
+pub mod serialize;
+pub mod simd;
+pub mod stats;
+mod target;
+#[cfg(feature = "std")]
+pub mod telemetry;
+#[cfg(all(test, feature = "std"))]
+mod test_support;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod tsc;
+pub mod tsx;
+pub mod wait;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod json;
+
+pub use aarch64::Aarch64Information;
+pub use audit::{AuditFinding, AuditReason};
+#[cfg(feature = "std")]
+pub use cross_check::{cross_check, Mismatch};
+#[cfg(feature = "std")]
+pub use crash::{install_panic_hook, report_line};
+#[cfg(feature = "std")]
+pub use decoders::{Decoded, Decoders};
+#[cfg(feature = "std")]
+pub use diff::{Change, Description, Diff};
+#[cfg(feature = "std")]
+pub use dump::{DumpError, RawDump, RawEntry};
+pub use effective::effective_features;
+pub use emulation::{is_emulated, Translator};
+pub use feature_set::{FeatureSet, MissingFeatures};
+pub use level::MicroarchLevel;
+pub use microarch::Microarchitecture;
+#[cfg(feature = "std")]
+pub use microcode::{microcode_revision, processor_revision, MicrocodeRevision, MicrocodeSource, ProcessorRevision};
+#[cfg(all(feature = "msr", target_os = "linux"))]
+pub use msr::MsrInformation;
+#[cfg(all(feature = "numa", any(target_os = "linux", windows)))]
+pub use numa::{numa_map, numa_nodes, CpuPlacement};
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub use per_core::{common_features, hybrid_layout, per_core, HybridLayout};
+#[cfg(all(feature = "std", feature = "caches", target_os = "linux"))]
+pub use per_core::{cache_sharing, SharedCache};
+pub use power::{power_management, HwpRequest, PowerManagement};
+pub use profile::{Profile, ProfileComparison};
+#[cfg(feature = "std")]
+pub use registry::Registry;
+pub use report::ReportStyle;
+#[cfg(feature = "security")]
+pub use security::{L1tf, Mds, SecuritySummary, SpecStoreBypass, SpectreV2, Srbds};
+pub use simd::{best_simd_level, SimdLevel};
+pub use stats::{FeatureStats, LeafStats};
+pub use target::{TargetFeatureError, UnknownTargetFeature};
+pub use tsc::{read_tsc, read_tscp};
+pub use tsx::{tsx_status, TsxStatus};
+
+/// The 12 character vendor identification, such as "GenuineIntel" or
+/// "AuthenticAMD".
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VendorId {
+    bytes: [u8; 12],
+}
+
+impl VendorId {
+    fn new<S: CpuidSource>(source: &S) -> VendorId {
+        let (_, b, c, d) = source.cpuid(RequestType::BasicInformation as u32, 0);
+
+        let mut vendor_id = VendorId { bytes: [0; 12] };
+        // The identification is spelled out in EBX, EDX, ECX order
+        let register_bytes =
+            le_bytes(b)
+            .chain(le_bytes(d))
+            .chain(le_bytes(c));
+        for (output, input) in vendor_id.bytes.iter_mut().zip(register_bytes) {
+            *output = input;
         }
-        BrandString { bytes: bytes }
+        vendor_id
     }
 }
 
-impl Deref for BrandString {
+impl Deref for VendorId {
     type Target = str;
 
     fn deref(&self) -> &str {
-        let nul_terminator = self.bytes.iter().position(|&b| b == 0).unwrap_or(0);
-        let usable_bytes = &self.bytes[..nul_terminator];
-        unsafe { str::from_utf8_unchecked(usable_bytes) }.trim()
+        let end = self.bytes.iter().position(|&b| b == 0).unwrap_or(12);
+        let bytes = self.bytes.get(..end).unwrap_or(&[]);
+        str::from_utf8(bytes).unwrap_or("")
     }
 }
 
-impl fmt::Display for BrandString {
+impl fmt::Display for VendorId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         (self as &str).fmt(f)
     }
 }
 
-impl fmt::Debug for BrandString {
+impl fmt::Debug for VendorId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         (self as &str).fmt(f)
     }
 }
 
-#[derive(Copy,Clone)]
-pub struct ThermalPowerManagementInformation {
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct VersionInformation {
     eax: u32,
     ebx: u32,
     ecx: u32,
+    edx: u32,
 }
 
-impl ThermalPowerManagementInformation {
-    fn new() -> ThermalPowerManagementInformation {
-        let (a, b, c, _) = cpuid(RequestType::ThermalPowerManagementInformation);
-        ThermalPowerManagementInformation { eax: a, ebx: b, ecx: c }
-    }
-
-    bit!(eax, {
-        0 => digital_temperature_sensor,
-        1 => intel_turbo_boost,
-        2 => arat,
-        // 3 - reserved
-        4 => pln,
-        5 => ecmd,
-        6 => ptm,
-        7 => hwp,
-        8 => hwp_notification,
-        9 => hwp_activity_window,
-        10 => hwp_energy_performance_preference,
-        // 12 - reserved
-        13 => hdc
-    });
-
-    pub fn number_of_interrupt_thresholds(self) -> u32 {
-        bits_of(self.ebx, 0, 3)
-    }
-
-    bit!(ecx, {
-        0 => hardware_coordination_feedback,
-        // 1-2 - reserved
-        3 => performance_energy_bias
-    });
-}
-
-impl fmt::Debug for ThermalPowerManagementInformation {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        dump!(self, f, "ThermalPowerManagementInformation", {
-            digital_temperature_sensor,
-            intel_turbo_boost,
-            arat,
-            pln,
-            ecmd,
-            ptm,
-            hwp,
-            hwp_notification,
-            hwp_activity_window,
-            hwp_energy_performance_preference,
-            hdc,
-
-            number_of_interrupt_thresholds,
-
-            hardware_coordination_feedback,
-            performance_energy_bias
-        })
+impl VersionInformation {
+    fn new<S: CpuidSource>(source: &S) -> VersionInformation {
+        let (a, b, c, d) = source.cpuid(RequestType::VersionInformation as u32, 0);
+        VersionInformation::from_registers(a, b, c, d)
     }
-}
 
-#[derive(Copy,Clone)]
-pub struct StructuredExtendedInformation {
-    ebx: u32,
-    ecx: u32,
-}
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32, edx: u32) -> VersionInformation {
+        VersionInformation { eax, ebx, ecx, edx }
+    }
 
-impl StructuredExtendedInformation {
-    fn new() -> StructuredExtendedInformation {
-        let (_, b, c, _) = cpuid(RequestType::StructuredExtendedInformation);
-        StructuredExtendedInformation { ebx: b, ecx: c }
-    }
-
-    bit!(ebx, {
-        0 => fsgsbase,
-        1 => ia32_tsc_adjust_msr,
-        // 2 - reserved
-        3 => bmi1,
-        4 => hle,
-        5 => avx2,
-        // 6 - reserved
-        7 => smep,
-        8 => bmi2,
-        9 => enhanced_rep_movsb_stosb,
-        10 => invpcid,
-        11 => rtm,
-        12 => pqm,
-        13 => deprecates_fpu_cs_ds,
-        // 14 - reserved
-        15 => pqe,
-        // 16-17 - reserved
-        18 => rdseed,
-        19 => adx,
-        20 => smap,
-        // 21-24 - reserved
-        25 => intel_processor_trace
-        // 26-31 - reserved
-    });
-
-    bit!(ecx, {
-        0 => prefetchwt1
-    });
-}
-
-impl fmt::Debug for StructuredExtendedInformation {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        dump!(self, f, "StructuredExtendedInformation", {
-            fsgsbase,
-            ia32_tsc_adjust_msr,
-            bmi1,
-            hle,
-            avx2,
-            smep,
-            bmi2,
-            enhanced_rep_movsb_stosb,
-            invpcid,
-            rtm,
-            pqm,
-            deprecates_fpu_cs_ds,
-            pqe,
-            rdseed,
-            adx,
-            smap,
-            intel_processor_trace,
-            prefetchwt1
-        })
+    registers!(eax, ebx, ecx, edx);
+
+    fn processor_signature(self) -> u32 {
+        self.eax
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub enum CacheLineAssociativity {
-    Disabled,
-    DirectMapped,
-    TwoWay,
-    FourWay,
-    EightWay,
-    SixteenWay,
-    Full,
-}
+leaf! {
+    impl VersionInformation {
+        raw { eax, ebx, ecx, edx }
 
-#[derive(Copy, Clone)]
-pub struct CacheLine(u32);
+        pub fn family_id(self) -> u32 {
+            let family_id = bits_of(self.eax, 8, 11);
+            let extended_family_id = bits_of(self.eax, 20, 27);
 
-impl CacheLine {
-    fn new() -> CacheLine {
-        let (_, _, c, _) = cpuid(RequestType::CacheLine);
-        CacheLine(c)
-    }
+            if family_id != 0x0F {
+                family_id
+            } else {
+                extended_family_id + family_id
+            }
+        }
 
-    pub fn cache_line_size(self) -> u32 {
-        bits_of(self.0, 0, 7)
-    }
+        pub fn model_id(self) -> u32 {
+            let family_id = bits_of(self.eax, 8, 11);
+            let model_id = bits_of(self.eax, 4, 7);
+            let extended_model_id = bits_of(self.eax, 16, 19);
 
-    pub fn l2_associativity(self) -> Option<CacheLineAssociativity> {
-        match bits_of(self.0, 12, 15) {
-            0x00 => Some(CacheLineAssociativity::Disabled),
-            0x01 => Some(CacheLineAssociativity::DirectMapped),
-            0x02 => Some(CacheLineAssociativity::TwoWay),
-            0x04 => Some(CacheLineAssociativity::FourWay),
-            0x06 => Some(CacheLineAssociativity::EightWay),
-            0x08 => Some(CacheLineAssociativity::SixteenWay),
-            0x0F => Some(CacheLineAssociativity::Full),
-            _ => None,
+            if family_id == 0x06 || family_id == 0x0F {
+                (extended_model_id << 4) + model_id
+            } else {
+                model_id
+            }
         }
-    }
 
-    pub fn cache_size(self) -> u32 {
-        bits_of(self.0, 16, 31)
-    }
-}
+        pub fn stepping(self) -> u32 {
+            bits_of(self.eax, 0, 3)
+        }
 
-impl fmt::Debug for CacheLine {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        dump!(self, f, "CacheLine", {
-            cache_line_size,
-            l2_associativity,
-            cache_size
-        })
+        /// The 8-bit APIC ID the querying logical processor was assigned
+        /// at reset. Processors with more than 255 logical processors
+        /// need the x2APIC ID instead.
+        pub fn initial_apic_id(self) -> u32 {
+            bits_of(self.ebx, 24, 31)
+        }
+
+        /// The size of the line CLFLUSH flushes, in bytes, if the
+        /// instruction is supported.
+        pub fn clflush_line_size(self) -> Option<u32> {
+            if self.clfsh() {
+                Some(bits_of(self.ebx, 8, 15) * 8)
+            } else {
+                None
+            }
+        }
+
+        pub fn brand_string(self) -> Option<&'static str> {
+            let brand_index = bits_of(self.ebx, 0, 7);
+            let processor_signature = self.processor_signature();
+
+            match brand_index {
+                0x00 => None,
+                0x01 => Some("Intel(R) Celeron(R)"),
+                0x02 => Some("Intel(R) Pentium(R) III"),
+                0x03 => {
+                    if processor_signature == 0x06B1 {
+                        Some("Intel(R) Celeron(R)")
+                    } else {
+                        Some("Intel(R) Pentium(R) III Xeon(R)")
+                    }
+                },
+                0x04 => Some("Intel(R) Pentium(R) III"),
+                0x06 => Some("Mobile Intel(R) Pentium(R) III-M"),
+                0x07 => Some("Mobile Intel(R) Celeron(R)"),
+                0x08 => Some("Intel(R) Pentium(R) 4"),
+                0x09 => Some("Intel(R) Pentium(R) 4"),
+                0x0A => Some("Intel(R) Celeron(R)"),
+                0x0B => {
+                    if processor_signature == 0x0F13 {
+                        Some("Intel(R) Xeon(R) MP")
+                    } else {
+                        Some("Intel(R) Xeon(R)")
+                    }
+                },
+                0x0C => Some("Intel(R) Xeon(R) MP"),
+                0x0E => {
+                    if processor_signature == 0x0F13 {
+                        Some("Intel(R) Xeon(R)")
+                    } else {
+                        Some("Mobile Intel(R) Pentium(R) 4-M")
+                    }
+                },
+                0x0F => Some("Mobile Intel(R) Celeron(R)"),
+                0x11 => Some("Mobile Genuine Intel(R)"),
+                0x12 => Some("Intel(R) Celeron(R) M"),
+                0x13 => Some("Mobile Intel(R) Celeron(R)"),
+                0x14 => Some("Intel(R) Celeron(R)"),
+                0x15 => Some("Mobile Genuine Intel(R)"),
+                0x16 => Some("Intel(R) Pentium(R) M"),
+                0x17 => Some("Mobile Intel(R) Celeron(R)"),
+                _ => None,
+            }
+        }
+
+        flags {
+            ecx => {
+                 0 => sse3,
+                 1 => pclmulqdq,
+                 2 => dtes64,
+                 3 => monitor,
+                 4 => ds_cpl,
+                 5 => vmx,
+                 6 => smx,
+                 7 => eist,
+                 8 => tm2,
+                 9 => ssse3,
+                10 => cnxt_id,
+                11 => sdbg,
+                12 => fma,
+                13 => cmpxchg16b,
+                14 => xtpr_update_control,
+                15 => pdcm,
+                // 16 - reserved
+                17 => pcid,
+                18 => dca,
+                19 => sse4_1,
+                20 => sse4_2,
+                21 => x2apic,
+                22 => movbe,
+                23 => popcnt,
+                24 => tsc_deadline,
+                25 => aesni,
+                26 => xsave,
+                27 => osxsave,
+                28 => avx,
+                29 => f16c,
+                30 => rdrand,
+                31 => hypervisor
+            },
+            edx => {
+                0 => fpu,
+                1 => vme,
+                2 => de,
+                3 => pse,
+                4 => tsc,
+                5 => msr,
+                6 => pae,
+                7 => mce,
+                8 => cx8,
+                9 => apic,
+                // 10 - reserved
+                11 => sep,
+                12 => mtrr,
+                13 => pge,
+                14 => mca,
+                15 => cmov,
+                16 => pat,
+                17 => pse_36,
+                18 => psn,
+                19 => clfsh,
+                // 20 - reserved
+                21 => ds,
+                22 => acpi,
+                23 => mmx,
+                24 => fxsr,
+                25 => sse,
+                26 => sse2,
+                27 => ss,
+                28 => htt,
+                29 => tm,
+                // 30 -reserved
+                31 => pbe
+            }
+        }
     }
 }
 
-#[derive(Copy, Clone)]
-pub struct TimeStampCounter {
-    edx: u32,
+/// Well-known hypervisors, identified by their vendor signature.
+#[cfg(feature = "hypervisor")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Hypervisor {
+    Kvm,
+    HyperV,
+    VMware,
+    Xen,
+    Tcg,
+    Bhyve,
+    VirtualBox,
+    Parallels,
+    Acrn,
+    Unknown,
 }
 
-impl TimeStampCounter {
-    fn new() -> TimeStampCounter {
-        let (_, _, _, d) = cpuid(RequestType::TimeStampCounter);
-        TimeStampCounter { edx: d }
-    }
+// Xen may relocate its leaves (e.g. when also offering Hyper-V
+// enlightenments) in steps of 0x100, up to this base.
+#[cfg(any(feature = "std", feature = "hypervisor"))]
+const XEN_LEAF_BASE_LIMIT: u32 = 0x4001_0000;
 
-    bit!(edx, {
-        // 0-7 - reserved
-        8 => invariant_tsc
-        // 9-31 - reserved
-    });
+/// The hypervisor the processor is running under, if any.
+#[cfg(feature = "hypervisor")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HypervisorInformation {
+    max_leaf: u32,
+    signature: [u8; 12],
 }
 
-impl fmt::Debug for TimeStampCounter {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        dump!(self, f, "TimeStampCounter", {
-            invariant_tsc
-        })
+#[cfg(feature = "hypervisor")]
+impl HypervisorInformation {
+    fn new<S: CpuidSource>(source: &S) -> HypervisorInformation {
+        HypervisorInformation::at(source, RequestType::HypervisorInformation as u32)
     }
-}
 
-#[derive(Copy,Clone)]
-pub struct PhysicalAddressSize(u32);
+    fn at<S: CpuidSource>(source: &S, base: u32) -> HypervisorInformation {
+        let (a, b, c, d) = source.cpuid(base, 0);
 
-impl PhysicalAddressSize {
-    fn new() -> PhysicalAddressSize {
-        let (a, _, _, _) = cpuid(RequestType::PhysicalAddressSize);
-        PhysicalAddressSize(a)
-    }
+        let mut signature = [0; 12];
+        let register_bytes =
+            le_bytes(b)
+            .chain(le_bytes(c))
+            .chain(le_bytes(d));
+        for (output, input) in signature.iter_mut().zip(register_bytes) {
+            *output = input;
+        }
 
-    pub fn physical_address_bits(self) -> u32 {
-        bits_of(self.0, 0, 7)
+        HypervisorInformation { max_leaf: a, signature }
     }
+}
+
+#[cfg(feature = "hypervisor")]
+leaf! {
+    impl HypervisorInformation {
+        raw { max_leaf, signature }
+
+        pub fn hypervisor(self) -> Hypervisor {
+            match &self.signature {
+                b"KVMKVMKVM\0\0\0" => Hypervisor::Kvm,
+                b"Microsoft Hv" => Hypervisor::HyperV,
+                b"VMwareVMware" => Hypervisor::VMware,
+                b"XenVMMXenVMM" => Hypervisor::Xen,
+                b"TCGTCGTCGTCG" => Hypervisor::Tcg,
+                b"bhyve bhyve " => Hypervisor::Bhyve,
+                b"VBoxVBoxVBox" => Hypervisor::VirtualBox,
+                b" lrpepyh  vr" => Hypervisor::Parallels,
+                b"ACRNACRNACRN" => Hypervisor::Acrn,
+                _ => Hypervisor::Unknown,
+            }
+        }
+
+        /// The raw vendor signature, such as "KVMKVMKVM".
+        pub fn signature(&self) -> &str {
+            let end = self.signature.iter().position(|&b| b == 0).unwrap_or(12);
+            let bytes = self.signature.get(..end).unwrap_or(&[]);
+            str::from_utf8(bytes).unwrap_or("")
+        }
 
-    pub fn linear_address_bits(self) -> u32 {
-        bits_of(self.0, 8, 15)
+        /// The highest hypervisor leaf.
+        pub fn max_leaf(self) -> u32 {
+            self.max_leaf
+        }
     }
 }
 
-impl fmt::Debug for PhysicalAddressSize {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        dump!(self, f, "PhysicalAddressSize", {
-            physical_address_bits,
-            linear_address_bits
+/// Paravirtual features offered by KVM.
+#[cfg(feature = "hypervisor")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KvmFeatures {
+    eax: u32,
+    edx: u32,
+}
+
+#[cfg(feature = "hypervisor")]
+impl KvmFeatures {
+    fn new<S: CpuidSource>(source: &S) -> KvmFeatures {
+        let (a, _, _, d) = source.cpuid(RequestType::HypervisorInformation as u32 + 1, 0);
+        KvmFeatures::from_registers(a, d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, edx: u32) -> KvmFeatures {
+        KvmFeatures { eax, edx }
+    }
+
+    registers!(eax, edx);
+}
+
+#[cfg(feature = "hypervisor")]
+leaf! {
+    impl KvmFeatures {
+        raw { eax, edx }
+
+        flags {
+            eax => {
+                0 => kvmclock,
+                1 => nop_io_delay,
+                2 => mmu_op,
+                3 => kvmclock2,
+                4 => async_pf,
+                5 => steal_time,
+                6 => pv_eoi,
+                7 => pv_unhalt,
+                // 8 - reserved
+                9 => pv_tlb_flush,
+                10 => async_pf_vmexit,
+                11 => pv_send_ipi,
+                12 => poll_control,
+                13 => pv_sched_yield,
+                14 => async_pf_int,
+                15 => msi_ext_dest_id,
+                16 => hc_map_gpa_range,
+                17 => migration_control,
+                // 18-23 - reserved
+                24 => kvmclock_stable
+                // 25-31 - reserved
+            },
+            edx => {
+                // vCPUs are never preempted for an unlimited time
+                0 => realtime_hint
+                // 1-31 - reserved
+            }
+        }
+    }
+}
+
+/// The timing leaf VMware defined at 0x4000_0010, which QEMU also
+/// offers KVM guests.
+#[cfg(feature = "hypervisor")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct VMwareTimingInformation {
+    eax: u32,
+    ebx: u32,
+}
+
+#[cfg(feature = "hypervisor")]
+impl VMwareTimingInformation {
+    fn new<S: CpuidSource>(source: &S) -> VMwareTimingInformation {
+        let (a, b, _, _) = source.cpuid(RequestType::HypervisorInformation as u32 + 0x10, 0);
+        VMwareTimingInformation::from_registers(a, b)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32) -> VMwareTimingInformation {
+        VMwareTimingInformation { eax, ebx }
+    }
+
+    registers!(eax, ebx);
+}
+
+#[cfg(feature = "hypervisor")]
+leaf! {
+    impl VMwareTimingInformation {
+        raw { eax, ebx }
+
+        /// The (virtual) TSC frequency in kHz.
+        pub fn tsc_frequency_khz(self) -> u32 {
+            self.eax
+        }
+
+        /// The (virtual) APIC bus frequency in kHz.
+        pub fn apic_bus_frequency_khz(self) -> u32 {
+            self.ebx
+        }
+    }
+}
+
+/// The Xen version, hypercall and HVM feature leaves.
+#[cfg(feature = "hypervisor")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct XenInformation {
+    base: u32,
+    version: u32,
+    hypercall_pages: u32,
+    hypercall_msr: u32,
+    features: u32,
+    hvm_eax: u32,
+    hvm_ebx: u32,
+    hvm_ecx: u32,
+    tsc_khz: u32,
+}
+
+#[cfg(feature = "hypervisor")]
+impl XenInformation {
+    fn new<S: CpuidSource>(source: &S) -> Option<XenInformation> {
+        XenInformation::find_base(source).map(|base| {
+            let max_leaf = HypervisorInformation::at(source, base).max_leaf();
+            let (version, _, _, _) = source.cpuid(base + 1, 0);
+            let (pages, msr, features, _) = source.cpuid(base + 2, 0);
+            let (_, _, tsc_khz, _) = if base + 3 <= max_leaf {
+                source.cpuid(base + 3, 0)
+            } else {
+                (0, 0, 0, 0)
+            };
+            let (a, b, c, _) = if base + 4 <= max_leaf {
+                source.cpuid(base + 4, 0)
+            } else {
+                (0, 0, 0, 0)
+            };
+
+            XenInformation {
+                base,
+                version,
+                hypercall_pages: pages,
+                hypercall_msr: msr,
+                features,
+                hvm_eax: a,
+                hvm_ebx: b,
+                hvm_ecx: c,
+                tsc_khz,
+            }
         })
     }
+
+    fn find_base<S: CpuidSource>(source: &S) -> Option<u32> {
+        let mut base = RequestType::HypervisorInformation as u32;
+        while base < XEN_LEAF_BASE_LIMIT {
+            let info = HypervisorInformation::at(source, base);
+            if info.hypervisor() == Hypervisor::Xen && info.max_leaf() >= base + 2 {
+                return Some(base);
+            }
+            base += 0x100;
+        }
+        None
+    }
 }
 
-/// Information about the currently running processor
-///
-/// Feature flags match the feature mnemonic listed in the Intel
-/// Instruction Set Reference. This struct provides a facade for flags
-/// so the consumer doesn't need to worry about which particular CPUID
-/// leaf provides the information.
+#[cfg(feature = "hypervisor")]
+leaf! {
+    impl XenInformation {
+        raw {
+            base, version, hypercall_pages, hypercall_msr, features, hvm_eax, hvm_ebx, hvm_ecx,
+            tsc_khz
+        }
+
+        /// The leaf at which the Xen range starts.
+        pub fn leaf_base(self) -> u32 {
+            self.base
+        }
+
+        pub fn major_version(self) -> u32 {
+            bits_of(self.version, 16, 31)
+        }
+
+        pub fn minor_version(self) -> u32 {
+            bits_of(self.version, 0, 15)
+        }
+
+        /// The number of hypercall transfer pages.
+        pub fn hypercall_pages(self) -> u32 {
+            self.hypercall_pages
+        }
+
+        /// The MSR used to register the hypercall transfer pages.
+        pub fn hypercall_msr(self) -> u32 {
+            self.hypercall_msr
+        }
+
+        pub fn vcpu_id(self) -> Option<u32> {
+            if self.vcpu_id_present() {
+                Some(self.hvm_ebx)
+            } else {
+                None
+            }
+        }
+
+        pub fn domain_id(self) -> Option<u32> {
+            if self.domain_id_present() {
+                Some(self.hvm_ecx)
+            } else {
+                None
+            }
+        }
+
+        /// The guest's TSC frequency in kHz, from the time leaf.
+        pub fn tsc_frequency_khz(self) -> u32 {
+            self.tsc_khz
+        }
+
+        flags {
+            features => {
+                0 => mmu_pt_update_preserve_ad
+                // 1-31 - reserved
+            },
+            hvm_eax => {
+                0 => apic_access_virtualization,
+                1 => x2apic_virtualization,
+                2 => iommu_mappings,
+                3 => vcpu_id_present,
+                4 => domain_id_present,
+                5 => upcall_vector
+                // 6-31 - reserved
+            }
+        }
+    }
+}
+
+// "Hv#1", identifying the Microsoft hypervisor interface
+#[cfg(feature = "hypervisor")]
+const HYPER_V_INTERFACE: u32 = 0x31237648;
+
+/// The Hyper-V identification, feature and enlightenment leaves.
 ///
-/// For data beyond simple feature flags, you will need to retrieve
-/// the nested struct and call the appropriate methods on it.
-#[derive(Debug,Clone)]
-pub struct Master {
-    // TODO: Rename struct
-    version_information: Option<VersionInformation>,
-    thermal_power_management_information: Option<ThermalPowerManagementInformation>,
-    structured_extended_information: Option<StructuredExtendedInformation>,
-    extended_processor_signature: Option<ExtendedProcessorSignature>,
-    brand_string: Option<BrandString>,
-    cache_line: Option<CacheLine>,
-    time_stamp_counter: Option<TimeStampCounter>,
-    physical_address_size: Option<PhysicalAddressSize>,
+/// Leaves beyond the maximum the hypervisor reports read as zero.
+#[cfg(feature = "hypervisor")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HyperVInformation {
+    version_eax: u32,
+    version_ebx: u32,
+    version_ecx: u32,
+    version_edx: u32,
+    privileges_eax: u32,
+    privileges_ebx: u32,
+    power_ecx: u32,
+    features_edx: u32,
+    recommendations_eax: u32,
+    recommendations_ebx: u32,
+    limits_eax: u32,
+    limits_ebx: u32,
+    limits_ecx: u32,
+    hardware_eax: u32,
+    cpu_management_eax: u32,
+    cpu_management_ebx: u32,
+    cpu_management_ecx: u32,
+    svm_eax: u32,
+    nested_hypervisor_eax: u32,
+    nested_hypervisor_edx: u32,
+    nested_eax: u32,
+    nested_ebx: u32,
 }
 
-impl Master {
-    fn new() -> Master {
-        fn when_supported<F, T>(max: u32, kind: RequestType, then: F) -> Option<T>
-            where F: FnOnce() -> T
-        {
-            if max >= kind as u32 {
-                Some(then())
+#[cfg(feature = "hypervisor")]
+impl HyperVInformation {
+    fn new<S: CpuidSource>(source: &S, max_leaf: u32) -> HyperVInformation {
+        let base = RequestType::HypervisorInformation as u32;
+        let query = |offset| {
+            if base + offset <= max_leaf {
+                source.cpuid(base + offset, 0)
             } else {
-                None
+                (0, 0, 0, 0)
             }
+        };
+
+        let (va, vb, vc, vd) = query(2);
+        let (pa, pb, pc, pd) = query(3);
+        let (ra, rb, _, _) = query(4);
+        let (la, lb, lc, _) = query(5);
+        let (ha, _, _, _) = query(6);
+        // Only the root partition sees the CPU management features
+        let (ca, cb, cc, _) = query(7);
+        let (sa, _, _, _) = query(8);
+        let (nha, _, _, nhd) = query(9);
+        let (na, nb, _, _) = query(0xA);
+
+        HyperVInformation {
+            version_eax: va,
+            version_ebx: vb,
+            version_ecx: vc,
+            version_edx: vd,
+            privileges_eax: pa,
+            privileges_ebx: pb,
+            power_ecx: pc,
+            features_edx: pd,
+            recommendations_eax: ra,
+            recommendations_ebx: rb,
+            limits_eax: la,
+            limits_ebx: lb,
+            limits_ecx: lc,
+            hardware_eax: ha,
+            cpu_management_eax: ca,
+            cpu_management_ebx: cb,
+            cpu_management_ecx: cc,
+            svm_eax: sa,
+            nested_hypervisor_eax: nha,
+            nested_hypervisor_edx: nhd,
+            nested_eax: na,
+            nested_ebx: nb,
         }
+    }
+}
 
-        let (max_value, _, _, _) = cpuid(RequestType::BasicInformation);
+#[cfg(feature = "hypervisor")]
+leaf! {
+    impl HyperVInformation {
+        raw {
+            version_eax, version_ebx, version_ecx, version_edx, privileges_eax, privileges_ebx,
+            power_ecx, features_edx, recommendations_eax, recommendations_ebx, limits_eax,
+            limits_ebx, limits_ecx, hardware_eax, cpu_management_eax, cpu_management_ebx,
+            cpu_management_ecx, svm_eax, nested_hypervisor_eax, nested_hypervisor_edx, nested_eax,
+            nested_ebx
+        }
 
-        let vi = when_supported(max_value, RequestType::VersionInformation, || {
-            VersionInformation::new()
-        });
-        let tpm = when_supported(max_value, RequestType::ThermalPowerManagementInformation, || {
-            ThermalPowerManagementInformation::new()
-        });
-        let sei = when_supported(max_value, RequestType::StructuredExtendedInformation, || {
-            StructuredExtendedInformation::new()
-        });
+        pub fn build_number(self) -> u32 {
+            self.version_eax
+        }
 
-        // Extended information
+        pub fn major_version(self) -> u32 {
+            bits_of(self.version_ebx, 16, 31)
+        }
 
-        let (max_value, _, _, _) = cpuid(RequestType::ExtendedFunctionInformation);
+        pub fn minor_version(self) -> u32 {
+            bits_of(self.version_ebx, 0, 15)
+        }
 
-        let eps = when_supported(max_value, RequestType::ExtendedProcessorSignature, || {
-            ExtendedProcessorSignature::new()
-        });
-        let brand_string = when_supported(max_value, RequestType::BrandString3, || {
-            BrandString::new()
-        });
-        let cache_line = when_supported(max_value, RequestType::CacheLine, || {
-            CacheLine::new()
-        });
-        let tsc = when_supported(max_value, RequestType::TimeStampCounter, || {
-            TimeStampCounter::new()
-        });
-        let pas = when_supported(max_value, RequestType::PhysicalAddressSize, || {
-            PhysicalAddressSize::new()
-        });
+        pub fn service_pack(self) -> u32 {
+            self.version_ecx
+        }
 
-        Master {
-            version_information: vi,
-            thermal_power_management_information: tpm,
-            structured_extended_information: sei,
-            extended_processor_signature: eps,
-            brand_string: brand_string,
-            cache_line: cache_line,
-            time_stamp_counter: tsc,
-            physical_address_size: pas,
+        pub fn service_branch(self) -> u32 {
+            bits_of(self.version_edx, 24, 31)
+        }
+
+        pub fn service_number(self) -> u32 {
+            bits_of(self.version_edx, 0, 23)
+        }
+
+        pub fn max_processor_power_state(self) -> u32 {
+            bits_of(self.power_ecx, 0, 3)
+        }
+
+        /// How often a spinlock should be retried before notifying the
+        /// hypervisor, or `None` if it should never be notified.
+        pub fn spinlock_retries(self) -> Option<u32> {
+            match self.recommendations_ebx {
+                0xFFFF_FFFF => None,
+                retries => Some(retries),
+            }
+        }
+
+        pub fn max_virtual_processors(self) -> u32 {
+            self.limits_eax
+        }
+
+        pub fn max_logical_processors(self) -> u32 {
+            self.limits_ebx
+        }
+
+        pub fn max_interrupt_vectors(self) -> u32 {
+            self.limits_ecx
+        }
+
+        /// The number of PASIDs available to shared virtual memory.
+        pub fn max_pasid_space_pasid_count(self) -> u32 {
+            bits_of(self.svm_eax, 11, 31)
+        }
+
+        pub fn enlightened_vmcs_version_low(self) -> u32 {
+            bits_of(self.nested_eax, 0, 7)
+        }
+
+        pub fn enlightened_vmcs_version_high(self) -> u32 {
+            bits_of(self.nested_eax, 8, 15)
+        }
+
+        flags {
+            privileges_eax => {
+                0 => access_vp_run_time_reg,
+                1 => access_partition_reference_counter,
+                2 => access_synic_regs,
+                3 => access_synthetic_timer_regs,
+                4 => access_intr_ctrl_regs,
+                5 => access_hypercall_msrs,
+                6 => access_vp_index,
+                7 => access_reset_reg,
+                8 => access_stats_reg,
+                9 => access_partition_reference_tsc,
+                10 => access_guest_idle_reg,
+                11 => access_frequency_regs,
+                12 => access_debug_regs,
+                13 => access_reenlightenment_controls
+                // 14-31 - reserved
+            },
+            privileges_ebx => {
+                0 => create_partitions,
+                1 => access_partition_id,
+                2 => access_memory_pool,
+                // 3 - reserved
+                4 => post_messages,
+                5 => signal_events,
+                6 => create_port,
+                7 => connect_port,
+                8 => access_stats,
+                // 9-10 - reserved
+                11 => debugging,
+                12 => cpu_management,
+                // 13-15 - reserved
+                16 => access_vsm,
+                17 => access_vp_registers,
+                // 18-19 - reserved
+                20 => enable_extended_hypercalls,
+                21 => start_virtual_processor,
+                22 => isolation
+                // 23-31 - reserved
+            },
+            features_edx => {
+                // 0 - deprecated
+                1 => guest_debugging,
+                2 => performance_monitor,
+                3 => cpu_dynamic_partitioning,
+                4 => xmm_hypercall_input,
+                5 => guest_idle_state,
+                6 => hypervisor_sleep_state,
+                7 => numa_distance_query,
+                8 => timer_frequency_msrs,
+                9 => synthetic_machine_check,
+                10 => guest_crash_msrs,
+                11 => debug_msrs,
+                12 => npiep,
+                13 => disable_hypervisor,
+                14 => extended_gva_ranges_for_flush,
+                15 => xmm_hypercall_output,
+                // 16 - reserved
+                17 => sint_polling_mode,
+                18 => hypercall_msr_lock,
+                19 => direct_synthetic_timers,
+                20 => register_pat_for_vsm,
+                21 => register_bndcfgs_for_vsm,
+                // 22 - reserved
+                23 => synthetic_time_unhalted_timer,
+                // 24-25 - reserved
+                26 => guest_lbr
+                // 27-31 - reserved
+            },
+            recommendations_eax => {
+                0 => recommend_hypercall_address_space_switch,
+                1 => recommend_hypercall_local_flush,
+                2 => recommend_hypercall_remote_flush,
+                3 => recommend_msr_apic_access,
+                4 => recommend_msr_reset,
+                5 => relaxed_timing,
+                6 => recommend_dma_remapping,
+                7 => recommend_interrupt_remapping,
+                8 => recommend_x2apic_msrs,
+                9 => deprecate_auto_eoi,
+                10 => recommend_synthetic_cluster_ipi,
+                11 => recommend_ex_processor_masks,
+                12 => nested_hypervisor,
+                13 => recommend_int_mbec_syscalls,
+                14 => recommend_enlightened_vmcs,
+                15 => synced_timeline,
+                // 16 - reserved
+                17 => recommend_direct_local_flush_entire,
+                18 => no_non_architectural_core_sharing
+                // 19-31 - reserved
+            },
+            hardware_eax => {
+                0 => apic_overlay_assist,
+                1 => msr_bitmaps,
+                2 => architectural_performance_counters,
+                3 => second_level_address_translation,
+                4 => dma_remapping_in_use,
+                5 => interrupt_remapping_in_use,
+                6 => memory_patrol_scrubber,
+                7 => dma_protection_in_use,
+                8 => hpet_requested,
+                9 => synthetic_timers_volatile
+                // 10-31 - reserved
+            },
+            cpu_management_eax => {
+                0 => start_logical_processor,
+                1 => create_root_virtual_processor,
+                2 => performance_counter_sync,
+                // 3-30 - reserved
+                31 => reserved_identity_bit
+            },
+            cpu_management_ebx => {
+                0 => processor_power_management,
+                1 => mwait_idle_states,
+                2 => logical_processor_idling
+                // 3-31 - reserved
+            },
+            cpu_management_ecx => {
+                0 => remap_guest_uncached
+                // 1-31 - reserved
+            },
+            svm_eax => {
+                0 => shared_virtual_memory
+                // 1-10 - reserved, 11-31 - PASID count
+            },
+            // What a hypervisor nested under Hyper-V may use
+            nested_hypervisor_eax => {
+                // 0-1 - reserved
+                2 => nested_access_synic_regs,
+                // 3 - reserved
+                4 => nested_access_intr_ctrl_regs,
+                5 => nested_access_hypercall_msrs,
+                6 => nested_access_vp_index,
+                // 7-11 - reserved
+                12 => nested_access_reenlightenment_controls
+                // 13-31 - reserved
+            },
+            nested_hypervisor_edx => {
+                // 0-3 - reserved
+                4 => nested_xmm_hypercall_input,
+                // 5-14 - reserved
+                15 => nested_xmm_hypercall_output,
+                // 16 - reserved
+                17 => nested_sint_polling_mode
+                // 18-31 - reserved
+            },
+            nested_eax => {
+                // 0-16 - enlightened VMCS version
+                17 => direct_virtual_flush,
+                18 => flush_guest_physical_address_space,
+                19 => enlightened_msr_bitmap,
+                20 => combine_virtualization_exceptions,
+                // 21 - reserved
+                22 => enlightened_tlb
+                // 23-31 - reserved
+            },
+            nested_ebx => {
+                0 => consistent_perf_global_ctrl
+                // 1-31 - reserved
+            }
         }
     }
+}
 
-    master_attr_reader!(version_information, VersionInformation);
-    master_attr_reader!(thermal_power_management_information, ThermalPowerManagementInformation);
-    master_attr_reader!(structured_extended_information, StructuredExtendedInformation);
-    master_attr_reader!(extended_processor_signature, ExtendedProcessorSignature);
-    master_attr_reader!(cache_line, CacheLine);
-    master_attr_reader!(time_stamp_counter, TimeStampCounter);
-    master_attr_reader!(physical_address_size, PhysicalAddressSize);
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ExtendedProcessorSignature {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
 
-    pub fn brand_string(&self) -> Option<&str> {
-        self.brand_string.as_ref().map(|bs| bs as &str).or({
-            self.version_information.and_then(|vi| vi.brand_string())
-        })
+impl ExtendedProcessorSignature {
+    fn new<S: CpuidSource>(source: &S) -> ExtendedProcessorSignature {
+        let (a, b, c, d) = source.cpuid(RequestType::ExtendedProcessorSignature as u32, 0);
+        ExtendedProcessorSignature::from_registers(a, b, c, d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32, edx: u32) -> ExtendedProcessorSignature {
+        ExtendedProcessorSignature { eax, ebx, ecx, edx }
     }
 
-    delegate_flag!(version_information, {
-        sse3,
-        pclmulqdq,
-        dtes64,
-        monitor,
-        ds_cpl,
-        vmx,
-        smx,
-        eist,
-        tm2,
-        ssse3,
-        cnxt_id,
-        sdbg,
-        fma,
-        cmpxchg16b,
-        xtpr_update_control,
-        pdcm,
-        pcid,
-        dca,
-        sse4_1,
-        sse4_2,
-        x2apic,
-        movbe,
-        popcnt,
-        tsc_deadline,
-        aesni,
-        xsave,
-        osxsave,
-        avx,
-        f16c,
-        rdrand,
-        fpu,
-        vme,
-        de,
-        pse,
-        tsc,
-        msr,
-        pae,
-        mce,
-        cx8,
-        apic,
-        sep,
-        mtrr,
-        pge,
-        mca,
-        cmov,
-        pat,
-        pse_36,
-        psn,
-        clfsh,
-        ds,
-        acpi,
-        mmx,
-        fxsr,
-        sse,
-        sse2,
-        ss,
-        htt,
-        tm,
-        pbe
-    });
-
-    delegate_flag!(thermal_power_management_information, {
-        digital_temperature_sensor,
-        intel_turbo_boost,
-        arat,
-        pln,
-        ecmd,
-        ptm,
-        hwp,
-        hwp_notification,
-        hwp_activity_window,
-        hwp_energy_performance_preference,
-        hdc,
-        hardware_coordination_feedback,
-        performance_energy_bias
-    });
-
-    delegate_flag!(structured_extended_information, {
-        fsgsbase,
-        ia32_tsc_adjust_msr,
-        bmi1,
-        hle,
-        avx2,
-        smep,
-        bmi2,
-        enhanced_rep_movsb_stosb,
-        invpcid,
-        rtm,
-        pqm,
-        deprecates_fpu_cs_ds,
-        pqe,
-        rdseed,
-        adx,
-        smap,
-        intel_processor_trace,
-        prefetchwt1
-    });
-
-    delegate_flag!(extended_processor_signature, {
-        lahf_sahf_in_64_bit,
-        lzcnt,
-        prefetchw,
-        syscall_sysret_in_64_bit,
-        execute_disable,
-        gigabyte_pages,
-        rdtscp_and_ia32_tsc_aux,
-        intel_64_bit_architecture
-    });
-
-    delegate_flag!(time_stamp_counter, {
-        invariant_tsc
-    });
+    registers!(eax, ebx, ecx, edx);
 }
 
-/// The main entrypoint to the CPU information
-#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
-pub fn master() -> Option<Master> {
-    Some(Master::new())
+leaf! {
+    impl ExtendedProcessorSignature {
+        raw { eax, ebx, ecx, edx }
+
+        // EAX and EBX are reserved by Intel. AMD repeats the signature of
+        // leaf 1 in EAX, but only adds the extended model to family 0xF.
+
+        pub fn family_id(self) -> u32 {
+            let family_id = bits_of(self.eax, 8, 11);
+            if family_id != 0x0F {
+                family_id
+            } else {
+                bits_of(self.eax, 20, 27) + family_id
+            }
+        }
+
+        pub fn model_id(self) -> u32 {
+            let model_id = bits_of(self.eax, 4, 7);
+            if bits_of(self.eax, 8, 11) != 0x0F {
+                model_id
+            } else {
+                (bits_of(self.eax, 16, 19) << 4) + model_id
+            }
+        }
+
+        pub fn stepping(self) -> u32 {
+            bits_of(self.eax, 0, 3)
+        }
+
+        /// The AMD package type, such as the socket, whose meaning
+        /// depends on the family.
+        pub fn package_type(self) -> u32 {
+            bits_of(self.ebx, 28, 31)
+        }
+
+        // Bits reserved by Intel are AMD-specific extensions.
+        flags {
+            ecx => {
+                0 => lahf_sahf_in_64_bit,
+                1 => cmp_legacy,
+                2 => svm,
+                3 => ext_apic_space,
+                4 => alt_mov_cr8,
+                5 => lzcnt, // AMD: ABM
+                6 => sse4a,
+                7 => misaligned_sse,
+                8 => prefetchw, // AMD: 3DNowPrefetch
+                9 => osvw,
+                10 => ibs,
+                11 => xop,
+                12 => skinit,
+                13 => wdt,
+                // 14 reserved
+                15 => lwp,
+                16 => fma4,
+                17 => tce,
+                // 18-20 reserved
+                21 => tbm,
+                22 => topology_extensions,
+                23 => perf_ctr_ext_core,
+                24 => perf_ctr_ext_nb,
+                // 25 reserved
+                26 => data_breakpoint_extension,
+                27 => perf_tsc,
+                28 => perf_ctr_ext_llc,
+                29 => monitorx,
+                30 => addr_mask_ext
+                // 31 reserved
+            },
+            edx => {
+                // 0-10 AMD: duplicates of leaf 1
+                11 => syscall_sysret_in_64_bit,
+                // 12-17 AMD: duplicates of leaf 1
+                // 18-19 reserved
+                20 => execute_disable, // AMD: NX
+                // 21 reserved
+                22 => mmxext,
+                // 23-24 AMD: duplicates of leaf 1
+                25 => ffxsr,
+                26 => gigabyte_pages,
+                27 => rdtscp_and_ia32_tsc_aux,
+                // 28 reserved
+                29 => intel_64_bit_architecture, // AMD: LM
+                30 => three_dnow_ext,
+                31 => three_dnow
+            }
+        }
+    }
 }
 
-/// The main entrypoint to the CPU information
-#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
-pub fn master() -> Option<Master> {
-    None
+/// The family, model and stepping, decoded by the rules of the
+/// processor's vendor. These are the values that errata and
+/// microarchitecture tables are indexed by.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProcessorSignature {
+    pub family: u32,
+    pub model: u32,
+    pub stepping: u32,
 }
 
-#[test]
-#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
-fn basic_genuine_intel() {
-    let (_, b, c, d) = cpuid(RequestType::BasicInformation);
+/// Formats the signature as vendors' documentation does, such as
+/// "Family 19h, Model 21h, Stepping 0".
+impl fmt::Display for ProcessorSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Family {:X}h, Model {:X}h, Stepping {}", self.family, self.model, self.stepping)
+    }
+}
 
-    assert_eq!(b"Genu", as_bytes(&b));
-    assert_eq!(b"ntel", as_bytes(&c));
-    assert_eq!(b"ineI", as_bytes(&d));
+// Without the extended range, leaf 0x8000_0000 may instead return the
+// registers of the highest basic leaf
+const fn in_extended_range(leaf: u32) -> bool {
+    leaf & 0xFFFF_0000 == RequestType::ExtendedFunctionInformation as u32
 }
 
-#[test]
-fn brand_string_contains_intel() {
-    assert!(master().unwrap().brand_string().unwrap().contains("Intel(R)"))
+// Writes a number in upper-case hexadecimal without leading zeros,
+// without the formatting machinery
+fn push_hex<F: FnMut(&str)>(push: &mut F, value: u32) {
+    const DIGITS: &str = "0123456789ABCDEF";
+    let mut started = false;
+    for shift in (0..8).rev() {
+        let digit = ((value >> (shift * 4)) & 0xF) as usize;
+        if digit != 0 || started || shift == 0 {
+            started = true;
+            push(DIGITS.get(digit..digit + 1).unwrap_or("0"));
+        }
+    }
+}
+
+// 3 calls of 4 registers of 4 bytes
+const BRAND_STRING_LENGTH: usize = 3 * 4 * 4;
+
+/// The processor's name, as reported by leaves 0x8000_0002 to
+/// 0x8000_0004, or as made up by `Master::synthesized_brand_string`.
+///
+/// It is a plain 48-byte value: `Copy`, `Send` and `Sync`, so it can be
+/// cached or shared between threads freely. Equality and hashing are
+/// of the text given by `as_str`, so padding does not matter, and it
+/// compares directly with `str`.
+#[derive(Copy, Clone)]
+pub struct BrandString {
+    bytes: [u8; BRAND_STRING_LENGTH],
+}
+
+impl BrandString {
+    fn new<S: CpuidSource>(source: &S) -> BrandString {
+        let leaves = [
+            RequestType::BrandString1 as u32,
+            RequestType::BrandString2 as u32,
+            RequestType::BrandString3 as u32,
+        ];
+
+        let mut brand_string = BrandString { bytes: [0; BRAND_STRING_LENGTH] };
+
+        // Zipping rather than slicing leaves no bounds checks behind
+        for (bytes, &leaf) in brand_string.bytes.chunks_mut(16).zip(leaves.iter()) {
+            let (a, b, c, d) = source.cpuid(leaf, 0);
+
+            let result_bytes =
+                le_bytes(a)
+                .chain(le_bytes(b))
+                .chain(le_bytes(c))
+                .chain(le_bytes(d));
+
+            for (output, input) in bytes.iter_mut().zip(result_bytes) {
+                *output = input
+            }
+        }
+
+        brand_string
+    }
+
+    // A name for processors without the brand string leaves, from the
+    // brand index table or the vendor and signature
+    fn synthesize(info: &Master) -> Option<BrandString> {
+        let mut brand_string = BrandString { bytes: [0; BRAND_STRING_LENGTH] };
+        let mut len = 0;
+        {
+            let mut push = |s: &str| {
+                for (output, &input) in brand_string.bytes.iter_mut().skip(len).zip(s.as_bytes()) {
+                    *output = input;
+                    len += 1;
+                }
+            };
+
+            if let Some(name) = info.version_information.and_then(|vi| vi.brand_string()) {
+                push(name);
+            } else {
+                let signature = info.processor_signature()?;
+                push(match info.vendor_id() {
+                    "GenuineIntel" => "Intel",
+                    "AuthenticAMD" => "AMD",
+                    "HygonGenuine" => "Hygon",
+                    "CentaurHauls" => "Centaur",
+                    "  Shanghai  " => "Zhaoxin",
+                    vendor => vendor.trim(),
+                });
+                if let Some(uarch) = info.microarchitecture() {
+                    push(" ");
+                    push(uarch.name());
+                }
+                push(" Family ");
+                push_hex(&mut push, signature.family);
+                push("h Model ");
+                push_hex(&mut push, signature.model);
+                push("h");
+            }
+        }
+        Some(brand_string)
+    }
+
+    /// The brand string without its NUL padding, and without the
+    /// leading spaces that Intel uses to right-justify it.
+    #[cfg_attr(all(test, not(debug_assertions)), no_panic)]
+    pub fn as_str(&self) -> &str {
+        // The string fills all 48 bytes when it has no terminator
+        let nul_terminator = self.bytes.iter().position(|&b| b == 0).unwrap_or(BRAND_STRING_LENGTH);
+        let bytes = self.bytes.get(..nul_terminator).unwrap_or(&[]);
+        // Brand strings are ASCII, so anything from the first other byte
+        // on is dropped rather than validated as UTF-8, which the
+        // compiler cannot prove free of panics
+        let ascii = bytes.iter().position(|b| !b.is_ascii()).unwrap_or(bytes.len());
+        let bytes = bytes.get(..ascii).unwrap_or(&[]).trim_ascii();
+        unsafe { str::from_utf8_unchecked(bytes) }
+    }
+}
+
+// Parses the nominal frequency from a suffix such as "@ 3.60GHz"
+fn brand_frequency_hz(brand: &str) -> Option<u64> {
+    let at = brand.rfind('@')?;
+    let text = brand.get(at + 1..)?.trim();
+
+    let (number, multiplier) = if let Some(n) = text.strip_suffix("GHz") {
+        (n, 1_000_000_000)
+    } else if let Some(n) = text.strip_suffix("MHz") {
+        (n, 1_000_000)
+    } else {
+        return None;
+    };
+    let number = number.trim_end();
+
+    let (whole, fraction) = match number.find('.') {
+        Some(dot) => (&number[..dot], &number[dot + 1..]),
+        None => (number, ""),
+    };
+    if whole.is_empty() || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut hz = whole.parse::<u64>().ok()?.checked_mul(multiplier)?;
+    let mut scale = multiplier;
+    for digit in fraction.bytes() {
+        scale /= 10;
+        hz = hz.checked_add(u64::from(digit - b'0') * scale)?;
+    }
+    Some(hz)
+}
+
+// Drops the trademark marks, the word "CPU" and the frequency suffix,
+// and collapses the whitespace left behind
+#[cfg(feature = "std")]
+fn normalize_brand(brand: &str) -> std::string::String {
+    let brand = match brand.rfind('@') {
+        Some(at) => &brand[..at],
+        None => brand,
+    };
+    let mut brand = std::string::String::from(brand);
+    for mark in &["(R)", "(r)", "(TM)", "(tm)"] {
+        brand = brand.replace(mark, "");
+    }
+    let words: std::vec::Vec<_> = brand.split_whitespace().filter(|&w| w != "CPU").collect();
+    words.join(" ")
+}
+
+impl Deref for BrandString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for BrandString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for BrandString {
+    fn eq(&self, other: &BrandString) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for BrandString {}
+
+impl hash::Hash for BrandString {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialEq<BrandString> for str {
+    fn eq(&self, other: &BrandString) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<BrandString> for &str {
+    fn eq(&self, other: &BrandString) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<str> for BrandString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for BrandString {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<BrandString> for std::string::String {
+    fn from(brand_string: BrandString) -> std::string::String {
+        brand_string.as_str().into()
+    }
+}
+
+impl fmt::Display for BrandString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl fmt::Debug for BrandString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for BrandString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BrandString {
+    fn deserialize<D>(deserializer: D) -> Result<BrandString, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        struct BrandStringVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BrandStringVisitor {
+            type Value = BrandString;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a brand string of at most {} bytes", BRAND_STRING_LENGTH)
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<BrandString, E>
+                where E: serde::de::Error
+            {
+                if v.len() > BRAND_STRING_LENGTH {
+                    return Err(E::invalid_length(v.len(), &self));
+                }
+                let mut brand_string = BrandString { bytes: [0; BRAND_STRING_LENGTH] };
+                for (d, s) in brand_string.bytes.iter_mut().zip(v.bytes()) {
+                    *d = s;
+                }
+                Ok(brand_string)
+            }
+        }
+
+        deserializer.deserialize_str(BrandStringVisitor)
+    }
+}
+
+/// The 96-bit processor serial number of the Pentium III and some
+/// embedded processors. Its upper 32 bits are the processor signature
+/// from leaf 1, and the rest come from leaf 3.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessorSerialNumber {
+    signature: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+impl ProcessorSerialNumber {
+    fn new<S: CpuidSource>(source: &S, signature: u32) -> ProcessorSerialNumber {
+        let (_, _, c, d) = source.cpuid(RequestType::ProcessorSerialNumber as u32, 0);
+        ProcessorSerialNumber::from_registers(signature, c, d)
+    }
+
+    /// Decodes register values already read from this leaf, with EAX of
+    /// leaf 1 as the signature.
+    pub const fn from_registers(signature: u32, ecx: u32, edx: u32) -> ProcessorSerialNumber {
+        ProcessorSerialNumber { signature, ecx, edx }
+    }
+
+    registers!(ecx, edx);
+}
+
+leaf! {
+    impl ProcessorSerialNumber {
+        raw { signature, ecx, edx }
+
+        /// The serial number as a single 96-bit value.
+        pub fn value(self) -> u128 {
+            (self.signature as u128) << 64 | (self.edx as u128) << 32 | self.ecx as u128
+        }
+    }
+}
+
+/// Formats the serial number as Intel does, in six groups of four
+/// hexadecimal digits from the most significant.
+impl fmt::Display for ProcessorSerialNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04X}-{:04X}-{:04X}-{:04X}-{:04X}-{:04X}",
+               self.signature >> 16, self.signature & 0xFFFF,
+               self.edx >> 16, self.edx & 0xFFFF,
+               self.ecx >> 16, self.ecx & 0xFFFF)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ThermalPowerManagementInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+impl ThermalPowerManagementInformation {
+    fn new<S: CpuidSource>(source: &S) -> ThermalPowerManagementInformation {
+        let (a, b, c, d) = source.cpuid(RequestType::ThermalPowerManagementInformation as u32, 0);
+        ThermalPowerManagementInformation::from_registers(a, b, c, d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32, edx: u32) -> ThermalPowerManagementInformation {
+        ThermalPowerManagementInformation { eax, ebx, ecx, edx }
+    }
+
+    registers!(eax, ebx, ecx, edx);
+}
+
+leaf! {
+    impl ThermalPowerManagementInformation {
+        raw { eax, ebx, ecx, edx }
+
+        pub fn number_of_interrupt_thresholds(self) -> u32 {
+            bits_of(self.ebx, 0, 3)
+        }
+
+        /// The number of classes Intel Thread Director sorts work into,
+        /// or zero without Thread Director.
+        pub fn thread_director_classes(self) -> u32 {
+            bits_of(self.ecx, 8, 15)
+        }
+
+        /// The size of the hardware feedback interface table, in bytes.
+        /// The table holds a row per logical processor.
+        pub fn hw_feedback_table_size(self) -> u32 {
+            (bits_of(self.edx, 8, 11) + 1) * 4096
+        }
+
+        /// This logical processor's row of the hardware feedback
+        /// interface table.
+        pub fn hw_feedback_table_index(self) -> u32 {
+            bits_of(self.edx, 16, 31)
+        }
+
+        flags {
+            eax => {
+                0 => digital_temperature_sensor,
+                1 => intel_turbo_boost,
+                2 => arat,
+                // 3 - reserved
+                4 => pln,
+                5 => ecmd,
+                6 => ptm,
+                7 => hwp,
+                8 => hwp_notification,
+                9 => hwp_activity_window,
+                10 => hwp_energy_performance_preference,
+                // 12 - reserved
+                13 => hdc,
+                14 => turbo_boost_max_3,
+                15 => hwp_highest_performance_change,
+                16 => hwp_peci_override,
+                17 => flexible_hwp,
+                18 => fast_hwp_request,
+                19 => hw_feedback,
+                20 => hwp_ignore_idle_logical_processor
+            },
+            ecx => {
+                0 => hardware_coordination_feedback,
+                // 1-2 - reserved
+                3 => performance_energy_bias
+            },
+            edx => {
+                0 => hw_feedback_performance,
+                1 => hw_feedback_efficiency
+                // 2-7 - reserved
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct StructuredExtendedInformation {
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+impl StructuredExtendedInformation {
+    fn new<S: CpuidSource>(source: &S) -> StructuredExtendedInformation {
+        let (_, b, c, d) = source.cpuid(RequestType::StructuredExtendedInformation as u32, 0);
+        StructuredExtendedInformation::from_registers(b, c, d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(ebx: u32, ecx: u32, edx: u32) -> StructuredExtendedInformation {
+        StructuredExtendedInformation { ebx, ecx, edx }
+    }
+
+    registers!(ebx, ecx, edx);
+}
+
+leaf! {
+    impl StructuredExtendedInformation {
+        raw { ebx, ecx, edx }
+
+        flags {
+            ebx => {
+                0 => fsgsbase,
+                1 => ia32_tsc_adjust_msr,
+                2 => sgx,
+                3 => bmi1,
+                4 => hle,
+                5 => avx2,
+                6 => fdp_excptn_only,
+                7 => smep,
+                8 => bmi2,
+                9 => enhanced_rep_movsb_stosb,
+                10 => invpcid,
+                11 => rtm,
+                12 => pqm,
+                13 => deprecates_fpu_cs_ds,
+                14 => mpx,
+                15 => pqe,
+                16 => avx512f,
+                17 => avx512dq,
+                18 => rdseed,
+                19 => adx,
+                20 => smap,
+                21 => avx512_ifma,
+                // 22 - reserved
+                23 => clflushopt,
+                24 => clwb,
+                25 => intel_processor_trace,
+                26 => avx512pf,
+                27 => avx512er,
+                28 => avx512cd,
+                29 => sha,
+                30 => avx512bw,
+                31 => avx512vl
+            },
+            ecx => {
+                0 => prefetchwt1,
+                1 => avx512_vbmi,
+                // 2-4 - not yet decoded
+                5 => waitpkg,
+                6 => avx512_vbmi2,
+                7 => cet_ss,
+                8 => gfni,
+                9 => vaes,
+                10 => vpclmulqdq,
+                11 => avx512_vnni,
+                12 => avx512_bitalg,
+                // 13 - not yet decoded
+                14 => avx512_vpopcntdq,
+                // 15 - reserved
+                16 => la57,
+                // 17-21 - not yet decoded
+                22 => rdpid,
+                23 => kl,
+                // 24 - not yet decoded
+                25 => cldemote,
+                // 26 - reserved
+                27 => movdiri,
+                28 => movdir64b
+            },
+            edx => {
+                // 0-1 - not yet decoded
+                2 => avx512_4vnniw,
+                3 => avx512_4fmaps,
+                // 4 - not yet decoded
+                5 => uintr,
+                // 6-7 - not yet decoded
+                8 => avx512_vp2intersect,
+                9 => srbds_ctrl,
+                10 => md_clear,
+                11 => rtm_always_abort,
+                // 12-13 - not yet decoded
+                14 => serialize,
+                15 => hybrid,
+                // 16-17 - not yet decoded
+                18 => pconfig,
+                19 => arch_lbr,
+                20 => cet_ibt,
+                // 21 - not yet decoded
+                22 => amx_bf16,
+                23 => avx512_fp16,
+                24 => amx_tile,
+                25 => amx_int8,
+                26 => ibrs_ibpb,
+                27 => stibp,
+                28 => l1d_flush,
+                29 => arch_capabilities,
+                30 => core_capabilities,
+                31 => ssbd
+            }
+        }
+    }
+}
+
+/// Sub-leaf 1 of the structured extended feature leaf, holding the
+/// newer features.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct StructuredExtendedInformation1 {
+    eax: u32,
+    edx: u32,
+}
+
+impl StructuredExtendedInformation1 {
+    // Sub-leaf 0 reports the highest sub-leaf in EAX
+    fn new<S: CpuidSource>(source: &S) -> Option<StructuredExtendedInformation1> {
+        let leaf = RequestType::StructuredExtendedInformation as u32;
+        let (max_subleaf, _, _, _) = source.cpuid(leaf, 0);
+        if max_subleaf < 1 {
+            return None;
+        }
+        let (a, _, _, d) = source.cpuid(leaf, 1);
+        Some(StructuredExtendedInformation1::from_registers(a, d))
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, edx: u32) -> StructuredExtendedInformation1 {
+        StructuredExtendedInformation1 { eax, edx }
+    }
+
+    registers!(eax, edx);
+}
+
+leaf! {
+    impl StructuredExtendedInformation1 {
+        raw { eax, edx }
+
+        flags {
+            eax => {
+                0 => sha512,
+                1 => sm3,
+                2 => sm4,
+                3 => rao_int,
+                4 => avx_vnni,
+                5 => avx512_bf16,
+                6 => lass,
+                7 => cmpccxadd,
+                8 => arch_perfmon_ext,
+                // 9 - reserved
+                10 => fzlrm,
+                11 => fsrs,
+                12 => fsrcs,
+                // 13-16 - reserved
+                17 => fred,
+                18 => lkgs,
+                19 => wrmsrns,
+                // 20 - not yet decoded
+                21 => amx_fp16,
+                22 => hreset,
+                23 => avx_ifma,
+                // 24-25 - reserved
+                26 => lam,
+                27 => msrlist
+            },
+            edx => {
+                // 0-3 - reserved
+                4 => avx_vnni_int8,
+                5 => avx_ne_convert,
+                // 6-7 - reserved
+                8 => amx_complex,
+                // 9 - reserved
+                10 => avx_vnni_int16,
+                // 11-13 - reserved
+                14 => prefetchi,
+                // 15-16 - reserved
+                17 => uiret_uif_from_rflags,
+                18 => cet_sss,
+                19 => avx10,
+                // 20 - reserved
+                21 => apx_f
+            }
+        }
+    }
+}
+
+/// An event that every processor with an architectural performance
+/// monitoring unit counts the same way, unless it reports otherwise.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum ArchEvent {
+    CoreCycles = 0,
+    InstructionsRetired = 1,
+    ReferenceCycles = 2,
+    LlcReferences = 3,
+    LlcMisses = 4,
+    BranchInstructionsRetired = 5,
+    BranchMispredictsRetired = 6,
+    TopdownSlots = 7,
+}
+
+/// The architectural performance monitoring unit: how many counters it
+/// has, how wide they are and which events they can count.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PerformanceMonitoringInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+impl PerformanceMonitoringInformation {
+    // A version of zero means there is no architectural PMU, as on AMD
+    fn new<S: CpuidSource>(source: &S) -> Option<PerformanceMonitoringInformation> {
+        let (a, b, c, d) = source.cpuid(RequestType::PerformanceMonitoring as u32, 0);
+        let pmu = PerformanceMonitoringInformation::from_registers(a, b, c, d);
+        if pmu.version_id() != 0 { Some(pmu) } else { None }
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32, edx: u32) -> PerformanceMonitoringInformation {
+        PerformanceMonitoringInformation { eax, ebx, ecx, edx }
+    }
+
+    registers!(eax, ebx, ecx, edx);
+
+    /// Whether `event` can be counted. An event is unavailable if it is
+    /// beyond the events the processor enumerates or if its bit in EBX
+    /// is set.
+    pub fn supports_event(self, event: ArchEvent) -> bool {
+        let index = event as u32;
+        index < self.event_count() && self.ebx & (1 << index) == 0
+    }
+
+    /// Whether fixed-function counter `index` exists. Since version 5 the
+    /// counters need not be contiguous, so ECX lists them as a bitmap.
+    pub fn has_fixed_function_counter(self, index: u32) -> bool {
+        index < self.fixed_function_counters() || (index < 32 && self.ecx & (1 << index) != 0)
+    }
+}
+
+leaf! {
+    impl PerformanceMonitoringInformation {
+        raw { eax, ebx, ecx, edx }
+
+        pub fn version_id(self) -> u32 {
+            bits_of(self.eax, 0, 7)
+        }
+
+        /// General-purpose counters per logical processor.
+        pub fn general_purpose_counters(self) -> u32 {
+            bits_of(self.eax, 8, 15)
+        }
+
+        pub fn counter_width_bits(self) -> u32 {
+            bits_of(self.eax, 16, 23)
+        }
+
+        /// How many architectural events EBX enumerates.
+        pub fn event_count(self) -> u32 {
+            bits_of(self.eax, 24, 31)
+        }
+
+        /// Contiguous fixed-function counters, starting from counter 0.
+        pub fn fixed_function_counters(self) -> u32 {
+            if self.version_id() > 1 { bits_of(self.edx, 0, 4) } else { 0 }
+        }
+
+        pub fn fixed_counter_width_bits(self) -> u32 {
+            if self.version_id() > 1 { bits_of(self.edx, 5, 12) } else { 0 }
+        }
+
+        flags {
+            edx => {
+                15 => any_thread_deprecated
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum TopologyLevelType {
+    Smt,
+    Core,
+    Module,
+    Tile,
+    Die,
+    DieGroup,
+}
+
+/// A single level of the processor topology, as enumerated by leaf 0xB
+/// or its successor, leaf 0x1F.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TopologyLevel {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+impl TopologyLevel {
+    registers!(eax, ebx, ecx, edx);
+
+    pub fn level_number(self) -> u32 {
+        bits_of(self.ecx, 0, 7)
+    }
+}
+
+leaf! {
+    impl TopologyLevel {
+        raw { eax, ebx, ecx, edx }
+
+        pub fn level_type(self) -> Option<TopologyLevelType> {
+            match bits_of(self.ecx, 8, 15) {
+                1 => Some(TopologyLevelType::Smt),
+                2 => Some(TopologyLevelType::Core),
+                3 => Some(TopologyLevelType::Module),
+                4 => Some(TopologyLevelType::Tile),
+                5 => Some(TopologyLevelType::Die),
+                6 => Some(TopologyLevelType::DieGroup),
+                _ => None,
+            }
+        }
+
+        /// How far to shift the x2APIC ID right to identify the level above
+        /// this one.
+        pub fn apic_id_shift(self) -> u32 {
+            bits_of(self.eax, 0, 4)
+        }
+
+        /// The number of logical processors at this level, within the level
+        /// above it. Only meant for display; hybrid processors report what
+        /// the querying core has.
+        pub fn logical_processors(self) -> u32 {
+            bits_of(self.ebx, 0, 15)
+        }
+
+        /// The x2APIC ID of the querying logical processor.
+        pub fn x2apic_id(self) -> u32 {
+            self.edx
+        }
+    }
+}
+
+// SMT, core, module, tile, die and die group
+const MAX_TOPOLOGY_LEVELS: usize = 6;
+
+/// Every level of the processor topology, from the innermost outwards.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ExtendedTopology {
+    levels: [TopologyLevel; MAX_TOPOLOGY_LEVELS],
+    len: usize,
+}
+
+impl ExtendedTopology {
+    // Processors without the leaf report no levels at all
+    fn new<S: CpuidSource>(source: &S, leaf: RequestType) -> Option<ExtendedTopology> {
+        let leaf = leaf as u32;
+        let empty = TopologyLevel { eax: 0, ebx: 0, ecx: 0, edx: 0 };
+        let mut levels = [empty; MAX_TOPOLOGY_LEVELS];
+        let mut len = 0;
+
+        for (subleaf, level) in levels.iter_mut().enumerate() {
+            let (a, b, c, d) = source.cpuid(leaf, subleaf as u32);
+
+            // A level type of zero terminates the list
+            if bits_of(c, 8, 15) == 0 {
+                break;
+            }
+
+            *level = TopologyLevel { eax: a, ebx: b, ecx: c, edx: d };
+            len += 1;
+        }
+
+        if len == 0 {
+            None
+        } else {
+            Some(ExtendedTopology { levels, len })
+        }
+    }
+
+    pub fn levels(&self) -> &[TopologyLevel] {
+        self.levels.get(..self.len).unwrap_or(&[])
+    }
+
+    pub fn level(&self, level_type: TopologyLevelType) -> Option<TopologyLevel> {
+        self.levels().iter().cloned().find(|l| l.level_type() == Some(level_type))
+    }
+
+    /// The number of logical processors in the package, which Intel
+    /// documents as for display only, not for counting the processors
+    /// that are enabled.
+    pub fn logical_processors(&self) -> u32 {
+        self.levels().last().map(|l| l.logical_processors()).unwrap_or(0)
+    }
+
+    /// The number of logical processors in each core.
+    pub fn threads_per_core(&self) -> u32 {
+        self.level(TopologyLevelType::Smt).map(|l| l.logical_processors()).unwrap_or(1)
+    }
+
+    // The x2APIC ID bits below the core and below the package
+    fn smt_shift(&self) -> u32 {
+        self.level(TopologyLevelType::Smt).map(|l| l.apic_id_shift()).unwrap_or(0)
+    }
+
+    fn package_shift(&self) -> u32 {
+        self.levels().last().map(|l| l.apic_id_shift()).unwrap_or(0)
+    }
+
+    /// The number of the logical processor with `x2apic_id` within its
+    /// core.
+    pub fn smt_id(&self, x2apic_id: u32) -> u32 {
+        x2apic_id & low_bits(self.smt_shift())
+    }
+
+    /// The number of the core holding the logical processor with
+    /// `x2apic_id`, unique within its package. Cores need not be
+    /// numbered contiguously; the IDs of disabled cores are skipped.
+    pub fn core_id(&self, x2apic_id: u32) -> u32 {
+        let within_package = x2apic_id & low_bits(self.package_shift());
+        within_package.checked_shr(self.smt_shift()).unwrap_or(0)
+    }
+
+    /// The number of the package, or socket, holding the logical
+    /// processor with `x2apic_id`.
+    pub fn package_id(&self, x2apic_id: u32) -> u32 {
+        x2apic_id.checked_shr(self.package_shift()).unwrap_or(0)
+    }
+}
+
+fn low_bits(count: u32) -> u32 {
+    1u32.checked_shl(count).map_or(!0, |bit| bit - 1)
+}
+
+impl fmt::Debug for ExtendedTopology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.levels()).finish()
+    }
+}
+
+/// A state component that XSAVE can save and restore, numbered by its
+/// bit in XCR0 or `IA32_XSS`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum XsaveComponent {
+    X87 = 0,
+    Sse = 1,
+    Avx = 2,
+    MpxBoundRegisters = 3,
+    MpxBoundConfiguration = 4,
+    Opmask = 5,
+    ZmmHi256 = 6,
+    Hi16Zmm = 7,
+    ProcessorTrace = 8,
+    Pkru = 9,
+    Pasid = 10,
+    CetUser = 11,
+    CetSupervisor = 12,
+    Hdc = 13,
+    Uintr = 14,
+    Lbr = 15,
+    Hwp = 16,
+    TileConfig = 17,
+    TileData = 18,
+    Apx = 19,
+}
+
+/// The state components XSAVE supports and the instructions that
+/// manage them, from sub-leaves 0 and 1 of leaf 0xD.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ExtendedStateInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+    eax_1: u32,
+    ecx_1: u32,
+    edx_1: u32,
+}
+
+impl ExtendedStateInformation {
+    fn new<S: CpuidSource>(source: &S) -> ExtendedStateInformation {
+        let leaf = RequestType::ExtendedState as u32;
+        ExtendedStateInformation::from_registers(source.cpuid(leaf, 0), source.cpuid(leaf, 1))
+    }
+
+    /// Decodes register values already read from sub-leaves 0 and 1.
+    pub const fn from_registers(subleaf_0: Registers, subleaf_1: Registers) -> ExtendedStateInformation {
+        let (a, b, c, d) = subleaf_0;
+        let (a1, _, c1, d1) = subleaf_1;
+        ExtendedStateInformation { eax: a, ebx: b, ecx: c, edx: d, eax_1: a1, ecx_1: c1, edx_1: d1 }
+    }
+
+    registers!(eax, ebx, ecx, edx);
+
+    /// Whether the processor supports saving the component, through
+    /// XCR0 for user state or `IA32_XSS` for supervisor state. Whether
+    /// the operating system has enabled it is a separate matter.
+    pub fn supports_component(self, component: XsaveComponent) -> bool {
+        (self.xcr0_components() | self.xss_components()) & (1 << component as u32) != 0
+    }
+}
+
+leaf! {
+    impl ExtendedStateInformation {
+        raw { eax, ebx, ecx, edx, eax_1, ecx_1, edx_1 }
+
+        /// The user state components that can be enabled in XCR0.
+        pub fn xcr0_components(self) -> u64 {
+            (self.edx as u64) << 32 | self.eax as u64
+        }
+
+        /// The supervisor state components that can be enabled in
+        /// `IA32_XSS`.
+        pub fn xss_components(self) -> u64 {
+            (self.edx_1 as u64) << 32 | self.ecx_1 as u64
+        }
+
+        /// The size in bytes of the XSAVE area for the components
+        /// currently enabled in XCR0.
+        pub fn enabled_area_size(self) -> u32 {
+            self.ebx
+        }
+
+        /// The size in bytes of the XSAVE area for every supported user
+        /// state component.
+        pub fn max_area_size(self) -> u32 {
+            self.ecx
+        }
+
+        flags {
+            eax_1 => {
+                0 => xsaveopt,
+                1 => xsavec,
+                2 => xgetbv_ecx1,
+                3 => xsaves,
+                4 => xfd
+            }
+        }
+    }
+}
+
+/// Intel Resource Director Technology (RDT) monitoring capabilities.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RdtMonitoringInformation {
+    max_rmid: u32,
+    resources: u32,
+    l3_eax: u32,
+    l3_ebx: u32,
+    l3_ecx: u32,
+    l3_edx: u32,
+}
+
+impl RdtMonitoringInformation {
+    fn new<S: CpuidSource>(source: &S) -> RdtMonitoringInformation {
+        let (_, b, _, d) = source.cpuid(RequestType::RdtMonitoring as u32, 0);
+        let mut info = RdtMonitoringInformation {
+            max_rmid: b,
+            resources: d,
+            l3_eax: 0,
+            l3_ebx: 0,
+            l3_ecx: 0,
+            l3_edx: 0,
+        };
+
+        if info.l3_monitoring() {
+            let (a, b, c, d) = source.cpuid(RequestType::RdtMonitoring as u32, 1);
+            info.l3_eax = a;
+            info.l3_ebx = b;
+            info.l3_ecx = c;
+            info.l3_edx = d;
+        }
+
+        info
+    }
+}
+
+leaf! {
+    impl RdtMonitoringInformation {
+        raw { max_rmid, resources, l3_eax, l3_ebx, l3_ecx, l3_edx }
+
+        /// The highest RMID of any resource type.
+        pub fn max_rmid(self) -> u32 {
+            self.max_rmid
+        }
+
+        /// The highest RMID usable for L3 monitoring.
+        pub fn l3_max_rmid(self) -> u32 {
+            self.l3_ecx
+        }
+
+        /// Multiply IA32_QM_CTR values by this to obtain bytes.
+        pub fn l3_counter_scaling_factor(self) -> u32 {
+            self.l3_ebx
+        }
+
+        /// The width of the IA32_QM_CTR counter, in bits.
+        pub fn l3_counter_width(self) -> u32 {
+            24 + bits_of(self.l3_eax, 0, 7)
+        }
+
+        flags {
+            resources => {
+                // 0 - reserved
+                1 => l3_monitoring
+                // 2-31 - reserved
+            },
+            l3_edx => {
+                0 => l3_occupancy_monitoring,
+                1 => l3_total_bandwidth_monitoring,
+                2 => l3_local_bandwidth_monitoring
+                // 3-31 - reserved
+            }
+        }
+    }
+}
+
+/// Cache allocation technology (CAT) details for one cache level.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CacheAllocationInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+impl CacheAllocationInformation {
+    fn new<S: CpuidSource>(source: &S, res_id: u32) -> CacheAllocationInformation {
+        let (a, b, c, d) = source.cpuid(RequestType::RdtAllocation as u32, res_id);
+        CacheAllocationInformation { eax: a, ebx: b, ecx: c, edx: d }
+    }
+
+    registers!(eax, ebx, ecx, edx);
+}
+
+leaf! {
+    impl CacheAllocationInformation {
+        raw { eax, ebx, ecx, edx }
+
+        /// The length of the capacity bitmask, in bits.
+        pub fn capacity_bitmask_length(self) -> u32 {
+            bits_of(self.eax, 0, 4) + 1
+        }
+
+        /// Allocation units that may be shared with other entities, such
+        /// as integrated graphics.
+        pub fn shareable_resource_bitmap(self) -> u32 {
+            self.ebx
+        }
+
+        /// The number of classes of service.
+        pub fn cos_count(self) -> u32 {
+            bits_of(self.edx, 0, 15) + 1
+        }
+
+        flags {
+            ecx => {
+                // 0-1 - reserved
+                2 => code_and_data_prioritization,
+                3 => non_contiguous_capacity_bitmask
+                // 4-31 - reserved
+            }
+        }
+    }
+}
+
+/// Memory bandwidth allocation (MBA) details.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MemoryBandwidthAllocationInformation {
+    eax: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+impl MemoryBandwidthAllocationInformation {
+    fn new<S: CpuidSource>(source: &S) -> MemoryBandwidthAllocationInformation {
+        let (a, _, c, d) = source.cpuid(RequestType::RdtAllocation as u32, 3);
+        MemoryBandwidthAllocationInformation::from_registers(a, c, d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ecx: u32, edx: u32) -> MemoryBandwidthAllocationInformation {
+        MemoryBandwidthAllocationInformation { eax, ecx, edx }
+    }
+
+    registers!(eax, ecx, edx);
+}
+
+leaf! {
+    impl MemoryBandwidthAllocationInformation {
+        raw { eax, ecx, edx }
+
+        pub fn max_throttling_value(self) -> u32 {
+            bits_of(self.eax, 0, 11) + 1
+        }
+
+        /// The number of classes of service.
+        pub fn cos_count(self) -> u32 {
+            bits_of(self.edx, 0, 15) + 1
+        }
+
+        flags {
+            ecx => {
+                // 0-1 - reserved
+                2 => linear_delay
+                // 3-31 - reserved
+            }
+        }
+    }
+}
+
+/// Intel Resource Director Technology (RDT) allocation capabilities.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RdtAllocationInformation {
+    ebx: u32,
+    l3: Option<CacheAllocationInformation>,
+    l2: Option<CacheAllocationInformation>,
+    mba: Option<MemoryBandwidthAllocationInformation>,
+}
+
+impl RdtAllocationInformation {
+    fn new<S: CpuidSource>(source: &S) -> RdtAllocationInformation {
+        let (_, b, _, _) = source.cpuid(RequestType::RdtAllocation as u32, 0);
+        let mut info = RdtAllocationInformation { ebx: b, l3: None, l2: None, mba: None };
+
+        if info.l3_cat() {
+            info.l3 = Some(CacheAllocationInformation::new(source, 1));
+        }
+        if info.l2_cat() {
+            info.l2 = Some(CacheAllocationInformation::new(source, 2));
+        }
+        if info.mba() {
+            info.mba = Some(MemoryBandwidthAllocationInformation::new(source));
+        }
+
+        info
+    }
+
+    registers!(ebx);
+}
+
+leaf! {
+    impl RdtAllocationInformation {
+        raw { ebx, l3, l2, mba }
+
+        pub fn l3_cat_information(self) -> Option<CacheAllocationInformation> {
+            self.l3
+        }
+
+        pub fn l2_cat_information(self) -> Option<CacheAllocationInformation> {
+            self.l2
+        }
+
+        pub fn mba_information(self) -> Option<MemoryBandwidthAllocationInformation> {
+            self.mba
+        }
+
+        flags {
+            ebx => {
+                // 0 - reserved
+                1 => l3_cat,
+                2 => l2_cat,
+                3 => mba
+                // 4-31 - not yet decoded
+            }
+        }
+    }
+}
+
+/// The ratio of the TSC to the core crystal clock, and the crystal
+/// clock's frequency.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TscFrequencyInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+}
+
+impl TscFrequencyInformation {
+    fn new<S: CpuidSource>(source: &S) -> TscFrequencyInformation {
+        let (a, b, c, _) = source.cpuid(RequestType::TscFrequency as u32, 0);
+        TscFrequencyInformation::from_registers(a, b, c)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32) -> TscFrequencyInformation {
+        TscFrequencyInformation { eax, ebx, ecx }
+    }
+
+    registers!(eax, ebx, ecx);
+}
+
+leaf! {
+    impl TscFrequencyInformation {
+        raw { eax, ebx, ecx }
+
+        pub fn denominator(self) -> u32 {
+            self.eax
+        }
+
+        pub fn numerator(self) -> u32 {
+            self.ebx
+        }
+
+        /// The core crystal clock frequency in Hz, or zero if not reported.
+        pub fn crystal_frequency_hz(self) -> u32 {
+            self.ecx
+        }
+    }
+}
+
+/// The nominal frequencies of the processor, in MHz. These are for
+/// display; they are not measured.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessorFrequencyInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+}
+
+impl ProcessorFrequencyInformation {
+    fn new<S: CpuidSource>(source: &S) -> ProcessorFrequencyInformation {
+        let (a, b, c, _) = source.cpuid(RequestType::ProcessorFrequency as u32, 0);
+        ProcessorFrequencyInformation::from_registers(a, b, c)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32) -> ProcessorFrequencyInformation {
+        ProcessorFrequencyInformation { eax, ebx, ecx }
+    }
+
+    registers!(eax, ebx, ecx);
+}
+
+leaf! {
+    impl ProcessorFrequencyInformation {
+        raw { eax, ebx, ecx }
+
+        pub fn base_frequency_mhz(self) -> u32 {
+            bits_of(self.eax, 0, 15)
+        }
+
+        pub fn max_frequency_mhz(self) -> u32 {
+            bits_of(self.ebx, 0, 15)
+        }
+
+        pub fn bus_frequency_mhz(self) -> u32 {
+            bits_of(self.ecx, 0, 15)
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KeyLockerInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+}
+
+impl KeyLockerInformation {
+    fn new<S: CpuidSource>(source: &S) -> KeyLockerInformation {
+        let (a, b, c, _) = source.cpuid(RequestType::KeyLocker as u32, 0);
+        KeyLockerInformation::from_registers(a, b, c)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32) -> KeyLockerInformation {
+        KeyLockerInformation { eax, ebx, ecx }
+    }
+
+    registers!(eax, ebx, ecx);
+}
+
+leaf! {
+    impl KeyLockerInformation {
+        raw { eax, ebx, ecx }
+
+        flags {
+            eax => {
+                0 => kl_cpl0_only_restriction,
+                1 => kl_no_encrypt_restriction,
+                2 => kl_no_decrypt_restriction
+                // 3-31 - reserved
+            },
+            ebx => {
+                0 => aeskle,
+                // 1 - reserved
+                2 => wide_kl,
+                // 3 - reserved
+                4 => iwkey_backup
+                // 5-31 - reserved
+            },
+            ecx => {
+                0 => loadiwkey_no_backup,
+                1 => iwkey_randomization
+                // 2-31 - reserved
+            }
+        }
+    }
+}
+
+/// The kind of core in a hybrid processor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum CoreType {
+    /// An efficiency core (E-core), built on an Intel Atom microarchitecture
+    Efficiency,
+    /// A performance core (P-core), built on an Intel Core microarchitecture
+    Performance,
+}
+
+/// Identifies the kind of core the querying logical processor is.
+///
+/// On hybrid processors the values differ between cores, so this
+/// describes only the core that executed CPUID.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NativeModelIdentification {
+    eax: u32,
+}
+
+impl NativeModelIdentification {
+    fn new<S: CpuidSource>(source: &S) -> NativeModelIdentification {
+        let (a, _, _, _) = source.cpuid(RequestType::NativeModelIdentification as u32, 0);
+        NativeModelIdentification::from_registers(a)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32) -> NativeModelIdentification {
+        NativeModelIdentification { eax }
+    }
+
+    registers!(eax);
+}
+
+leaf! {
+    impl NativeModelIdentification {
+        raw { eax }
+
+        pub fn native_model_id(self) -> u32 {
+            bits_of(self.eax, 0, 23)
+        }
+
+        pub fn core_type(self) -> Option<CoreType> {
+            match bits_of(self.eax, 24, 31) {
+                0x20 => Some(CoreType::Efficiency),
+                0x40 => Some(CoreType::Performance),
+                _ => None,
+            }
+        }
+    }
+}
+
+// Each sub-leaf carries up to 3 targets; only a handful are defined.
+const PCONFIG_MAX_TARGETS: usize = 3 * 4;
+
+/// The targets supported by the PCONFIG instruction.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PconfigInformation {
+    targets: [u32; PCONFIG_MAX_TARGETS],
+    len: usize,
+}
+
+impl PconfigInformation {
+    fn new<S: CpuidSource>(source: &S) -> PconfigInformation {
+        let mut info = PconfigInformation { targets: [0; PCONFIG_MAX_TARGETS], len: 0 };
+
+        for subleaf in 0..(PCONFIG_MAX_TARGETS / 3) as u32 {
+            let (a, b, c, d) = source.cpuid(RequestType::Pconfig as u32, subleaf);
+
+            // Sub-leaf type 1 lists target identifiers, anything else ends the list
+            if bits_of(a, 0, 11) != 1 {
+                break;
+            }
+
+            for &id in &[b, c, d] {
+                if id == 0 {
+                    return info;
+                }
+                match info.targets.get_mut(info.len) {
+                    Some(slot) => *slot = id,
+                    None => return info,
+                }
+                info.len += 1;
+            }
+        }
+
+        info
+    }
+}
+
+leaf! {
+    impl PconfigInformation {
+        raw { targets, len }
+
+        /// The raw identifiers of the supported PCONFIG targets.
+        pub fn target_ids(&self) -> &[u32] {
+            self.targets.get(..self.len).unwrap_or(&[])
+        }
+
+        /// Multi-key total memory encryption can be configured
+        pub fn mktme(self) -> bool {
+            self.target_ids().contains(&1)
+        }
+    }
+}
+
+/// Capabilities of architectural last branch records (LBRs).
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct LastBranchRecordInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+}
+
+impl LastBranchRecordInformation {
+    fn new<S: CpuidSource>(source: &S) -> LastBranchRecordInformation {
+        let (a, b, c, _) = source.cpuid(RequestType::LastBranchRecords as u32, 0);
+        LastBranchRecordInformation::from_registers(a, b, c)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32) -> LastBranchRecordInformation {
+        LastBranchRecordInformation { eax, ebx, ecx }
+    }
+
+    registers!(eax, ebx, ecx);
+
+    /// Whether `depth` may be written to IA32_LBR_DEPTH. Valid depths
+    /// are multiples of 8 up to 64.
+    pub fn supports_depth(self, depth: u32) -> bool {
+        if depth == 0 || depth & 7 != 0 || depth > 64 {
+            return false;
+        }
+        let idx = (depth / 8 - 1) as u8;
+        bits_of(self.eax, idx, idx) != 0
+    }
+}
+
+leaf! {
+    impl LastBranchRecordInformation {
+        raw { eax, ebx, ecx }
+
+        /// The largest supported number of LBR entries, or 0 if none are.
+        pub fn max_depth(self) -> u32 {
+            match bits_of(self.eax, 0, 7) {
+                0 => 0,
+                depths => (32 - depths.leading_zeros()) * 8,
+            }
+        }
+
+        flags {
+            eax => {
+                // 8-29 - reserved
+                30 => deep_c_state_reset,
+                31 => ip_values_contain_lip
+            },
+            ebx => {
+                0 => cpl_filtering,
+                1 => branch_filtering,
+                2 => call_stack_mode
+                // 3-31 - reserved
+            },
+            ecx => {
+                0 => mispredict_bit,
+                1 => timed_lbrs,
+                2 => branch_type_field
+                // 3-31 - not yet decoded
+            }
+        }
+    }
+}
+
+/// Geometry of the AMX tile registers, as described by palette 1.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TileInformation {
+    max_palette: u32,
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+}
+
+impl TileInformation {
+    fn new<S: CpuidSource>(source: &S) -> TileInformation {
+        let (p, _, _, _) = source.cpuid(RequestType::TileInformation as u32, 0);
+        let (a, b, c, _) = source.cpuid(RequestType::TileInformation as u32, 1);
+        TileInformation { max_palette: p, eax: a, ebx: b, ecx: c }
+    }
+
+    registers!(eax, ebx, ecx);
+}
+
+leaf! {
+    impl TileInformation {
+        raw { max_palette, eax, ebx, ecx }
+
+        /// The highest palette number supported.
+        pub fn max_palette(self) -> u32 {
+            self.max_palette
+        }
+
+        pub fn total_tile_bytes(self) -> u32 {
+            bits_of(self.eax, 0, 15)
+        }
+
+        pub fn bytes_per_tile(self) -> u32 {
+            bits_of(self.eax, 16, 31)
+        }
+
+        pub fn bytes_per_row(self) -> u32 {
+            bits_of(self.ebx, 0, 15)
+        }
+
+        /// The number of tile registers.
+        pub fn max_names(self) -> u32 {
+            bits_of(self.ebx, 16, 31)
+        }
+
+        pub fn max_rows(self) -> u32 {
+            bits_of(self.ecx, 0, 15)
+        }
+    }
+}
+
+/// Limits of the AMX tile matrix multiply unit.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TmulInformation {
+    ebx: u32,
+}
+
+impl TmulInformation {
+    fn new<S: CpuidSource>(source: &S) -> TmulInformation {
+        let (_, b, _, _) = source.cpuid(RequestType::TmulInformation as u32, 0);
+        TmulInformation::from_registers(b)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(ebx: u32) -> TmulInformation {
+        TmulInformation { ebx }
+    }
+
+    registers!(ebx);
+}
+
+leaf! {
+    impl TmulInformation {
+        raw { ebx }
+
+        /// The maximum number of rows or columns.
+        pub fn tmul_maxk(self) -> u32 {
+            bits_of(self.ebx, 0, 7)
+        }
+
+        /// The maximum number of column bytes.
+        pub fn tmul_maxn(self) -> u32 {
+            bits_of(self.ebx, 8, 23)
+        }
+    }
+}
+
+// Spelled out in EBX, EDX, ECX order, like the vendor identification
+const TDX_SIGNATURE: &[u8; 12] = b"IntelTDX    ";
+
+/// The leaf that the TDX module shows a guest running in an Intel
+/// Trust Domain Extensions (TDX) trust domain. Only present inside a
+/// trust domain.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TdxGuestInformation {
+    max_subleaf: u32,
+}
+
+impl TdxGuestInformation {
+    fn new<S: CpuidSource>(source: &S) -> Option<TdxGuestInformation> {
+        let (a, b, c, d) = source.cpuid(RequestType::TdxGuest as u32, 0);
+
+        let signature =
+            le_bytes(b)
+            .chain(le_bytes(d))
+            .chain(le_bytes(c));
+        if signature.eq(TDX_SIGNATURE.iter().cloned()) {
+            Some(TdxGuestInformation { max_subleaf: a })
+        } else {
+            None
+        }
+    }
+}
+
+leaf! {
+    impl TdxGuestInformation {
+        raw { max_subleaf }
+
+        /// The highest sub-leaf of the TDX leaf.
+        pub fn max_subleaf(self) -> u32 {
+            self.max_subleaf
+        }
+    }
+}
+
+/// The associativity of a cache or TLB, as the extended cache and TLB
+/// leaves report it.
+#[cfg(any(feature = "caches", feature = "amd"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum CacheLineAssociativity {
+    Disabled,
+    DirectMapped,
+    TwoWay,
+    ThreeWay,
+    FourWay,
+    SixWay,
+    EightWay,
+    SixteenWay,
+    ThirtyTwoWay,
+    FortyEightWay,
+    SixtyFourWay,
+    NinetySixWay,
+    OneHundredTwentyEightWay,
+    /// Set associative with this many ways, where no other variant
+    /// names the count.
+    Ways(u8),
+    Full,
+}
+
+// The 4-bit encoding shared by the L2 and L3 fields of leaf
+// 0x8000_0006. Intel reserves the 3 and 6 way values.
+#[cfg(any(feature = "caches", feature = "amd"))]
+fn l2_l3_associativity(encoded: u32) -> Option<CacheLineAssociativity> {
+    match encoded {
+        0x00 => Some(CacheLineAssociativity::Disabled),
+        0x01 => Some(CacheLineAssociativity::DirectMapped),
+        0x02 => Some(CacheLineAssociativity::TwoWay),
+        0x03 => Some(CacheLineAssociativity::ThreeWay),
+        0x04 => Some(CacheLineAssociativity::FourWay),
+        0x05 => Some(CacheLineAssociativity::SixWay),
+        0x06 => Some(CacheLineAssociativity::EightWay),
+        0x08 => Some(CacheLineAssociativity::SixteenWay),
+        0x0A => Some(CacheLineAssociativity::ThirtyTwoWay),
+        0x0B => Some(CacheLineAssociativity::FortyEightWay),
+        0x0C => Some(CacheLineAssociativity::SixtyFourWay),
+        0x0D => Some(CacheLineAssociativity::NinetySixWay),
+        0x0E => Some(CacheLineAssociativity::OneHundredTwentyEightWay),
+        0x0F => Some(CacheLineAssociativity::Full),
+        _ => None,
+    }
+}
+
+/// L2 and L3 cache and TLB characteristics.
+///
+/// Cache sizes are in kilobytes and line sizes in bytes. Intel
+/// processors only report the L2 cache; the L3 and TLB values are
+/// reported by AMD processors.
+#[cfg(feature = "caches")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CacheLine {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+#[cfg(feature = "caches")]
+impl CacheLine {
+    fn new<S: CpuidSource>(source: &S) -> CacheLine {
+        let (a, b, c, d) = source.cpuid(RequestType::CacheLine as u32, 0);
+        CacheLine::from_registers(a, b, c, d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32, edx: u32) -> CacheLine {
+        CacheLine { eax, ebx, ecx, edx }
+    }
+
+    registers!(eax, ebx, ecx, edx);
+}
+
+#[cfg(feature = "caches")]
+leaf! {
+    impl CacheLine {
+        raw { eax, ebx, ecx, edx }
+
+        /// The L2 cache line size.
+        pub fn cache_line_size(self) -> u32 {
+            bits_of(self.ecx, 0, 7)
+        }
+
+        pub fn l2_lines_per_tag(self) -> u32 {
+            bits_of(self.ecx, 8, 11)
+        }
+
+        pub fn l2_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.ecx, 12, 15))
+        }
+
+        /// The L2 cache size.
+        pub fn cache_size(self) -> u32 {
+            bits_of(self.ecx, 16, 31)
+        }
+
+        pub fn l3_cache_line_size(self) -> u32 {
+            bits_of(self.edx, 0, 7)
+        }
+
+        pub fn l3_lines_per_tag(self) -> u32 {
+            bits_of(self.edx, 8, 11)
+        }
+
+        pub fn l3_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.edx, 12, 15))
+        }
+
+        /// The L3 cache size, which the hardware reports in 512KB units.
+        pub fn l3_cache_size(self) -> u32 {
+            bits_of(self.edx, 18, 31) * 512
+        }
+
+        pub fn l2_data_tlb_2m_4m_entries(self) -> u32 {
+            bits_of(self.eax, 16, 27)
+        }
+
+        pub fn l2_data_tlb_2m_4m_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.eax, 28, 31))
+        }
+
+        pub fn l2_instruction_tlb_2m_4m_entries(self) -> u32 {
+            bits_of(self.eax, 0, 11)
+        }
+
+        pub fn l2_instruction_tlb_2m_4m_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.eax, 12, 15))
+        }
+
+        pub fn l2_data_tlb_4k_entries(self) -> u32 {
+            bits_of(self.ebx, 16, 27)
+        }
+
+        pub fn l2_data_tlb_4k_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.ebx, 28, 31))
+        }
+
+        pub fn l2_instruction_tlb_4k_entries(self) -> u32 {
+            bits_of(self.ebx, 0, 11)
+        }
+
+        pub fn l2_instruction_tlb_4k_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.ebx, 12, 15))
+        }
+    }
+}
+
+// The L1 encoding is the number of ways itself, with zero reserved
+#[cfg(feature = "caches")]
+fn l1_associativity(ways: u32) -> Option<CacheLineAssociativity> {
+    match ways {
+        0x00 => None,
+        0x01 => Some(CacheLineAssociativity::DirectMapped),
+        0x02 => Some(CacheLineAssociativity::TwoWay),
+        0x03 => Some(CacheLineAssociativity::ThreeWay),
+        0x04 => Some(CacheLineAssociativity::FourWay),
+        0x06 => Some(CacheLineAssociativity::SixWay),
+        0x08 => Some(CacheLineAssociativity::EightWay),
+        0x10 => Some(CacheLineAssociativity::SixteenWay),
+        0x20 => Some(CacheLineAssociativity::ThirtyTwoWay),
+        0x30 => Some(CacheLineAssociativity::FortyEightWay),
+        0x40 => Some(CacheLineAssociativity::SixtyFourWay),
+        0x60 => Some(CacheLineAssociativity::NinetySixWay),
+        0x80 => Some(CacheLineAssociativity::OneHundredTwentyEightWay),
+        0xFF => Some(CacheLineAssociativity::Full),
+        ways => Some(CacheLineAssociativity::Ways(ways as u8)),
+    }
+}
+
+/// L1 cache and TLB characteristics, as reported by AMD processors.
+///
+/// Cache sizes are in kilobytes and line sizes in bytes. Other vendors
+/// report zero.
+#[cfg(feature = "caches")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct L1CacheAndTlbInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+#[cfg(feature = "caches")]
+impl L1CacheAndTlbInformation {
+    fn new<S: CpuidSource>(source: &S) -> L1CacheAndTlbInformation {
+        let (a, b, c, d) = source.cpuid(RequestType::L1CacheAndTlb as u32, 0);
+        L1CacheAndTlbInformation::from_registers(a, b, c, d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32, edx: u32) -> L1CacheAndTlbInformation {
+        L1CacheAndTlbInformation { eax, ebx, ecx, edx }
+    }
+
+    registers!(eax, ebx, ecx, edx);
+}
+
+#[cfg(feature = "caches")]
+leaf! {
+    impl L1CacheAndTlbInformation {
+        raw { eax, ebx, ecx, edx }
+
+        pub fn data_cache_size(self) -> u32 {
+            bits_of(self.ecx, 24, 31)
+        }
+
+        pub fn data_cache_associativity(self) -> Option<CacheLineAssociativity> {
+            l1_associativity(bits_of(self.ecx, 16, 23))
+        }
+
+        pub fn data_cache_lines_per_tag(self) -> u32 {
+            bits_of(self.ecx, 8, 15)
+        }
+
+        pub fn data_cache_line_size(self) -> u32 {
+            bits_of(self.ecx, 0, 7)
+        }
+
+        pub fn instruction_cache_size(self) -> u32 {
+            bits_of(self.edx, 24, 31)
+        }
+
+        pub fn instruction_cache_associativity(self) -> Option<CacheLineAssociativity> {
+            l1_associativity(bits_of(self.edx, 16, 23))
+        }
+
+        pub fn instruction_cache_lines_per_tag(self) -> u32 {
+            bits_of(self.edx, 8, 15)
+        }
+
+        pub fn instruction_cache_line_size(self) -> u32 {
+            bits_of(self.edx, 0, 7)
+        }
+
+        pub fn data_tlb_2m_4m_entries(self) -> u32 {
+            bits_of(self.eax, 16, 23)
+        }
+
+        pub fn data_tlb_2m_4m_associativity(self) -> Option<CacheLineAssociativity> {
+            l1_associativity(bits_of(self.eax, 24, 31))
+        }
+
+        pub fn instruction_tlb_2m_4m_entries(self) -> u32 {
+            bits_of(self.eax, 0, 7)
+        }
+
+        pub fn instruction_tlb_2m_4m_associativity(self) -> Option<CacheLineAssociativity> {
+            l1_associativity(bits_of(self.eax, 8, 15))
+        }
+
+        pub fn data_tlb_4k_entries(self) -> u32 {
+            bits_of(self.ebx, 16, 23)
+        }
+
+        pub fn data_tlb_4k_associativity(self) -> Option<CacheLineAssociativity> {
+            l1_associativity(bits_of(self.ebx, 24, 31))
+        }
+
+        pub fn instruction_tlb_4k_entries(self) -> u32 {
+            bits_of(self.ebx, 0, 7)
+        }
+
+        pub fn instruction_tlb_4k_associativity(self) -> Option<CacheLineAssociativity> {
+            l1_associativity(bits_of(self.ebx, 8, 15))
+        }
+    }
+}
+
+#[cfg(feature = "caches")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum CacheType {
+    Data,
+    Instruction,
+    Unified,
+}
+
+/// Describes a single cache, as enumerated by Intel's leaf 4 or AMD's
+/// leaf 0x8000_001D, which share a layout.
+#[cfg(feature = "caches")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CacheParameters {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+#[cfg(feature = "caches")]
+impl CacheParameters {
+    registers!(eax, ebx, ecx, edx);
+
+    /// Identifies this cache among those of its level and type; logical
+    /// processors whose APIC IDs give the same value share it.
+    pub fn sharing_id(self, apic_id: u32) -> u32 {
+        apic_id >> sharing_shift(self.max_logical_processors_sharing())
+    }
+}
+
+#[cfg(feature = "caches")]
+leaf! {
+    impl CacheParameters {
+        raw { eax, ebx, ecx, edx }
+
+        pub fn cache_type(self) -> Option<CacheType> {
+            match bits_of(self.eax, 0, 4) {
+                1 => Some(CacheType::Data),
+                2 => Some(CacheType::Instruction),
+                3 => Some(CacheType::Unified),
+                _ => None,
+            }
+        }
+
+        pub fn level(self) -> u32 {
+            bits_of(self.eax, 5, 7)
+        }
+
+        pub fn max_logical_processors_sharing(self) -> u32 {
+            bits_of(self.eax, 14, 25) + 1
+        }
+
+        /// Only reported by Intel processors.
+        pub fn max_cores_per_package(self) -> u32 {
+            bits_of(self.eax, 26, 31) + 1
+        }
+
+        /// The line size, in bytes.
+        pub fn line_size(self) -> u32 {
+            bits_of(self.ebx, 0, 11) + 1
+        }
+
+        pub fn physical_line_partitions(self) -> u32 {
+            bits_of(self.ebx, 12, 21) + 1
+        }
+
+        pub fn ways(self) -> u32 {
+            bits_of(self.ebx, 22, 31) + 1
+        }
+
+        pub fn sets(self) -> u32 {
+            self.ecx.wrapping_add(1)
+        }
+
+        /// The total size, in bytes.
+        pub fn size(self) -> u64 {
+            self.ways() as u64
+                * self.physical_line_partitions() as u64
+                * self.line_size() as u64
+                * self.sets() as u64
+        }
+
+        flags {
+            eax => {
+                8 => self_initializing,
+                9 => fully_associative
+            },
+            edx => {
+                // WBINVD/INVD may not act on lower level caches of sharing threads
+                0 => write_back_invalidate,
+                1 => inclusive,
+                2 => complex_indexing
+                // 3-31 - reserved
+            }
+        }
+    }
+}
+
+// The APIC ID bits that tell apart the logical processors sharing
+// something, of which there may be up to `count`
+#[cfg(any(feature = "caches", feature = "amd"))]
+fn sharing_shift(count: u32) -> u32 {
+    32 - count.saturating_sub(1).leading_zeros()
+}
+
+// L1d, L1i, L2, L3 and the occasional L4 leave plenty of room
+#[cfg(feature = "caches")]
+const MAX_CACHES: usize = 8;
+
+/// Every cache of the processor, as enumerated by the sub-leaves of
+/// the deterministic cache parameter leaf.
+#[cfg(feature = "caches")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeterministicCacheParameters {
+    caches: [CacheParameters; MAX_CACHES],
+    len: usize,
+}
+
+#[cfg(feature = "caches")]
+impl DeterministicCacheParameters {
+    fn new<S: CpuidSource>(source: &S, leaf: RequestType) -> DeterministicCacheParameters {
+        let leaf = leaf as u32;
+        let empty = CacheParameters { eax: 0, ebx: 0, ecx: 0, edx: 0 };
+        let mut caches = [empty; MAX_CACHES];
+        let mut len = 0;
+
+        for (subleaf, cache) in caches.iter_mut().enumerate() {
+            let (a, b, c, d) = source.cpuid(leaf, subleaf as u32);
+
+            // A cache type of zero terminates the list
+            if bits_of(a, 0, 4) == 0 {
+                break;
+            }
+
+            *cache = CacheParameters { eax: a, ebx: b, ecx: c, edx: d };
+            len += 1;
+        }
+
+        DeterministicCacheParameters { caches, len }
+    }
+
+    pub fn caches(&self) -> &[CacheParameters] {
+        self.caches.get(..self.len).unwrap_or(&[])
+    }
+}
+
+#[cfg(feature = "caches")]
+impl fmt::Debug for DeterministicCacheParameters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.caches()).finish()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimeStampCounter {
+    edx: u32,
+}
+
+impl TimeStampCounter {
+    fn new<S: CpuidSource>(source: &S) -> TimeStampCounter {
+        let (_, _, _, d) = source.cpuid(RequestType::TimeStampCounter as u32, 0);
+        TimeStampCounter::from_registers(d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(edx: u32) -> TimeStampCounter {
+        TimeStampCounter { edx }
+    }
+
+    registers!(edx);
+}
+
+leaf! {
+    impl TimeStampCounter {
+        raw { edx }
+
+        // Only invariant_tsc is defined by Intel, the rest are AMD's
+        // advanced power management features.
+        flags {
+            edx => {
+                0 => temperature_sensor,
+                1 => frequency_id_control,
+                2 => voltage_id_control,
+                3 => thermal_trip,
+                4 => hardware_thermal_control,
+                // 5 - reserved
+                6 => one_hundred_mhz_steps,
+                7 => hardware_pstate,
+                8 => invariant_tsc,
+                9 => core_performance_boost,
+                10 => effective_frequency_read_only,
+                11 => processor_feedback_interface,
+                12 => processor_power_reporting,
+                13 => connected_standby,
+                14 => rapl
+                // 15-31 - reserved
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PhysicalAddressSize {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+}
+
+impl PhysicalAddressSize {
+    fn new<S: CpuidSource>(source: &S) -> PhysicalAddressSize {
+        let (a, b, c, _) = source.cpuid(RequestType::PhysicalAddressSize as u32, 0);
+        PhysicalAddressSize::from_registers(a, b, c)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32) -> PhysicalAddressSize {
+        PhysicalAddressSize { eax, ebx, ecx }
+    }
+
+    registers!(eax, ebx, ecx);
+
+    /// The most physical memory that can be addressed, in bytes.
+    pub fn max_physical_memory_bytes(self) -> u64 {
+        address_space_bytes(self.physical_address_bits())
+    }
+
+    /// The size of the linear (virtual) address space, in bytes.
+    pub fn max_linear_address_bytes(self) -> u64 {
+        address_space_bytes(self.linear_address_bits())
+    }
+
+    /// The guest physical address width, falling back to the physical
+    /// address width when none is given.
+    pub fn effective_guest_physical_address_bits(self) -> u32 {
+        match self.guest_physical_address_bits() {
+            0 => self.physical_address_bits(),
+            bits => bits,
+        }
+    }
+}
+
+leaf! {
+    impl PhysicalAddressSize {
+        raw { eax, ebx, ecx }
+
+        pub fn physical_address_bits(self) -> u32 {
+            bits_of(self.eax, 0, 7)
+        }
+
+        pub fn linear_address_bits(self) -> u32 {
+            bits_of(self.eax, 8, 15)
+        }
+
+        /// The guest physical address width a hypervisor should report to
+        /// its guests. Zero means the same as `physical_address_bits`.
+        pub fn guest_physical_address_bits(self) -> u32 {
+            bits_of(self.eax, 16, 23)
+        }
+
+        /// The number of logical processors in the package, as reported by
+        /// AMD processors. With SMT each core counts once per thread.
+        pub fn logical_processor_count(self) -> u32 {
+            bits_of(self.ecx, 0, 7) + 1
+        }
+
+        /// The number of low APIC ID bits identifying a core within the
+        /// package. Zero means it has to be derived from the core count.
+        pub fn apic_id_size(self) -> u32 {
+            bits_of(self.ecx, 12, 15)
+        }
+
+        /// The width of the performance time-stamp counter.
+        pub fn perf_tsc_size(self) -> u32 {
+            match bits_of(self.ecx, 16, 17) {
+                0 => 40,
+                1 => 48,
+                2 => 56,
+                _ => 64,
+            }
+        }
+
+        // Intel only defines wbnoinvd, the rest are AMD extensions
+        flags {
+            ebx => {
+                0 => clzero,
+                1 => inst_ret_cnt_msr,
+                2 => rstr_fp_err_ptrs,
+                3 => invlpgb,
+                4 => rdpru,
+                // 5 - reserved
+                6 => mbe,
+                // 7 - reserved
+                8 => mcommit,
+                9 => wbnoinvd,
+                // 10-11 - reserved
+                12 => amd_ibpb,
+                13 => int_wbinvd,
+                14 => amd_ibrs,
+                15 => amd_stibp,
+                16 => ibrs_always_on,
+                17 => stibp_always_on,
+                18 => ibrs_preferred,
+                19 => ibrs_same_mode,
+                20 => efer_lmsle_unsupported,
+                21 => invlpgb_nested_pages,
+                // 22 - reserved
+                23 => ppin,
+                24 => amd_ssbd,
+                25 => virt_ssbd,
+                26 => ssb_no,
+                27 => cppc,
+                28 => psfd,
+                29 => btc_no,
+                30 => ibpb_ret
+                // 31 - reserved
+            }
+        }
+    }
+}
+
+/// Matches the "address sizes" line of `/proc/cpuinfo`.
+impl fmt::Display for PhysicalAddressSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} bits physical, {} bits virtual",
+               self.physical_address_bits(), self.linear_address_bits())
+    }
+}
+
+// Saturates rather than overflowing for a 64-bit address space
+fn address_space_bytes(bits: u32) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        1 << bits
+    }
+}
+
+/// AMD secure virtual machine (SVM) capabilities.
+#[cfg(feature = "amd")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SvmFeatures {
+    eax: u32,
+    ebx: u32,
+    edx: u32,
+}
+
+#[cfg(feature = "amd")]
+impl SvmFeatures {
+    fn new<S: CpuidSource>(source: &S) -> SvmFeatures {
+        let (a, b, _, d) = source.cpuid(RequestType::SvmFeatures as u32, 0);
+        SvmFeatures::from_registers(a, b, d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, edx: u32) -> SvmFeatures {
+        SvmFeatures { eax, ebx, edx }
+    }
+
+    registers!(eax, ebx, edx);
+}
+
+#[cfg(feature = "amd")]
+leaf! {
+    impl SvmFeatures {
+        raw { eax, ebx, edx }
+
+        pub fn svm_revision(self) -> u32 {
+            bits_of(self.eax, 0, 7)
+        }
+
+        /// The number of address space identifiers.
+        pub fn asid_count(self) -> u32 {
+            self.ebx
+        }
+
+        flags {
+            edx => {
+                0 => nested_paging,
+                1 => lbr_virtualization,
+                2 => svm_lock,
+                3 => nrip_save,
+                4 => tsc_rate_msr,
+                5 => vmcb_clean,
+                6 => flush_by_asid,
+                7 => decode_assists,
+                8 => pmc_virtualization,
+                // 9 - reserved
+                10 => pause_filter,
+                // 11 - reserved
+                12 => pause_filter_threshold,
+                13 => avic,
+                // 14 - reserved
+                15 => vmsave_virtualization,
+                16 => vgif,
+                17 => gmet,
+                18 => x2avic,
+                19 => sss_check,
+                20 => spec_ctrl,
+                21 => rogpt,
+                // 22 - reserved
+                23 => host_mce_override,
+                24 => tlbi_ctl,
+                25 => vnmi,
+                26 => ibs_virtualization,
+                27 => ext_lvt_avic_access_chg,
+                28 => nested_virt_vmcb_addr_chk,
+                29 => bus_lock_threshold
+                // 30-31 - reserved
+            }
+        }
+    }
+}
+
+/// TLB characteristics for 1GB pages, as reported by AMD processors.
+#[cfg(feature = "amd")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GigabytePageTlbInformation {
+    eax: u32,
+    ebx: u32,
+}
+
+#[cfg(feature = "amd")]
+impl GigabytePageTlbInformation {
+    fn new<S: CpuidSource>(source: &S) -> GigabytePageTlbInformation {
+        let (a, b, _, _) = source.cpuid(RequestType::GigabytePageTlb as u32, 0);
+        GigabytePageTlbInformation::from_registers(a, b)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32) -> GigabytePageTlbInformation {
+        GigabytePageTlbInformation { eax, ebx }
+    }
+
+    registers!(eax, ebx);
+}
+
+#[cfg(feature = "amd")]
+leaf! {
+    impl GigabytePageTlbInformation {
+        raw { eax, ebx }
+
+        pub fn l1_data_tlb_entries(self) -> u32 {
+            bits_of(self.eax, 16, 27)
+        }
+
+        pub fn l1_data_tlb_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.eax, 28, 31))
+        }
+
+        pub fn l1_instruction_tlb_entries(self) -> u32 {
+            bits_of(self.eax, 0, 11)
+        }
+
+        pub fn l1_instruction_tlb_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.eax, 12, 15))
+        }
+
+        pub fn l2_data_tlb_entries(self) -> u32 {
+            bits_of(self.ebx, 16, 27)
+        }
+
+        pub fn l2_data_tlb_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.ebx, 28, 31))
+        }
+
+        pub fn l2_instruction_tlb_entries(self) -> u32 {
+            bits_of(self.ebx, 0, 11)
+        }
+
+        pub fn l2_instruction_tlb_associativity(self) -> Option<CacheLineAssociativity> {
+            l2_l3_associativity(bits_of(self.ebx, 12, 15))
+        }
+    }
+}
+
+/// Hints about how wide AMD's floating point datapath really is.
+#[cfg(feature = "amd")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PerformanceOptimizationIdentifiers {
+    eax: u32,
+}
+
+#[cfg(feature = "amd")]
+impl PerformanceOptimizationIdentifiers {
+    fn new<S: CpuidSource>(source: &S) -> PerformanceOptimizationIdentifiers {
+        let (a, _, _, _) = source.cpuid(RequestType::PerformanceOptimization as u32, 0);
+        PerformanceOptimizationIdentifiers::from_registers(a)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32) -> PerformanceOptimizationIdentifiers {
+        PerformanceOptimizationIdentifiers { eax }
+    }
+
+    registers!(eax);
+}
+
+#[cfg(feature = "amd")]
+leaf! {
+    impl PerformanceOptimizationIdentifiers {
+        raw { eax }
+
+        flags {
+            eax => {
+                // 128-bit SSE instructions execute at full width
+                0 => fp128,
+                // MOVU* is preferred over MOVL/MOVH
+                1 => movu,
+                // 256-bit AVX instructions execute at full width
+                2 => fp256
+                // 3-31 - reserved
+            }
+        }
+    }
+}
+
+/// AMD instruction-based sampling (IBS) capabilities.
+#[cfg(feature = "amd")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InstructionBasedSamplingInformation {
+    eax: u32,
+}
+
+#[cfg(feature = "amd")]
+impl InstructionBasedSamplingInformation {
+    fn new<S: CpuidSource>(source: &S) -> InstructionBasedSamplingInformation {
+        let (a, _, _, _) = source.cpuid(RequestType::InstructionBasedSampling as u32, 0);
+        InstructionBasedSamplingInformation::from_registers(a)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32) -> InstructionBasedSamplingInformation {
+        InstructionBasedSamplingInformation { eax }
+    }
+
+    registers!(eax);
+}
+
+#[cfg(feature = "amd")]
+leaf! {
+    impl InstructionBasedSamplingInformation {
+        raw { eax }
+
+        flags {
+            eax => {
+                0 => ibs_feature_flags_valid,
+                1 => fetch_sampling,
+                2 => op_sampling,
+                3 => read_write_op_counter,
+                4 => op_counting,
+                5 => branch_target_address,
+                6 => op_counter_extended,
+                7 => rip_invalid_check,
+                8 => op_branch_fuse,
+                9 => fetch_control_extended,
+                10 => op_data4,
+                11 => l3_miss_filtering
+                // 12-31 - reserved
+            }
+        }
+    }
+}
+
+/// The position of the querying logical processor within an AMD
+/// processor.
+#[cfg(feature = "amd")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessorTopologyInformation {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+}
+
+#[cfg(feature = "amd")]
+impl ProcessorTopologyInformation {
+    fn new<S: CpuidSource>(source: &S) -> ProcessorTopologyInformation {
+        let (a, b, c, _) = source.cpuid(RequestType::ProcessorTopology as u32, 0);
+        ProcessorTopologyInformation::from_registers(a, b, c)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32) -> ProcessorTopologyInformation {
+        ProcessorTopologyInformation { eax, ebx, ecx }
+    }
+
+    registers!(eax, ebx, ecx);
+}
+
+#[cfg(feature = "amd")]
+leaf! {
+    impl ProcessorTopologyInformation {
+        raw { eax, ebx, ecx }
+
+        pub fn extended_apic_id(self) -> u32 {
+            self.eax
+        }
+
+        pub fn core_id(self) -> u32 {
+            bits_of(self.ebx, 0, 7)
+        }
+
+        pub fn threads_per_core(self) -> u32 {
+            bits_of(self.ebx, 8, 15) + 1
+        }
+
+        pub fn node_id(self) -> u32 {
+            bits_of(self.ecx, 0, 7)
+        }
+
+        pub fn nodes_per_processor(self) -> u32 {
+            bits_of(self.ecx, 8, 10) + 1
+        }
+    }
+}
+
+/// Core complex (CCX) and die groupings of AMD Zen processors.
+///
+/// A CCX is the set of cores sharing one L3 cache, so it is derived
+/// from the L3 entry of leaf 0x8000_001D together with the extended
+/// APIC ID of leaf 0x8000_001E. The die is the node reported by leaf
+/// 0x8000_001E. All identifiers describe the logical processor that
+/// executed CPUID.
+#[cfg(feature = "amd")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CoreComplexInformation {
+    l3_eax: Option<u32>,
+    topology: ProcessorTopologyInformation,
+}
+
+#[cfg(feature = "amd")]
+impl CoreComplexInformation {
+    fn new<S: CpuidSource>(source: &S) -> CoreComplexInformation {
+        #[cfg(feature = "caches")]
+        let l3 = DeterministicCacheParameters::new(source, RequestType::CacheProperties)
+            .caches().iter().find(|c| c.level() == 3).map(|c| c.eax);
+        #[cfg(not(feature = "caches"))]
+        let l3 = None;
+
+        CoreComplexInformation { l3_eax: l3, topology: ProcessorTopologyInformation::new(source) }
+    }
+}
+
+#[cfg(feature = "amd")]
+leaf! {
+    impl CoreComplexInformation {
+        raw { l3_eax, topology }
+
+        pub fn extended_apic_id(self) -> u32 {
+            self.topology.extended_apic_id()
+        }
+
+        /// The number of logical processors sharing the L3 cache of this CCX.
+        pub fn logical_processors_per_ccx(self) -> Option<u32> {
+            self.l3_eax.map(|a| bits_of(a, 14, 25) + 1)
+        }
+
+        /// Identifies the CCX; logical processors with the same value
+        /// share an L3 cache.
+        pub fn ccx_id(self) -> Option<u32> {
+            self.logical_processors_per_ccx().map(|n| {
+                self.extended_apic_id() >> sharing_shift(n)
+            })
+        }
+
+        /// Identifies the die (CCD on Zen, node in AMD terminology).
+        pub fn die_id(self) -> u32 {
+            self.topology.node_id()
+        }
+
+        pub fn dies_per_processor(self) -> u32 {
+            self.topology.nodes_per_processor()
+        }
+    }
+}
+
+/// AMD memory encryption (SME and SEV) capabilities.
+#[cfg(feature = "amd")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct EncryptedMemoryCapabilities {
+    eax: u32,
+    ebx: u32,
+    ecx: u32,
+    edx: u32,
+}
+
+#[cfg(feature = "amd")]
+impl EncryptedMemoryCapabilities {
+    fn new<S: CpuidSource>(source: &S) -> EncryptedMemoryCapabilities {
+        let (a, b, c, d) = source.cpuid(RequestType::EncryptedMemory as u32, 0);
+        EncryptedMemoryCapabilities::from_registers(a, b, c, d)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32, ecx: u32, edx: u32) -> EncryptedMemoryCapabilities {
+        EncryptedMemoryCapabilities { eax, ebx, ecx, edx }
+    }
+
+    registers!(eax, ebx, ecx, edx);
+}
+
+#[cfg(feature = "amd")]
+leaf! {
+    impl EncryptedMemoryCapabilities {
+        raw { eax, ebx, ecx, edx }
+
+        /// The page table bit that marks a page as encrypted.
+        pub fn c_bit_position(self) -> u32 {
+            bits_of(self.ebx, 0, 5)
+        }
+
+        /// How many physical address bits are lost when memory encryption
+        /// is enabled.
+        pub fn physical_address_reduction(self) -> u32 {
+            bits_of(self.ebx, 6, 11)
+        }
+
+        pub fn vmpl_count(self) -> u32 {
+            bits_of(self.ebx, 12, 15)
+        }
+
+        /// The number of encrypted guests supported simultaneously.
+        pub fn max_encrypted_guests(self) -> u32 {
+            self.ecx
+        }
+
+        /// The lowest ASID usable by an SEV guest that does not use SEV-ES.
+        pub fn min_sev_no_es_asid(self) -> u32 {
+            self.edx
+        }
+
+        flags {
+            eax => {
+                0 => sme,
+                1 => sev,
+                2 => page_flush_msr,
+                3 => sev_es,
+                4 => sev_snp,
+                5 => vmpl,
+                6 => rmpquery,
+                7 => vmpl_supervisor_shadow_stack,
+                8 => secure_tsc,
+                9 => tsc_aux_virtualization,
+                10 => hardware_cache_coherency,
+                11 => sev_64_bit_host,
+                12 => restricted_injection,
+                13 => alternate_injection,
+                14 => debug_swap,
+                15 => prevent_host_ibs,
+                16 => vte,
+                17 => vmgexit_parameter,
+                18 => virtual_tom_msr,
+                19 => ibs_virtual_guest_control,
+                // 20-23 - reserved
+                24 => vmsa_register_protection,
+                25 => smt_protection,
+                // 26-27 - reserved
+                28 => svsm_communication_page_msr,
+                29 => nested_virtual_snp_msr
+                // 30-31 - reserved
+            }
+        }
+    }
+}
+
+/// Newer AMD feature and mitigation bits.
+#[cfg(feature = "amd")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ExtendedFeatureIdentification2 {
+    eax: u32,
+    ebx: u32,
+}
+
+#[cfg(feature = "amd")]
+impl ExtendedFeatureIdentification2 {
+    fn new<S: CpuidSource>(source: &S) -> ExtendedFeatureIdentification2 {
+        let (a, b, _, _) = source.cpuid(RequestType::ExtendedFeatureIdentification2 as u32, 0);
+        ExtendedFeatureIdentification2::from_registers(a, b)
+    }
+
+    /// Decodes register values already read from this leaf.
+    pub const fn from_registers(eax: u32, ebx: u32) -> ExtendedFeatureIdentification2 {
+        ExtendedFeatureIdentification2 { eax, ebx }
+    }
+
+    registers!(eax, ebx);
+}
+
+#[cfg(feature = "amd")]
+leaf! {
+    impl ExtendedFeatureIdentification2 {
+        raw { eax, ebx }
+
+        /// The size of a microcode patch, in 16-byte units.
+        pub fn microcode_patch_size(self) -> u32 {
+            bits_of(self.ebx, 0, 11)
+        }
+
+        flags {
+            eax => {
+                0 => no_nested_data_breakpoints,
+                1 => fs_gs_base_non_serializing,
+                2 => lfence_always_serializing,
+                3 => smm_page_config_lock,
+                // 4-5 - reserved
+                6 => null_select_clears_base,
+                7 => upper_address_ignore,
+                8 => automatic_ibrs,
+                9 => no_smm_ctl_msr,
+                10 => fast_short_rep_stosb,
+                11 => fast_short_repe_cmpsb,
+                // 12 - reserved
+                13 => prefetch_ctl_msr,
+                // 14-16 - reserved
+                17 => cpuid_user_disable,
+                18 => epsf,
+                // 19-26 - reserved
+                27 => sbpb,
+                28 => ibpb_brtype,
+                29 => srso_no,
+                30 => srso_user_kernel_no,
+                31 => srso_msr_fix
+            }
+        }
+    }
+}
+
+/// Decodes a CPUID leaf that this crate does not cover itself.
+///
+/// Implement this for vendor- or hypervisor-specific leaves and pass
+/// it to `decode` to reuse the crate's query machinery, or register it
+/// with `Decoders` to decode snapshots and extend reports.
+pub trait LeafDecoder {
+    type Output;
+
+    /// The leaf to query.
+    fn leaf(&self) -> u32;
+
+    /// The sub-leaf to query.
+    fn subleaf(&self) -> u32 {
+        0
+    }
+
+    fn decode(&self, eax: u32, ebx: u32, ecx: u32, edx: u32) -> Self::Output;
+}
+
+/// A confidential computing environment the code runs in, from
+/// `Master::confidential_computing`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum ConfidentialComputing {
+    /// An Intel TDX trust domain.
+    Tdx,
+    /// An AMD SEV guest, whose memory is encrypted but whose registers
+    /// are not. Only recognized from the `SEV_STATUS` MSR.
+    Sev,
+    /// An AMD SEV-ES guest, whose registers are encrypted too. Only
+    /// recognized from the `SEV_STATUS` MSR.
+    SevEs,
+    /// An AMD SEV-SNP guest.
+    SevSnp,
+}
+
+/// Control-flow Enforcement Technology support, from `Master::cet`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cet {
+    /// Shadow stacks, which catch overwritten return addresses.
+    pub shadow_stack: bool,
+    /// Indirect branch tracking, which requires indirect branches to
+    /// land on `ENDBR` instructions.
+    pub indirect_branch_tracking: bool,
+    /// Supervisor shadow stacks can be marked busy by the processor.
+    pub supervisor_shadow_stack: bool,
+    /// XSAVE can switch the user-mode CET state between threads.
+    pub user_state: bool,
+    /// XSAVE can switch the supervisor-mode CET state.
+    pub supervisor_state: bool,
+}
+
+impl Cet {
+    /// Whether an operating system can offer user-mode shadow stacks,
+    /// which needs XSAVE to switch their state between threads.
+    pub fn user_shadow_stack(self) -> bool {
+        self.shadow_stack && self.user_state
+    }
+
+    /// Whether an operating system can offer user-mode indirect branch
+    /// tracking.
+    pub fn user_indirect_branch_tracking(self) -> bool {
+        self.indirect_branch_tracking && self.user_state
+    }
+}
+
+/// A subset of AVX-512, each enumerated by its own flag.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Avx512Subset {
+    F,
+    Cd,
+    Vl,
+    Bw,
+    Dq,
+    Ifma,
+    Vbmi,
+    Vbmi2,
+    Vnni,
+    Bitalg,
+    Vpopcntdq,
+    Bf16,
+    Fp16,
+    Vp2intersect,
+}
+
+impl Avx512Subset {
+    pub const ALL: &'static [Avx512Subset] = &[
+        Avx512Subset::F,
+        Avx512Subset::Cd,
+        Avx512Subset::Vl,
+        Avx512Subset::Bw,
+        Avx512Subset::Dq,
+        Avx512Subset::Ifma,
+        Avx512Subset::Vbmi,
+        Avx512Subset::Vbmi2,
+        Avx512Subset::Vnni,
+        Avx512Subset::Bitalg,
+        Avx512Subset::Vpopcntdq,
+        Avx512Subset::Bf16,
+        Avx512Subset::Fp16,
+        Avx512Subset::Vp2intersect,
+    ];
+
+    /// The flag that enumerates the subset.
+    pub fn feature(self) -> Feature {
+        match self {
+            Avx512Subset::F => Feature::Avx512f,
+            Avx512Subset::Cd => Feature::Avx512cd,
+            Avx512Subset::Vl => Feature::Avx512vl,
+            Avx512Subset::Bw => Feature::Avx512bw,
+            Avx512Subset::Dq => Feature::Avx512dq,
+            Avx512Subset::Ifma => Feature::Avx512Ifma,
+            Avx512Subset::Vbmi => Feature::Avx512Vbmi,
+            Avx512Subset::Vbmi2 => Feature::Avx512Vbmi2,
+            Avx512Subset::Vnni => Feature::Avx512Vnni,
+            Avx512Subset::Bitalg => Feature::Avx512Bitalg,
+            Avx512Subset::Vpopcntdq => Feature::Avx512Vpopcntdq,
+            Avx512Subset::Bf16 => Feature::Avx512Bf16,
+            Avx512Subset::Fp16 => Feature::Avx512Fp16,
+            Avx512Subset::Vp2intersect => Feature::Avx512Vp2intersect,
+        }
+    }
+}
+
+/// The AVX-512 subsets a processor supports, from `Master::avx512`.
+/// Like the flags, this says nothing of whether the operating system
+/// has enabled the register state; `SimdLevel` checks that as well.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Avx512Support {
+    subsets: u32,
+}
+
+impl Avx512Support {
+    pub fn has(self, subset: Avx512Subset) -> bool {
+        self.subsets & (1 << subset as u32) != 0
+    }
+
+    /// Whether every one of `subsets` is supported, as dispatchers
+    /// usually need several together.
+    pub fn all_of(self, subsets: &[Avx512Subset]) -> bool {
+        subsets.iter().all(|&s| self.has(s))
+    }
+
+    /// Every supported subset.
+    pub fn subsets(self) -> impl Iterator<Item = Avx512Subset> {
+        Avx512Subset::ALL.iter().cloned().filter(move |&s| self.has(s))
+    }
+}
+
+/// Advanced Matrix Extensions support, from `Master::amx`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Amx {
+    /// The tile registers and the instructions that load, store and
+    /// configure them.
+    pub tile: bool,
+    /// Multiplication of bfloat16 tiles.
+    pub bf16: bool,
+    /// Multiplication of 8-bit integer tiles.
+    pub int8: bool,
+    /// Multiplication of half precision tiles.
+    pub fp16: bool,
+    /// Multiplication of complex half precision tiles.
+    pub complex: bool,
+    /// XSAVE can manage the tile configuration (`TILECFG`).
+    pub tile_config_state: bool,
+    /// XSAVE can manage the tile registers themselves (`TILEDATA`).
+    pub tile_data_state: bool,
+    /// The geometry of the tiles.
+    pub tiles: Option<TileInformation>,
+    /// The limits of the matrix multiply unit.
+    pub tmul: Option<TmulInformation>,
+}
+
+impl Amx {
+    /// Whether the processor can run AMX code, given an operating
+    /// system that enables the tile state. Linux also requires each
+    /// process to request it, with `arch_prctl(ARCH_REQ_XCOMP_PERM)`.
+    pub fn usable(self) -> bool {
+        self.tile && self.tile_config_state && self.tile_data_state
+    }
+}
+
+/// Information about the currently running processor
+///
+/// Feature flags match the feature mnemonic listed in the Intel
+/// Instruction Set Reference. This struct provides a facade for flags
+/// so the consumer doesn't need to worry about which particular CPUID
+/// leaf provides the information.
+///
+/// For data beyond simple feature flags, you will need to retrieve
+/// the nested struct and call the appropriate methods on it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Master {
+    // TODO: Rename struct
+    vendor_id: VendorId,
+    version_information: Option<VersionInformation>,
+    processor_serial_number: Option<ProcessorSerialNumber>,
+    #[cfg(feature = "caches")]
+    deterministic_cache_parameters: Option<DeterministicCacheParameters>,
+    thermal_power_management_information: Option<ThermalPowerManagementInformation>,
+    structured_extended_information: Option<StructuredExtendedInformation>,
+    structured_extended_information_1: Option<StructuredExtendedInformation1>,
+    performance_monitoring_information: Option<PerformanceMonitoringInformation>,
+    extended_topology: Option<ExtendedTopology>,
+    extended_state_information: Option<ExtendedStateInformation>,
+    rdt_monitoring_information: Option<RdtMonitoringInformation>,
+    rdt_allocation_information: Option<RdtAllocationInformation>,
+    tsc_frequency_information: Option<TscFrequencyInformation>,
+    processor_frequency_information: Option<ProcessorFrequencyInformation>,
+    key_locker_information: Option<KeyLockerInformation>,
+    native_model_identification: Option<NativeModelIdentification>,
+    pconfig_information: Option<PconfigInformation>,
+    last_branch_record_information: Option<LastBranchRecordInformation>,
+    tile_information: Option<TileInformation>,
+    tmul_information: Option<TmulInformation>,
+    tdx_guest_information: Option<TdxGuestInformation>,
+    extended_processor_signature: Option<ExtendedProcessorSignature>,
+    #[cfg(feature = "hypervisor")]
+    hypervisor_information: Option<HypervisorInformation>,
+    #[cfg(feature = "hypervisor")]
+    kvm_features: Option<KvmFeatures>,
+    #[cfg(feature = "hypervisor")]
+    hyper_v_information: Option<HyperVInformation>,
+    #[cfg(feature = "hypervisor")]
+    xen_information: Option<XenInformation>,
+    #[cfg(feature = "hypervisor")]
+    vmware_timing_information: Option<VMwareTimingInformation>,
+    brand_string: Option<BrandString>,
+    #[cfg(feature = "caches")]
+    l1_cache_and_tlb_information: Option<L1CacheAndTlbInformation>,
+    #[cfg(feature = "caches")]
+    cache_line: Option<CacheLine>,
+    time_stamp_counter: Option<TimeStampCounter>,
+    physical_address_size: Option<PhysicalAddressSize>,
+    #[cfg(feature = "amd")]
+    svm_features: Option<SvmFeatures>,
+    #[cfg(feature = "amd")]
+    gigabyte_page_tlb_information: Option<GigabytePageTlbInformation>,
+    #[cfg(feature = "amd")]
+    performance_optimization_identifiers: Option<PerformanceOptimizationIdentifiers>,
+    #[cfg(feature = "amd")]
+    instruction_based_sampling_information: Option<InstructionBasedSamplingInformation>,
+    #[cfg(feature = "amd")]
+    processor_topology_information: Option<ProcessorTopologyInformation>,
+    #[cfg(feature = "amd")]
+    core_complex_information: Option<CoreComplexInformation>,
+    #[cfg(feature = "amd")]
+    encrypted_memory_capabilities: Option<EncryptedMemoryCapabilities>,
+    #[cfg(feature = "amd")]
+    extended_feature_identification_2: Option<ExtendedFeatureIdentification2>,
+    aarch64_information: Option<Aarch64Information>,
+}
+
+impl Master {
+    fn new<S: CpuidSource>(source: &S) -> Master {
+        let source = &OncePerPass::new(source);
+
+        fn when_supported<F, T>(max: u32, kind: RequestType, then: F) -> Option<T>
+            where F: FnOnce() -> T
+        {
+            if max >= kind as u32 {
+                Some(then())
+            } else {
+                None
+            }
+        }
+
+        let (max_value, _, _, _) = source.cpuid(RequestType::BasicInformation as u32, 0);
+
+        let vendor_id = VendorId::new(source);
+        let vi = when_supported(max_value, RequestType::VersionInformation, || {
+            VersionInformation::new(source)
+        });
+        // Leaf 3 only holds the serial number while the processor reports
+        // it; once disabled, it reads as reserved until the next reset
+        let psn = match vi {
+            Some(v) if v.psn() => when_supported(max_value, RequestType::ProcessorSerialNumber, || {
+                ProcessorSerialNumber::new(source, v.eax())
+            }),
+            _ => None,
+        };
+        #[cfg(feature = "caches")]
+        let dcp = when_supported(max_value, RequestType::DeterministicCacheParameters, || {
+            DeterministicCacheParameters::new(source, RequestType::DeterministicCacheParameters)
+        });
+        let tpm = when_supported(max_value, RequestType::ThermalPowerManagementInformation, || {
+            ThermalPowerManagementInformation::new(source)
+        });
+        let sei = when_supported(max_value, RequestType::StructuredExtendedInformation, || {
+            StructuredExtendedInformation::new(source)
+        });
+        let sei1 = sei.and_then(|_| StructuredExtendedInformation1::new(source));
+        let pmi = if max_value >= RequestType::PerformanceMonitoring as u32 {
+            PerformanceMonitoringInformation::new(source)
+        } else {
+            None
+        };
+        // Leaf 0x1F adds module, tile and die levels to leaf 0xB
+        let v2_topology = if max_value >= RequestType::V2ExtendedTopology as u32 {
+            ExtendedTopology::new(source, RequestType::V2ExtendedTopology)
+        } else {
+            None
+        };
+        let topology = v2_topology.or_else(|| {
+            if max_value >= RequestType::ExtendedTopology as u32 {
+                ExtendedTopology::new(source, RequestType::ExtendedTopology)
+            } else {
+                None
+            }
+        });
+        let esi = when_supported(max_value, RequestType::ExtendedState, || {
+            ExtendedStateInformation::new(source)
+        });
+        let pqm = sei.map(|s| s.pqm()).unwrap_or(false);
+        let rdtm = if pqm {
+            when_supported(max_value, RequestType::RdtMonitoring, || {
+                RdtMonitoringInformation::new(source)
+            })
+        } else {
+            None
+        };
+        let pqe = sei.map(|s| s.pqe()).unwrap_or(false);
+        let rdta = if pqe {
+            when_supported(max_value, RequestType::RdtAllocation, || {
+                RdtAllocationInformation::new(source)
+            })
+        } else {
+            None
+        };
+        let tfi = when_supported(max_value, RequestType::TscFrequency, || {
+            TscFrequencyInformation::new(source)
+        });
+        let pfi = when_supported(max_value, RequestType::ProcessorFrequency, || {
+            ProcessorFrequencyInformation::new(source)
+        });
+        let kl = sei.map(|s| s.kl()).unwrap_or(false);
+        let kli = if kl {
+            when_supported(max_value, RequestType::KeyLocker, || {
+                KeyLockerInformation::new(source)
+            })
+        } else {
+            None
+        };
+        let hybrid = sei.map(|s| s.hybrid()).unwrap_or(false);
+        let nmi = if hybrid {
+            when_supported(max_value, RequestType::NativeModelIdentification, || {
+                NativeModelIdentification::new(source)
+            })
+        } else {
+            None
+        };
+        let pconfig = sei.map(|s| s.pconfig()).unwrap_or(false);
+        let pci = if pconfig {
+            when_supported(max_value, RequestType::Pconfig, || {
+                PconfigInformation::new(source)
+            })
+        } else {
+            None
+        };
+        let arch_lbr = sei.map(|s| s.arch_lbr()).unwrap_or(false);
+        let lbr = if arch_lbr {
+            when_supported(max_value, RequestType::LastBranchRecords, || {
+                LastBranchRecordInformation::new(source)
+            })
+        } else {
+            None
+        };
+        let amx_tile = sei.map(|s| s.amx_tile()).unwrap_or(false);
+        let ti = if amx_tile {
+            when_supported(max_value, RequestType::TileInformation, || {
+                TileInformation::new(source)
+            })
+        } else {
+            None
+        };
+        let tmul = if amx_tile {
+            when_supported(max_value, RequestType::TmulInformation, || {
+                TmulInformation::new(source)
+            })
+        } else {
+            None
+        };
+        let tdx = if max_value >= RequestType::TdxGuest as u32 {
+            TdxGuestInformation::new(source)
+        } else {
+            None
+        };
+
+        #[cfg(feature = "hypervisor")]
+        let hi = if vi.map(|v| v.hypervisor()).unwrap_or(false) {
+            Some(HypervisorInformation::new(source))
+        } else {
+            None
+        };
+        #[cfg(feature = "hypervisor")]
+        let kvm = hi.and_then(|h| {
+            // Older KVM versions report a maximum leaf of zero
+            let max_leaf = h.max_leaf();
+            let has_features = max_leaf == 0 || max_leaf > RequestType::HypervisorInformation as u32;
+            if h.hypervisor() == Hypervisor::Kvm && has_features {
+                Some(KvmFeatures::new(source))
+            } else {
+                None
+            }
+        });
+        #[cfg(feature = "hypervisor")]
+        let hyper_v = hi.and_then(|h| {
+            let base = RequestType::HypervisorInformation as u32;
+            let max_leaf = h.max_leaf();
+            if h.hypervisor() != Hypervisor::HyperV || max_leaf <= base {
+                return None;
+            }
+            let (interface, _, _, _) = source.cpuid(base + 1, 0);
+            if interface == HYPER_V_INTERFACE {
+                Some(HyperVInformation::new(source, max_leaf))
+            } else {
+                None
+            }
+        });
+        #[cfg(feature = "hypervisor")]
+        let vmware = hi.and_then(|h| {
+            let timing_leaf = RequestType::HypervisorInformation as u32 + 0x10;
+            let offered = h.hypervisor() == Hypervisor::VMware || h.hypervisor() == Hypervisor::Kvm;
+            if offered && h.max_leaf() >= timing_leaf {
+                Some(VMwareTimingInformation::new(source))
+            } else {
+                None
+            }
+        });
+        // Xen offering Hyper-V enlightenments moves its own leaves up
+        #[cfg(feature = "hypervisor")]
+        let xen = hi.and_then(|h| {
+            match h.hypervisor() {
+                Hypervisor::Xen | Hypervisor::HyperV => XenInformation::new(source),
+                _ => None,
+            }
+        });
+
+        // Extended information
+
+        let (max_value, _, _, _) = source.cpuid(RequestType::ExtendedFunctionInformation as u32, 0);
+        let max_value = if in_extended_range(max_value) { max_value } else { 0 };
+
+        let eps = when_supported(max_value, RequestType::ExtendedProcessorSignature, || {
+            ExtendedProcessorSignature::new(source)
+        });
+        let brand_string = when_supported(max_value, RequestType::BrandString3, || {
+            BrandString::new(source)
+        }).filter(|b| !b.as_str().is_empty());
+        #[cfg(feature = "caches")]
+        let l1 = when_supported(max_value, RequestType::L1CacheAndTlb, || {
+            L1CacheAndTlbInformation::new(source)
+        });
+        #[cfg(feature = "caches")]
+        let cache_line = when_supported(max_value, RequestType::CacheLine, || {
+            CacheLine::new(source)
+        });
+        let tsc = when_supported(max_value, RequestType::TimeStampCounter, || {
+            TimeStampCounter::new(source)
+        });
+        let pas = when_supported(max_value, RequestType::PhysicalAddressSize, || {
+            PhysicalAddressSize::new(source)
+        });
+        #[cfg(feature = "amd")]
+        let svmf = if eps.map(|e| e.svm()).unwrap_or(false) {
+            when_supported(max_value, RequestType::SvmFeatures, || {
+                SvmFeatures::new(source)
+            })
+        } else {
+            None
+        };
+        #[cfg(feature = "amd")]
+        let gbtlb = when_supported(max_value, RequestType::GigabytePageTlb, || {
+            GigabytePageTlbInformation::new(source)
+        });
+        #[cfg(feature = "amd")]
+        let poi = when_supported(max_value, RequestType::PerformanceOptimization, || {
+            PerformanceOptimizationIdentifiers::new(source)
+        });
+        #[cfg(feature = "amd")]
+        let ibsi = if eps.map(|e| e.ibs()).unwrap_or(false) {
+            when_supported(max_value, RequestType::InstructionBasedSampling, || {
+                InstructionBasedSamplingInformation::new(source)
+            })
+        } else {
+            None
+        };
+        #[cfg(feature = "amd")]
+        let topoext = eps.map(|e| e.topology_extensions()).unwrap_or(false);
+        #[cfg(feature = "amd")]
+        let pti = if topoext {
+            when_supported(max_value, RequestType::ProcessorTopology, || {
+                ProcessorTopologyInformation::new(source)
+            })
+        } else {
+            None
+        };
+        #[cfg(feature = "amd")]
+        let cci = if topoext {
+            when_supported(max_value, RequestType::ProcessorTopology, || {
+                CoreComplexInformation::new(source)
+            })
+        } else {
+            None
+        };
+        #[cfg(feature = "amd")]
+        let emc = when_supported(max_value, RequestType::EncryptedMemory, || {
+            EncryptedMemoryCapabilities::new(source)
+        });
+        #[cfg(feature = "amd")]
+        let efi2 = when_supported(max_value, RequestType::ExtendedFeatureIdentification2, || {
+            ExtendedFeatureIdentification2::new(source)
+        });
+
+        // AMD leaves leaf 4 empty and reports its caches here instead
+        #[cfg(all(feature = "caches", feature = "amd"))]
+        let dcp = match dcp {
+            Some(p) if !p.caches().is_empty() => Some(p),
+            _ if topoext => {
+                when_supported(max_value, RequestType::CacheProperties, || {
+                    DeterministicCacheParameters::new(source, RequestType::CacheProperties)
+                })
+            },
+            other => other,
+        };
+
+        Master {
+            vendor_id,
+            version_information: vi,
+            processor_serial_number: psn,
+            #[cfg(feature = "caches")]
+            deterministic_cache_parameters: dcp,
+            thermal_power_management_information: tpm,
+            structured_extended_information: sei,
+            structured_extended_information_1: sei1,
+            performance_monitoring_information: pmi,
+            extended_topology: topology,
+            extended_state_information: esi,
+            rdt_monitoring_information: rdtm,
+            rdt_allocation_information: rdta,
+            tsc_frequency_information: tfi,
+            processor_frequency_information: pfi,
+            key_locker_information: kli,
+            native_model_identification: nmi,
+            pconfig_information: pci,
+            last_branch_record_information: lbr,
+            tile_information: ti,
+            tmul_information: tmul,
+            tdx_guest_information: tdx,
+            extended_processor_signature: eps,
+            #[cfg(feature = "hypervisor")]
+            hypervisor_information: hi,
+            #[cfg(feature = "hypervisor")]
+            kvm_features: kvm,
+            #[cfg(feature = "hypervisor")]
+            hyper_v_information: hyper_v,
+            #[cfg(feature = "hypervisor")]
+            xen_information: xen,
+            #[cfg(feature = "hypervisor")]
+            vmware_timing_information: vmware,
+            brand_string,
+            #[cfg(feature = "caches")]
+            l1_cache_and_tlb_information: l1,
+            #[cfg(feature = "caches")]
+            cache_line,
+            time_stamp_counter: tsc,
+            physical_address_size: pas,
+            #[cfg(feature = "amd")]
+            svm_features: svmf,
+            #[cfg(feature = "amd")]
+            gigabyte_page_tlb_information: gbtlb,
+            #[cfg(feature = "amd")]
+            performance_optimization_identifiers: poi,
+            #[cfg(feature = "amd")]
+            instruction_based_sampling_information: ibsi,
+            #[cfg(feature = "amd")]
+            processor_topology_information: pti,
+            #[cfg(feature = "amd")]
+            core_complex_information: cci,
+            #[cfg(feature = "amd")]
+            encrypted_memory_capabilities: emc,
+            #[cfg(feature = "amd")]
+            extended_feature_identification_2: efi2,
+            aarch64_information: None,
+        }
+    }
+
+    /// Queries the current processor in a single pass, issuing each
+    /// CPUID query exactly once, in leaf order. Meant for capturing at
+    /// startup: afterwards every accessor only reads what was stored.
+    ///
+    /// A recent processor needs around 30 queries. Each costs on the
+    /// order of a hundred cycles on bare metal, but traps to the
+    /// hypervisor in a virtual machine, where a query can take a few
+    /// microseconds. `cargo bench --bench master` measures it: in a
+    /// Firecracker guest, capturing took about 60µs, `master` about 25µs
+    /// with the shared leaves cached, and `get` a nanosecond.
+    pub fn capture() -> Result<Master, CpuidError> {
+        master_from(&Native)
+    }
+
+    /// Decodes a dump captured elsewhere, such as on another machine.
+    #[cfg(feature = "std")]
+    pub fn from_dump(dump: &RawDump) -> Master {
+        Master::new(dump)
+    }
+
+    /// Decodes the registers that `source` reports.
+    pub fn from_source<S: CpuidSource>(source: &S) -> Master {
+        Master::new(source)
+    }
+
+    master_attr_reader!(version_information, VersionInformation);
+    master_attr_reader!(processor_serial_number, ProcessorSerialNumber);
+    #[cfg(feature = "caches")]
+    master_attr_reader!(deterministic_cache_parameters, DeterministicCacheParameters);
+    master_attr_reader!(thermal_power_management_information, ThermalPowerManagementInformation);
+    master_attr_reader!(structured_extended_information, StructuredExtendedInformation);
+    master_attr_reader!(structured_extended_information_1, StructuredExtendedInformation1);
+    master_attr_reader!(performance_monitoring_information, PerformanceMonitoringInformation);
+    master_attr_reader!(extended_topology, ExtendedTopology);
+    master_attr_reader!(extended_state_information, ExtendedStateInformation);
+    master_attr_reader!(rdt_monitoring_information, RdtMonitoringInformation);
+    master_attr_reader!(rdt_allocation_information, RdtAllocationInformation);
+    master_attr_reader!(tsc_frequency_information, TscFrequencyInformation);
+    master_attr_reader!(processor_frequency_information, ProcessorFrequencyInformation);
+    master_attr_reader!(key_locker_information, KeyLockerInformation);
+    master_attr_reader!(native_model_identification, NativeModelIdentification);
+    master_attr_reader!(pconfig_information, PconfigInformation);
+    master_attr_reader!(last_branch_record_information, LastBranchRecordInformation);
+    master_attr_reader!(tile_information, TileInformation);
+    master_attr_reader!(tmul_information, TmulInformation);
+    master_attr_reader!(tdx_guest_information, TdxGuestInformation);
+    master_attr_reader!(extended_processor_signature, ExtendedProcessorSignature);
+    #[cfg(feature = "hypervisor")]
+    master_attr_reader!(hypervisor_information, HypervisorInformation);
+    #[cfg(feature = "hypervisor")]
+    master_attr_reader!(kvm_features, KvmFeatures);
+    #[cfg(feature = "hypervisor")]
+    master_attr_reader!(hyper_v_information, HyperVInformation);
+    #[cfg(feature = "hypervisor")]
+    master_attr_reader!(xen_information, XenInformation);
+    #[cfg(feature = "hypervisor")]
+    master_attr_reader!(vmware_timing_information, VMwareTimingInformation);
+    #[cfg(feature = "caches")]
+    master_attr_reader!(l1_cache_and_tlb_information, L1CacheAndTlbInformation);
+    #[cfg(feature = "caches")]
+    master_attr_reader!(cache_line, CacheLine);
+    master_attr_reader!(time_stamp_counter, TimeStampCounter);
+    master_attr_reader!(physical_address_size, PhysicalAddressSize);
+    #[cfg(feature = "amd")]
+    master_attr_reader!(svm_features, SvmFeatures);
+    #[cfg(feature = "amd")]
+    master_attr_reader!(gigabyte_page_tlb_information, GigabytePageTlbInformation);
+    #[cfg(feature = "amd")]
+    master_attr_reader!(performance_optimization_identifiers, PerformanceOptimizationIdentifiers);
+    #[cfg(feature = "amd")]
+    master_attr_reader!(instruction_based_sampling_information, InstructionBasedSamplingInformation);
+    #[cfg(feature = "amd")]
+    master_attr_reader!(processor_topology_information, ProcessorTopologyInformation);
+    #[cfg(feature = "amd")]
+    master_attr_reader!(core_complex_information, CoreComplexInformation);
+    #[cfg(feature = "amd")]
+    master_attr_reader!(encrypted_memory_capabilities, EncryptedMemoryCapabilities);
+    #[cfg(feature = "amd")]
+    master_attr_reader!(extended_feature_identification_2, ExtendedFeatureIdentification2);
+    master_attr_reader!(aarch64_information, Aarch64Information);
+
+    /// The vendor identification, such as "GenuineIntel".
+    pub fn vendor_id(&self) -> &str {
+        &self.vendor_id
+    }
+
+    /// Every feature flag by name, with whether it is supported.
+    pub fn flags<'a>(&'a self) -> impl Iterator<Item = (&'static str, bool)> + 'a {
+        Master::FLAGS.iter().map(move |&(name, has)| (name, has(self)))
+    }
+
+    /// The supported features on one line, separated by commas, such as
+    /// "sse3, ssse3, sse4_1". `FeatureSet`'s `Display` does the same
+    /// without allocating.
+    #[cfg(feature = "std")]
+    pub fn flags_string(&self) -> std::string::String {
+        format!("{}", FeatureSet::from(self))
+    }
+
+    /// Looks up a feature flag by its method name, such as "avx2",
+    /// returning `None` if the name is not known.
+    pub fn has_feature(&self, name: &str) -> Option<bool> {
+        Master::FLAGS.iter().find(|f| f.0 == name).map(|&(_, has)| has(self))
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        (Master::FLAGS[feature as usize].1)(self)
+    }
+
+    /// The brand string, such as "Intel(R) Core(TM) i7-8700K CPU @
+    /// 3.70GHz", as the processor reports it. `None` on processors
+    /// without the brand string leaves, or whose leaves are all zero.
+    pub fn brand_string(&self) -> Option<&str> {
+        self.brand_string.as_ref().map(BrandString::as_str)
+    }
+
+    /// A name for processors without a brand string, from the brand
+    /// index of leaf 1 or, failing that, the vendor and signature, such
+    /// as "Intel P6 Family 6h Model 8h". `None` when the processor
+    /// reports a brand string, so a made-up name is never mistaken for
+    /// the processor's own.
+    pub fn synthesized_brand_string(&self) -> Option<BrandString> {
+        match self.brand_string {
+            Some(_) => None,
+            None => BrandString::synthesize(self),
+        }
+    }
+
+    /// The nominal frequency given at the end of the brand string, such
+    /// as "@ 3.60GHz", which most Intel processors include.
+    pub fn brand_frequency_hz(&self) -> Option<u64> {
+        self.brand_string().and_then(brand_frequency_hz)
+    }
+
+    /// The brand string in the form inventories and displays want, such
+    /// as "Intel Core i7-9700K" for "Intel(R) Core(TM) i7-9700K CPU @
+    /// 3.60GHz".
+    #[cfg(feature = "std")]
+    pub fn normalized_brand(&self) -> Option<std::string::String> {
+        self.brand_string().map(normalize_brand)
+    }
+
+    /// The size of a cache line in bytes, such as for padding data to
+    /// avoid false sharing. This is the line size of the L1 data cache,
+    /// falling back to the CLFLUSH line size and then AMD's L1 and L2
+    /// cache leaves.
+    pub fn cache_line_size(&self) -> Option<u32> {
+        #[cfg(feature = "caches")]
+        let l1d = self.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]).iter()
+            .find(|c| c.level() == 1 && c.cache_type() == Some(CacheType::Data))
+            .map(|c| c.line_size());
+        #[cfg(not(feature = "caches"))]
+        let l1d = None;
+        let size = l1d.or_else(|| self.version_information.and_then(|vi| vi.clflush_line_size()));
+        #[cfg(feature = "caches")]
+        let size = size
+            .or_else(|| self.l1_cache_and_tlb_information.map(|l1| l1.data_cache_line_size()))
+            .or_else(|| self.cache_line.map(|cl| cl.cache_line_size()));
+
+        size.filter(|&size| size != 0)
+    }
+
+    /// The number of logical processors in the package, as the
+    /// processor describes itself in the extended topology leaves or
+    /// AMD's leaf 0x8000_0008.
+    ///
+    /// Intel documents the count of leaf 0xB as being for display only:
+    /// it reflects how the processor was built and configured at reset,
+    /// not which processors the firmware and operating system enabled,
+    /// so it may differ from the processors code can run on. Count
+    /// those with the operating system, such as through
+    /// `std::thread::available_parallelism`.
+    pub fn logical_cores(&self) -> Option<u32> {
+        let from_topology = self.extended_topology().map(|t| t.logical_processors());
+        if let Some(n) = from_topology.filter(|&n| n != 0) {
+            return Some(n);
+        }
+        #[cfg(feature = "amd")]
+        {
+            if self.processor_topology_information.is_some() {
+                return self.physical_address_size.map(|p| p.logical_processor_count());
+            }
+        }
+        match self.version_information {
+            Some(vi) if !vi.htt() => Some(1),
+            _ => None,
+        }
+    }
+
+    /// The APIC ID of the logical processor the information was captured
+    /// on: the x2APIC ID where the topology leaves report it, then AMD's
+    /// extended APIC ID, then the 8-bit initial APIC ID.
+    pub fn apic_id(&self) -> Option<u32> {
+        let apic_id = self.extended_topology().and_then(|t| t.levels().first().map(|l| l.x2apic_id()));
+        #[cfg(feature = "amd")]
+        let apic_id = apic_id.or_else(|| self.processor_topology_information.map(|t| t.extended_apic_id()));
+        apic_id.or_else(|| self.version_information.map(|vi| vi.initial_apic_id()))
+    }
+
+    /// The number of physical cores in the package, from
+    /// `logical_cores`, so with the same caveats. Returns `None` for
+    /// hybrid processors, whose cores do not all run the same number of
+    /// threads, so the count cannot be derived from a single core.
+    pub fn physical_cores(&self) -> Option<u32> {
+        if self.supports(Feature::Hybrid) {
+            return None;
+        }
+        let threads = self.threads_per_core().unwrap_or(1);
+        self.logical_cores().map(|n| n / threads.max(1))
+    }
+
+    /// The number of logical processors in each core, from the extended
+    /// topology leaves or AMD's leaf 0x8000_001E. On hybrid processors
+    /// this is the number in the core the information was captured on.
+    pub fn threads_per_core(&self) -> Option<u32> {
+        let threads = self.extended_topology().map(|t| t.threads_per_core());
+        #[cfg(feature = "amd")]
+        let threads = threads.or_else(|| self.processor_topology_information.map(|t| t.threads_per_core()));
+        threads
+    }
+
+    /// The width of linear (virtual) addresses. Leaf 0x8000_0008 can
+    /// disagree with the LA57 flag under some hypervisors, so this is
+    /// at least 57 bits with 5-level paging and at most 48 without.
+    pub fn linear_address_bits(&self) -> Option<u32> {
+        let bits = self.physical_address_size?.linear_address_bits();
+        if self.supports(Feature::La57) {
+            Some(bits.max(57))
+        } else {
+            Some(bits.min(48))
+        }
+    }
+
+    /// Whether the processor supports 5-level paging, and so 57-bit
+    /// linear addresses. The operating system decides whether to use
+    /// it.
+    pub fn five_level_paging_supported(&self) -> bool {
+        self.supports(Feature::La57)
+    }
+
+    /// Whether XSAVE can save and restore a state component, as
+    /// described by `ExtendedStateInformation::supports_component`.
+    pub fn xsave_supports(&self, component: XsaveComponent) -> bool {
+        self.extended_state_information.map(|e| e.supports_component(component)).unwrap_or(false)
+    }
+
+    /// Whether the processor offers user interrupts, with the XSAVE
+    /// state component an operating system needs to switch their state
+    /// between threads.
+    pub fn user_interrupts(&self) -> bool {
+        self.supports(Feature::Uintr) && self.xsave_supports(XsaveComponent::Uintr)
+    }
+
+    /// The family, model and stepping by the vendor's rules. AMD only
+    /// adds the extended model to family 0xF, where Intel also adds it
+    /// to family 6, and repeats the signature in leaf 0x8000_0001,
+    /// which is used if leaf 1 reads as zero.
+    pub fn processor_signature(&self) -> Option<ProcessorSignature> {
+        let vi = self.version_information.filter(|vi| vi.eax() != 0);
+        match self.vendor_id() {
+            "AuthenticAMD" | "HygonGenuine" => {
+                let eax = vi.map(|vi| vi.eax())
+                    .or_else(|| self.extended_processor_signature.map(|e| e.eax()))
+                    .filter(|&eax| eax != 0)?;
+                let eps = ExtendedProcessorSignature::from_registers(eax, 0, 0, 0);
+                Some(ProcessorSignature {
+                    family: eps.family_id(),
+                    model: eps.model_id(),
+                    stepping: eps.stepping(),
+                })
+            },
+            _ => vi.map(|vi| ProcessorSignature {
+                family: vi.family_id(),
+                model: vi.model_id(),
+                stepping: vi.stepping(),
+            }),
+        }
+    }
+
+    /// Which AVX-512 subsets the processor supports.
+    pub fn avx512(&self) -> Avx512Support {
+        let subsets = Avx512Subset::ALL.iter()
+            .filter(|s| self.supports(s.feature()))
+            .fold(0, |subsets, &s| subsets | 1 << s as u32);
+        Avx512Support { subsets }
+    }
+
+    /// What the processor offers for Advanced Matrix Extensions, and
+    /// the size of its tiles.
+    pub fn amx(&self) -> Amx {
+        Amx {
+            tile: self.supports(Feature::AmxTile),
+            bf16: self.supports(Feature::AmxBf16),
+            int8: self.supports(Feature::AmxInt8),
+            fp16: self.supports(Feature::AmxFp16),
+            complex: self.supports(Feature::AmxComplex),
+            tile_config_state: self.xsave_supports(XsaveComponent::TileConfig),
+            tile_data_state: self.xsave_supports(XsaveComponent::TileData),
+            tiles: self.tile_information,
+            tmul: self.tmul_information,
+        }
+    }
+
+    /// What the processor offers for Control-flow Enforcement
+    /// Technology. Like the flags, this says nothing of whether the
+    /// operating system has enabled it.
+    pub fn cet(&self) -> Cet {
+        let component = |c| self.xsave_supports(c);
+
+        Cet {
+            shadow_stack: self.supports(Feature::CetSs),
+            indirect_branch_tracking: self.supports(Feature::CetIbt),
+            supervisor_shadow_stack: self.supports(Feature::CetSss),
+            user_state: component(XsaveComponent::CetUser),
+            supervisor_state: component(XsaveComponent::CetSupervisor),
+        }
+    }
+
+    /// A hint at the confidential computing environment the code runs
+    /// in, so that services relying on attestation can branch early.
+    ///
+    /// A TDX trust domain is recognized by its leaf. For AMD, CPUID only
+    /// says that the processor supports SEV-SNP, not that this guest
+    /// uses it, so a virtual machine that reports SEV-SNP is taken to
+    /// be an SEV-SNP guest, and SEV and SEV-ES guests are not
+    /// recognized. The `SEV_STATUS` MSR says which is active, and the
+    /// free function `confidential_computing` reads it under the `msr`
+    /// feature. Only attestation proves any of them.
+    pub fn confidential_computing(&self) -> Option<ConfidentialComputing> {
+        if self.tdx_guest_information.is_some() {
+            Some(ConfidentialComputing::Tdx)
+        } else if self.supports(Feature::Hypervisor) && self.supports(Feature::SevSnp) {
+            Some(ConfidentialComputing::SevSnp)
+        } else {
+            None
+        }
+    }
+
+    /// The frequency of the time stamp counter. This comes from the
+    /// ratio to the crystal clock, using the crystal frequencies Intel
+    /// documents for processors that do not report it, then from the
+    /// base frequency, and then from the hypervisor.
+    pub fn tsc_frequency_hz(&self) -> Option<u64> {
+        let from_crystal = self.tsc_frequency_information.and_then(|tfi| {
+            if tfi.denominator() == 0 || tfi.numerator() == 0 {
+                return None;
+            }
+            let crystal_hz = match tfi.crystal_frequency_hz() {
+                0 => self.default_crystal_frequency_hz()?,
+                hz => hz as u64,
+            };
+            Some(crystal_hz * tfi.numerator() as u64 / tfi.denominator() as u64)
+        });
+        let from_base = || {
+            self.processor_frequency_information
+                .map(|pfi| pfi.base_frequency_mhz() as u64 * 1_000_000)
+                .filter(|&hz| hz != 0)
+        };
+        let frequency = from_crystal.or_else(from_base);
+        #[cfg(feature = "hypervisor")]
+        let frequency = frequency.or_else(|| self.hypervisor_tsc_frequency());
+        frequency
+    }
+
+    /// The TSC frequency in Hz that the hypervisor reports to its
+    /// guests, from whichever of the VMware timing leaf (offered by
+    /// VMware and by QEMU to KVM guests) and the Xen time leaf is
+    /// present. Hyper-V only reports it through an MSR, which the free
+    /// function `hypervisor_tsc_frequency` reads with the `msr` feature.
+    #[cfg(feature = "hypervisor")]
+    pub fn hypervisor_tsc_frequency(&self) -> Option<u64> {
+        let from_timing_leaf = self.vmware_timing_information
+            .map(|vti| vti.tsc_frequency_khz());
+        let from_xen = || self.xen_information.map(|xi| xi.tsc_frequency_khz());
+
+        from_timing_leaf.filter(|&khz| khz != 0)
+            .or_else(|| from_xen().filter(|&khz| khz != 0))
+            .map(|khz| khz as u64 * 1000)
+    }
+
+    /// The frequency in Hz of the local APIC timer that the hypervisor
+    /// reports to its guests, from the VMware timing leaf. As with
+    /// `hypervisor_tsc_frequency`, Hyper-V only reports it through an
+    /// MSR.
+    #[cfg(feature = "hypervisor")]
+    pub fn hypervisor_apic_frequency(&self) -> Option<u64> {
+        self.vmware_timing_information
+            .map(|vti| vti.apic_bus_frequency_khz())
+            .filter(|&khz| khz != 0)
+            .map(|khz| khz as u64 * 1000)
+    }
+
+    // The crystal frequencies of processors whose leaf 0x15 omits it
+    fn default_crystal_frequency_hz(&self) -> Option<u64> {
+        if self.vendor_id() != "GenuineIntel" {
+            return None;
+        }
+        let vi = self.version_information?;
+        if vi.family_id() != 6 {
+            return None;
+        }
+        match vi.model_id() {
+            0x4E | 0x5E | 0x8E | 0x9E => Some(24_000_000),
+            0x55 | 0x5F => Some(25_000_000),
+            0x5C => Some(19_200_000),
+            _ => None,
+        }
+    }
+}
+
+// Every flag of `Master` by leaf, with the stable ID of its feature
+// and, where it has one, its name in `-C target-feature`. A new flag
+// takes the next unused ID, wherever it goes in the table. Each leaf
+// gives its type and its (leaf, sub-leaf), except PCONFIG, whose flag
+// is decoded from a list of targets rather than a bit. The table is
+// passed to the macro named, so that `master_flags!` and the exported
+// `statically_known!` share it. Leaves behind a Cargo feature carry its
+// `#[cfg]`; none of their flags has a rustc name.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __feature_table {
+    ($($callback:tt)+) => {
+        $($callback)+! {{
+            version_information (VersionInformation, 0x1, 0) => {
+                1: sse3 => Sse3 ("sse3"),
+                2: pclmulqdq => Pclmulqdq ("pclmulqdq"),
+                3: dtes64 => Dtes64,
+                4: monitor => Monitor,
+                5: ds_cpl => DsCpl,
+                6: vmx => Vmx,
+                7: smx => Smx,
+                8: eist => Eist,
+                9: tm2 => Tm2,
+                10: ssse3 => Ssse3 ("ssse3"),
+                11: cnxt_id => CnxtId,
+                12: sdbg => Sdbg,
+                13: fma => Fma ("fma"),
+                14: cmpxchg16b => Cmpxchg16b ("cmpxchg16b"),
+                15: xtpr_update_control => XtprUpdateControl,
+                16: pdcm => Pdcm,
+                17: pcid => Pcid,
+                18: dca => Dca,
+                19: sse4_1 => Sse4_1 ("sse4.1"),
+                20: sse4_2 => Sse4_2 ("sse4.2"),
+                21: x2apic => X2apic,
+                22: movbe => Movbe ("movbe"),
+                23: popcnt => Popcnt ("popcnt"),
+                24: tsc_deadline => TscDeadline,
+                25: aesni => Aesni ("aes"),
+                26: xsave => Xsave ("xsave"),
+                27: osxsave => Osxsave,
+                28: avx => Avx ("avx"),
+                29: f16c => F16c ("f16c"),
+                30: rdrand => Rdrand ("rdrand"),
+                31: hypervisor => Hypervisor,
+                32: fpu => Fpu ("x87"),
+                33: vme => Vme,
+                34: de => De,
+                35: pse => Pse,
+                36: tsc => Tsc,
+                37: msr => Msr,
+                38: pae => Pae,
+                39: mce => Mce,
+                40: cx8 => Cx8 (llvm "cx8"),
+                41: apic => Apic,
+                42: sep => Sep,
+                43: mtrr => Mtrr,
+                44: pge => Pge,
+                45: mca => Mca,
+                46: cmov => Cmov (llvm "cmov"),
+                47: pat => Pat,
+                48: pse_36 => Pse36,
+                49: psn => Psn,
+                50: clfsh => Clfsh,
+                51: ds => Ds,
+                52: acpi => Acpi,
+                53: mmx => Mmx,
+                54: fxsr => Fxsr ("fxsr"),
+                55: sse => Sse ("sse"),
+                56: sse2 => Sse2 ("sse2"),
+                57: ss => Ss,
+                58: htt => Htt,
+                59: tm => Tm,
+                60: pbe => Pbe
+            },
+
+            thermal_power_management_information (ThermalPowerManagementInformation, 0x6, 0) => {
+                61: digital_temperature_sensor => DigitalTemperatureSensor,
+                62: intel_turbo_boost => IntelTurboBoost,
+                63: arat => Arat,
+                64: pln => Pln,
+                65: ecmd => Ecmd,
+                66: ptm => Ptm,
+                67: hwp => Hwp,
+                68: hwp_notification => HwpNotification,
+                69: hwp_activity_window => HwpActivityWindow,
+                70: hwp_energy_performance_preference => HwpEnergyPerformancePreference,
+                71: hdc => Hdc,
+                72: turbo_boost_max_3 => TurboBoostMax3,
+                73: hwp_highest_performance_change => HwpHighestPerformanceChange,
+                74: hwp_peci_override => HwpPeciOverride,
+                75: flexible_hwp => FlexibleHwp,
+                76: fast_hwp_request => FastHwpRequest,
+                77: hw_feedback => HwFeedback,
+                78: hwp_ignore_idle_logical_processor => HwpIgnoreIdleLogicalProcessor,
+                79: hardware_coordination_feedback => HardwareCoordinationFeedback,
+                80: performance_energy_bias => PerformanceEnergyBias,
+                81: hw_feedback_performance => HwFeedbackPerformance,
+                82: hw_feedback_efficiency => HwFeedbackEfficiency
+            },
+
+            structured_extended_information (StructuredExtendedInformation, 0x7, 0) => {
+                83: fsgsbase => Fsgsbase (llvm "fsgsbase"),
+                84: ia32_tsc_adjust_msr => Ia32TscAdjustMsr,
+                85: sgx => Sgx (llvm "sgx"),
+                86: bmi1 => Bmi1 ("bmi1"),
+                87: hle => Hle,
+                88: avx2 => Avx2 ("avx2"),
+                89: fdp_excptn_only => FdpExcptnOnly,
+                90: smep => Smep,
+                91: bmi2 => Bmi2 ("bmi2"),
+                92: enhanced_rep_movsb_stosb => EnhancedRepMovsbStosb ("ermsb"),
+                93: invpcid => Invpcid (llvm "invpcid"),
+                94: rtm => Rtm ("rtm"),
+                95: pqm => Pqm,
+                96: deprecates_fpu_cs_ds => DeprecatesFpuCsDs,
+                97: mpx => Mpx,
+                98: pqe => Pqe,
+                99: avx512f => Avx512f ("avx512f"),
+                100: avx512dq => Avx512dq ("avx512dq"),
+                101: rdseed => Rdseed ("rdseed"),
+                102: adx => Adx ("adx"),
+                103: smap => Smap,
+                104: avx512_ifma => Avx512Ifma ("avx512ifma"),
+                105: clflushopt => Clflushopt (llvm "clflushopt"),
+                106: clwb => Clwb (llvm "clwb"),
+                107: intel_processor_trace => IntelProcessorTrace,
+                108: avx512pf => Avx512pf,
+                109: avx512er => Avx512er,
+                110: avx512cd => Avx512cd ("avx512cd"),
+                111: sha => Sha ("sha"),
+                112: avx512bw => Avx512bw ("avx512bw"),
+                113: avx512vl => Avx512vl ("avx512vl"),
+                114: prefetchwt1 => Prefetchwt1,
+                115: avx512_vbmi => Avx512Vbmi ("avx512vbmi"),
+                116: waitpkg => Waitpkg (llvm "waitpkg"),
+                117: avx512_vbmi2 => Avx512Vbmi2 ("avx512vbmi2"),
+                118: cet_ss => CetSs (llvm "shstk"),
+                119: gfni => Gfni ("gfni"),
+                120: vaes => Vaes ("vaes"),
+                121: vpclmulqdq => Vpclmulqdq ("vpclmulqdq"),
+                122: avx512_vnni => Avx512Vnni ("avx512vnni"),
+                123: avx512_bitalg => Avx512Bitalg ("avx512bitalg"),
+                124: avx512_vpopcntdq => Avx512Vpopcntdq ("avx512vpopcntdq"),
+                125: la57 => La57,
+                126: rdpid => Rdpid (llvm "rdpid"),
+                127: kl => Kl ("kl"),
+                128: cldemote => Cldemote (llvm "cldemote"),
+                129: movdiri => Movdiri (llvm "movdiri"),
+                130: movdir64b => Movdir64b (llvm "movdir64b"),
+                131: avx512_4vnniw => Avx512_4vnniw,
+                132: avx512_4fmaps => Avx512_4fmaps,
+                133: uintr => Uintr (llvm "uintr"),
+                134: avx512_vp2intersect => Avx512Vp2intersect ("avx512vp2intersect"),
+                135: srbds_ctrl => SrbdsCtrl,
+                136: md_clear => MdClear,
+                137: rtm_always_abort => RtmAlwaysAbort,
+                138: serialize => Serialize (llvm "serialize"),
+                139: hybrid => Hybrid,
+                140: pconfig => Pconfig (llvm "pconfig"),
+                141: arch_lbr => ArchLbr,
+                142: cet_ibt => CetIbt,
+                143: amx_bf16 => AmxBf16 ("amx-bf16"),
+                144: avx512_fp16 => Avx512Fp16 ("avx512fp16"),
+                145: amx_tile => AmxTile ("amx-tile"),
+                146: amx_int8 => AmxInt8 ("amx-int8"),
+                147: ibrs_ibpb => IbrsIbpb,
+                148: stibp => Stibp,
+                149: l1d_flush => L1dFlush,
+                150: arch_capabilities => ArchCapabilities,
+                151: core_capabilities => CoreCapabilities,
+                152: ssbd => Ssbd
+            },
+
+            structured_extended_information_1 (StructuredExtendedInformation1, 0x7, 1) => {
+                153: sha512 => Sha512 ("sha512"),
+                154: sm3 => Sm3 ("sm3"),
+                155: sm4 => Sm4 ("sm4"),
+                156: rao_int => RaoInt (llvm "raoint"),
+                157: avx_vnni => AvxVnni ("avxvnni"),
+                158: avx512_bf16 => Avx512Bf16 ("avx512bf16"),
+                159: lass => Lass,
+                160: cmpccxadd => Cmpccxadd (llvm "cmpccxadd"),
+                161: arch_perfmon_ext => ArchPerfmonExt,
+                162: fzlrm => Fzlrm,
+                163: fsrs => Fsrs,
+                164: fsrcs => Fsrcs,
+                165: fred => Fred,
+                166: lkgs => Lkgs,
+                167: wrmsrns => Wrmsrns,
+                168: amx_fp16 => AmxFp16 ("amx-fp16"),
+                169: hreset => Hreset (llvm "hreset"),
+                170: avx_ifma => AvxIfma ("avxifma"),
+                171: lam => Lam,
+                172: msrlist => Msrlist,
+                173: avx_vnni_int8 => AvxVnniInt8 ("avxvnniint8"),
+                174: avx_ne_convert => AvxNeConvert ("avxneconvert"),
+                175: amx_complex => AmxComplex ("amx-complex"),
+                176: avx_vnni_int16 => AvxVnniInt16 ("avxvnniint16"),
+                177: prefetchi => Prefetchi (llvm "prefetchi"),
+                178: uiret_uif_from_rflags => UiretUifFromRflags,
+                179: cet_sss => CetSss,
+                180: avx10 => Avx10,
+                181: apx_f => ApxF ("apxf")
+            },
+
+            extended_state_information (ExtendedStateInformation, 0xD, 0) => {
+                182: xsaveopt => Xsaveopt ("xsaveopt"),
+                183: xsavec => Xsavec ("xsavec"),
+                184: xgetbv_ecx1 => XgetbvEcx1,
+                185: xsaves => Xsaves ("xsaves"),
+                186: xfd => Xfd
+            },
+
+            rdt_monitoring_information (RdtMonitoringInformation, 0xF, 0) => {
+                187: l3_monitoring => L3Monitoring,
+                188: l3_occupancy_monitoring => L3OccupancyMonitoring,
+                189: l3_total_bandwidth_monitoring => L3TotalBandwidthMonitoring,
+                190: l3_local_bandwidth_monitoring => L3LocalBandwidthMonitoring
+            },
+
+            rdt_allocation_information (RdtAllocationInformation, 0x10, 0) => {
+                191: l3_cat => L3Cat,
+                192: l2_cat => L2Cat,
+                193: mba => Mba
+            },
+
+            key_locker_information (KeyLockerInformation, 0x19, 0) => {
+                194: kl_cpl0_only_restriction => KlCpl0OnlyRestriction,
+                195: kl_no_encrypt_restriction => KlNoEncryptRestriction,
+                196: kl_no_decrypt_restriction => KlNoDecryptRestriction,
+                197: aeskle => Aeskle,
+                198: wide_kl => WideKl ("widekl"),
+                199: iwkey_backup => IwkeyBackup,
+                200: loadiwkey_no_backup => LoadiwkeyNoBackup,
+                201: iwkey_randomization => IwkeyRandomization
+            },
+
+            pconfig_information () => {
+                202: mktme => Mktme
+            },
+
+            last_branch_record_information (LastBranchRecordInformation, 0x1C, 0) => {
+                203: deep_c_state_reset => DeepCStateReset,
+                204: ip_values_contain_lip => IpValuesContainLip,
+                205: cpl_filtering => CplFiltering,
+                206: branch_filtering => BranchFiltering,
+                207: call_stack_mode => CallStackMode,
+                208: mispredict_bit => MispredictBit,
+                209: timed_lbrs => TimedLbrs,
+                210: branch_type_field => BranchTypeField
+            },
+
+            #[cfg(feature = "hypervisor")]
+            kvm_features (KvmFeatures, 0x4000_0001, 0) => {
+                211: kvmclock => Kvmclock,
+                212: nop_io_delay => NopIoDelay,
+                213: mmu_op => MmuOp,
+                214: kvmclock2 => Kvmclock2,
+                215: async_pf => AsyncPf,
+                216: steal_time => StealTime,
+                217: pv_eoi => PvEoi,
+                218: pv_unhalt => PvUnhalt,
+                219: pv_tlb_flush => PvTlbFlush,
+                220: async_pf_vmexit => AsyncPfVmexit,
+                221: pv_send_ipi => PvSendIpi,
+                222: poll_control => PollControl,
+                223: pv_sched_yield => PvSchedYield,
+                224: async_pf_int => AsyncPfInt,
+                225: msi_ext_dest_id => MsiExtDestId,
+                226: hc_map_gpa_range => HcMapGpaRange,
+                227: migration_control => MigrationControl,
+                228: kvmclock_stable => KvmclockStable,
+                229: realtime_hint => RealtimeHint
+            },
+
+            extended_processor_signature (ExtendedProcessorSignature, 0x8000_0001, 0) => {
+                230: lahf_sahf_in_64_bit => LahfSahfIn64Bit ("lahfsahf"),
+                231: cmp_legacy => CmpLegacy,
+                232: svm => Svm,
+                233: ext_apic_space => ExtApicSpace,
+                234: alt_mov_cr8 => AltMovCr8,
+                235: lzcnt => Lzcnt ("lzcnt"),
+                236: sse4a => Sse4a ("sse4a"),
+                237: misaligned_sse => MisalignedSse,
+                238: prefetchw => Prefetchw ("prfchw"),
+                239: osvw => Osvw,
+                240: ibs => Ibs,
+                241: xop => Xop ("xop"),
+                242: skinit => Skinit,
+                243: wdt => Wdt,
+                244: lwp => Lwp (llvm "lwp"),
+                245: fma4 => Fma4 (llvm "fma4"),
+                246: tce => Tce,
+                247: tbm => Tbm ("tbm"),
+                248: topology_extensions => TopologyExtensions,
+                249: perf_ctr_ext_core => PerfCtrExtCore,
+                250: perf_ctr_ext_nb => PerfCtrExtNb,
+                251: data_breakpoint_extension => DataBreakpointExtension,
+                252: perf_tsc => PerfTsc,
+                253: perf_ctr_ext_llc => PerfCtrExtLlc,
+                254: monitorx => Monitorx (llvm "mwaitx"),
+                255: addr_mask_ext => AddrMaskExt,
+                256: syscall_sysret_in_64_bit => SyscallSysretIn64Bit,
+                257: execute_disable => ExecuteDisable,
+                258: mmxext => Mmxext,
+                259: ffxsr => Ffxsr,
+                260: gigabyte_pages => GigabytePages,
+                261: rdtscp_and_ia32_tsc_aux => RdtscpAndIa32TscAux,
+                262: intel_64_bit_architecture => Intel64BitArchitecture,
+                263: three_dnow_ext => ThreeDnowExt,
+                264: three_dnow => ThreeDnow
+            },
+
+            physical_address_size (PhysicalAddressSize, 0x8000_0008, 0) => {
+                265: clzero => Clzero (llvm "clzero"),
+                266: inst_ret_cnt_msr => InstRetCntMsr,
+                267: rstr_fp_err_ptrs => RstrFpErrPtrs,
+                268: invlpgb => Invlpgb,
+                269: rdpru => Rdpru (llvm "rdpru"),
+                270: mbe => Mbe,
+                271: mcommit => Mcommit,
+                272: wbnoinvd => Wbnoinvd (llvm "wbnoinvd"),
+                273: amd_ibpb => AmdIbpb,
+                274: int_wbinvd => IntWbinvd,
+                275: amd_ibrs => AmdIbrs,
+                276: amd_stibp => AmdStibp,
+                277: ibrs_always_on => IbrsAlwaysOn,
+                278: stibp_always_on => StibpAlwaysOn,
+                279: ibrs_preferred => IbrsPreferred,
+                280: ibrs_same_mode => IbrsSameMode,
+                281: efer_lmsle_unsupported => EferLmsleUnsupported,
+                282: invlpgb_nested_pages => InvlpgbNestedPages,
+                283: ppin => Ppin,
+                284: amd_ssbd => AmdSsbd,
+                285: virt_ssbd => VirtSsbd,
+                286: ssb_no => SsbNo,
+                287: cppc => Cppc,
+                288: psfd => Psfd,
+                289: btc_no => BtcNo,
+                290: ibpb_ret => IbpbRet
+            },
+
+            #[cfg(feature = "amd")]
+            svm_features (SvmFeatures, 0x8000_000A, 0) => {
+                291: nested_paging => NestedPaging,
+                292: lbr_virtualization => LbrVirtualization,
+                293: svm_lock => SvmLock,
+                294: nrip_save => NripSave,
+                295: tsc_rate_msr => TscRateMsr,
+                296: vmcb_clean => VmcbClean,
+                297: flush_by_asid => FlushByAsid,
+                298: decode_assists => DecodeAssists,
+                299: pmc_virtualization => PmcVirtualization,
+                300: pause_filter => PauseFilter,
+                301: pause_filter_threshold => PauseFilterThreshold,
+                302: avic => Avic,
+                303: vmsave_virtualization => VmsaveVirtualization,
+                304: vgif => Vgif,
+                305: gmet => Gmet,
+                306: x2avic => X2avic,
+                307: sss_check => SssCheck,
+                308: spec_ctrl => SpecCtrl,
+                309: rogpt => Rogpt,
+                310: host_mce_override => HostMceOverride,
+                311: tlbi_ctl => TlbiCtl,
+                312: vnmi => Vnmi,
+                313: ibs_virtualization => IbsVirtualization,
+                314: ext_lvt_avic_access_chg => ExtLvtAvicAccessChg,
+                315: nested_virt_vmcb_addr_chk => NestedVirtVmcbAddrChk,
+                316: bus_lock_threshold => BusLockThreshold
+            },
+
+            #[cfg(feature = "amd")]
+            performance_optimization_identifiers (PerformanceOptimizationIdentifiers, 0x8000_001A, 0) => {
+                317: fp128 => Fp128,
+                318: movu => Movu,
+                319: fp256 => Fp256
+            },
+
+            #[cfg(feature = "amd")]
+            instruction_based_sampling_information (InstructionBasedSamplingInformation, 0x8000_001B, 0) => {
+                320: ibs_feature_flags_valid => IbsFeatureFlagsValid,
+                321: fetch_sampling => FetchSampling,
+                322: op_sampling => OpSampling,
+                323: read_write_op_counter => ReadWriteOpCounter,
+                324: op_counting => OpCounting,
+                325: branch_target_address => BranchTargetAddress,
+                326: op_counter_extended => OpCounterExtended,
+                327: rip_invalid_check => RipInvalidCheck,
+                328: op_branch_fuse => OpBranchFuse,
+                329: fetch_control_extended => FetchControlExtended,
+                330: op_data4 => OpData4,
+                331: l3_miss_filtering => L3MissFiltering
+            },
+
+            #[cfg(feature = "amd")]
+            encrypted_memory_capabilities (EncryptedMemoryCapabilities, 0x8000_001F, 0) => {
+                332: sme => Sme,
+                333: sev => Sev,
+                334: page_flush_msr => PageFlushMsr,
+                335: sev_es => SevEs,
+                336: sev_snp => SevSnp,
+                337: vmpl => Vmpl,
+                338: rmpquery => Rmpquery,
+                339: vmpl_supervisor_shadow_stack => VmplSupervisorShadowStack,
+                340: secure_tsc => SecureTsc,
+                341: tsc_aux_virtualization => TscAuxVirtualization,
+                342: hardware_cache_coherency => HardwareCacheCoherency,
+                343: sev_64_bit_host => Sev64BitHost,
+                344: restricted_injection => RestrictedInjection,
+                345: alternate_injection => AlternateInjection,
+                346: debug_swap => DebugSwap,
+                347: prevent_host_ibs => PreventHostIbs,
+                348: vte => Vte,
+                349: vmgexit_parameter => VmgexitParameter,
+                350: virtual_tom_msr => VirtualTomMsr,
+                351: ibs_virtual_guest_control => IbsVirtualGuestControl,
+                352: vmsa_register_protection => VmsaRegisterProtection,
+                353: smt_protection => SmtProtection,
+                354: svsm_communication_page_msr => SvsmCommunicationPageMsr,
+                355: nested_virtual_snp_msr => NestedVirtualSnpMsr
+            },
+
+            #[cfg(feature = "amd")]
+            extended_feature_identification_2 (ExtendedFeatureIdentification2, 0x8000_0021, 0) => {
+                356: no_nested_data_breakpoints => NoNestedDataBreakpoints,
+                357: fs_gs_base_non_serializing => FsGsBaseNonSerializing,
+                358: lfence_always_serializing => LfenceAlwaysSerializing,
+                359: smm_page_config_lock => SmmPageConfigLock,
+                360: null_select_clears_base => NullSelectClearsBase,
+                361: upper_address_ignore => UpperAddressIgnore,
+                362: automatic_ibrs => AutomaticIbrs,
+                363: no_smm_ctl_msr => NoSmmCtlMsr,
+                364: fast_short_rep_stosb => FastShortRepStosb,
+                365: fast_short_repe_cmpsb => FastShortRepeCmpsb,
+                366: prefetch_ctl_msr => PrefetchCtlMsr,
+                367: cpuid_user_disable => CpuidUserDisable,
+                368: epsf => Epsf,
+                369: sbpb => Sbpb,
+                370: ibpb_brtype => IbpbBrtype,
+                371: srso_no => SrsoNo,
+                372: srso_user_kernel_no => SrsoUserKernelNo,
+                373: srso_msr_fix => SrsoMsrFix
+            },
+
+            time_stamp_counter (TimeStampCounter, 0x8000_0007, 0) => {
+                374: temperature_sensor => TemperatureSensor,
+                375: frequency_id_control => FrequencyIdControl,
+                376: voltage_id_control => VoltageIdControl,
+                377: thermal_trip => ThermalTrip,
+                378: hardware_thermal_control => HardwareThermalControl,
+                379: one_hundred_mhz_steps => OneHundredMhzSteps,
+                380: hardware_pstate => HardwarePstate,
+                381: invariant_tsc => InvariantTsc,
+                382: core_performance_boost => CorePerformanceBoost,
+                383: effective_frequency_read_only => EffectiveFrequencyReadOnly,
+                384: processor_feedback_interface => ProcessorFeedbackInterface,
+                385: processor_power_reporting => ProcessorPowerReporting,
+                386: connected_standby => ConnectedStandby,
+                387: rapl => Rapl
+            }
+        }}
+    };
+}
+
+__feature_table!(master_flags);
+
+impl Feature {
+    pub const fn name(self) -> &'static str {
+        Master::FLAGS[self as usize].0
+    }
+
+    /// A number for the feature that never changes and is never given
+    /// to another feature, even if this one is renamed or removed, for
+    /// storing features compactly in wire formats and databases. IDs
+    /// start from 1.
+    pub const fn id(self) -> u16 {
+        Feature::IDS[self as usize]
+    }
+
+    /// Looks up a feature by its `id`. Returns `None` for IDs that this
+    /// release does not know, such as those of features added later.
+    pub fn from_id(id: u16) -> Option<Feature> {
+        Feature::IDS.iter().position(|&i| i == id).map(|i| Feature::ALL[i])
+    }
+
+    /// Where CPUID reports the feature, as its leaf, sub-leaf, register
+    /// and bit. KVM's leaf is given at the usual hypervisor base of
+    /// 0x4000_0000. MKTME, which is decoded from the PCONFIG targets
+    /// rather than a bit, has none.
+    pub const fn location(self) -> Option<(u32, u32, Register, u32)> {
+        Feature::LOCATIONS[self as usize]
+    }
+
+    // For `supports!`, which evaluates it in a constant so that an
+    // unknown name fails to compile
+    #[doc(hidden)]
+    pub const fn __named(name: &str) -> Feature {
+        let mut i = 0;
+        while i < Feature::ALL.len() {
+            if const_str_eq(Feature::ALL[i].name(), name) {
+                return Feature::ALL[i];
+            }
+            i += 1;
+        }
+        panic!("unknown feature name")
+    }
+}
+
+// A flag of a leaf's `FLAGS`: its name, its accessor, and which sub-leaf
+// after the leaf's first, register and bit report it
+type LeafFlag<T> = (&'static str, fn(T) -> bool, Option<(u32, Register, u32)>);
+
+// Looks up a flag in a leaf's `FLAGS`, whose locations count sub-leaves
+// from the leaf's first
+const fn locate_flag<T>(flags: &[LeafFlag<T>],
+                        leaf: u32, subleaf: u32, name: &str)
+                        -> Option<(u32, u32, Register, u32)> {
+    let mut i = 0;
+    while i < flags.len() {
+        if const_str_eq(flags[i].0, name) {
+            return match flags[i].2 {
+                Some((after, register, bit)) => Some((leaf, subleaf + after, register, bit)),
+                None => None,
+            };
+        }
+        i += 1;
+    }
+    None
+}
+
+const fn const_str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl str::FromStr for Feature {
+    type Err = ParseFeatureError;
+
+    fn from_str(s: &str) -> Result<Feature, ParseFeatureError> {
+        Feature::ALL.iter().cloned().find(|f| f.name() == s).ok_or(ParseFeatureError(()))
+    }
+}
+
+/// The error from parsing an unknown feature name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParseFeatureError(());
+
+impl fmt::Display for ParseFeatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("unknown feature name")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseFeatureError {}
+
+/// Why the processor could not be queried, as distinct from it
+/// reporting that a feature is absent.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum CpuidError {
+    /// The processor reports that the leaf is beyond the highest one it
+    /// supports.
+    UnsupportedLeaf(u32),
+    /// The processor predates the CPUID instruction.
+    CpuidUnavailable,
+    /// The target architecture has no CPUID instruction.
+    UnsupportedArch,
+}
+
+impl fmt::Display for CpuidError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CpuidError::UnsupportedLeaf(leaf) => write!(f, "CPUID leaf 0x{:x} is not supported", leaf),
+            CpuidError::CpuidUnavailable => f.write_str("the processor does not support CPUID"),
+            CpuidError::UnsupportedArch => f.write_str("CPUID is not available on this architecture"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CpuidError {}
+
+/// The main entrypoint to the CPU information
+///
+/// With the `std` feature, each leaf is only queried on the first call,
+/// except those that differ between cores, as described in the
+/// [`cache`](cache/index.html) module.
+pub fn master() -> Option<Master> {
+    try_master().ok()
+}
+
+/// The CPU information, queried afresh on the current core.
+pub fn master_uncached() -> Option<Master> {
+    Master::capture().ok()
+}
+
+/// The CPU information, or why it could not be queried.
+pub fn try_master() -> Result<Master, CpuidError> {
+    master_from(&DefaultSource::default())
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn master_from<S: CpuidSource>(source: &S) -> Result<Master, CpuidError> {
+    if !has_cpuid() {
+        return Err(CpuidError::CpuidUnavailable);
+    }
+    let master = Master::new(source);
+    #[cfg(feature = "tracing")]
+    logging::captured(&master);
+    Ok(master)
+}
+
+#[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+fn master_from<S: CpuidSource>(source: &S) -> Result<Master, CpuidError> {
+    let mut master = Master::new(source);
+    master.aarch64_information = Some(Aarch64Information::new());
+    #[cfg(feature = "tracing")]
+    logging::captured(&master);
+    Ok(master)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86",
+              all(target_arch = "aarch64", target_os = "linux"))))]
+fn master_from<S: CpuidSource>(_source: &S) -> Result<Master, CpuidError> {
+    Err(CpuidError::UnsupportedArch)
+}
+
+/// The CPU information, queried on the first call and shared by every
+/// later one. Prefer this to `master` on hot paths, as CPUID is slow
+/// and serializes the processor's pipeline.
+#[cfg(feature = "std")]
+pub fn get() -> Option<&'static Master> {
+    static MASTER: std::sync::OnceLock<Option<Master>> = std::sync::OnceLock::new();
+    MASTER.get_or_init(master).as_ref()
+}
+
+/// Whether the processor supports a feature and the operating system
+/// has enabled the register state it needs, by the name of its method
+/// on `Master`, such as "avx2". Like `is_x86_feature_detected!`, but
+/// covering every flag the crate decodes, and backed by `get`.
+///
+/// ```
+/// if cupid::supports!("avx2") {
+///     // use the AVX2 implementation
+/// }
+/// ```
+///
+/// The name is looked up at compile time, so an unknown one is an
+/// error:
+///
+/// ```compile_fail
+/// cupid::supports!("no_such_feature");
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! supports {
+    ($name:expr) => {{
+        const FEATURE: $crate::Feature = $crate::Feature::__named($name);
+        $crate::__enabled_features().contains(FEATURE)
+    }}
+}
+
+// For `supports!`: the features of `get` that may be executed, as
+// found by `Master::enabled_features`
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub fn __enabled_features() -> &'static FeatureSet {
+    static ENABLED: std::sync::OnceLock<FeatureSet> = std::sync::OnceLock::new();
+    ENABLED.get_or_init(|| match get() {
+        Some(info) => info.enabled_features(xcr0()),
+        None => FeatureSet::new(),
+    })
+}
+
+/// Defines a function that calls the implementation for the highest
+/// SIMD tier the processor supports, as found by `best_simd_level`
+/// but always asking the processor itself, even when the `testing`
+/// feature stands a dump in for it, as the chosen code runs on it.
+/// The tier is detected on the first call and the chosen function
+/// pointer is cached, so later calls cost one atomic load and an
+/// indirect call.
+///
+/// The tiers are tried in the order given, so list the highest first,
+/// and a `scalar` implementation must come last. The tier names are
+/// those of `SimdLevel`'s `Display`, without punctuation: `sse2`,
+/// `sse42`, `avx`, `avx2` (with FMA), `avx512` and `avx10`.
+/// Implementations are plain functions or non-capturing closures; one
+/// with `#[target_feature]` is unsafe to call and so needs a closure
+/// around it.
+///
+/// ```
+/// fn sum_scalar(data: &[u32]) -> u32 {
+///     data.iter().sum()
+/// }
+///
+/// #[cfg(target_arch = "x86_64")]
+/// #[target_feature(enable = "avx2")]
+/// unsafe fn sum_avx2(data: &[u32]) -> u32 {
+///     data.iter().sum()
+/// }
+///
+/// #[cfg(target_arch = "x86_64")]
+/// cupid::dispatch! {
+///     pub fn sum(data: &[u32]) -> u32;
+///     avx2 => |data| unsafe { sum_avx2(data) },
+///     scalar => sum_scalar,
+/// }
+///
+/// # #[cfg(target_arch = "x86_64")]
+/// assert_eq!(6, sum(&[1, 2, 3]));
+/// ```
+#[macro_export]
+macro_rules! dispatch {
+    ($(#[$attr:meta])* $vis:vis fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;
+     $($tiers:tt)+) => {
+        $(#[$attr])*
+        $vis fn $name($($arg: $ty),*) $(-> $ret)? {
+            type Imp = fn($($ty),*) $(-> $ret)?;
+            static CHOSEN: $crate::__dispatch::AtomicPtr<()> =
+                $crate::__dispatch::AtomicPtr::new($crate::__dispatch::null_mut());
+
+            let mut chosen = CHOSEN.load($crate::__dispatch::Ordering::Relaxed);
+            if chosen.is_null() {
+                let level = $crate::__dispatch::simd_level();
+                let imp: Imp = $crate::dispatch!(@select level; $($tiers)+);
+                // Racing first calls choose the same pointer, so either
+                // store may win
+                chosen = imp as *mut ();
+                CHOSEN.store(chosen, $crate::__dispatch::Ordering::Relaxed);
+            }
+            // Only ever stored from an `Imp` above
+            let imp = unsafe { $crate::__dispatch::transmute::<*mut (), Imp>(chosen) };
+            imp($($arg),*)
+        }
+    };
+    (@select $level:ident; scalar => $fallback:expr $(,)?) => {
+        $fallback as Imp
+    };
+    (@select $level:ident; $tier:ident => $imp:expr, $($rest:tt)+) => {
+        if $level >= $crate::__dispatch_tier!($tier) {
+            $imp as Imp
+        } else {
+            $crate::dispatch!(@select $level; $($rest)+)
+        }
+    };
+}
+
+// For `dispatch!`, as crates on the 2015 edition cannot name `core`
+// without declaring it
+#[doc(hidden)]
+pub mod __dispatch {
+    pub use core::mem::transmute;
+    pub use core::ptr::null_mut;
+    pub use core::sync::atomic::{AtomicPtr, Ordering};
+
+    use super::{master_from, processor_xcr0, Processor, SimdLevel};
+
+    pub fn simd_level() -> SimdLevel {
+        match master_from(&Processor) {
+            Ok(info) => SimdLevel::detect(&info, processor_xcr0().unwrap_or(0)),
+            Err(_) => SimdLevel::Scalar,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dispatch_tier {
+    (sse2) => { $crate::SimdLevel::Sse2 };
+    (sse42) => { $crate::SimdLevel::Sse42 };
+    (avx) => { $crate::SimdLevel::Avx };
+    (avx2) => { $crate::SimdLevel::Avx2Fma };
+    (avx512) => { $crate::SimdLevel::Avx512 };
+    (avx10) => { $crate::SimdLevel::Avx10 };
+}
+
+/// The CPU information as a JSON document, laid out as described in
+/// the [`json`](json/index.html) module.
+#[cfg(feature = "std")]
+pub fn to_json() -> Option<std::string::String> {
+    master().map(|m| m.to_json())
+}
+
+/// The CPU information as a Markdown report, as described by
+/// `Master::to_markdown`.
+#[cfg(feature = "std")]
+pub fn to_markdown() -> Option<std::string::String> {
+    master().map(|m| m.to_markdown())
+}
+
+/// The CPU information as a TOML document, laid out as described in
+/// the [`toml`](toml/index.html) module.
+#[cfg(feature = "toml")]
+pub fn to_toml() -> Option<std::string::String> {
+    master().map(|m| m.to_toml())
+}
+
+/// The CPU information as a YAML document, laid out as described in
+/// the [`yaml`](yaml/index.html) module.
+#[cfg(feature = "yaml")]
+pub fn to_yaml() -> Option<std::string::String> {
+    master().map(|m| m.to_yaml())
+}
+
+/// The brand string without trademarks or frequency, as described by
+/// `Master::normalized_brand`.
+#[cfg(feature = "std")]
+pub fn normalized_brand() -> Option<std::string::String> {
+    master().and_then(|m| m.normalized_brand())
+}
+
+/// How many of the known flags of each leaf are enabled, as described
+/// in the [`stats`](stats/index.html) module.
+pub fn stats() -> Option<FeatureStats> {
+    master().map(|m| m.stats())
+}
+
+/// How the processor differs from the baseline profile called `name`,
+/// as described by `Master::compare_to_profile`.
+pub fn compare_to_profile(name: &str) -> Option<ProfileComparison> {
+    master().and_then(|m| m.compare_to_profile(name))
+}
+
+/// The TSC frequency in Hz that the hypervisor reports, as described
+/// by `Master::hypervisor_tsc_frequency`. With the `msr` feature on
+/// Linux, Hyper-V's frequency MSR is read as well, which usually
+/// requires root.
+#[cfg(feature = "hypervisor")]
+pub fn hypervisor_tsc_frequency() -> Option<u64> {
+    let info = master()?;
+    info.hypervisor_tsc_frequency()
+        .or_else(|| hyper_v_frequencies(&info).and_then(|(tsc, _)| tsc))
+}
+
+/// The local APIC timer frequency in Hz that the hypervisor reports,
+/// as described by `Master::hypervisor_apic_frequency`. Like
+/// `hypervisor_tsc_frequency`, this reads Hyper-V's MSR with the `msr`
+/// feature.
+#[cfg(feature = "hypervisor")]
+pub fn hypervisor_apic_frequency() -> Option<u64> {
+    let info = master()?;
+    info.hypervisor_apic_frequency()
+        .or_else(|| hyper_v_frequencies(&info).and_then(|(_, apic)| apic))
+}
+
+// Hyper-V's TSC and APIC timer frequencies, from its synthetic MSRs
+#[cfg(all(feature = "hypervisor", feature = "msr", target_os = "linux"))]
+fn hyper_v_frequencies(info: &Master) -> Option<(Option<u64>, Option<u64>)> {
+    if !info.hyper_v_information()?.access_frequency_regs() {
+        return None;
+    }
+    let msrs = MsrInformation::read(0).ok()?;
+    Some((msrs.hyper_v_tsc_frequency_hz(), msrs.hyper_v_apic_frequency_hz()))
+}
+
+#[cfg(all(feature = "hypervisor", not(all(feature = "msr", target_os = "linux"))))]
+fn hyper_v_frequencies(_info: &Master) -> Option<(Option<u64>, Option<u64>)> {
+    None
+}
+
+/// The frequency of the time stamp counter, as described by
+/// `Master::tsc_frequency_hz`.
+pub fn tsc_frequency_hz() -> Option<u64> {
+    master().and_then(|m| m.tsc_frequency_hz())
+}
+
+/// The size of a cache line in bytes, as described by
+/// `Master::cache_line_size`.
+pub fn cache_line_size() -> Option<usize> {
+    master().and_then(|m| m.cache_line_size()).map(|size| size as usize)
+}
+
+/// A hash identifying the processor model and configuration, as
+/// described in the [`fingerprint`](fingerprint/index.html) module.
+pub fn fingerprint() -> Option<u64> {
+    master().map(|m| m.fingerprint())
+}
+
+/// The number of logical processors in the package, as described by
+/// `Master::logical_cores`. Not the number the operating system offers.
+pub fn logical_cores() -> Option<usize> {
+    master().and_then(|m| m.logical_cores()).map(|n| n as usize)
+}
+
+/// The number of physical cores in the package, as described by
+/// `Master::physical_cores`.
+pub fn physical_cores() -> Option<usize> {
+    master().and_then(|m| m.physical_cores()).map(|n| n as usize)
+}
+
+/// Whether the time stamp counter runs at a constant rate in every
+/// power state, so that it can serve as a monotonic clock. Intel and
+/// AMD both report this in leaf 0x8000_0007.
+pub fn invariant_tsc() -> bool {
+    master().map(|m| m.invariant_tsc()).unwrap_or(false)
+}
+
+/// Whether the processor supports 5-level paging, as described by
+/// `Master::five_level_paging_supported`.
+pub fn five_level_paging_supported() -> bool {
+    master().map(|m| m.five_level_paging_supported()).unwrap_or(false)
+}
+
+/// Which AVX-512 subsets the processor supports, as described by
+/// `Master::avx512`.
+pub fn avx512() -> Option<Avx512Support> {
+    master().map(|m| m.avx512())
+}
+
+/// What the processor offers for Advanced Matrix Extensions, as
+/// described by `Master::amx`.
+pub fn amx() -> Option<Amx> {
+    master().map(|m| m.amx())
+}
+
+/// The family, model and stepping by the vendor's rules, as described
+/// by `Master::processor_signature`.
+pub fn processor_signature() -> Option<ProcessorSignature> {
+    master().and_then(|m| m.processor_signature())
+}
+
+/// What the processor offers for Control-flow Enforcement Technology,
+/// as described by `Master::cet`.
+pub fn cet() -> Option<Cet> {
+    master().map(|m| m.cet())
+}
+
+/// The confidential computing environment the code runs in. With the
+/// `msr` feature on Linux, an AMD guest's `SEV_STATUS` MSR says which
+/// protection is active; otherwise this is the hint described by
+/// `Master::confidential_computing`.
+pub fn confidential_computing() -> Option<ConfidentialComputing> {
+    let info = master()?;
+    match info.confidential_computing() {
+        Some(ConfidentialComputing::Tdx) => Some(ConfidentialComputing::Tdx),
+        hint => sev_status(&info).unwrap_or(hint),
+    }
+}
+
+// The protection SEV_STATUS reports in a virtual machine, if readable
+#[cfg(all(feature = "msr", target_os = "linux"))]
+fn sev_status(info: &Master) -> Option<Option<ConfidentialComputing>> {
+    if !info.supports(Feature::Hypervisor) {
+        return None;
+    }
+    MsrInformation::read(0).ok()?.sev_status().map(|s| s.confidential_computing())
+}
+
+#[cfg(not(all(feature = "msr", target_os = "linux")))]
+fn sev_status(_info: &Master) -> Option<Option<ConfidentialComputing>> {
+    None
+}
+
+/// The CPU information in the layout of `lscpu`, as described in the
+/// [`lscpu`](lscpu/index.html) module.
+#[cfg(feature = "std")]
+pub fn to_lscpu() -> Option<std::string::String> {
+    master().map(|m| m.to_lscpu())
+}
+
+/// The CPU information as metrics text, laid out as described in the
+/// [`metrics`](metrics/index.html) module.
+#[cfg(feature = "std")]
+pub fn to_metrics() -> Option<std::string::String> {
+    master().map(|m| m.to_metrics())
+}
+
+/// Queries a leaf and sub-leaf directly, for anything not yet decoded.
+///
+/// Returns `None` when the processor reports that the leaf is beyond
+/// the highest one it supports. Only the basic and extended ranges are
+/// checked; leaves in other ranges, such as the hypervisor's, are always
+/// queried.
+pub fn raw(leaf: u32, subleaf: u32) -> Option<Registers> {
+    try_raw(leaf, subleaf).ok()
+}
+
+/// Queries a leaf and sub-leaf directly, or reports why it could not be.
+pub fn try_raw(leaf: u32, subleaf: u32) -> Result<Registers, CpuidError> {
+    raw_from(&DefaultSource::default(), leaf, subleaf)
+}
+
+/// Queries a leaf and sub-leaf afresh on the current core, bypassing
+/// the cache.
+pub fn raw_uncached(leaf: u32, subleaf: u32) -> Option<Registers> {
+    raw_from(&Native, leaf, subleaf).ok()
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn raw_from<S: CpuidSource>(source: &S, leaf: u32, subleaf: u32) -> Result<Registers, CpuidError> {
+    if !has_cpuid() {
+        Err(CpuidError::CpuidUnavailable)
+    } else if leaf_supported(source, leaf) {
+        Ok(source.cpuid(leaf, subleaf))
+    } else {
+        Err(CpuidError::UnsupportedLeaf(leaf))
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+fn raw_from<S: CpuidSource>(_source: &S, _leaf: u32, _subleaf: u32) -> Result<Registers, CpuidError> {
+    Err(CpuidError::UnsupportedArch)
+}
+
+/// The highest basic leaf the processor supports, as reported by leaf 0.
+pub fn max_basic_leaf() -> Option<u32> {
+    raw(RequestType::BasicInformation as u32, 0).map(|(a, _, _, _)| a)
+}
+
+/// The highest extended leaf the processor supports, as reported by
+/// leaf 0x8000_0000, or `None` if it has no extended leaves.
+pub fn max_extended_leaf() -> Option<u32> {
+    let base = RequestType::ExtendedFunctionInformation as u32;
+    raw(base, 0).map(|(a, _, _, _)| a).filter(|&max| max >= base)
+}
+
+/// The highest hypervisor leaf, as reported by leaf 0x4000_0000, or
+/// `None` if not running under a hypervisor.
+pub fn max_hypervisor_leaf() -> Option<u32> {
+    let (_, _, c, _) = raw(RequestType::VersionInformation as u32, 0)?;
+    let base = RequestType::HypervisorInformation as u32;
+    if c & (1 << 31) != 0 {
+        raw(base, 0).map(|(a, _, _, _)| a).filter(|&max| max >= base)
+    } else {
+        None
+    }
+}
+
+/// Every supported basic, extended and hypervisor leaf, with the
+/// sub-leaves of those known to have them, as `(leaf, subleaf,
+/// registers)`. These are the queries that `RawDump::capture` records.
+#[cfg(feature = "std")]
+pub fn all_leaves() -> impl Iterator<Item = (u32, u32, Registers)> {
+    RawDump::capture().into_iter().map(|e| (e.leaf, e.subleaf, (e.eax, e.ebx, e.ecx, e.edx)))
+}
+
+/// Runs a third-party decoder against the current processor.
+///
+/// Returns `None` when the processor reports that the leaf is beyond
+/// the highest one it supports.
+pub fn decode<D: LeafDecoder>(decoder: &D) -> Option<D::Output> {
+    try_decode(decoder).ok()
+}
+
+/// Runs a third-party decoder against the current processor, or reports
+/// why its leaf could not be queried.
+pub fn try_decode<D: LeafDecoder>(decoder: &D) -> Result<D::Output, CpuidError> {
+    try_raw(decoder.leaf(), decoder.subleaf()).map(|(a, b, c, d)| decoder.decode(a, b, c, d))
+}
+
+/// The kind of core the calling thread is currently running on.
+///
+/// This queries the processor on every call, so it reflects the
+/// core the thread was scheduled on at that instant. Returns `None`
+/// on processors that are not hybrid.
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+pub fn core_type() -> Option<CoreType> {
+    if !has_cpuid() {
+        return None;
+    }
+    let (max_value, _, _, _) = cpuid(RequestType::BasicInformation);
+    if max_value < RequestType::NativeModelIdentification as u32 {
+        return None;
+    }
+    if !StructuredExtendedInformation::new(&Native).hybrid() {
+        return None;
+    }
+    NativeModelIdentification::new(&Native).core_type()
+}
+
+/// The kind of core the calling thread is currently running on.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+pub fn core_type() -> Option<CoreType> {
+    None
+}
+
+/// The x2APIC ID of the logical processor the calling thread is
+/// currently running on, from leaf 0x1F or 0xB. Like `core_type`, this
+/// queries the processor on every call; the thread may have moved by
+/// the time it returns unless it is pinned. Returns `None` on
+/// processors without either leaf.
+pub fn current_x2apic_id() -> Option<u32> {
+    if !has_cpuid() {
+        return None;
+    }
+    x2apic_id_from(&Native)
+}
+
+/// The legacy 8-bit initial APIC ID of the logical processor the
+/// calling thread is currently running on, from leaf 1. Only unique on
+/// systems with fewer than 256 logical processors; prefer
+/// `current_x2apic_id` where it is available.
+pub fn current_apic_id() -> Option<u32> {
+    if !has_cpuid() {
+        return None;
+    }
+    let (max_value, _, _, _) = Native.cpuid(RequestType::BasicInformation as u32, 0);
+    if max_value < RequestType::VersionInformation as u32 {
+        return None;
+    }
+    Some(VersionInformation::new(&Native).initial_apic_id())
+}
+
+/// The number of the core the calling thread is currently running on,
+/// unique within its socket, as described by `ExtendedTopology::core_id`.
+/// Like `current_x2apic_id`, this may be stale by the time it returns
+/// unless the thread is pinned.
+pub fn current_core_id() -> Option<u32> {
+    current_topology_id(|t, id| t.core_id(id))
+}
+
+/// The number of the socket the calling thread is currently running
+/// on, as described by `ExtendedTopology::package_id`.
+pub fn current_socket_id() -> Option<u32> {
+    current_topology_id(|t, id| t.package_id(id))
+}
+
+/// The number of the logical processor the calling thread is currently
+/// running on within its core, as described by `ExtendedTopology::smt_id`.
+pub fn current_smt_id() -> Option<u32> {
+    current_topology_id(|t, id| t.smt_id(id))
+}
+
+// The shift widths are the same on every logical processor, so only
+// the x2APIC ID needs querying afresh
+fn current_topology_id<F>(f: F) -> Option<u32>
+    where F: FnOnce(&ExtendedTopology, u32) -> u32
+{
+    let x2apic_id = current_x2apic_id()?;
+    with_topology(|topology| f(topology, x2apic_id))
+}
+
+#[cfg(feature = "std")]
+fn with_topology<F, T>(f: F) -> Option<T>
+    where F: FnOnce(&ExtendedTopology) -> T
+{
+    get()?.extended_topology().map(f)
+}
+
+#[cfg(not(feature = "std"))]
+fn with_topology<F, T>(f: F) -> Option<T>
+    where F: FnOnce(&ExtendedTopology) -> T
+{
+    master()?.extended_topology().map(f)
+}
+
+// Every sub-leaf of the topology leaves reports the x2APIC ID in EDX,
+// but a leaf without a first level is not implemented
+fn x2apic_id_from<S: CpuidSource>(source: &S) -> Option<u32> {
+    let (max_value, _, _, _) = source.cpuid(RequestType::BasicInformation as u32, 0);
+    let leaves = [RequestType::V2ExtendedTopology as u32, RequestType::ExtendedTopology as u32];
+    leaves.iter()
+        .filter(|&&leaf| max_value >= leaf)
+        .map(|&leaf| source.cpuid(leaf, 0))
+        .find(|&(_, _, c, _)| bits_of(c, 8, 15) != 0)
+        .map(|(_, _, _, d)| d)
+}
+
+/// The register state the operating system has enabled for XSAVE, as
+/// the bits of XCR0. Returns `None` if the operating system has not
+/// enabled XSAVE.
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+pub fn xcr0() -> Option<u64> {
+    #[cfg(feature = "testing")]
+    {
+        if let Some(dump) = testing::fake_dump() {
+            let (max_value, _, _, _) = dump.cpuid(RequestType::BasicInformation as u32, 0);
+            let osxsave = max_value >= RequestType::VersionInformation as u32 &&
+                VersionInformation::new(dump).osxsave();
+            return if osxsave { Some(testing::xcr0(dump)) } else { None };
+        }
+    }
+    processor_xcr0()
+}
+
+/// The register state the operating system has enabled for XSAVE.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+pub fn xcr0() -> Option<u64> {
+    None
+}
+
+// XCR0 as the processor itself reports it, even when the `testing`
+// feature stands a dump in for it
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn processor_xcr0() -> Option<u64> {
+    if !has_cpuid() {
+        return None;
+    }
+    let (max_value, _, _, _) = cpuid_count(RequestType::BasicInformation as u32, 0);
+    if max_value < RequestType::VersionInformation as u32 {
+        return None;
+    }
+    if VersionInformation::new(&Processor).osxsave() {
+        Some(xgetbv(0))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+fn processor_xcr0() -> Option<u64> {
+    None
+}
+
+#[cfg(all(test, feature = "std"))]
+use test_support::{authentic_amd_dump, genuine_intel_dump};
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn basic_genuine_intel() {
+    let (_, b, c, d) = cpuid(RequestType::BasicInformation);
+
+    assert_eq!(*b"Genu", b.to_le_bytes());
+    assert_eq!(*b"ntel", c.to_le_bytes());
+    assert_eq!(*b"ineI", d.to_le_bytes());
+}
+
+#[test]
+fn brand_string_contains_intel() {
+    assert!(master().unwrap().brand_string().unwrap().contains("Intel(R)"))
+}
+
+#[test]
+#[cfg(feature = "amd")]
+fn ccx_id_uses_l3_sharing_width() {
+    // Zen 2: four cores with SMT share an L3, on the second die
+    let cci = CoreComplexInformation {
+        l3_eax: Some((7 << 14) | (3 << 5) | 3),
+        topology: ProcessorTopologyInformation { eax: 0x1d, ebx: 0x10e, ecx: (1 << 8) | 1 },
+    };
+
+    assert_eq!(Some(8), cci.logical_processors_per_ccx());
+    assert_eq!(Some(3), cci.ccx_id());
+    assert_eq!(1, cci.die_id());
+    assert_eq!(2, cci.dies_per_processor());
+}
+
+#[test]
+fn bits_of_handles_full_and_out_of_range_widths() {
+    assert_eq!(0xDEADBEEF, bits_of(0xDEADBEEF, 0, 31));
+    assert_eq!(0xDEADBEEF, bits_of(0xDEADBEEF, 0, 63));
+    assert_eq!(0xDEADBEE, bits_of(0xDEADBEEF, 4, 63));
+    assert_eq!(0xD, bits_of(0xDEADBEEF, 28, 31));
+    assert_eq!(0, bits_of(0xDEADBEEF, 32, 40));
+    assert_eq!(1, bits_of(0xDEADBEEF, 0, 0));
+}
+
+// The decoders are generic over their source, and the tests hand them
+// sources that panic, so the guarantee is checked on the processor's own
+#[cfg(all(test, any(target_arch = "x86_64", target_arch = "x86")))]
+#[cfg_attr(all(not(debug_assertions), not(feature = "testing")), no_panic)]
+fn native_master() -> Master {
+    Master::new(&Native)
+}
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn query_path_links_without_panics() {
+    // Only meaningful in optimized builds, where `no_panic` is active
+    let master = native_master();
+    let _ = master.brand_string();
+}
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn decode_runs_custom_leaf_decoders() {
+    struct Vendor;
+
+    impl LeafDecoder for Vendor {
+        type Output = [u32; 3];
+
+        fn leaf(&self) -> u32 { 0 }
+
+        fn decode(&self, _: u32, b: u32, c: u32, d: u32) -> [u32; 3] {
+            [b, d, c]
+        }
+    }
+
+    struct Beyond;
+
+    impl LeafDecoder for Beyond {
+        type Output = ();
+
+        fn leaf(&self) -> u32 { 0x0000_FFFF }
+
+        fn decode(&self, _: u32, _: u32, _: u32, _: u32) {}
+    }
+
+    let vendor = decode(&Vendor).unwrap();
+    assert!(vendor.iter().all(|&r| r != 0));
+    assert_eq!(None, decode(&Beyond));
+}
+
+#[test]
+fn feature_ids_are_unique_and_round_trip() {
+    // A shared ID would look up the same feature for both
+    assert!(Feature::ALL.iter().all(|&f| f.id() != 0 && Feature::from_id(f.id()) == Some(f)));
+    assert_eq!(1, Feature::Sse3.id());
+    assert_eq!(None, Feature::from_id(0));
+}
+
+#[test]
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
+fn master_decodes_from_dump() {
+    let live = Master::new(&Native);
+    let recorded = Master::from_dump(&RawDump::capture());
+    assert_eq!(live.to_json(), recorded.to_json());
+
+    let mut dump = genuine_intel_dump(1);
+    dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+    let info = Master::from_dump(&dump);
+    assert_eq!("GenuineIntel", info.vendor_id());
+    assert!(info.clone().sse4_2());
+    assert!(info.clone().sse2());
+    assert!(!info.avx());
+}
+
+#[test]
+fn flag_iterators_match_accessors() {
+    let vi = VersionInformation { eax: 0, ebx: 0, ecx: 1 << 20, edx: 1 << 26 };
+    assert_eq!(64 - 4, vi.iter().count());
+    assert!(vi.iter().all(|(name, set)| set == (name == "sse4_2" || name == "sse2")));
+    assert_eq!(Some(("sse3", false)), vi.iter().next());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn has_feature_looks_up_flags_by_name() {
+    let mut dump = genuine_intel_dump(1);
+    dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+    let info = Master::from_dump(&dump);
+    assert_eq!(Some(true), info.has_feature("sse4_2"));
+    assert_eq!(Some(false), info.has_feature("avx2"));
+    assert_eq!(None, info.has_feature("no_such_feature"));
+}
+
+#[test]
+fn features_round_trip_through_names() {
+    assert_eq!(Master::FLAGS.len(), Feature::ALL.len());
+    for (i, &feature) in Feature::ALL.iter().enumerate() {
+        assert_eq!(i, feature as usize);
+        assert_eq!(Ok(feature), feature.name().parse());
+    }
+    assert_eq!("sse4_2", Feature::Sse4_2.name());
+    assert!("no_such_feature".parse::<Feature>().is_err());
+}
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn supports_matches_the_flag_methods() {
+    let info = master().unwrap();
+    assert!(info.supports(Feature::Sse2));
+    assert_eq!(info.clone().avx(), info.supports(Feature::Avx));
+}
+
+#[test]
+fn model_id_extends_the_base_family_f() {
+    // Zen 2 (Rome): family 0xF + 0x8, model 0x31
+    let vi = VersionInformation { eax: 0x0083_0F10, ebx: 0, ecx: 0, edx: 0 };
+    assert_eq!(0x17, vi.family_id());
+    assert_eq!(0x31, vi.model_id());
+}
+
+#[test]
+fn brand_string_handles_padding_and_missing_terminator() {
+    let mut brand_string = BrandString { bytes: [b'x'; BRAND_STRING_LENGTH] };
+    assert_eq!(BRAND_STRING_LENGTH, brand_string.as_str().len());
+
+    for (d, &s) in brand_string.bytes.iter_mut().zip(b"      Intel(R) Core(TM) CPU\0") {
+        *d = s;
+    }
+    assert_eq!(brand_string, "Intel(R) Core(TM) CPU");
+
+    brand_string.bytes[6] = 0xFF;
+    assert_eq!("", brand_string.as_str());
+}
+
+#[test]
+fn brand_frequency_parses_the_suffix() {
+    assert_eq!(Some(3_600_000_000), brand_frequency_hz("Intel(R) Core(TM) i7-4790 CPU @ 3.60GHz"));
+    assert_eq!(Some(2_000_000_000), brand_frequency_hz("Intel(R) Xeon(R) CPU @ 2.00 GHz"));
+    assert_eq!(Some(1_066_000_000), brand_frequency_hz("Intel(R) Pentium(R) M @ 1066MHz"));
+    assert_eq!(Some(123_456_789), brand_frequency_hz("@ 0.123456789GHz"));
+    assert_eq!(None, brand_frequency_hz("AMD Ryzen 9 5950X 16-Core Processor"));
+    assert_eq!(None, brand_frequency_hz("CPU @ .GHz"));
+    assert_eq!(None, brand_frequency_hz("CPU @ 3.6xGHz"));
+}
+
+#[test]
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
+fn get_caches_the_information() {
+    let first = get().unwrap();
+    assert!(std::ptr::eq(first, get().unwrap()));
+    assert_eq!(master().unwrap().to_json(), first.to_json());
+}
+
+#[test]
+fn from_registers_decodes_without_hardware() {
+    let sei = StructuredExtendedInformation::from_registers(1 << 5, 0, 0);
+    assert!(sei.avx2());
+    assert!(!sei.bmi2());
+
+    let pas = PhysicalAddressSize::from_registers(0x3027, 0, 0);
+    assert_eq!(39, pas.physical_address_bits());
+    assert_eq!(48, pas.linear_address_bits());
+}
+
+#[test]
+fn register_getters_return_the_raw_values() {
+    let vi = VersionInformation::from_registers(1, 2, 3, 4);
+    assert_eq!((1, 2, 3, 4), (vi.eax(), vi.ebx(), vi.ecx(), vi.edx()));
+    assert_eq!(7, TmulInformation::from_registers(7).ebx());
+}
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn raw_checks_the_maximum_leaf() {
+    let (max_leaf, _, _, _) = raw(0, 0).unwrap();
+    assert_eq!(Some(cpuid_count(1, 0).3), raw(1, 0).map(|r| r.3));
+    assert_eq!(None, raw(max_leaf + 1, 0));
+
+    let (max_extended, _, _, _) = raw(0x8000_0000, 0).unwrap();
+    assert_eq!(None, raw(max_extended + 1, 0));
+}
+
+#[test]
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
+fn all_leaves_walks_the_basic_and_extended_ranges() {
+    let (max_basic, _, _, _) = cpuid_count(0, 0);
+    let (max_extended, _, _, _) = cpuid_count(0x8000_0000, 0);
+
+    let leaves: std::vec::Vec<_> = all_leaves().collect();
+    assert!((0..max_basic + 1).all(|l| leaves.iter().any(|e| e.0 == l && e.1 == 0)));
+    assert!(leaves.iter().any(|e| e.0 == max_extended));
+    assert_eq!((0, 0, cpuid_count(0, 0)), leaves[0]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn snapshots_compare_and_hash_by_value() {
+    use std::collections::HashSet;
+
+    let mut dump = genuine_intel_dump(1);
+    dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+    let a = Master::from_dump(&dump);
+    dump.insert(1, 0, (0x0006_06A6, 0, 0, 1 << 26));
+    let b = Master::from_dump(&dump);
+
+    assert!(a != b);
+    let fleet: HashSet<Master> = vec![a.clone(), b, a].into_iter().collect();
+    assert_eq!(2, fleet.len());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn alternate_debug_lists_only_set_flags() {
+    let sei = StructuredExtendedInformation::from_registers(1 << 5, 0, 0);
+    assert!(format!("{:?}", sei).contains("bmi2: false"));
+
+    let pretty = format!("{:#?}", sei);
+    assert!(pretty.contains("avx2: true"));
+    assert!(!pretty.contains("false"));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn flags_string_joins_supported_features() {
+    let mut dump = genuine_intel_dump(1);
+    dump.insert(1, 0, (0x0006_06A6, 0, 1 | 1 << 20, 1 << 26));
+    assert_eq!("sse3, sse4_2, sse2", Master::from_dump(&dump).flags_string());
+    assert_eq!("", format!("{}", FeatureSet::new()));
+}
+
+#[test]
+fn speculation_control_bits() {
+    let sei = StructuredExtendedInformation::from_registers(0, 0, 1 << 10 | 1 << 26 | 1 << 29 | 1 << 31);
+    assert!(sei.md_clear());
+    assert!(sei.ibrs_ibpb());
+    assert!(sei.arch_capabilities());
+    assert!(sei.ssbd());
+    assert!(!sei.stibp());
+    assert!(!sei.l1d_flush());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn supports_macro_matches_the_snapshot() {
+    let enabled = get().map(|m| m.enabled_features(xcr0())).unwrap_or_default();
+    assert_eq!(enabled.contains(Feature::Sse2), supports!("sse2"));
+    assert_eq!(enabled.contains(Feature::Avx512f), supports!("avx512f"));
+    assert_eq!(Feature::Sse4_2, Feature::__named("sse4_2"));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn tsc_frequency_falls_back_to_model_crystal() {
+    let mut dump = genuine_intel_dump(0x16);
+    dump.insert(1, 0, (0x0009_06EA, 0, 0, 0));
+    dump.insert(0x15, 0, (2, 300, 0, 0));
+    dump.insert(0x16, 0, (3600, 4900, 100, 0));
+    assert_eq!(Some(3_600_000_000), Master::from_dump(&dump).tsc_frequency_hz());
+
+    dump.insert(0x15, 0, (2, 300, 25_000_000, 0));
+    assert_eq!(Some(3_750_000_000), Master::from_dump(&dump).tsc_frequency_hz());
+
+    dump.insert(0x15, 0, (0, 0, 0, 0));
+    assert_eq!(Some(3_600_000_000), Master::from_dump(&dump).tsc_frequency_hz());
+}
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn invariant_tsc_reads_the_power_management_leaf() {
+    let (max_extended, _, _, _) = cpuid_count(0x8000_0000, 0);
+    let expected = max_extended >= 0x8000_0007 && cpuid_count(0x8000_0007, 0).3 & (1 << 8) != 0;
+    assert_eq!(expected, invariant_tsc());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn cache_line_size_falls_back_to_clflush() {
+    let mut dump = genuine_intel_dump(1);
+    dump.insert(1, 0, (0x0006_06A6, 8 << 8, 0, 1 << 19));
+    assert_eq!(Some(64), Master::from_dump(&dump).cache_line_size());
+
+    dump.insert(1, 0, (0x0006_06A6, 8 << 8, 0, 0));
+    assert_eq!(None, Master::from_dump(&dump).cache_line_size());
+}
+
+#[test]
+#[cfg(feature = "caches")]
+fn l1_associativity_maps_every_encoding() {
+    // Zen 4: a 32KB 8-way instruction cache and a 48KB 12-way data cache
+    let l1 = L1CacheAndTlbInformation::from_registers(0xFF48_FF40, 0xFF48_FF40, 0x300C_0140, 0x2008_0140);
+    assert_eq!(Some(CacheLineAssociativity::Ways(12)), l1.data_cache_associativity());
+    assert_eq!(Some(CacheLineAssociativity::EightWay), l1.instruction_cache_associativity());
+    assert_eq!(Some(CacheLineAssociativity::Full), l1.data_tlb_4k_associativity());
+
+    assert_eq!(None, l1_associativity(0));
+    assert_eq!(Some(CacheLineAssociativity::DirectMapped), l1_associativity(1));
+    assert_eq!(Some(CacheLineAssociativity::SixWay), l1_associativity(6));
+    assert!((2..0xFF).all(|ways| l1_associativity(ways).is_some()));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn core_counts_from_extended_topology() {
+    let mut dump = genuine_intel_dump(0xB);
+    dump.insert(1, 0, (0x0005_0654, 0x0010_0800, 0, 1 << 28));
+    dump.insert(0xB, 0, (1, 2, 0x100, 3));
+    dump.insert(0xB, 1, (5, 16, 0x201, 3));
+    let info = Master::from_dump(&dump);
+    assert_eq!(2, info.extended_topology().unwrap().levels().len());
+    assert_eq!(Some(16), info.logical_cores());
+    assert_eq!(Some(8), info.physical_cores());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn topology_ids_split_the_x2apic_id() {
+    // Two threads per core and up to 32 cores per package
+    let mut dump = genuine_intel_dump(0xB);
+    dump.insert(0xB, 0, (1, 2, 0x100, 0x4B));
+    dump.insert(0xB, 1, (6, 48, 0x201, 0x4B));
+    let info = Master::from_dump(&dump);
+    let topology = info.extended_topology().unwrap();
+    assert_eq!(1, topology.smt_id(0x4B));
+    assert_eq!(5, topology.core_id(0x4B));
+    assert_eq!(1, topology.package_id(0x4B));
+
+    // Without an SMT level, every thread is a core
+    let mut dump = genuine_intel_dump(0xB);
+    dump.insert(0xB, 0, (4, 16, 0x201, 0x13));
+    let topology = *Master::from_dump(&dump).extended_topology().unwrap();
+    assert_eq!(0, topology.smt_id(0x13));
+    assert_eq!(3, topology.core_id(0x13));
+    assert_eq!(1, topology.package_id(0x13));
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "amd"))]
+fn core_counts_from_amd_leaves() {
+    let mut dump = authentic_amd_dump(0xD);
+    dump.insert(1, 0, (0x00A2_0F12, 0x0010_0800, 0, 1 << 28));
+    dump.insert(0x8000_0000, 0, (0x8000_001E, 0, 0, 0));
+    dump.insert(0x8000_0001, 0, (0, 0, 1 << 22, 0));
+    dump.insert(0x8000_0008, 0, (0x3030, 0, 0x700B, 0));
+    dump.insert(0x8000_001E, 0, (0, 0x100, 0, 0));
+    let info = Master::from_dump(&dump);
+    assert_eq!(Some(12), info.logical_cores());
+    assert_eq!(Some(6), info.physical_cores());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn address_sizes() {
+    let pas = PhysicalAddressSize::from_registers(0x0030_3028, 0, 0);
+    assert_eq!(40, pas.physical_address_bits());
+    assert_eq!(48, pas.guest_physical_address_bits());
+    assert_eq!(1 << 40, pas.max_physical_memory_bytes());
+    assert_eq!(1 << 48, pas.max_linear_address_bytes());
+    assert_eq!("40 bits physical, 48 bits virtual", format!("{}", pas));
+
+    let pas = PhysicalAddressSize::from_registers(0x3030, 0, 0);
+    assert_eq!(48, pas.effective_guest_physical_address_bits());
+}
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn try_raw_reports_unsupported_leaves() {
+    let (max_value, _, _, _) = cpuid_count(0, 0);
+    assert!(try_raw(max_value, 0).is_ok());
+    assert_eq!(Err(CpuidError::UnsupportedLeaf(max_value + 1)), try_raw(max_value + 1, 0));
+}
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn cpuid_is_available() {
+    assert!(has_cpuid());
+    assert!(try_master().is_ok());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn leaf_debug_lists_fields_then_flags() {
+    let pas = PhysicalAddressSize::from_registers(0x3028, 1 << 9, 0);
+    let debug = format!("{:?}", pas);
+    assert!(debug.starts_with("PhysicalAddressSize { physical_address_bits: 40, linear_address_bits: 48"));
+    assert!(debug.contains("wbnoinvd: true"));
+}
+
+#[test]
+fn flags_are_usable_in_const_contexts() {
+    const LEAF: VersionInformation = VersionInformation::from_registers(0x0009_06EA, 0, 1 << 20, 1 << 26);
+    const SSE4_2: bool = LEAF.sse4_2();
+    const STEPPING: u32 = bits_of(LEAF.eax(), 0, 3);
+    assert!(SSE4_2 && LEAF.sse2() && !LEAF.avx());
+    assert_eq!(0xA, STEPPING);
+}
+
+#[test]
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
+fn cached_queries_match_fresh_ones() {
+    // Leaf 0 holds nothing that differs between cores
+    assert_eq!(raw_uncached(0, 0), raw(0, 0));
+    assert_eq!(raw(0, 0), raw(0, 0));
+    assert_eq!(master_uncached().map(|m| FeatureSet::from(&m)), master().map(|m| FeatureSet::from(&m)));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn capture_queries_each_leaf_once() {
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    let queries = RefCell::new(Vec::new());
+    let source = |leaf, subleaf| {
+        queries.borrow_mut().push((leaf, subleaf));
+        match leaf {
+            0 => (7, 0x756E_6547, 0x6C65_746E, 0x4965_6E69),
+            7 => (1, 0, 0, 0),
+            _ => (0, 0, 0, 0),
+        }
+    };
+    Master::from_source(&source);
+
+    let mut queries = queries.into_inner();
+    let all = queries.len();
+    queries.sort();
+    queries.dedup();
+    assert_eq!(all, queries.len());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn processor_serial_number_follows_the_psn_flag() {
+    let mut dump = genuine_intel_dump(3);
+    dump.insert(1, 0, (0x0000_0673, 0, 0, 1 << 18));
+    dump.insert(3, 0, (0, 0, 0x89AB_CDEF, 0x0123_4567));
+
+    let info = Master::from_dump(&dump);
+    let psn = info.processor_serial_number().unwrap();
+    assert_eq!("0000-0673-0123-4567-89AB-CDEF", format!("{}", psn));
+    assert_eq!(0x0000_0673_0123_4567_89AB_CDEF, psn.value());
+
+    dump.insert(1, 0, (0x0000_0673, 0, 0, 0));
+    assert!(Master::from_dump(&dump).processor_serial_number().is_none());
+}
+
+#[test]
+fn thermal_power_management_decodes_hwp_extensions() {
+    let tpm = ThermalPowerManagementInformation::from_registers(0x001B_CFF7, 0, 0, 0);
+    assert!(tpm.turbo_boost_max_3());
+    assert!(tpm.hwp_highest_performance_change());
+    assert!(tpm.hwp_peci_override());
+    assert!(tpm.flexible_hwp());
+    assert!(!tpm.fast_hwp_request());
+    assert!(tpm.hw_feedback());
+    assert!(tpm.hwp_ignore_idle_logical_processor());
+}
+
+#[test]
+fn thermal_power_management_decodes_thread_director() {
+    let tpm = ThermalPowerManagementInformation::from_registers(0x0080_0000, 0, 0x0000_0409, 0x0017_0103);
+    assert!(tpm.hw_feedback_performance());
+    assert!(tpm.hw_feedback_efficiency());
+    assert_eq!(4, tpm.thread_director_classes());
+    assert_eq!(8192, tpm.hw_feedback_table_size());
+    assert_eq!(0x17, tpm.hw_feedback_table_index());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn cet_combines_flags_and_xsave_state() {
+    let mut dump = genuine_intel_dump(0xD);
+    dump.insert(7, 0, (0, 0, 1 << 7, 1 << 20));
+    dump.insert(0xD, 1, (0xF, 0, 1 << 11, 0));
+
+    let cet = Master::from_dump(&dump).cet();
+    assert!(cet.shadow_stack && cet.indirect_branch_tracking);
+    assert!(cet.user_state && !cet.supervisor_state);
+    assert!(cet.user_shadow_stack());
+
+    dump.insert(0xD, 1, (0xF, 0, 0, 0));
+    assert!(!Master::from_dump(&dump).cet().user_shadow_stack());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn linear_address_bits_follow_la57() {
+    let mut dump = genuine_intel_dump(7);
+    dump.insert(0x8000_0000, 0, (0x8000_0008, 0, 0, 0));
+    dump.insert(0x8000_0008, 0, (0x3027, 0, 0, 0));
+    let info = Master::from_dump(&dump);
+    assert_eq!(Some(48), info.linear_address_bits());
+    assert!(!info.five_level_paging_supported());
+
+    dump.insert(7, 0, (0, 0, 1 << 16, 0));
+    let info = Master::from_dump(&dump);
+    assert_eq!(Some(57), info.linear_address_bits());
+    assert!(info.five_level_paging_supported());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn user_interrupts_need_their_xsave_state() {
+    let mut dump = genuine_intel_dump(0xD);
+    dump.insert(7, 0, (0, 0, 0, 1 << 5));
+    assert!(!Master::from_dump(&dump).user_interrupts());
+
+    dump.insert(0xD, 1, (0xF, 0, 1 << 14, 0));
+    let info = Master::from_dump(&dump);
+    assert!(info.xsave_supports(XsaveComponent::Uintr));
+    assert!(info.user_interrupts());
+}
+
+#[test]
+fn structured_extended_information_decodes_every_ebx_bit() {
+    // As reported by a Skylake desktop processor
+    let sei = StructuredExtendedInformation::from_registers(0x029C_6FBF, 0, 0);
+    assert!(sei.sgx() && !sei.fdp_excptn_only() && sei.mpx());
+    assert!(sei.clflushopt() && !sei.clwb() && !sei.sha());
+}
+
+#[test]
+fn structured_extended_information_decodes_direct_stores() {
+    let sei = StructuredExtendedInformation::from_registers(0, (1 << 25) | (1 << 28), 0);
+    assert!(sei.cldemote() && !sei.movdiri() && sei.movdir64b());
+
+    let sei = StructuredExtendedInformation::from_registers(0, 1 << 22, 1 << 14);
+    assert!(sei.rdpid() && sei.serialize() && !sei.hybrid());
+
+    // As reported by an Ice Lake server processor
+    let sei = StructuredExtendedInformation::from_registers(0, 0x4041_7F5E, 0);
+    assert!(sei.gfni() && sei.vaes() && sei.vpclmulqdq());
+    assert_eq!(Some(Feature::Movdir64b), Feature::from_linux_name("movdir64b"));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn amx_summarizes_flags_state_and_tiles() {
+    let mut dump = genuine_intel_dump(0x1E);
+    dump.insert(7, 0, (1, 0, 0, (1 << 22) | (1 << 24) | (1 << 25)));
+    dump.insert(7, 1, (1 << 21, 0, 0, 0));
+    dump.insert(0xD, 0, (0x6_02E7, 0, 0, 0));
+    dump.insert(0x1D, 0, (1, 0, 0, 0));
+    dump.insert(0x1D, 1, (0x0400_2000, 0x0008_0040, 0x10, 0));
+    dump.insert(0x1E, 0, (0, 0x4010, 0, 0));
+
+    let amx = Master::from_dump(&dump).amx();
+    assert!(amx.tile && amx.bf16 && amx.int8 && amx.fp16 && !amx.complex);
+    assert!(amx.usable());
+    assert_eq!(Some(1024), amx.tiles.map(|t| t.bytes_per_tile()));
+    assert_eq!(Some(8), amx.tiles.map(|t| t.max_names()));
+    assert_eq!(Some(16), amx.tmul.map(|t| t.tmul_maxk()));
+
+    dump.insert(0xD, 0, (0x2E7, 0, 0, 0));
+    assert!(!Master::from_dump(&dump).amx().usable());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn avx512_collects_subsets_from_every_register() {
+    use Avx512Subset::*;
+
+    let mut dump = genuine_intel_dump(7);
+    dump.insert(7, 0, (0, 0xD3BF_FFFB, 0x0040_5F4E, 0x0000_0110));
+
+    let avx512 = Master::from_dump(&dump).avx512();
+    assert!(avx512.all_of(&[F, Cd, Vl, Bw, Dq, Ifma, Vbmi, Vbmi2, Vnni, Bitalg, Vpopcntdq]));
+    assert!(!avx512.has(Fp16) && avx512.has(Vp2intersect));
+    assert!(!avx512.all_of(&[F, Bf16]));
+    assert_eq!(12, avx512.subsets().count());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn performance_monitoring_counters_and_events() {
+    let mut dump = genuine_intel_dump(0xA);
+    // Version 5 with 8 48-bit counters and the LLC miss event missing,
+    // 3 contiguous fixed counters and counter 5 on its own
+    dump.insert(0xA, 0, (0x0830_0805, 0x10, 0x20, 0x8603));
+
+    let info = Master::from_dump(&dump);
+    let pmu = info.performance_monitoring_information().unwrap();
+    assert_eq!(5, pmu.version_id());
+    assert_eq!(8, pmu.general_purpose_counters());
+    assert_eq!(48, pmu.counter_width_bits());
+    assert_eq!(3, pmu.fixed_function_counters());
+    assert_eq!(48, pmu.fixed_counter_width_bits());
+    assert!(pmu.any_thread_deprecated());
+    assert!(pmu.has_fixed_function_counter(2) && pmu.has_fixed_function_counter(5));
+    assert!(!pmu.has_fixed_function_counter(4));
+    assert!(pmu.supports_event(ArchEvent::CoreCycles));
+    assert!(!pmu.supports_event(ArchEvent::LlcMisses));
+    assert!(pmu.supports_event(ArchEvent::TopdownSlots));
+
+    dump.insert(0xA, 0, (0, 0, 0, 0));
+    assert!(Master::from_dump(&dump).performance_monitoring_information().is_none());
+}
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn max_leaves_bound_the_ranges() {
+    let max_basic = max_basic_leaf().unwrap();
+    assert!(max_basic >= 1);
+    assert_eq!(cpuid_count(0, 0).0, max_basic);
+    assert!(max_extended_leaf().unwrap() >= 0x8000_0000);
+
+    let hypervisor = master().unwrap().hypervisor();
+    assert_eq!(hypervisor, max_hypervisor_leaf().is_some());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn processor_signature_follows_the_vendor() {
+    // Family 6, model 0xA with an extended model of 1: Intel adds the
+    // extended model to family 6, AMD does not
+    let mut dump = authentic_amd_dump(1);
+    dump.insert(1, 0, (0x0001_06A2, 0, 0, 0));
+    let amd = Master::from_dump(&dump).processor_signature().unwrap();
+    assert_eq!(ProcessorSignature { family: 6, model: 0xA, stepping: 2 }, amd);
+
+    dump.insert(0, 0, (1, 0x756E_6547, 0x6C65_746E, 0x4965_6E69));
+    assert_eq!(0x1A, Master::from_dump(&dump).processor_signature().unwrap().model);
+
+    // A Zen 3 whose leaf 1 reads as zero, as under some hypervisors
+    let mut dump = authentic_amd_dump(1);
+    dump.insert(0x8000_0000, 0, (0x8000_0001, 0, 0, 0));
+    dump.insert(0x8000_0001, 0, (0x00A2_0F10, 0x2000_0000, 0, 0));
+    let info = Master::from_dump(&dump);
+    let zen3 = info.processor_signature().unwrap();
+    assert_eq!("Family 19h, Model 21h, Stepping 0", format!("{}", zen3));
+    assert_eq!(2, info.extended_processor_signature().unwrap().package_type());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn brand_string_is_synthesized_without_the_extended_leaves() {
+    // A Pentium III, which has a brand index but no brand string
+    let mut dump = genuine_intel_dump(3);
+    dump.insert(1, 0, (0x0000_0683, 0x02, 0, 0));
+    // Leaf 0x8000_0000 returning leaf 3's registers, as out-of-range
+    // leaves do on Intel processors
+    dump.insert(0x8000_0000, 0, (0x8000_0000 | 0x7FFF_FFFF, 0, 0, 0));
+    let info = Master::from_dump(&dump);
+    assert_eq!(None, info.brand_string());
+    assert_eq!(Some("Intel(R) Pentium(R) III"), info.synthesized_brand_string().as_deref());
+    assert!(info.extended_processor_signature().is_none());
+
+    dump.insert(1, 0, (0x0000_0683, 0, 0, 0));
+    let info = Master::from_dump(&dump);
+    assert_eq!(Some("Intel P6 Family 6h Model 8h"), info.synthesized_brand_string().as_deref());
+    assert!(format!("{}", info).contains("\nBrand:         Intel P6 Family 6h Model 8h\n"));
+
+    // An all-zero brand string, as some virtual models report
+    dump.insert(0x8000_0000, 0, (0x8000_0004, 0, 0, 0));
+    dump.insert(0x8000_0002, 0, (0, 0, 0, 0));
+    let info = Master::from_dump(&dump);
+    assert_eq!(None, info.brand_string());
+    assert_eq!(Some("Intel P6 Family 6h Model 8h"), info.synthesized_brand_string().as_deref());
+
+    dump.insert(0x8000_0002, 0, (0x6574_6E49, 0x2952_286C, 0, 0));
+    let info = Master::from_dump(&dump);
+    assert_eq!(Some("Intel(R)"), info.brand_string());
+    assert_eq!(None, info.synthesized_brand_string());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn normalized_brand_drops_marks_and_frequency() {
+    assert_eq!("Intel Core i7-9700K", normalize_brand("Intel(R) Core(TM) i7-9700K CPU @ 3.60GHz"));
+    assert_eq!("Intel Core2 Duo E8400", normalize_brand("Intel(R) Core(TM)2 Duo CPU     E8400  @ 3.00GHz"));
+    assert_eq!("Intel Xeon Platinum 8480+", normalize_brand("      Intel(R) Xeon(R) Platinum 8480+"));
+    assert_eq!("AMD Athlon 64 X2 Dual Core Processor 4200+",
+               normalize_brand("AMD Athlon(tm) 64 X2 Dual Core Processor 4200+"));
+    assert_eq!("AMD Ryzen 9 7950X 16-Core Processor", normalize_brand("AMD Ryzen 9 7950X 16-Core Processor"));
+}
+
+#[test]
+fn brand_string_is_a_thread_safe_value() {
+    fn assert_value<T: Copy + Send + Sync + Eq + hash::Hash + 'static>() {}
+    assert_value::<BrandString>();
+
+    let mut padded = BrandString { bytes: [0; BRAND_STRING_LENGTH] };
+    let mut unpadded = padded;
+    for (d, &s) in padded.bytes.iter_mut().zip(b"    AMD Ryzen 5") {
+        *d = s;
+    }
+    for (d, &s) in unpadded.bytes.iter_mut().zip(b"AMD Ryzen 5") {
+        *d = s;
+    }
+    assert_eq!(padded, unpadded);
+    assert!("AMD Ryzen 5" == padded);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn brand_string_hashes_and_crosses_threads() {
+    use std::collections::HashSet;
+
+    let mut brand = BrandString { bytes: [0; BRAND_STRING_LENGTH] };
+    for (d, &s) in brand.bytes.iter_mut().zip(b"AMD EPYC 7601 32-Core Processor") {
+        *d = s;
+    }
+    let sent = std::thread::spawn(move || brand).join().unwrap();
+    let set: HashSet<BrandString> = vec![brand, sent].into_iter().collect();
+    assert_eq!(1, set.len());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn confidential_computing_recognizes_tdx_and_sev_snp() {
+    let mut dump = genuine_intel_dump(0x23);
+    dump.insert(1, 0, (0x0008_06F8, 0, 0x8000_0000, 0));
+    assert_eq!(None, Master::from_dump(&dump).confidential_computing());
+
+    // "Inte", "    ", "lTDX"
+    dump.insert(0x21, 0, (0, 0x6574_6E49, 0x2020_2020, 0x5844_546C));
+    let info = Master::from_dump(&dump);
+    assert_eq!(Some(ConfidentialComputing::Tdx), info.confidential_computing());
+    assert_eq!(Some(0), info.tdx_guest_information().map(|t| t.max_subleaf()));
+
+    // An EPYC guest, with SEV-SNP but not a trust domain
+    let mut dump = authentic_amd_dump(0x10);
+    dump.insert(1, 0, (0x00A0_0F11, 0, 0x8000_0000, 0));
+    dump.insert(0x8000_0000, 0, (0x8000_001F, 0, 0, 0));
+    dump.insert(0x8000_001F, 0, (0x0000_001F, 0x0000_5130, 0, 0));
+    let expected = if cfg!(feature = "amd") { Some(ConfidentialComputing::SevSnp) } else { None };
+    assert_eq!(expected, Master::from_dump(&dump).confidential_computing());
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "hypervisor"))]
+fn hypervisor_timing_comes_from_whichever_leaf_is_present() {
+    let mut dump = genuine_intel_dump(0xD);
+    dump.insert(1, 0, (0x0005_0654, 0, 0x8000_0000, 0));
+    assert_eq!(None, Master::from_dump(&dump).hypervisor_tsc_frequency());
+
+    // QEMU's timing leaf for a KVM guest
+    dump.insert(0x4000_0000, 0, (0x4000_0010, 0x4B4D_564B, 0x564B_4D56, 0x0000_004D));
+    dump.insert(0x4000_0010, 0, (2_400_000, 1_000_000, 0, 0));
+    let info = Master::from_dump(&dump);
+    assert_eq!(Some(2_400_000_000), info.hypervisor_tsc_frequency());
+    assert_eq!(Some(1_000_000_000), info.hypervisor_apic_frequency());
+    assert_eq!(Some(2_400_000_000), info.tsc_frequency_hz());
+
+    // Xen's time leaf, which has no APIC frequency
+    dump.insert(0x4000_0000, 0, (0x4000_0005, 0x566E_6558, 0x6558_4D4D, 0x4D4D_566E));
+    dump.insert(0x4000_0003, 0, (0, 0, 2_900_000, 0));
+    let info = Master::from_dump(&dump);
+    assert_eq!(Some(2_900_000_000), info.hypervisor_tsc_frequency());
+    assert_eq!(None, info.hypervisor_apic_frequency());
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "hypervisor"))]
+fn hyper_v_decodes_management_svm_and_nested_leaves() {
+    let mut dump = genuine_intel_dump(0xD);
+    dump.insert(1, 0, (0x0005_0654, 0, 0x8000_0000, 0));
+    dump.insert(0x4000_0000, 0, (0x4000_000A, 0x7263_694D, 0x666F_736F, 0x7648_2074));
+    dump.insert(0x4000_0001, 0, (HYPER_V_INTERFACE, 0, 0, 0));
+    dump.insert(0x4000_0007, 0, (0x8000_0001, 0x6, 0x1, 0));
+    dump.insert(0x4000_0008, 0, ((512 << 11) | 1, 0, 0, 0));
+    dump.insert(0x4000_0009, 0, (0x1064, 0, 0, 0x0002_8010));
+    dump.insert(0x4000_000A, 0, (0x0002_0101, 0, 0, 0));
+
+    let info = Master::from_dump(&dump);
+    let hv = info.hyper_v_information().unwrap();
+    assert!(hv.start_logical_processor() && hv.reserved_identity_bit());
+    assert!(!hv.processor_power_management() && hv.mwait_idle_states() && hv.logical_processor_idling());
+    assert!(hv.remap_guest_uncached());
+    assert!(hv.shared_virtual_memory());
+    assert_eq!(512, hv.max_pasid_space_pasid_count());
+    assert!(hv.nested_access_synic_regs() && hv.nested_access_reenlightenment_controls());
+    assert!(!hv.nested_access_intr_ctrl_regs());
+    assert!(hv.nested_xmm_hypercall_input() && hv.nested_xmm_hypercall_output());
+    assert!(hv.nested_sint_polling_mode());
+    assert_eq!(1, hv.enlightened_vmcs_version_low());
+    assert!(hv.direct_virtual_flush());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn x2apic_id_prefers_leaf_1f_and_skips_empty_leaves() {
+    let mut dump = genuine_intel_dump(0xD);
+    assert_eq!(None, x2apic_id_from(&dump));
+
+    dump.insert(0xB, 0, (1, 2, 0x0000_0100, 0x13));
+    assert_eq!(Some(0x13), x2apic_id_from(&dump));
+
+    // Leaf 0x1F in range but empty, as on processors that only
+    // implement leaf 0xB
+    dump.insert(0, 0, (0x1F, 0x756E_6547, 0x6C65_746E, 0x4965_6E69));
+    assert_eq!(Some(0x13), x2apic_id_from(&dump));
+
+    dump.insert(0x1F, 0, (1, 2, 0x0000_0100, 0x113));
+    assert_eq!(Some(0x113), x2apic_id_from(&dump));
+}
+
+#[test]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn current_apic_id_is_reported() {
+    // The thread may migrate between queries, so the value itself
+    // cannot be checked
+    assert!(current_apic_id().is_some());
 }
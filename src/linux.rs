@@ -0,0 +1,288 @@
+//! The names Linux gives features in `/proc/cpuinfo`.
+
+use super::{Feature, FeatureSet, Master};
+
+// Features the kernel lists, by the name it uses. Features it hides or
+// does not know are omitted.
+const NAMES: &[(Feature, &str)] = &[
+    (Feature::Sse3, "pni"),
+    (Feature::Pclmulqdq, "pclmulqdq"),
+    (Feature::Dtes64, "dtes64"),
+    (Feature::Monitor, "monitor"),
+    (Feature::DsCpl, "ds_cpl"),
+    (Feature::Vmx, "vmx"),
+    (Feature::Smx, "smx"),
+    (Feature::Eist, "est"),
+    (Feature::Tm2, "tm2"),
+    (Feature::Ssse3, "ssse3"),
+    (Feature::CnxtId, "cid"),
+    (Feature::Sdbg, "sdbg"),
+    (Feature::Fma, "fma"),
+    (Feature::Cmpxchg16b, "cx16"),
+    (Feature::XtprUpdateControl, "xtpr"),
+    (Feature::Pdcm, "pdcm"),
+    (Feature::Pcid, "pcid"),
+    (Feature::Dca, "dca"),
+    (Feature::Sse4_1, "sse4_1"),
+    (Feature::Sse4_2, "sse4_2"),
+    (Feature::X2apic, "x2apic"),
+    (Feature::Movbe, "movbe"),
+    (Feature::Popcnt, "popcnt"),
+    (Feature::TscDeadline, "tsc_deadline_timer"),
+    (Feature::Aesni, "aes"),
+    (Feature::Xsave, "xsave"),
+    (Feature::Avx, "avx"),
+    (Feature::F16c, "f16c"),
+    (Feature::Rdrand, "rdrand"),
+    (Feature::Hypervisor, "hypervisor"),
+    (Feature::Fpu, "fpu"),
+    (Feature::Vme, "vme"),
+    (Feature::De, "de"),
+    (Feature::Pse, "pse"),
+    (Feature::Tsc, "tsc"),
+    (Feature::Msr, "msr"),
+    (Feature::Pae, "pae"),
+    (Feature::Mce, "mce"),
+    (Feature::Cx8, "cx8"),
+    (Feature::Apic, "apic"),
+    (Feature::Sep, "sep"),
+    (Feature::Mtrr, "mtrr"),
+    (Feature::Pge, "pge"),
+    (Feature::Mca, "mca"),
+    (Feature::Cmov, "cmov"),
+    (Feature::Pat, "pat"),
+    (Feature::Pse36, "pse36"),
+    (Feature::Psn, "pn"),
+    (Feature::Clfsh, "clflush"),
+    (Feature::Ds, "dts"),
+    (Feature::Acpi, "acpi"),
+    (Feature::Mmx, "mmx"),
+    (Feature::Fxsr, "fxsr"),
+    (Feature::Sse, "sse"),
+    (Feature::Sse2, "sse2"),
+    (Feature::Ss, "ss"),
+    (Feature::Htt, "ht"),
+    (Feature::Tm, "tm"),
+    (Feature::Pbe, "pbe"),
+    (Feature::DigitalTemperatureSensor, "dtherm"),
+    (Feature::IntelTurboBoost, "ida"),
+    (Feature::Arat, "arat"),
+    (Feature::Pln, "pln"),
+    (Feature::Ptm, "pts"),
+    (Feature::Hwp, "hwp"),
+    (Feature::HwpNotification, "hwp_notify"),
+    (Feature::HwpActivityWindow, "hwp_act_window"),
+    (Feature::HwpEnergyPerformancePreference, "hwp_epp"),
+    (Feature::HwFeedback, "hfi"),
+    (Feature::PerformanceEnergyBias, "epb"),
+    (Feature::Fsgsbase, "fsgsbase"),
+    (Feature::Ia32TscAdjustMsr, "tsc_adjust"),
+    (Feature::Sgx, "sgx"),
+    (Feature::Bmi1, "bmi1"),
+    (Feature::Hle, "hle"),
+    (Feature::Avx2, "avx2"),
+    (Feature::Smep, "smep"),
+    (Feature::Bmi2, "bmi2"),
+    (Feature::EnhancedRepMovsbStosb, "erms"),
+    (Feature::Invpcid, "invpcid"),
+    (Feature::Rtm, "rtm"),
+    (Feature::Pqm, "cqm"),
+    (Feature::Mpx, "mpx"),
+    (Feature::Pqe, "rdt_a"),
+    (Feature::Avx512f, "avx512f"),
+    (Feature::Avx512dq, "avx512dq"),
+    (Feature::Rdseed, "rdseed"),
+    (Feature::Adx, "adx"),
+    (Feature::Smap, "smap"),
+    (Feature::Avx512Ifma, "avx512ifma"),
+    (Feature::Clflushopt, "clflushopt"),
+    (Feature::Clwb, "clwb"),
+    (Feature::IntelProcessorTrace, "intel_pt"),
+    (Feature::Avx512pf, "avx512pf"),
+    (Feature::Avx512er, "avx512er"),
+    (Feature::Avx512cd, "avx512cd"),
+    (Feature::Sha, "sha_ni"),
+    (Feature::Avx512bw, "avx512bw"),
+    (Feature::Avx512vl, "avx512vl"),
+    (Feature::Avx512Vbmi, "avx512vbmi"),
+    (Feature::Waitpkg, "waitpkg"),
+    (Feature::Avx512Vbmi2, "avx512_vbmi2"),
+    (Feature::CetSs, "shstk"),
+    (Feature::Gfni, "gfni"),
+    (Feature::Vaes, "vaes"),
+    (Feature::Vpclmulqdq, "vpclmulqdq"),
+    (Feature::Avx512Vnni, "avx512_vnni"),
+    (Feature::Avx512Bitalg, "avx512_bitalg"),
+    (Feature::Avx512Vpopcntdq, "avx512_vpopcntdq"),
+    (Feature::La57, "la57"),
+    (Feature::Rdpid, "rdpid"),
+    (Feature::Cldemote, "cldemote"),
+    (Feature::Movdiri, "movdiri"),
+    (Feature::Movdir64b, "movdir64b"),
+    (Feature::Avx512_4vnniw, "avx512_4vnniw"),
+    (Feature::Avx512_4fmaps, "avx512_4fmaps"),
+    (Feature::Avx512Vp2intersect, "avx512_vp2intersect"),
+    (Feature::SrbdsCtrl, "srbds_ctrl"),
+    (Feature::MdClear, "md_clear"),
+    (Feature::RtmAlwaysAbort, "rtm_always_abort"),
+    (Feature::Serialize, "serialize"),
+    (Feature::Hybrid, "hybrid_cpu"),
+    (Feature::Pconfig, "pconfig"),
+    (Feature::ArchLbr, "arch_lbr"),
+    (Feature::CetIbt, "ibt"),
+    (Feature::AmxBf16, "amx_bf16"),
+    (Feature::Avx512Fp16, "avx512_fp16"),
+    (Feature::AmxTile, "amx_tile"),
+    (Feature::AmxInt8, "amx_int8"),
+    (Feature::Stibp, "intel_stibp"),
+    (Feature::L1dFlush, "flush_l1d"),
+    (Feature::ArchCapabilities, "arch_capabilities"),
+    (Feature::CoreCapabilities, "core_capabilities"),
+    (Feature::AvxVnni, "avx_vnni"),
+    (Feature::Avx512Bf16, "avx512_bf16"),
+    (Feature::Cmpccxadd, "cmpccxadd"),
+    (Feature::Fzlrm, "fzrm"),
+    (Feature::Fsrs, "fsrs"),
+    (Feature::Fsrcs, "fsrc"),
+    (Feature::Fred, "fred"),
+    (Feature::Lkgs, "lkgs"),
+    (Feature::AmxFp16, "amx_fp16"),
+    (Feature::AvxIfma, "avx_ifma"),
+    (Feature::Lam, "lam"),
+    (Feature::Xsaveopt, "xsaveopt"),
+    (Feature::Xsavec, "xsavec"),
+    (Feature::XgetbvEcx1, "xgetbv1"),
+    (Feature::Xsaves, "xsaves"),
+    (Feature::Xfd, "xfd"),
+    (Feature::L3Monitoring, "cqm_llc"),
+    (Feature::L3OccupancyMonitoring, "cqm_occup_llc"),
+    (Feature::L3TotalBandwidthMonitoring, "cqm_mbm_total"),
+    (Feature::L3LocalBandwidthMonitoring, "cqm_mbm_local"),
+    (Feature::L3Cat, "cat_l3"),
+    (Feature::L2Cat, "cat_l2"),
+    (Feature::Mba, "mba"),
+    (Feature::LahfSahfIn64Bit, "lahf_lm"),
+    (Feature::CmpLegacy, "cmp_legacy"),
+    (Feature::Svm, "svm"),
+    (Feature::ExtApicSpace, "extapic"),
+    (Feature::AltMovCr8, "cr8_legacy"),
+    (Feature::Lzcnt, "abm"),
+    (Feature::Sse4a, "sse4a"),
+    (Feature::MisalignedSse, "misalignsse"),
+    (Feature::Prefetchw, "3dnowprefetch"),
+    (Feature::Osvw, "osvw"),
+    (Feature::Ibs, "ibs"),
+    (Feature::Xop, "xop"),
+    (Feature::Skinit, "skinit"),
+    (Feature::Wdt, "wdt"),
+    (Feature::Lwp, "lwp"),
+    (Feature::Fma4, "fma4"),
+    (Feature::Tce, "tce"),
+    (Feature::Tbm, "tbm"),
+    (Feature::TopologyExtensions, "topoext"),
+    (Feature::PerfCtrExtCore, "perfctr_core"),
+    (Feature::PerfCtrExtNb, "perfctr_nb"),
+    (Feature::DataBreakpointExtension, "bpext"),
+    (Feature::PerfTsc, "ptsc"),
+    (Feature::PerfCtrExtLlc, "perfctr_llc"),
+    (Feature::Monitorx, "mwaitx"),
+    (Feature::SyscallSysretIn64Bit, "syscall"),
+    (Feature::ExecuteDisable, "nx"),
+    (Feature::Mmxext, "mmxext"),
+    (Feature::Ffxsr, "fxsr_opt"),
+    (Feature::GigabytePages, "pdpe1gb"),
+    (Feature::RdtscpAndIa32TscAux, "rdtscp"),
+    (Feature::Intel64BitArchitecture, "lm"),
+    (Feature::ThreeDnowExt, "3dnowext"),
+    (Feature::ThreeDnow, "3dnow"),
+    (Feature::Clzero, "clzero"),
+    (Feature::InstRetCntMsr, "irperf"),
+    (Feature::RstrFpErrPtrs, "xsaveerptr"),
+    (Feature::Rdpru, "rdpru"),
+    (Feature::Wbnoinvd, "wbnoinvd"),
+    (Feature::Ppin, "amd_ppin"),
+    (Feature::VirtSsbd, "virt_ssbd"),
+    (Feature::Cppc, "cppc"),
+    (Feature::NestedPaging, "npt"),
+    (Feature::LbrVirtualization, "lbrv"),
+    (Feature::SvmLock, "svm_lock"),
+    (Feature::NripSave, "nrip_save"),
+    (Feature::TscRateMsr, "tsc_scale"),
+    (Feature::VmcbClean, "vmcb_clean"),
+    (Feature::FlushByAsid, "flushbyasid"),
+    (Feature::DecodeAssists, "decodeassists"),
+    (Feature::PauseFilter, "pausefilter"),
+    (Feature::PauseFilterThreshold, "pfthreshold"),
+    (Feature::Avic, "avic"),
+    (Feature::VmsaveVirtualization, "v_vmsave_vmload"),
+    (Feature::Vgif, "vgif"),
+    (Feature::X2avic, "x2avic"),
+    (Feature::SpecCtrl, "v_spec_ctrl"),
+    (Feature::Vnmi, "vnmi"),
+    (Feature::Sme, "sme"),
+    (Feature::Sev, "sev"),
+    (Feature::SevEs, "sev_es"),
+    (Feature::SevSnp, "sev_snp"),
+    (Feature::InvariantTsc, "nonstop_tsc"),
+    (Feature::CorePerformanceBoost, "cpb"),
+    (Feature::ProcessorFeedbackInterface, "proc_feedback"),
+];
+
+impl Feature {
+    /// The name Linux uses for the feature in `/proc/cpuinfo`, such as
+    /// "pni" for `Sse3`, or `None` if the kernel does not list it.
+    pub fn linux_name(self) -> Option<&'static str> {
+        NAMES.iter().find(|n| n.0 == self).map(|n| n.1)
+    }
+
+    /// Looks up a feature by its name in `/proc/cpuinfo`.
+    pub fn from_linux_name(name: &str) -> Option<Feature> {
+        NAMES.iter().find(|n| n.1 == name).map(|n| n.0)
+    }
+}
+
+impl FeatureSet {
+    /// The Linux names of the features in the set that the kernel lists.
+    pub fn linux_names<'a>(&'a self) -> impl Iterator<Item = &'static str> + 'a {
+        self.iter().filter_map(Feature::linux_name)
+    }
+}
+
+impl Master {
+    /// The supported features by their Linux names, separated by
+    /// spaces, in the style of the "Flags" line of `lscpu`.
+    #[cfg(feature = "std")]
+    pub fn linux_flags_string(&self) -> std::string::String {
+        let set = FeatureSet::from(self);
+        let names: std::vec::Vec<_> = set.linux_names().collect();
+        names.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use genuine_intel_dump;
+
+    #[test]
+    fn linux_names_round_trip() {
+        assert_eq!(Some("aes"), Feature::Aesni.linux_name());
+        assert_eq!(Some("sse4_1"), Feature::Sse4_1.linux_name());
+        assert_eq!(Some(Feature::Eist), Feature::from_linux_name("est"));
+        assert_eq!(None, Feature::Osxsave.linux_name());
+        for &feature in Feature::ALL {
+            if let Some(name) = feature.linux_name() {
+                assert_eq!(Some(feature), Feature::from_linux_name(name));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn linux_flags_string_uses_kernel_names() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 | 1 << 20, 1 << 26));
+        assert_eq!("pni sse4_2 sse2", Master::from_dump(&dump).linux_flags_string());
+    }
+}
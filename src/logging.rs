@@ -0,0 +1,78 @@
+//! Recording the processor's capabilities with `tracing`.
+//!
+//! Each capture of the CPU information from the processor emits a
+//! `DEBUG` event, and `log_summary!` emits an `INFO` event describing
+//! the processor, so that services can record what they are running on
+//! next to their own structured logs. Events use the `cupid` target.
+//!
+//! ```
+//! // at startup, after installing a subscriber
+//! cupid::log_summary!();
+//! ```
+
+use super::{Feature, FeatureSet, Master};
+
+// The features worth a line in a service's logs: those that most
+// often decide which implementation of something runs
+const NOTABLE: &[Feature] = &[
+    Feature::Sse4_2,
+    Feature::Popcnt,
+    Feature::Aesni,
+    Feature::Pclmulqdq,
+    Feature::Avx,
+    Feature::Avx2,
+    Feature::Fma,
+    Feature::Bmi2,
+    Feature::Sha,
+    Feature::Avx512f,
+    Feature::Avx512Vnni,
+    Feature::Avx10,
+    Feature::AmxTile,
+    Feature::Hypervisor,
+];
+
+fn notable_features(info: &Master) -> FeatureSet {
+    NOTABLE.iter().cloned().filter(|&f| info.supports(f)).collect()
+}
+
+pub(crate) fn captured(info: &Master) {
+    tracing::debug!(
+        target: "cupid",
+        vendor = info.vendor_id(),
+        signature = info.processor_signature().map(tracing::field::display),
+        features = %notable_features(info),
+        "captured CPU information"
+    );
+}
+
+/// Emits an `INFO` event describing `info`: its vendor, brand,
+/// signature, microarchitecture and x86-64 level, and which of a few
+/// notable features it supports. `log_summary!` calls this with the
+/// running processor.
+pub fn log_summary(info: &Master) {
+    tracing::info!(
+        target: "cupid",
+        vendor = info.vendor_id(),
+        brand = info.brand_string().map(|b| b.trim()),
+        signature = info.processor_signature().map(tracing::field::display),
+        microarchitecture = info.microarchitecture().map(tracing::field::display),
+        level = info.microarch_level().map(tracing::field::display),
+        features = %notable_features(info),
+        "CPU capabilities"
+    );
+}
+
+/// Emits an `INFO` event describing the processor, as described by
+/// `logging::log_summary`. Does nothing if the processor cannot be
+/// queried. Given an expression, describes that `Master` instead.
+#[macro_export]
+macro_rules! log_summary {
+    () => {
+        if let Some(info) = $crate::get() {
+            $crate::logging::log_summary(info);
+        }
+    };
+    ($info:expr) => {
+        $crate::logging::log_summary(&$info)
+    };
+}
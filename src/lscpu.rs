@@ -0,0 +1,171 @@
+//! A rendering of the processor description in the layout of `lscpu`,
+//! so that scripts which scrape it can read cupid's output instead.
+//!
+//! ```text
+//! Architecture:        x86_64
+//! CPU op-mode(s):      32-bit, 64-bit
+//! Byte Order:          Little Endian
+//! Address sizes:       39 bits physical, 48 bits virtual
+//! CPU(s):              12
+//! Thread(s) per core:  2
+//! Core(s) per socket:  6
+//! Vendor ID:           GenuineIntel
+//! CPU family:          6
+//! Model:               158
+//! Model name:          Intel(R) Core(TM) i7-8700K CPU @ 3.70GHz
+//! Stepping:            10
+//! Virtualization:      VT-x
+//! L1d cache:           32K
+//! ...
+//! Flags:               fpu vme de pse ...
+//! ```
+//!
+//! As for lscpu, `CPU(s)` is the number of processors the operating
+//! system knows of. CPUID cannot count them, so it is always that of
+//! the machine doing the rendering, even for a dump taken elsewhere.
+//! The other lines come from CPUID, which only describes the package
+//! it runs on, so there is no `Socket(s)` line. Lines for values the
+//! processor does not report are left out.
+
+use std::fmt::{self, Write};
+use std::string::String;
+use std::thread;
+
+#[cfg(feature = "caches")]
+use super::CacheType;
+#[cfg(feature = "hypervisor")]
+use super::Hypervisor;
+use super::{Feature, Master};
+#[cfg(target_os = "linux")]
+use super::per_core::parse_cpu_list;
+
+impl Master {
+    /// Renders the processor description in the layout of `lscpu`.
+    pub fn to_lscpu(&self) -> String {
+        let mut out = String::new();
+
+        let long_mode = self.supports(Feature::Intel64BitArchitecture);
+        line(&mut out, "Architecture", if long_mode { "x86_64" } else { "i686" });
+        line(&mut out, "CPU op-mode(s)", if long_mode { "32-bit, 64-bit" } else { "32-bit" });
+        line(&mut out, "Byte Order", "Little Endian");
+        if let (Some(pas), Some(linear)) = (self.physical_address_size(), self.linear_address_bits()) {
+            line(&mut out, "Address sizes", format_args!("{} bits physical, {} bits virtual",
+                                                         pas.physical_address_bits(), linear));
+        }
+
+        let topology = self.extended_topology();
+        let threads = topology.map(|t| t.threads_per_core());
+        #[cfg(feature = "amd")]
+        let threads = threads.or_else(|| self.processor_topology_information().map(|t| t.threads_per_core()));
+        if let Some(cpus) = cpu_count() {
+            line(&mut out, "CPU(s)", cpus);
+        }
+        if let Some(threads) = threads {
+            line(&mut out, "Thread(s) per core", threads);
+        }
+        if let (Some(topology), Some(threads)) = (topology, threads) {
+            line(&mut out, "Core(s) per socket", topology.logical_processors() / threads.max(1));
+        }
+
+        line(&mut out, "Vendor ID", self.vendor_id());
+        if let Some(vi) = self.version_information() {
+            line(&mut out, "CPU family", vi.family_id());
+            line(&mut out, "Model", vi.model_id());
+        }
+        // lscpu falls back to a name of its own too
+        let synthesized = self.synthesized_brand_string();
+        let brand = self.brand_string().or_else(|| synthesized.as_ref().map(|b| b.as_str()));
+        if let Some(brand) = brand.map(|b| b.trim()).filter(|b| !b.is_empty()) {
+            line(&mut out, "Model name", brand);
+        }
+        if let Some(vi) = self.version_information() {
+            line(&mut out, "Stepping", vi.stepping());
+        }
+
+        if self.supports(Feature::Vmx) {
+            line(&mut out, "Virtualization", "VT-x");
+        } else if self.supports(Feature::Svm) {
+            line(&mut out, "Virtualization", "AMD-V");
+        }
+        #[cfg(feature = "hypervisor")]
+        if let Some(hi) = self.hypervisor_information() {
+            let vendor = match hi.hypervisor() {
+                Hypervisor::Kvm => "KVM",
+                Hypervisor::HyperV => "Microsoft",
+                Hypervisor::VMware => "VMware",
+                Hypervisor::Xen => "Xen",
+                Hypervisor::Tcg => "TCG",
+                Hypervisor::Bhyve => "bhyve",
+                Hypervisor::VirtualBox => "Oracle",
+                Hypervisor::Parallels => "Parallels",
+                Hypervisor::Acrn => "ACRN",
+                Hypervisor::Unknown => hi.signature(),
+            };
+            line(&mut out, "Hypervisor vendor", vendor);
+            line(&mut out, "Virtualization type", "full");
+        }
+
+        #[cfg(feature = "caches")]
+        for cache in self.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]) {
+            let kind = match cache.cache_type() {
+                Some(CacheType::Data) => "d",
+                Some(CacheType::Instruction) => "i",
+                Some(CacheType::Unified) | None => "",
+            };
+            line(&mut out, format_args!("L{}{} cache", cache.level(), kind),
+                 format_args!("{}K", cache.size() / 1024));
+        }
+
+        line(&mut out, "Flags", self.linux_flags_string());
+        out
+    }
+}
+
+// lscpu counts the processors present, whether online or not
+#[cfg(target_os = "linux")]
+fn cpu_count() -> Option<usize> {
+    let present = std::fs::read_to_string("/sys/devices/system/cpu/present").ok()
+        .and_then(|text| parse_cpu_list(text.trim()))
+        .map(|cpus| cpus.len())
+        .filter(|&n| n != 0);
+    present.or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cpu_count() -> Option<usize> {
+    thread::available_parallelism().ok().map(|n| n.get())
+}
+
+// lscpu aligns the values in a column after the longest label
+fn line<L: fmt::Display, V: fmt::Display>(out: &mut String, label: L, value: V) {
+    let label = format!("{}:", label);
+    let _ = writeln!(out, "{:<21}{}", label, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn lscpu_uses_its_field_names() {
+        let mut dump = genuine_intel_dump(0xB);
+        dump.insert(1, 0, (0x0009_06EA, 0, 1 << 5, 1 << 26));
+        dump.insert(0xB, 0, (1, 2, 0x100, 0));
+        dump.insert(0xB, 1, (4, 12, 0x201, 0));
+        dump.insert(0x8000_0000, 0, (0x8000_0008, 0, 0, 0));
+        dump.insert(0x8000_0001, 0, (0, 0, 0, 1 << 29));
+        dump.insert(0x8000_0008, 0, (0x3027, 0, 0, 0));
+        let lscpu = Master::from_dump(&dump).to_lscpu();
+
+        assert!(lscpu.starts_with("Architecture:        x86_64\nCPU op-mode(s):      32-bit, 64-bit\n"));
+        assert!(lscpu.contains("\nAddress sizes:       39 bits physical, 48 bits virtual\n"));
+        let cpus = lscpu.split("\nCPU(s):").nth(1).and_then(|rest| rest.lines().next()).unwrap();
+        assert!(cpus.trim().parse::<usize>().unwrap() >= 1);
+        assert!(lscpu.contains("\nThread(s) per core:  2\nCore(s) per socket:  6\n"));
+        assert!(lscpu.contains("\nModel:               158\n"));
+        assert!(lscpu.contains("\nVirtualization:      VT-x\n"));
+        assert!(lscpu.contains("\nModel name:          Intel Skylake Family 6h Model 9Eh\n"));
+        assert!(lscpu.ends_with("\nFlags:               vmx sse2 lm\n"));
+    }
+}
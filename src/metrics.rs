@@ -0,0 +1,106 @@
+//! A rendering of the processor description as metrics, in the
+//! Prometheus text exposition format.
+//!
+//! ```text
+//! # HELP cupid_info The processor identity.
+//! # TYPE cupid_info gauge
+//! cupid_info{vendor="GenuineIntel",brand="...",family="6",model="158",stepping="10"} 1
+//! # HELP cupid_feature Whether the processor supports a feature.
+//! # TYPE cupid_feature gauge
+//! cupid_feature{name="sse3"} 1
+//! ...
+//! # HELP cupid_cache_size_bytes The size of each cache.
+//! # TYPE cupid_cache_size_bytes gauge
+//! cupid_cache_size_bytes{level="1",type="data"} 32768
+//! ...
+//! ```
+//!
+//! Identity values the processor does not report are empty labels.
+
+use std::fmt::Write;
+use std::string::{String, ToString};
+
+#[cfg(feature = "caches")]
+use super::CacheType;
+use super::Master;
+
+impl Master {
+    /// Renders the processor description as metrics text.
+    pub fn to_metrics(&self) -> String {
+        let mut out = String::new();
+
+        header(&mut out, "cupid_info", "The processor identity.");
+        let (family, model, stepping) = match self.version_information() {
+            Some(vi) => (vi.family_id().to_string(), vi.model_id().to_string(), vi.stepping().to_string()),
+            None => (String::new(), String::new(), String::new()),
+        };
+        out.push_str("cupid_info{vendor=");
+        label(&mut out, self.vendor_id());
+        out.push_str(",brand=");
+        label(&mut out, self.brand_string().map(|b| b.trim()).unwrap_or(""));
+        out.push_str(",family=");
+        label(&mut out, &family);
+        out.push_str(",model=");
+        label(&mut out, &model);
+        out.push_str(",stepping=");
+        label(&mut out, &stepping);
+        out.push_str("} 1\n");
+
+        header(&mut out, "cupid_feature", "Whether the processor supports a feature.");
+        for (name, set) in self.flags() {
+            writeln!(out, "cupid_feature{{name=\"{}\"}} {}", name, set as u8).unwrap();
+        }
+
+        header(&mut out, "cupid_cache_size_bytes", "The size of each cache.");
+        #[cfg(feature = "caches")]
+        for cache in self.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]) {
+            let cache_type = match cache.cache_type() {
+                Some(CacheType::Data) => "data",
+                Some(CacheType::Instruction) => "instruction",
+                Some(CacheType::Unified) => "unified",
+                None => "unknown",
+            };
+            writeln!(out, "cupid_cache_size_bytes{{level=\"{}\",type=\"{}\"}} {}",
+                     cache.level(), cache_type, cache.size()).unwrap();
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} gauge", name).unwrap();
+}
+
+// A quoted label value, with backslashes, quotes and newlines escaped
+fn label(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn metrics_label_identity_and_features() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+        let metrics = Master::from_dump(&dump).to_metrics();
+
+        assert!(metrics.contains("cupid_info{vendor=\"GenuineIntel\",brand=\"\",family=\"6\",model=\"106\",stepping=\"6\"} 1\n"));
+        assert!(metrics.contains("cupid_feature{name=\"sse4_2\"} 1\n"));
+        assert!(metrics.contains("cupid_feature{name=\"avx2\"} 0\n"));
+        assert!(metrics.contains("# TYPE cupid_cache_size_bytes gauge\n"));
+    }
+}
@@ -0,0 +1,218 @@
+//! Identifying the microarchitecture from the processor signature.
+
+use core::fmt;
+
+use super::Master;
+
+/// A processor core microarchitecture. Intel's hybrid processors report
+/// the microarchitecture of their performance cores.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Microarchitecture {
+    // Intel
+    P6,
+    NetBurst,
+    Core,
+    Penryn,
+    Nehalem,
+    Westmere,
+    SandyBridge,
+    IvyBridge,
+    Haswell,
+    Broadwell,
+    Skylake,
+    CascadeLake,
+    CooperLake,
+    PalmCove,
+    SunnyCove,
+    WillowCove,
+    CypressCove,
+    GoldenCove,
+    RaptorCove,
+    RedwoodCove,
+    LionCove,
+    Bonnell,
+    Silvermont,
+    Airmont,
+    Goldmont,
+    GoldmontPlus,
+    Tremont,
+    Gracemont,
+    Crestmont,
+    KnightsLanding,
+    KnightsMill,
+    // AMD
+    K8,
+    K10,
+    Bobcat,
+    Bulldozer,
+    Piledriver,
+    Steamroller,
+    Excavator,
+    Jaguar,
+    Zen,
+    ZenPlus,
+    Zen2,
+    Zen3,
+    Zen4,
+    Zen5,
+}
+
+impl Microarchitecture {
+    /// Looks up the microarchitecture by vendor identification and the
+    /// display family, model and stepping, as from `VersionInformation`.
+    /// The stepping only matters where one model spans several, as
+    /// Skylake-SP, Cascade Lake and Cooper Lake share model 55h.
+    pub fn lookup(vendor: &str, family: u32, model: u32, stepping: u32) -> Option<Microarchitecture> {
+        use self::Microarchitecture::*;
+
+        match (vendor, family) {
+            ("GenuineIntel", 0x06) => Some(match model {
+                0x0F | 0x16 => Core,
+                0x17 | 0x1D => Penryn,
+                0x1A | 0x1E | 0x1F | 0x2E => Nehalem,
+                0x25 | 0x2C | 0x2F => Westmere,
+                0x2A | 0x2D => SandyBridge,
+                0x3A | 0x3E => IvyBridge,
+                0x3C | 0x3F | 0x45 | 0x46 => Haswell,
+                0x3D | 0x47 | 0x4F | 0x56 => Broadwell,
+                0x55 => match stepping {
+                    0x5..=0x7 => CascadeLake,
+                    0xA | 0xB => CooperLake,
+                    _ => Skylake,
+                },
+                0x4E | 0x5E | 0x8E | 0x9E | 0xA5 | 0xA6 => Skylake,
+                0x66 => PalmCove,
+                0x6A | 0x6C | 0x7D | 0x7E | 0x9D => SunnyCove,
+                0x8C | 0x8D => WillowCove,
+                0xA7 | 0xA8 => CypressCove,
+                0x8F | 0x97 | 0x9A => GoldenCove,
+                0xB7 | 0xBA | 0xBF | 0xCF => RaptorCove,
+                0xAA | 0xAC | 0xAD | 0xAE => RedwoodCove,
+                0xBD | 0xC5 | 0xC6 => LionCove,
+                0x1C | 0x26 | 0x27 | 0x35 | 0x36 => Bonnell,
+                0x37 | 0x4A | 0x4D | 0x5A | 0x5D => Silvermont,
+                0x4C => Airmont,
+                0x5C | 0x5F => Goldmont,
+                0x7A => GoldmontPlus,
+                0x86 | 0x96 | 0x9C => Tremont,
+                0xBE => Gracemont,
+                0xAF | 0xB6 => Crestmont,
+                0x57 => KnightsLanding,
+                0x85 => KnightsMill,
+                0x01..=0x0E => P6,
+                _ => return None,
+            }),
+            ("GenuineIntel", 0x0F) => Some(NetBurst),
+
+            ("AuthenticAMD", 0x0F) => Some(K8),
+            ("AuthenticAMD", 0x10) | ("AuthenticAMD", 0x12) => Some(K10),
+            ("AuthenticAMD", 0x14) => Some(Bobcat),
+            ("AuthenticAMD", 0x15) => match model {
+                0x00 | 0x01 => Some(Bulldozer),
+                0x02 | 0x10..=0x1F => Some(Piledriver),
+                0x30..=0x3F => Some(Steamroller),
+                0x60..=0x7F => Some(Excavator),
+                _ => None,
+            },
+            ("AuthenticAMD", 0x16) => Some(Jaguar),
+            ("AuthenticAMD", 0x17) => match model {
+                0x08 | 0x18 => Some(ZenPlus),
+                0x00..=0x2F => Some(Zen),
+                _ => Some(Zen2),
+            },
+            ("AuthenticAMD", 0x19) => match model {
+                0x10..=0x1F | 0x60..=0x7F | 0xA0..=0xAF => Some(Zen4),
+                _ => Some(Zen3),
+            },
+            ("AuthenticAMD", 0x1A) => Some(Zen5),
+
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        use self::Microarchitecture::*;
+
+        match self {
+            P6 => "P6",
+            NetBurst => "NetBurst",
+            Core => "Core",
+            Penryn => "Penryn",
+            Nehalem => "Nehalem",
+            Westmere => "Westmere",
+            SandyBridge => "Sandy Bridge",
+            IvyBridge => "Ivy Bridge",
+            Haswell => "Haswell",
+            Broadwell => "Broadwell",
+            Skylake => "Skylake",
+            CascadeLake => "Cascade Lake",
+            CooperLake => "Cooper Lake",
+            PalmCove => "Palm Cove",
+            SunnyCove => "Sunny Cove",
+            WillowCove => "Willow Cove",
+            CypressCove => "Cypress Cove",
+            GoldenCove => "Golden Cove",
+            RaptorCove => "Raptor Cove",
+            RedwoodCove => "Redwood Cove",
+            LionCove => "Lion Cove",
+            Bonnell => "Bonnell",
+            Silvermont => "Silvermont",
+            Airmont => "Airmont",
+            Goldmont => "Goldmont",
+            GoldmontPlus => "Goldmont Plus",
+            Tremont => "Tremont",
+            Gracemont => "Gracemont",
+            Crestmont => "Crestmont",
+            KnightsLanding => "Knights Landing",
+            KnightsMill => "Knights Mill",
+            K8 => "K8",
+            K10 => "K10",
+            Bobcat => "Bobcat",
+            Bulldozer => "Bulldozer",
+            Piledriver => "Piledriver",
+            Steamroller => "Steamroller",
+            Excavator => "Excavator",
+            Jaguar => "Jaguar",
+            Zen => "Zen",
+            ZenPlus => "Zen+",
+            Zen2 => "Zen 2",
+            Zen3 => "Zen 3",
+            Zen4 => "Zen 4",
+            Zen5 => "Zen 5",
+        }
+    }
+}
+
+impl fmt::Display for Microarchitecture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Master {
+    /// The core microarchitecture, if the processor signature is known.
+    pub fn microarchitecture(&self) -> Option<Microarchitecture> {
+        self.processor_signature().and_then(|s| {
+            Microarchitecture::lookup(self.vendor_id(), s.family, s.model, s.stepping)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn microarchitecture_lookup() {
+        assert_eq!(Some(Microarchitecture::GoldenCove), Microarchitecture::lookup("GenuineIntel", 6, 0x97, 2));
+        assert_eq!(Some(Microarchitecture::Skylake), Microarchitecture::lookup("GenuineIntel", 6, 0x55, 4));
+        assert_eq!(Some(Microarchitecture::CascadeLake), Microarchitecture::lookup("GenuineIntel", 6, 0x55, 7));
+        assert_eq!(Some(Microarchitecture::CooperLake), Microarchitecture::lookup("GenuineIntel", 6, 0x55, 11));
+        assert_eq!(Some(Microarchitecture::Zen2), Microarchitecture::lookup("AuthenticAMD", 0x17, 0x31, 0));
+        assert_eq!(Some(Microarchitecture::Zen3), Microarchitecture::lookup("AuthenticAMD", 0x19, 0x21, 0));
+        assert_eq!(None, Microarchitecture::lookup("AuthenticAMD", 6, 0x97, 0));
+        assert_eq!("Zen 3", Microarchitecture::Zen3.name());
+    }
+}
@@ -0,0 +1,135 @@
+//! The microcode revision of the running processor, alongside its
+//! signature.
+//!
+//! Errata and security advisories are indexed by the signature and
+//! list the microcode revision that fixes them, so neither is much use
+//! without the other. CPUID does not report the revision, so it is
+//! read from the first of these that has it:
+//!
+//! 1. On Linux, `/sys/devices/system/cpu/cpu0/microcode/version`.
+//! 2. On Linux, the `microcode` field of `/proc/cpuinfo`, for kernels
+//!    without the sysfs file.
+//! 3. With the `msr` feature on Linux, `IA32_BIOS_SIGN_ID` or AMD's
+//!    `PATCH_LEVEL`, which usually requires root. Hypervisors often
+//!    report zero here, which is treated as unknown.
+//!
+//! Elsewhere the revision is unknown. Each source describes the first
+//! logical processor; the kernel loads the same revision on every
+//! core, except while an update is being applied.
+
+use core::fmt;
+use std::fs;
+
+use super::{cpuinfo, master, ProcessorSignature};
+
+/// Where a microcode revision was read from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum MicrocodeSource {
+    Sysfs,
+    ProcCpuinfo,
+    Msr,
+}
+
+/// A microcode revision, as vendors' advisories write it in hex.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MicrocodeRevision {
+    pub revision: u32,
+    pub source: MicrocodeSource,
+}
+
+impl fmt::Display for MicrocodeRevision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:x}", self.revision)
+    }
+}
+
+/// The signature and microcode revision of the running processor,
+/// which together determine the errata that apply to it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProcessorRevision {
+    pub signature: ProcessorSignature,
+    /// `None` where no source in the module documentation has it.
+    pub microcode: Option<MicrocodeRevision>,
+}
+
+/// Formats the revision as "Family 6h, Model 9Eh, Stepping 10,
+/// Microcode 0xf4", or with "Microcode unknown".
+impl fmt::Display for ProcessorRevision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.microcode {
+            Some(microcode) => write!(f, "{}, Microcode {}", self.signature, microcode),
+            None => write!(f, "{}, Microcode unknown", self.signature),
+        }
+    }
+}
+
+// A place to read the revision from, in the order they are tried
+type Reader = (MicrocodeSource, fn() -> Option<u32>);
+
+/// The microcode revision of the running processor, from the first
+/// source in the module documentation that has it.
+pub fn microcode_revision() -> Option<MicrocodeRevision> {
+    let sources: [Reader; 3] = [
+        (MicrocodeSource::Sysfs, read_sysfs),
+        (MicrocodeSource::ProcCpuinfo, read_proc_cpuinfo),
+        (MicrocodeSource::Msr, read_msr),
+    ];
+    sources.iter()
+        .filter_map(|&(source, read)| read().map(|revision| MicrocodeRevision { revision, source }))
+        .next()
+}
+
+/// The signature of the running processor with its microcode revision.
+/// Returns `None` if CPUID is unavailable or reports no signature.
+pub fn processor_revision() -> Option<ProcessorRevision> {
+    Some(ProcessorRevision {
+        signature: master()?.processor_signature()?,
+        microcode: microcode_revision(),
+    })
+}
+
+fn read_sysfs() -> Option<u32> {
+    let text = fs::read_to_string("/sys/devices/system/cpu/cpu0/microcode/version").ok()?;
+    u32::from_str_radix(text.trim().trim_start_matches("0x"), 16).ok()
+}
+
+fn read_proc_cpuinfo() -> Option<u32> {
+    cpuinfo::microcode_revision(&fs::read_to_string("/proc/cpuinfo").ok()?)
+}
+
+#[cfg(all(feature = "msr", target_os = "linux"))]
+fn read_msr() -> Option<u32> {
+    let amd = matches!(master()?.vendor_id(), "AuthenticAMD" | "HygonGenuine");
+    let value = super::MsrInformation::read(0).ok()?.bios_sign_id()?;
+    let revision = if amd { value as u32 } else { (value >> 32) as u32 };
+    Some(revision).filter(|&r| r != 0)
+}
+
+#[cfg(not(all(feature = "msr", target_os = "linux")))]
+fn read_msr() -> Option<u32> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn processor_revision_pairs_signature_and_microcode() {
+        let signature = ProcessorSignature { family: 6, model: 0x9E, stepping: 10 };
+        let microcode = MicrocodeRevision { revision: 0xF4, source: MicrocodeSource::Sysfs };
+        let revision = ProcessorRevision { signature, microcode: Some(microcode) };
+        assert_eq!("Family 6h, Model 9Eh, Stepping 10, Microcode 0xf4", format!("{}", revision));
+
+        let unknown = ProcessorRevision { microcode: None, ..revision };
+        assert_eq!("Family 6h, Model 9Eh, Stepping 10, Microcode unknown", format!("{}", unknown));
+
+        if let Some(live) = processor_revision() {
+            assert_eq!(master().and_then(|m| m.processor_signature()), Some(live.signature));
+        }
+    }
+}
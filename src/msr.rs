@@ -0,0 +1,293 @@
+//! Reading model-specific registers through Linux's `/dev/cpu/*/msr`.
+//!
+//! Some questions, such as whether a processor is affected by a
+//! vulnerability or what its base frequency is, are answered by MSRs
+//! rather than CPUID. Reading them requires the `msr` kernel module and,
+//! usually, root.
+
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::os::unix::fs::FileExt;
+
+use super::power::HwpRequest;
+use super::ConfidentialComputing;
+
+const IA32_BIOS_SIGN_ID: u64 = 0x8B;
+const IA32_ARCH_CAPABILITIES: u64 = 0x10A;
+const IA32_TSX_CTRL: u64 = 0x122;
+const IA32_ENERGY_PERF_BIAS: u64 = 0x1B0;
+const IA32_HWP_REQUEST: u64 = 0x774;
+const MSR_PLATFORM_INFO: u64 = 0xCE;
+const HV_X64_MSR_TSC_FREQUENCY: u64 = 0x4000_0022;
+const HV_X64_MSR_APIC_FREQUENCY: u64 = 0x4000_0023;
+const SEV_STATUS: u64 = 0xC001_0131;
+
+// The bus clock that the platform info ratios are multiplied by, on
+// processors since Sandy Bridge
+const BUS_CLOCK_HZ: u64 = 100_000_000;
+
+/// The MSRs of one logical processor. Each is `None` if the processor
+/// does not implement it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MsrInformation {
+    arch_capabilities: Option<ArchCapabilities>,
+    tsx_ctrl: Option<TsxCtrl>,
+    platform_info: Option<PlatformInfo>,
+    energy_perf_bias: Option<u64>,
+    hwp_request: Option<HwpRequest>,
+    bios_sign_id: Option<u64>,
+    hyper_v_tsc_frequency: Option<u64>,
+    hyper_v_apic_frequency: Option<u64>,
+    sev_status: Option<SevStatus>,
+}
+
+impl MsrInformation {
+    /// Reads the MSRs of the logical processor numbered `cpu`. Fails if
+    /// the device cannot be opened, such as when the `msr` module is not
+    /// loaded or without permission.
+    pub fn read(cpu: usize) -> io::Result<MsrInformation> {
+        let file = File::open(format!("/dev/cpu/{}/msr", cpu))?;
+        let read = |msr: u64| {
+            let mut value = [0; 8];
+            file.read_exact_at(&mut value, msr).ok().map(|_| u64::from_le_bytes(value))
+        };
+
+        Ok(MsrInformation {
+            arch_capabilities: read(IA32_ARCH_CAPABILITIES).map(ArchCapabilities::from_value),
+            tsx_ctrl: read(IA32_TSX_CTRL).map(TsxCtrl::from_value),
+            platform_info: read(MSR_PLATFORM_INFO).map(PlatformInfo::from_value),
+            energy_perf_bias: read(IA32_ENERGY_PERF_BIAS),
+            hwp_request: read(IA32_HWP_REQUEST).map(HwpRequest::from_value),
+            bios_sign_id: read(IA32_BIOS_SIGN_ID),
+            hyper_v_tsc_frequency: read(HV_X64_MSR_TSC_FREQUENCY),
+            hyper_v_apic_frequency: read(HV_X64_MSR_APIC_FREQUENCY),
+            sev_status: read(SEV_STATUS).map(SevStatus::from_value),
+        })
+    }
+
+    master_attr_reader!(arch_capabilities, ArchCapabilities);
+    master_attr_reader!(tsx_ctrl, TsxCtrl);
+    master_attr_reader!(platform_info, PlatformInfo);
+    master_attr_reader!(hwp_request, HwpRequest);
+    master_attr_reader!(sev_status, SevStatus);
+
+    /// The energy-performance bias, from 0, favouring performance, to
+    /// 15, favouring energy efficiency.
+    pub fn energy_perf_bias(&self) -> Option<u32> {
+        self.energy_perf_bias.map(|value| (value & 0xF) as u32)
+    }
+
+    /// `IA32_BIOS_SIGN_ID`, whose upper half holds the microcode
+    /// revision on Intel processors. AMD's `PATCH_LEVEL` is at the same
+    /// address and holds it in the lower half.
+    pub fn bios_sign_id(&self) -> Option<u64> {
+        self.bios_sign_id
+    }
+
+    /// The TSC frequency in Hz that Hyper-V reports to its guests.
+    pub fn hyper_v_tsc_frequency_hz(&self) -> Option<u64> {
+        self.hyper_v_tsc_frequency
+    }
+
+    /// The local APIC timer frequency in Hz that Hyper-V reports to its
+    /// guests.
+    pub fn hyper_v_apic_frequency_hz(&self) -> Option<u64> {
+        self.hyper_v_apic_frequency
+    }
+}
+
+/// `IA32_ARCH_CAPABILITIES`, which reports the vulnerabilities the
+/// processor is not affected by and the mitigations it offers.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ArchCapabilities {
+    value: u64,
+}
+
+impl ArchCapabilities {
+    pub const fn from_value(value: u64) -> ArchCapabilities {
+        ArchCapabilities { value }
+    }
+
+    pub fn value(self) -> u64 {
+        self.value
+    }
+}
+
+leaf! {
+    impl ArchCapabilities {
+        raw { value }
+
+        flags {
+            value => {
+                0 => rdcl_no,
+                1 => ibrs_all,
+                2 => rsba,
+                3 => skip_l1dfl_vmentry,
+                4 => ssb_no,
+                5 => mds_no,
+                6 => if_pschange_mc_no,
+                7 => tsx_ctrl,
+                8 => taa_no,
+                // 9-12 - not yet decoded
+                13 => sbdr_ssdp_no,
+                14 => fbsdp_no,
+                15 => psdp_no,
+                // 16 - reserved
+                17 => fb_clear,
+                // 18 - not yet decoded
+                19 => rrsba,
+                20 => bhi_no,
+                // 21-23 - not yet decoded
+                24 => pbrsb_no,
+                // 25 - not yet decoded
+                26 => gds_no,
+                27 => rfds_no
+            }
+        }
+    }
+}
+
+/// `IA32_TSX_CTRL`, which disables Transactional Synchronization
+/// Extensions as a mitigation for TSX asynchronous abort.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TsxCtrl {
+    value: u64,
+}
+
+impl TsxCtrl {
+    pub const fn from_value(value: u64) -> TsxCtrl {
+        TsxCtrl { value }
+    }
+
+    pub fn value(self) -> u64 {
+        self.value
+    }
+}
+
+leaf! {
+    impl TsxCtrl {
+        raw { value }
+
+        flags {
+            value => {
+                0 => rtm_disable,
+                1 => tsx_cpuid_clear
+            }
+        }
+    }
+}
+
+/// `SEV_STATUS`, which an AMD guest reads to learn which of SEV,
+/// SEV-ES and SEV-SNP protect it. Unlike CPUID, the hypervisor cannot
+/// intercept it.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SevStatus {
+    value: u64,
+}
+
+impl SevStatus {
+    pub const fn from_value(value: u64) -> SevStatus {
+        SevStatus { value }
+    }
+
+    pub fn value(self) -> u64 {
+        self.value
+    }
+
+    /// The most complete protection that is active, if any.
+    pub fn confidential_computing(self) -> Option<ConfidentialComputing> {
+        if self.sev_snp_enabled() {
+            Some(ConfidentialComputing::SevSnp)
+        } else if self.sev_es_enabled() {
+            Some(ConfidentialComputing::SevEs)
+        } else if self.sev_enabled() {
+            Some(ConfidentialComputing::Sev)
+        } else {
+            None
+        }
+    }
+}
+
+leaf! {
+    impl SevStatus {
+        raw { value }
+
+        flags {
+            value => {
+                0 => sev_enabled,
+                1 => sev_es_enabled,
+                2 => sev_snp_enabled
+            }
+        }
+    }
+}
+
+/// `MSR_PLATFORM_INFO`, which holds the frequency ratios of the
+/// processor.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PlatformInfo {
+    value: u64,
+}
+
+impl PlatformInfo {
+    pub const fn from_value(value: u64) -> PlatformInfo {
+        PlatformInfo { value }
+    }
+
+    pub fn value(self) -> u64 {
+        self.value
+    }
+
+    /// The base frequency, assuming a 100 MHz bus clock.
+    pub fn base_frequency_hz(self) -> u64 {
+        self.max_non_turbo_ratio() as u64 * BUS_CLOCK_HZ
+    }
+}
+
+leaf! {
+    impl PlatformInfo {
+        raw { value }
+
+        /// The ratio of the base frequency to the bus clock.
+        pub fn max_non_turbo_ratio(self) -> u32 {
+            ((self.value >> 8) & 0xFF) as u32
+        }
+
+        /// The ratio of the lowest operating frequency to the bus clock.
+        pub fn max_efficiency_ratio(self) -> u32 {
+            ((self.value >> 40) & 0xFF) as u32
+        }
+
+        flags {
+            value => {
+                23 => ppin_cap,
+                28 => programmable_ratio_limits,
+                29 => programmable_tdp_limits
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn msr_values_decode() {
+        let caps = ArchCapabilities::from_value(1 | 1 << 5 | 1 << 27);
+        assert!(caps.rdcl_no() && caps.mds_no() && caps.rfds_no());
+        assert!(!caps.ibrs_all());
+
+        let info = PlatformInfo::from_value(0x0000_0C00_0000_2400);
+        assert_eq!(36, info.max_non_turbo_ratio());
+        assert_eq!(12, info.max_efficiency_ratio());
+        assert_eq!(3_600_000_000, info.base_frequency_hz());
+
+        assert_eq!(None, SevStatus::from_value(0).confidential_computing());
+        assert_eq!(Some(ConfidentialComputing::Sev), SevStatus::from_value(1).confidential_computing());
+        assert_eq!(Some(ConfidentialComputing::SevEs), SevStatus::from_value(0b11).confidential_computing());
+        assert_eq!(Some(ConfidentialComputing::SevSnp), SevStatus::from_value(0b111).confidential_computing());
+    }
+}
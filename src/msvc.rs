@@ -0,0 +1,38 @@
+//! The query routines for MSVC targets, through the `core::arch`
+//! intrinsics, which compile to the same instructions as the inline
+//! assembly used elsewhere.
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86 as arch;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64 as arch;
+
+pub fn cpuid_count(leaf: u32, subleaf: u32) -> (u32, u32, u32, u32) {
+    let r = unsafe { arch::__cpuid_count(leaf, subleaf) };
+    (r.eax, r.ebx, r.ecx, r.edx)
+}
+
+// Faults unless OSXSAVE is set, which the callers check.
+pub fn xgetbv(xcr: u32) -> u64 {
+    #[target_feature(enable = "xsave")]
+    unsafe fn read(xcr: u32) -> u64 {
+        arch::_xgetbv(xcr)
+    }
+
+    unsafe { read(xcr) }
+}
+
+#[cfg(target_arch = "x86")]
+pub fn has_cpuid() -> bool {
+    arch::has_cpuid()
+}
+
+pub fn rdtsc() -> u64 {
+    unsafe { arch::_rdtsc() }
+}
+
+pub fn rdtscp() -> (u64, u32) {
+    let mut aux = 0;
+    let tsc = unsafe { arch::__rdtscp(&mut aux) };
+    (tsc, aux)
+}
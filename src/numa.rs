@@ -0,0 +1,164 @@
+//! Which NUMA node each logical processor belongs to, next to where
+//! CPUID places it in the topology.
+//!
+//! CPUID says nothing about memory, so the nodes come from the
+//! operating system: sysfs on Linux and `GetNumaProcessorNode` on
+//! Windows. On Linux, the processors' x2APIC IDs are captured with
+//! `per_core`, so that each one's socket, core and thread can be read
+//! alongside its node.
+
+use std::collections::BTreeMap;
+
+/// Where a logical processor sits, by the operating system's NUMA
+/// nodes and by the CPUID topology. The topology is `None` where it
+/// could not be captured for the processor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CpuPlacement {
+    pub node: u32,
+    pub x2apic_id: Option<u32>,
+    pub socket: Option<u32>,
+    pub core: Option<u32>,
+    pub smt: Option<u32>,
+}
+
+/// The NUMA node of each logical processor, by its index. Returns
+/// `None` if the operating system does not report them, such as on a
+/// Linux kernel built without NUMA support.
+pub fn numa_nodes() -> Option<BTreeMap<usize, u32>> {
+    os::numa_nodes()
+}
+
+/// The NUMA node and CPUID topology of each logical processor, by its
+/// index.
+pub fn numa_map() -> Option<BTreeMap<usize, CpuPlacement>> {
+    let nodes = numa_nodes()?;
+    let topology = os::topology();
+
+    let map = nodes.into_iter().map(|(cpu, node)| {
+        let placement = topology.as_ref().and_then(|t| t.get(&cpu));
+        (cpu, CpuPlacement {
+            node,
+            x2apic_id: placement.map(|p| p.0),
+            socket: placement.map(|p| p.1),
+            core: placement.map(|p| p.2),
+            smt: placement.map(|p| p.3),
+        })
+    }).collect();
+    Some(map)
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) mod os {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::Path;
+
+    use super::super::per_core::{parse_cpu_list, per_core};
+
+    pub fn numa_nodes() -> Option<BTreeMap<usize, u32>> {
+        read_nodes(Path::new("/sys/devices/system/node"))
+    }
+
+    // Each node is a directory, such as "node1", listing its
+    // processors in "cpulist"
+    pub fn read_nodes(root: &Path) -> Option<BTreeMap<usize, u32>> {
+        let mut nodes = BTreeMap::new();
+        for entry in fs::read_dir(root).ok()? {
+            let entry = entry.ok()?;
+            let name = entry.file_name();
+            let node = match name.to_str().and_then(|n| n.strip_prefix("node")) {
+                Some(number) => match number.parse() {
+                    Ok(node) => node,
+                    Err(_) => continue,
+                },
+                None => continue,
+            };
+            let list = fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            for cpu in parse_cpu_list(list.trim())? {
+                nodes.insert(cpu, node);
+            }
+        }
+
+        if nodes.is_empty() {
+            None
+        } else {
+            Some(nodes)
+        }
+    }
+
+    // The x2APIC, socket, core and thread IDs of each processor
+    pub fn topology() -> Option<BTreeMap<usize, (u32, u32, u32, u32)>> {
+        let cores = per_core()?;
+        let map = cores.into_iter().filter_map(|(cpu, info)| {
+            let topology = info.extended_topology()?;
+            let x2apic_id = topology.levels().first()?.x2apic_id();
+            Some((cpu, (x2apic_id,
+                        topology.package_id(x2apic_id),
+                        topology.core_id(x2apic_id),
+                        topology.smt_id(x2apic_id))))
+        }).collect();
+        Some(map)
+    }
+}
+
+#[cfg(windows)]
+pub(crate) mod os {
+    use std::collections::BTreeMap;
+
+    // Only the processors of the calling thread's processor group, the
+    // only ones `GetNumaProcessorNode` can name
+    const MAX_PROCESSORS: u8 = 64;
+    // What `GetNumaProcessorNode` reports for a processor that does
+    // not exist
+    const NO_NODE: u8 = 0xFF;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetNumaProcessorNode(processor: u8, node_number: *mut u8) -> i32;
+    }
+
+    pub fn numa_nodes() -> Option<BTreeMap<usize, u32>> {
+        let mut nodes = BTreeMap::new();
+        for processor in 0..MAX_PROCESSORS {
+            let mut node = NO_NODE;
+            let found = unsafe { GetNumaProcessorNode(processor, &mut node) };
+            if found != 0 && node != NO_NODE {
+                nodes.insert(processor as usize, node as u32);
+            }
+        }
+
+        if nodes.is_empty() {
+            None
+        } else {
+            Some(nodes)
+        }
+    }
+
+    // Capturing each processor's x2APIC ID needs pinning, which is only
+    // implemented for Linux
+    pub fn topology() -> Option<BTreeMap<usize, (u32, u32, u32, u32)>> {
+        None
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numa_nodes_are_read_from_each_node_directory() {
+        use std::fs;
+
+        let root = std::env::temp_dir().join(format!("cupid-numa-{}", std::process::id()));
+        for &(node, list) in &[("node0", "0-1,4\n"), ("node1", "2-3\n")] {
+            fs::create_dir_all(root.join(node)).unwrap();
+            fs::write(root.join(node).join("cpulist"), list).unwrap();
+        }
+        fs::create_dir_all(root.join("power")).unwrap();
+
+        let nodes = os::read_nodes(&root);
+        fs::remove_dir_all(&root).unwrap();
+        let nodes: std::vec::Vec<_> = nodes.unwrap().into_iter().collect();
+        assert_eq!(vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 0)], nodes);
+    }
+}
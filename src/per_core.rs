@@ -0,0 +1,249 @@
+//! Capturing a description of every logical processor.
+//!
+//! CPUID describes the processor the calling thread is running on.
+//! Hybrid and multi-socket systems can report different values on
+//! different cores, such as the core type and APIC IDs, so a thread is
+//! pinned to each processor in turn. Pinning and querying hundreds of
+//! processors one after another is slow, so several short-lived
+//! threads share the work.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::string::String;
+use std::thread;
+use std::vec::Vec;
+
+#[cfg(feature = "caches")]
+use super::CacheType;
+use super::{has_cpuid, CoreType, FeatureSet, Master, Native};
+
+// The size of glibc's `cpu_set_t`, enough for 1024 processors
+const MASK_BYTES: usize = 128;
+
+// The most threads capturing processors at once. Each is busy for
+// little more than its migrations, so more would mostly add overhead.
+const MAX_THREADS: usize = 16;
+
+extern "C" {
+    fn sched_getaffinity(pid: i32, size: usize, mask: *mut u8) -> i32;
+    fn sched_setaffinity(pid: i32, size: usize, mask: *const u8) -> i32;
+}
+
+/// A description of each online logical processor, by its index.
+/// Processors the thread is not allowed to run on are left out.
+/// Returns `None` if the online processors cannot be determined.
+///
+/// The calling thread's affinity is left alone, as the processors are
+/// visited by threads of their own, several at once.
+pub fn per_core() -> Option<BTreeMap<usize, Master>> {
+    if !has_cpuid() {
+        return None;
+    }
+    let online: Vec<usize> = online_cpus()?.into_iter()
+        .filter(|&cpu| cpu < MASK_BYTES * 8)
+        .collect();
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_THREADS);
+    let share = online.len().div_ceil(threads).max(1);
+    let captures: Vec<_> = online.chunks(share).map(|cpus| {
+        let owned = cpus.to_vec();
+        // Fall back to the calling thread if no thread can be spawned
+        thread::Builder::new()
+            .name("cupid-per-core".into())
+            .spawn(move || capture(&owned))
+            .map_err(|_| capture_here(cpus))
+    }).collect();
+
+    let mut cores = BTreeMap::new();
+    for capture in captures {
+        match capture {
+            Ok(thread) => cores.extend(thread.join().ok()?),
+            Err(captured) => cores.extend(captured),
+        }
+    }
+    Some(cores)
+}
+
+// Pins the calling thread to each of `cpus` in turn, describing those
+// it may run on
+fn capture(cpus: &[usize]) -> Vec<(usize, Master)> {
+    cpus.iter().filter_map(|&cpu| {
+        let mut mask = [0u8; MASK_BYTES];
+        mask[cpu / 8] = 1 << (cpu % 8);
+        if unsafe { sched_setaffinity(0, MASK_BYTES, mask.as_ptr()) } == 0 {
+            Some((cpu, Master::new(&Native)))
+        } else {
+            None
+        }
+    }).collect()
+}
+
+// Like `capture`, restoring the calling thread's affinity afterwards
+fn capture_here(cpus: &[usize]) -> Vec<(usize, Master)> {
+    let mut original = [0u8; MASK_BYTES];
+    if unsafe { sched_getaffinity(0, MASK_BYTES, original.as_mut_ptr()) } != 0 {
+        return Vec::new();
+    }
+
+    let captured = capture(cpus);
+    unsafe { sched_setaffinity(0, MASK_BYTES, original.as_ptr()) };
+    captured
+}
+
+/// The features supported by every online logical processor, which
+/// are the ones safe to use in a thread that may migrate between them.
+pub fn common_features() -> Option<FeatureSet> {
+    per_core()?.values()
+        .map(FeatureSet::from)
+        .fold(None, |common, f| Some(common.map_or(f, |c: FeatureSet| c.intersection(&f))))
+}
+
+/// The logical processors of a hybrid processor, by the kind of core.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HybridLayout {
+    pub performance: Vec<usize>,
+    pub efficiency: Vec<usize>,
+}
+
+/// Which online logical processors are performance cores and which are
+/// efficiency cores, so that latency-critical work can be kept apart
+/// from background work. Returns `None` if the processor is not hybrid.
+pub fn hybrid_layout() -> Option<HybridLayout> {
+    let mut layout = HybridLayout::default();
+    for (cpu, info) in per_core()? {
+        match info.native_model_identification().and_then(|n| n.core_type()) {
+            Some(CoreType::Performance) => layout.performance.push(cpu),
+            Some(CoreType::Efficiency) => layout.efficiency.push(cpu),
+            None => {},
+        }
+    }
+
+    if layout.performance.is_empty() && layout.efficiency.is_empty() {
+        None
+    } else {
+        Some(layout)
+    }
+}
+
+/// One cache and the logical processors that share it.
+#[cfg(feature = "caches")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SharedCache {
+    pub level: u32,
+    pub cache_type: Option<CacheType>,
+    pub cpus: Vec<usize>,
+}
+
+/// Which online logical processors share each cache, such as the
+/// threads of a core sharing its L2, or the cores of a CCX sharing its
+/// L3. Each cache is listed once, ordered by level and type. Returns
+/// `None` if the online processors cannot be determined.
+#[cfg(feature = "caches")]
+pub fn cache_sharing() -> Option<Vec<SharedCache>> {
+    Some(share_caches(&per_core()?))
+}
+
+#[cfg(feature = "caches")]
+pub(crate) fn share_caches(cores: &BTreeMap<usize, Master>) -> Vec<SharedCache> {
+    // Hybrid processors share caches among different numbers of
+    // processors on each kind of core, so the count is part of the key
+    let mut caches = BTreeMap::new();
+    for (&cpu, info) in cores {
+        let apic_id = match info.apic_id() {
+            Some(id) => id,
+            None => continue,
+        };
+        let parameters = info.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]);
+        for cache in parameters {
+            let key = (cache.level(), cache.eax() & 0x1F, cache.max_logical_processors_sharing(),
+                       cache.sharing_id(apic_id));
+            caches.entry(key)
+                .or_insert_with(|| SharedCache {
+                    level: cache.level(),
+                    cache_type: cache.cache_type(),
+                    cpus: Vec::new(),
+                })
+                .cpus.push(cpu);
+        }
+    }
+    caches.into_values().collect()
+}
+
+// The kernel lists the online processors as ranges, such as "0-3,6"
+fn online_cpus() -> Option<Vec<usize>> {
+    let mut text = String::new();
+    File::open("/sys/devices/system/cpu/online")
+        .and_then(|mut f| f.read_to_string(&mut text))
+        .ok()?;
+    parse_cpu_list(text.trim())
+}
+
+pub(crate) fn parse_cpu_list(text: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in text.split(',').filter(|r| !r.is_empty()) {
+        let mut ends = range.splitn(2, '-');
+        let start: usize = ends.next()?.parse().ok()?;
+        let end = match ends.next() {
+            Some(end) => end.parse().ok()?,
+            None => start,
+        };
+        cpus.extend(start..=end);
+    }
+    Some(cpus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "caches")]
+    use genuine_intel_dump;
+    use master;
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    fn per_core_describes_each_processor() {
+        let cores = per_core().expect("online processors");
+        assert!(!cores.is_empty());
+        let info = master().unwrap();
+        assert!(cores.values().all(|m| m.vendor_id() == info.vendor_id()));
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    fn common_features_are_supported_everywhere() {
+        let common = common_features().expect("online processors");
+        for info in per_core().unwrap().values() {
+            assert!(common.is_subset(&FeatureSet::from(info)));
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    fn hybrid_layout_only_on_hybrid_processors() {
+        if let Some(layout) = hybrid_layout() {
+            assert!(master().unwrap().hybrid());
+            assert!(layout.performance.iter().all(|cpu| !layout.efficiency.contains(cpu)));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "caches")]
+    fn cache_sharing_groups_processors_by_apic_id() {
+        // Four threads, two per core: each core has its own L1d and the L2
+        // is shared by all
+        let cores: std::collections::BTreeMap<usize, Master> = (0..4).map(|cpu| {
+            let mut dump = genuine_intel_dump(0xB);
+            dump.insert(4, 0, (0x0000_4121, 0x01C0_003F, 0x3F, 0));
+            dump.insert(4, 1, (0x0000_C143, 0x03C0_003F, 0x3FF, 0));
+            dump.insert(0xB, 0, (1, 2, 0x100, cpu));
+            dump.insert(0xB, 1, (2, 4, 0x201, cpu));
+            (cpu as usize, Master::from_dump(&dump))
+        }).collect();
+
+        let caches = share_caches(&cores);
+        let groups: std::vec::Vec<_> = caches.iter().map(|c| (c.level, c.cpus.clone())).collect();
+        assert_eq!(vec![(1, vec![0, 1]), (1, vec![2, 3]), (2, vec![0, 1, 2, 3])], groups);
+        assert_eq!(Some(CacheType::Data), caches[0].cache_type);
+    }
+}
@@ -0,0 +1,157 @@
+//! The processor's energy-performance controls: the energy-performance
+//! bias (EPB) and hardware-controlled performance states (HWP).
+//!
+//! Leaf 6 reports which controls exist. Their current settings are
+//! MSRs, `IA32_ENERGY_PERF_BIAS` and `IA32_HWP_REQUEST`, which are only
+//! read by `power_management` with the `msr` feature on Linux.
+
+use core::fmt;
+
+use super::{master, Feature, Master};
+
+/// `IA32_HWP_REQUEST`, the performance range and preference that the
+/// operating system asks HWP for. Performance levels are on the scale
+/// of `IA32_HWP_CAPABILITIES`, specific to each processor.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HwpRequest {
+    value: u64,
+}
+
+impl HwpRequest {
+    pub const fn from_value(value: u64) -> HwpRequest {
+        HwpRequest { value }
+    }
+
+    pub fn value(self) -> u64 {
+        self.value
+    }
+
+    fn low_bits(self, start: u32, width: u32) -> u32 {
+        ((self.value >> start) & ((1 << width) - 1)) as u32
+    }
+}
+
+leaf! {
+    impl HwpRequest {
+        raw { value }
+
+        pub fn minimum_performance(self) -> u32 {
+            self.low_bits(0, 8)
+        }
+
+        pub fn maximum_performance(self) -> u32 {
+            self.low_bits(8, 8)
+        }
+
+        /// The performance to aim for, or zero to leave it to the
+        /// processor.
+        pub fn desired_performance(self) -> u32 {
+            self.low_bits(16, 8)
+        }
+
+        /// From 0, favouring performance, to 255, favouring energy
+        /// efficiency.
+        pub fn energy_performance_preference(self) -> u32 {
+            self.low_bits(24, 8)
+        }
+
+        /// The raw activity window encoding, or zero to leave it to the
+        /// processor.
+        pub fn activity_window(self) -> u32 {
+            self.low_bits(32, 10)
+        }
+
+        flags {
+            value => {
+                42 => package_control
+            }
+        }
+    }
+}
+
+/// The energy-performance controls the processor offers and, where
+/// they could be read, their current settings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PowerManagement {
+    /// `IA32_ENERGY_PERF_BIAS` exists.
+    pub epb: bool,
+    /// Hardware-controlled performance states can be enabled.
+    pub hwp: bool,
+    /// HWP requests can carry an energy-performance preference, which
+    /// takes the place of the EPB.
+    pub hwp_energy_performance_preference: bool,
+    /// HWP requests can set an activity window.
+    pub hwp_activity_window: bool,
+    /// The current bias, from 0, favouring performance, to 15,
+    /// favouring energy efficiency.
+    pub energy_perf_bias: Option<u32>,
+    /// The current HWP request of the first logical processor.
+    pub hwp_request: Option<HwpRequest>,
+}
+
+impl Master {
+    /// The energy-performance controls the processor offers. The
+    /// current settings are left as `None`; `power_management` reads
+    /// them with the `msr` feature.
+    pub fn power_management(&self) -> PowerManagement {
+        PowerManagement {
+            epb: self.supports(Feature::PerformanceEnergyBias),
+            hwp: self.supports(Feature::Hwp),
+            hwp_energy_performance_preference: self.supports(Feature::HwpEnergyPerformancePreference),
+            hwp_activity_window: self.supports(Feature::HwpActivityWindow),
+            energy_perf_bias: None,
+            hwp_request: None,
+        }
+    }
+}
+
+/// The energy-performance controls of the running processor. With the
+/// `msr` feature on Linux, the current settings of those it offers are
+/// read from the first logical processor, which usually requires root.
+pub fn power_management() -> Option<PowerManagement> {
+    let mut power = master()?.power_management();
+    read_settings(&mut power);
+    Some(power)
+}
+
+#[cfg(all(feature = "msr", target_os = "linux"))]
+fn read_settings(power: &mut PowerManagement) {
+    if !power.epb && !power.hwp {
+        return;
+    }
+    if let Ok(msrs) = super::MsrInformation::read(0) {
+        if power.epb {
+            power.energy_perf_bias = msrs.energy_perf_bias();
+        }
+        if power.hwp {
+            power.hwp_request = msrs.hwp_request().cloned();
+        }
+    }
+}
+
+#[cfg(not(all(feature = "msr", target_os = "linux")))]
+fn read_settings(_power: &mut PowerManagement) {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn power_management_reports_epb_and_hwp() {
+        let mut dump = genuine_intel_dump(6);
+        dump.insert(6, 0, ((1 << 7) | (1 << 10), 0, 1 << 3, 0));
+        let power = Master::from_dump(&dump).power_management();
+        assert!(power.epb && power.hwp && power.hwp_energy_performance_preference);
+        assert!(!power.hwp_activity_window);
+        assert_eq!(None, power.hwp_request);
+
+        let request = HwpRequest::from_value(0x0000_0400_8000_2A01);
+        assert_eq!((1, 0x2A, 0), (request.minimum_performance(),
+                                  request.maximum_performance(),
+                                  request.desired_performance()));
+        assert_eq!(0x80, request.energy_performance_preference());
+        assert!(request.package_control() && request.activity_window() == 0);
+    }
+}
@@ -0,0 +1,356 @@
+//! Named baseline profiles, such as a fleet's oldest processor, to
+//! compare the running processor against.
+//!
+//! Each profile lists the instruction set extensions of a processor
+//! generation or a psABI microarchitecture level, by extending an older
+//! profile. Features that depend on the platform or the operating
+//! system, such as virtualization and power management, are left out,
+//! so a processor always matches the profiles of its own generation.
+
+use core::fmt;
+
+use super::{Feature, FeatureSet, Master, MicroarchLevel};
+
+/// A named baseline feature set.
+#[derive(Debug)]
+pub struct Profile {
+    name: &'static str,
+    base: Option<&'static Profile>,
+    adds: &'static [Feature],
+}
+
+static X86_64: Profile = Profile {
+    name: "x86-64",
+    base: None,
+    adds: MicroarchLevel::V1.features(),
+};
+
+static X86_64_V2: Profile = Profile {
+    name: "x86-64-v2",
+    base: Some(&X86_64),
+    adds: MicroarchLevel::V2.features(),
+};
+
+// The level also requires OSXSAVE, which the operating system sets
+static X86_64_V3: Profile = Profile {
+    name: "x86-64-v3",
+    base: Some(&X86_64_V2),
+    adds: &[
+        Feature::Avx,
+        Feature::Avx2,
+        Feature::Bmi1,
+        Feature::Bmi2,
+        Feature::F16c,
+        Feature::Fma,
+        Feature::Lzcnt,
+        Feature::Movbe,
+    ],
+};
+
+static X86_64_V4: Profile = Profile {
+    name: "x86-64-v4",
+    base: Some(&X86_64_V3),
+    adds: MicroarchLevel::V4.features(),
+};
+
+static NEHALEM: Profile = Profile {
+    name: "Nehalem",
+    base: Some(&X86_64_V2),
+    adds: &[],
+};
+
+static WESTMERE: Profile = Profile {
+    name: "Westmere",
+    base: Some(&NEHALEM),
+    adds: &[Feature::Aesni, Feature::Pclmulqdq],
+};
+
+static SANDY_BRIDGE: Profile = Profile {
+    name: "Sandy Bridge",
+    base: Some(&WESTMERE),
+    adds: &[Feature::Avx, Feature::Xsave, Feature::Xsaveopt],
+};
+
+static IVY_BRIDGE: Profile = Profile {
+    name: "Ivy Bridge",
+    base: Some(&SANDY_BRIDGE),
+    adds: &[
+        Feature::EnhancedRepMovsbStosb,
+        Feature::F16c,
+        Feature::Fsgsbase,
+        Feature::Rdrand,
+    ],
+};
+
+static HASWELL: Profile = Profile {
+    name: "Haswell",
+    base: Some(&IVY_BRIDGE),
+    adds: &[
+        Feature::Avx2,
+        Feature::Bmi1,
+        Feature::Bmi2,
+        Feature::Fma,
+        Feature::Invpcid,
+        Feature::Lzcnt,
+        Feature::Movbe,
+    ],
+};
+
+static BROADWELL: Profile = Profile {
+    name: "Broadwell",
+    base: Some(&HASWELL),
+    adds: &[Feature::Adx, Feature::Prefetchw, Feature::Rdseed, Feature::Smap],
+};
+
+static SKYLAKE: Profile = Profile {
+    name: "Skylake",
+    base: Some(&BROADWELL),
+    adds: &[Feature::Clflushopt, Feature::Xsavec, Feature::Xsaves],
+};
+
+static SKYLAKE_X: Profile = Profile {
+    name: "Skylake-X",
+    base: Some(&SKYLAKE),
+    adds: &[
+        Feature::Avx512f,
+        Feature::Avx512bw,
+        Feature::Avx512cd,
+        Feature::Avx512dq,
+        Feature::Avx512vl,
+        Feature::Clwb,
+    ],
+};
+
+static ICE_LAKE: Profile = Profile {
+    name: "Ice Lake",
+    base: Some(&SKYLAKE_X),
+    adds: &[
+        Feature::Avx512Bitalg,
+        Feature::Avx512Ifma,
+        Feature::Avx512Vbmi,
+        Feature::Avx512Vbmi2,
+        Feature::Avx512Vnni,
+        Feature::Avx512Vpopcntdq,
+        Feature::Gfni,
+        Feature::Sha,
+        Feature::Vaes,
+        Feature::Vpclmulqdq,
+    ],
+};
+
+static SAPPHIRE_RAPIDS: Profile = Profile {
+    name: "Sapphire Rapids",
+    base: Some(&ICE_LAKE),
+    adds: &[
+        Feature::AmxBf16,
+        Feature::AmxInt8,
+        Feature::AmxTile,
+        Feature::Avx512Bf16,
+        Feature::Avx512Fp16,
+        Feature::AvxVnni,
+        Feature::Waitpkg,
+    ],
+};
+
+static ZEN: Profile = Profile {
+    name: "Zen",
+    base: Some(&X86_64_V3),
+    adds: &[
+        Feature::Adx,
+        Feature::Aesni,
+        Feature::Clflushopt,
+        Feature::Clzero,
+        Feature::Fsgsbase,
+        Feature::Pclmulqdq,
+        Feature::Prefetchw,
+        Feature::Rdrand,
+        Feature::Rdseed,
+        Feature::Sha,
+        Feature::Smap,
+        Feature::Sse4a,
+        Feature::Xsave,
+        Feature::Xsavec,
+        Feature::Xsaveopt,
+        Feature::Xsaves,
+    ],
+};
+
+static ZEN_2: Profile = Profile {
+    name: "Zen 2",
+    base: Some(&ZEN),
+    adds: &[Feature::Clwb, Feature::Wbnoinvd],
+};
+
+static ZEN_3: Profile = Profile {
+    name: "Zen 3",
+    base: Some(&ZEN_2),
+    adds: &[Feature::Invpcid],
+};
+
+static ZEN_4: Profile = Profile {
+    name: "Zen 4",
+    base: Some(&ZEN_3),
+    adds: &[
+        Feature::Avx512f,
+        Feature::Avx512bw,
+        Feature::Avx512cd,
+        Feature::Avx512dq,
+        Feature::Avx512vl,
+        Feature::Avx512Bf16,
+        Feature::Avx512Bitalg,
+        Feature::Avx512Ifma,
+        Feature::Avx512Vbmi,
+        Feature::Avx512Vbmi2,
+        Feature::Avx512Vnni,
+        Feature::Avx512Vpopcntdq,
+    ],
+};
+
+impl Profile {
+    /// Every profile, oldest first within each family.
+    pub const ALL: &'static [&'static Profile] = &[
+        &X86_64,
+        &X86_64_V2,
+        &X86_64_V3,
+        &X86_64_V4,
+        &NEHALEM,
+        &WESTMERE,
+        &SANDY_BRIDGE,
+        &IVY_BRIDGE,
+        &HASWELL,
+        &BROADWELL,
+        &SKYLAKE,
+        &SKYLAKE_X,
+        &ICE_LAKE,
+        &SAPPHIRE_RAPIDS,
+        &ZEN,
+        &ZEN_2,
+        &ZEN_3,
+        &ZEN_4,
+    ];
+
+    /// The profile called `name`, ignoring case, spaces and hyphens,
+    /// so that "zen3" finds "Zen 3".
+    pub fn named(name: &str) -> Option<&'static Profile> {
+        Profile::ALL.iter().cloned().find(|p| same_name(p.name, name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The profile this one extends, if any.
+    pub fn base(&self) -> Option<&'static Profile> {
+        self.base
+    }
+
+    /// Every feature of the profile, including those of the profiles it
+    /// extends.
+    pub fn features(&self) -> FeatureSet {
+        let mut features: FeatureSet = self.adds.iter().cloned().collect();
+        let mut base = self.base;
+        while let Some(profile) = base {
+            features.extend(profile.adds.iter().cloned());
+            base = profile.base;
+        }
+        features
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    let significant = |c: &char| *c != ' ' && *c != '-';
+    a.chars().filter(significant)
+        .map(|c| c.to_ascii_lowercase())
+        .eq(b.chars().filter(significant).map(|c| c.to_ascii_lowercase()))
+}
+
+// The features any profile mentions. Only these count as exceeding a
+// profile, since the processor has many more that no profile describes.
+pub(crate) fn profiled() -> FeatureSet {
+    Profile::ALL.iter().fold(FeatureSet::new(), |all, p| all.union(&p.features()))
+}
+
+/// How the processor differs from a profile.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProfileComparison {
+    /// The features of the profile the processor lacks.
+    pub missing: FeatureSet,
+    /// The features the processor has beyond the profile, among those
+    /// that any profile mentions.
+    pub extra: FeatureSet,
+}
+
+impl ProfileComparison {
+    /// Whether the processor has every feature of the profile, so that
+    /// code built for it will run.
+    pub fn meets_baseline(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl Master {
+    /// Compares the processor with the profile called `name`, as found
+    /// by `Profile::named`. Returns `None` if there is no such profile.
+    ///
+    /// ```
+    /// if let Some(info) = cupid::master() {
+    ///     if let Some(comparison) = info.compare_to_profile("Haswell") {
+    ///         println!("lacks {}, exceeds with {}", comparison.missing, comparison.extra);
+    ///     }
+    /// }
+    /// ```
+    pub fn compare_to_profile(&self, name: &str) -> Option<ProfileComparison> {
+        let baseline = Profile::named(name)?.features();
+        let present = FeatureSet::from(self).intersection(&profiled());
+        Some(ProfileComparison {
+            missing: baseline.difference(&present),
+            extra: present.difference(&baseline),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn profiles_compare_missing_and_extra_features() {
+        // Nehalem with AES-NI
+        let mut dump = genuine_intel_dump(0xB);
+        dump.insert(1, 0, (0x0001_06A5, 0, 0x0298_E3BD, 0xBFEB_FBFF));
+        dump.insert(0x8000_0000, 0, (0x8000_0008, 0, 0, 0));
+        dump.insert(0x8000_0001, 0, (0, 0, 0x0000_0001, 0x2810_0800));
+        let info = Master::from_dump(&dump);
+
+        let v2 = info.compare_to_profile("x86-64-v2").unwrap();
+        assert!(v2.meets_baseline());
+        assert_eq!(1, v2.extra.len());
+        assert!(v2.extra.contains(Feature::Aesni));
+
+        let haswell = info.compare_to_profile("haswell").unwrap();
+        assert!(!haswell.meets_baseline());
+        assert!(haswell.missing.contains(Feature::Avx2));
+        assert!(!haswell.missing.contains(Feature::Aesni));
+        assert!(haswell.extra.is_empty());
+
+        assert_eq!(Some("Zen 3"), Profile::named("zen3").map(|p| p.name()));
+        let v4 = Profile::named("x86-64-v4").unwrap().features();
+        assert!(v4.is_subset(&Profile::named("Zen 4").unwrap().features()));
+        assert!(v4.is_subset(&Profile::named("Skylake-X").unwrap().features()));
+        assert!(info.compare_to_profile("Pentium").is_none());
+
+        // OSXSAVE reflects the operating system, not the processor
+        assert!(!Profile::named("Sandy Bridge").unwrap().features().contains(Feature::Osxsave));
+        let ice_lake = Profile::named("Ice Lake").unwrap().features();
+        assert!(ice_lake.contains(Feature::Gfni) && ice_lake.contains(Feature::Vaes));
+        assert!(ice_lake.contains(Feature::Vpclmulqdq));
+    }
+}
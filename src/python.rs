@@ -0,0 +1,125 @@
+//! A Python module, `cupid`, whose `snapshot()` returns the decoded
+//! information as a dict:
+//!
+//! ```text
+//! >>> import cupid
+//! >>> info = cupid.snapshot()
+//! >>> info["brand"], info["features"]["avx2"]
+//! ('Intel(R) Core(TM) i7-8700K CPU @ 3.70GHz', True)
+//! ```
+//!
+//! The dict has the same keys as the [`json`](../json/index.html)
+//! rendering, with `None` for values the processor does not report,
+//! and `snapshot()` itself returns `None` without CPUID. To build the
+//! extension, e.g. `cargo rustc --release --features extension-module
+//! --crate-type cdylib` and rename the library to `cupid.so`. The
+//! `python` feature alone links against libpython, as tests need.
+
+// `pyfunction` converts the error type of `snapshot` into itself, in
+// code of its own beside the function
+#![allow(clippy::useless_conversion)]
+
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+use super::{master, CacheType, Master};
+
+/// The identity, features, caches and address sizes of the processor.
+#[pyfunction]
+fn snapshot(py: Python) -> PyResult<Option<Bound<PyDict>>> {
+    match master() {
+        Some(info) => to_dict(py, &info).map(Some),
+        None => Ok(None),
+    }
+}
+
+#[pymodule]
+fn cupid(module: &Bound<PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(self::snapshot, module)?)
+}
+
+fn to_dict<'py>(py: Python<'py>, info: &Master) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("vendor", info.vendor_id())?;
+    dict.set_item("brand", info.brand_string().map(|b| b.trim()))?;
+
+    let version = match info.version_information() {
+        Some(vi) => {
+            let version = PyDict::new_bound(py);
+            version.set_item("family", vi.family_id())?;
+            version.set_item("model", vi.model_id())?;
+            version.set_item("stepping", vi.stepping())?;
+            Some(version)
+        },
+        None => None,
+    };
+    dict.set_item("version", version)?;
+
+    let features = PyDict::new_bound(py);
+    for (name, set) in info.flags() {
+        features.set_item(name, set)?;
+    }
+    dict.set_item("features", features)?;
+
+    let caches = PyList::empty_bound(py);
+    for cache in info.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]) {
+        let cache_type = match cache.cache_type() {
+            Some(CacheType::Data) => Some("data"),
+            Some(CacheType::Instruction) => Some("instruction"),
+            Some(CacheType::Unified) => Some("unified"),
+            None => None,
+        };
+        let entry = PyDict::new_bound(py);
+        entry.set_item("level", cache.level())?;
+        entry.set_item("type", cache_type)?;
+        entry.set_item("size", cache.size())?;
+        entry.set_item("line_size", cache.line_size())?;
+        entry.set_item("ways", cache.ways())?;
+        entry.set_item("sets", cache.sets())?;
+        entry.set_item("shared_by", cache.max_logical_processors_sharing())?;
+        caches.append(entry)?;
+    }
+    dict.set_item("caches", caches)?;
+
+    let address_sizes = match (info.physical_address_size(), info.linear_address_bits()) {
+        (Some(pas), Some(linear)) => {
+            let sizes = PyDict::new_bound(py);
+            sizes.set_item("physical", pas.physical_address_bits())?;
+            sizes.set_item("linear", linear)?;
+            Some(sizes)
+        },
+        _ => None,
+    };
+    dict.set_item("address_sizes", address_sizes)?;
+
+    Ok(dict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn python_snapshot_has_the_json_keys() {
+        fn item<'py>(dict: &Bound<'py, PyAny>, key: &str) -> Bound<'py, PyAny> {
+            dict.downcast::<PyDict>().unwrap().get_item(key).unwrap().unwrap()
+        }
+
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0009_06EA, 0, 0, 1 << 26));
+
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dict = to_dict(py, &Master::from_dump(&dump)).unwrap().into_any();
+
+            assert_eq!("GenuineIntel", item(&dict, "vendor").extract::<std::string::String>().unwrap());
+            assert!(item(&dict, "brand").is_none());
+            assert_eq!(6, item(&item(&dict, "version"), "family").extract::<u32>().unwrap());
+            let features = item(&dict, "features");
+            assert!(item(&features, "sse2").extract::<bool>().unwrap());
+            assert!(!item(&features, "sse3").extract::<bool>().unwrap());
+            assert!(item(&dict, "address_sizes").is_none());
+        });
+    }
+}
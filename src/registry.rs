@@ -0,0 +1,139 @@
+//! Choosing between implementations that each need a combination of
+//! features.
+//!
+//! `dispatch!` ranks implementations along one axis, the SIMD tier. A
+//! `Registry` instead tags each implementation with the `FeatureSet`
+//! it requires, such as AVX2 with AES-NI against AVX-512 with VBMI2,
+//! and a priority, and selects the implementation with the highest
+//! priority whose requirements are met. The number of features an
+//! implementation requires says little about how fast it is, so the
+//! ranking is left to the caller.
+//!
+//! ```
+//! use cupid::{Feature, FeatureSet, Registry};
+//!
+//! fn hash_scalar(data: &[u8]) -> u64 { data.len() as u64 }
+//! fn hash_avx2_aes(data: &[u8]) -> u64 { data.len() as u64 }
+//! fn hash_avx512(data: &[u8]) -> u64 { data.len() as u64 }
+//!
+//! let mut registry: Registry<fn(&[u8]) -> u64> = Registry::new();
+//! registry
+//!     .register(2, &[Feature::Avx512f, Feature::Avx512Vbmi2], hash_avx512)
+//!     .register(1, &[Feature::Avx2, Feature::Aesni], hash_avx2_aes)
+//!     .register(0, &[], hash_scalar);
+//!
+//! let hash = *registry.best().unwrap();
+//! assert_eq!(3, hash(b"abc"));
+//! ```
+
+use std::vec::Vec;
+
+use super::{get, xcr0, Feature, FeatureSet, Master};
+
+/// Implementations of one operation, each with its priority and the
+/// features it requires.
+#[derive(Debug, Clone)]
+pub struct Registry<T> {
+    candidates: Vec<(u32, FeatureSet, T)>,
+}
+
+impl<T> Registry<T> {
+    pub fn new() -> Registry<T> {
+        Registry { candidates: Vec::new() }
+    }
+
+    /// Adds an implementation that may only be used when every one of
+    /// `required` is supported, to be preferred over those with a
+    /// lower `priority`. An implementation requiring nothing serves as
+    /// the fallback.
+    pub fn register(&mut self, priority: u32, required: &[Feature], implementation: T) -> &mut Registry<T> {
+        self.register_set(priority, required.iter().cloned().collect(), implementation)
+    }
+
+    /// Like `register`, with the requirements already collected.
+    pub fn register_set(&mut self, priority: u32, required: FeatureSet, implementation: T) -> &mut Registry<T> {
+        self.candidates.push((priority, required, implementation));
+        self
+    }
+
+    /// The implementation to use when `available` are the usable
+    /// features: of those whose requirements are all available, the
+    /// one with the highest priority. Ties go to the one registered
+    /// first. Returns `None` if none can be used.
+    pub fn select(&self, available: &FeatureSet) -> Option<&T> {
+        let mut best: Option<&(u32, FeatureSet, T)> = None;
+        for candidate in &self.candidates {
+            if !candidate.1.is_subset(available) {
+                continue;
+            }
+            if best.is_none_or(|b| candidate.0 > b.0) {
+                best = Some(candidate);
+            }
+        }
+        best.map(|(_, _, implementation)| implementation)
+    }
+
+    /// The implementation to use on the processor `info` describes,
+    /// with `xcr0` the register state its operating system has
+    /// enabled, as for `Master::enabled_features`.
+    pub fn select_for(&self, info: &Master, xcr0: Option<u64>) -> Option<&T> {
+        self.select(&info.enabled_features(xcr0))
+    }
+
+    /// The implementation to use on the running processor, among the
+    /// features the operating system has enabled. Only the fallback
+    /// can be chosen if the processor cannot be queried.
+    pub fn best(&self) -> Option<&T> {
+        match get() {
+            Some(info) => self.select_for(info, xcr0()),
+            None => self.select(&FeatureSet::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Registry<T> {
+        Registry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_selects_the_highest_priority_satisfied_candidate() {
+        let mut registry = Registry::new();
+        registry
+            .register(0, &[], "scalar")
+            .register(1, &[Feature::Avx2, Feature::Aesni], "avx2+aes")
+            .register(3, &[Feature::Avx512f, Feature::Avx512Vbmi2], "avx512+vbmi2")
+            .register(1, &[Feature::Avx2, Feature::Bmi2], "avx2+bmi2")
+            .register(2, &[Feature::Sha], "sha");
+
+        let set = |features: &[Feature]| features.iter().cloned().collect::<FeatureSet>();
+        assert_eq!(Some(&"scalar"), registry.select(&set(&[Feature::Avx2])));
+        assert_eq!(Some(&"avx2+aes"), registry.select(&set(&[Feature::Avx2, Feature::Aesni])));
+        // Equal priorities, so the first registered wins
+        let haswell = set(&[Feature::Avx2, Feature::Aesni, Feature::Bmi2]);
+        assert_eq!(Some(&"avx2+aes"), registry.select(&haswell));
+        // Fewer requirements, but a higher priority
+        let zen = set(&[Feature::Avx2, Feature::Aesni, Feature::Sha]);
+        assert_eq!(Some(&"sha"), registry.select(&zen));
+        let sapphire_rapids = set(&[Feature::Avx2, Feature::Sha, Feature::Avx512f, Feature::Avx512Vbmi2]);
+        assert_eq!(Some(&"avx512+vbmi2"), registry.select(&sapphire_rapids));
+
+        let empty: Registry<&str> = Registry::new();
+        assert_eq!(None, empty.select(&haswell));
+        // With a fallback, something is always chosen
+        assert!(registry.best().is_some());
+    }
+}
@@ -0,0 +1,554 @@
+//! A human-readable report of the processor description, as plain text
+//! or Markdown, and a machine-readable one of `key=value` lines.
+//!
+//! The human-readable forms, like `Debug`, may be reworded or
+//! rearranged in any release. Scripts and snapshot tests should use
+//! `Master::write_report` instead, whose lines look like:
+//!
+//! ```text
+//! vendor=GenuineIntel
+//! brand=Intel(R) Core(TM) i7-8700K CPU @ 3.70GHz
+//! version.family=6
+//! version.model=158
+//! version.stepping=10
+//! microarch=Skylake
+//! level=x86-64-v3
+//! hypervisor=
+//! hypervisor.signature=
+//! address_sizes.physical=39
+//! address_sizes.linear=48
+//! topology.core_type=
+//! topology.cores_per_package=6
+//! topology.threads_per_core=2
+//! topology.threads_per_ccx=
+//! cache.0.level=1
+//! cache.0.type=data
+//! cache.0.size=32768
+//! ...
+//! feature.sse3=true
+//! ...
+//! ```
+//!
+//! The lines always come in this order, with one `cache.N.` group per
+//! cache and one `feature.` line per flag, sorted by name. Keys are
+//! never renamed or removed, and new ones are only added at the end of
+//! their group or, for features, in their place by name, so that
+//! reports from different releases diff cleanly. Cache sizes are in bytes, `cache.N.shared_by` is the
+//! maximum number of logical processors sharing the cache, and values
+//! are written as they are, except that backslashes and control
+//! characters are escaped as Rust escapes them.
+//!
+//! `Decoded::write_report` continues the report with a
+//! `decoder.NAME=` line for each registered third-party decoder.
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::string::String;
+
+#[cfg(feature = "caches")]
+use super::CacheType;
+#[cfg(feature = "std")]
+use super::Decoded;
+#[cfg(feature = "hypervisor")]
+use super::Hypervisor;
+use super::{CoreType, Master};
+
+/// Which lines `Master::write_report` writes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum ReportStyle {
+    /// Every line, with unset flags as `false` and nothing after the
+    /// `=` for values the processor does not report.
+    Full,
+    /// Only the set flags and the values the processor reports.
+    Compact,
+}
+
+// Feature names are wrapped to fit a typical terminal
+const LINE_WIDTH: usize = 78;
+
+impl fmt::Display for Master {
+    /// A report grouped into the processor's identity, topology, caches
+    /// and the supported features of each leaf.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_identity(f)?;
+        self.fmt_topology(f)?;
+        #[cfg(feature = "caches")]
+        self.fmt_caches(f)?;
+        self.fmt_features(f)
+    }
+}
+
+impl Master {
+    fn fmt_identity(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.identity(|label, value| {
+            writeln!(f, "{}:{:pad$}{}", label, "", value, pad = 14 - label.len())
+        })
+    }
+
+    // Calls `visit` with each line of the processor's identity
+    fn identity<F>(&self, mut visit: F) -> fmt::Result
+        where F: FnMut(&str, &dyn fmt::Display) -> fmt::Result
+    {
+        visit("Vendor", &self.vendor_id())?;
+        if let Some(brand) = self.brand_string() {
+            visit("Brand", &brand)?;
+        } else if let Some(brand) = self.synthesized_brand_string() {
+            visit("Brand", &brand)?;
+        }
+        if let Some(vi) = self.version_information() {
+            visit("Family", &format_args!("{} (0x{:x})", vi.family_id(), vi.family_id()))?;
+            visit("Model", &format_args!("{} (0x{:x})", vi.model_id(), vi.model_id()))?;
+            visit("Stepping", &vi.stepping())?;
+        }
+        if let Some(uarch) = self.microarchitecture() {
+            visit("Microarch", &uarch)?;
+        }
+        if let Some(level) = self.microarch_level() {
+            visit("Level", &level)?;
+        }
+        #[cfg(feature = "hypervisor")]
+        if let Some(hi) = self.hypervisor_information() {
+            visit("Hypervisor", &format_args!("{:?} ({})", hi.hypervisor(), hi.signature()))?;
+        }
+        if let Some(pas) = self.physical_address_size() {
+            visit("Address sizes", &pas)?;
+        }
+        Ok(())
+    }
+
+    fn fmt_topology(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let core_type = self.native_model_identification().and_then(|n| n.core_type());
+        let cores = self.physical_cores();
+        let threads = self.threads_per_core();
+        #[cfg(feature = "amd")]
+        let ccx = self.core_complex_information().and_then(|c| c.logical_processors_per_ccx());
+        #[cfg(not(feature = "amd"))]
+        let ccx: Option<u32> = None;
+
+        if core_type.is_none() && cores.is_none() && threads.is_none() && ccx.is_none() {
+            return Ok(());
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Topology:")?;
+        if let Some(core_type) = core_type {
+            writeln!(f, "  Core type:         {:?}", core_type)?;
+        }
+        if let Some(cores) = cores {
+            writeln!(f, "  Cores per package: {}", cores)?;
+        }
+        if let Some(threads) = threads {
+            writeln!(f, "  Threads per core:  {}", threads)?;
+        }
+        if let Some(ccx) = ccx {
+            writeln!(f, "  Threads per CCX:   {}", ccx)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "caches")]
+    fn fmt_caches(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let caches = self.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]);
+        if caches.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Caches:")?;
+        for cache in caches {
+            let kind = match cache.cache_type() {
+                Some(CacheType::Data) => "d",
+                Some(CacheType::Instruction) => "i",
+                Some(CacheType::Unified) | None => "",
+            };
+            let (size, unit) = size(cache.size());
+            writeln!(f, "  L{}{:<2} {:>4} {}, {:>2}-way, {} byte lines, shared by {}",
+                     cache.level(), kind, size, unit, cache.ways(), cache.line_size(),
+                     cache.max_logical_processors_sharing())?;
+        }
+        Ok(())
+    }
+
+    fn fmt_features(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln!(f, "Features:")?;
+        self.feature_groups(|heading, names| features(f, heading, names))
+    }
+
+    // Calls `visit` with the set flags of each leaf that has any
+    fn feature_groups<F>(&self, mut visit: F) -> fmt::Result
+        where F: FnMut(&str, &mut dyn Iterator<Item = &'static str>) -> fmt::Result
+    {
+        macro_rules! group {
+            ($heading:expr, $leaf:expr) => {
+                if let Some(leaf) = $leaf {
+                    let mut names = leaf.iter().filter(|&(_, set)| set).map(|(name, _)| name).peekable();
+                    if names.peek().is_some() {
+                        visit($heading, &mut names)?;
+                    }
+                }
+            }
+        }
+
+        group!("Version information", self.version_information);
+        group!("Thermal and power management", self.thermal_power_management_information);
+        group!("Structured extended", self.structured_extended_information);
+        group!("Structured extended 1", self.structured_extended_information_1);
+        group!("RDT monitoring", self.rdt_monitoring_information);
+        group!("RDT allocation", self.rdt_allocation_information);
+        group!("Key Locker", self.key_locker_information);
+        group!("Last branch records", self.last_branch_record_information);
+        group!("Extended processor signature", self.extended_processor_signature);
+        group!("Power management", self.time_stamp_counter);
+        group!("Address sizes", self.physical_address_size);
+        #[cfg(feature = "amd")]
+        group!("SVM", self.svm_features);
+        #[cfg(feature = "amd")]
+        group!("Performance optimization", self.performance_optimization_identifiers);
+        #[cfg(feature = "amd")]
+        group!("Instruction-based sampling", self.instruction_based_sampling_information);
+        #[cfg(feature = "amd")]
+        group!("Encrypted memory", self.encrypted_memory_capabilities);
+        #[cfg(feature = "amd")]
+        group!("Extended features 2", self.extended_feature_identification_2);
+        #[cfg(feature = "hypervisor")]
+        group!("KVM", self.kvm_features);
+        #[cfg(feature = "hypervisor")]
+        group!("Hyper-V", self.hyper_v_information);
+        #[cfg(feature = "hypervisor")]
+        group!("Xen", self.xen_information);
+        Ok(())
+    }
+
+    /// The report as Markdown tables of the identity, the features of
+    /// each leaf and the caches, for pasting into issues and write-ups.
+    #[cfg(feature = "std")]
+    pub fn to_markdown(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        out.push_str("## Processor\n\n| | |\n|---|---|\n");
+        let _ = self.identity(|label, value| {
+            writeln!(out, "| {} | {} |", label, cell(&format!("{}", value)))
+        });
+
+        out.push_str("\n## Features\n\n| Leaf | Features |\n|---|---|\n");
+        let _ = self.feature_groups(|heading, names| {
+            write!(out, "| {} |", heading)?;
+            for name in names {
+                write!(out, " `{}`", name)?;
+            }
+            writeln!(out, " |")
+        });
+
+        #[cfg(feature = "caches")]
+        let caches = self.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]);
+        #[cfg(feature = "caches")]
+        if !caches.is_empty() {
+            out.push_str("\n## Caches\n\n| Level | Type | Size | Ways | Line size | Shared by |\n\
+                          |---|---|---|---|---|---|\n");
+            for cache in caches {
+                let kind = match cache.cache_type() {
+                    Some(CacheType::Data) => "Data",
+                    Some(CacheType::Instruction) => "Instruction",
+                    Some(CacheType::Unified) | None => "Unified",
+                };
+                let (size, unit) = size(cache.size());
+                let _ = writeln!(out, "| L{} | {} | {} {} | {} | {} | {} |",
+                                 cache.level(), kind, size, unit, cache.ways(),
+                                 cache.line_size(), cache.max_logical_processors_sharing());
+            }
+        }
+
+        out
+    }
+}
+
+impl Master {
+    /// Writes the processor description as `key=value` lines, in the
+    /// stable layout described in the module documentation.
+    ///
+    /// ```
+    /// use cupid::ReportStyle;
+    ///
+    /// if let Some(info) = cupid::master() {
+    ///     let mut report = String::new();
+    ///     info.write_report(&mut report, ReportStyle::Compact).unwrap();
+    ///     assert!(report.starts_with("vendor="));
+    /// }
+    /// ```
+    pub fn write_report<W: fmt::Write + ?Sized>(&self, out: &mut W, style: ReportStyle) -> fmt::Result {
+        let mut lines = Lines { out, style };
+
+        lines.text("vendor", Some(self.vendor_id()))?;
+        lines.text("brand", self.brand_string())?;
+
+        let vi = self.version_information();
+        lines.value("version.family", vi.map(|v| v.family_id()))?;
+        lines.value("version.model", vi.map(|v| v.model_id()))?;
+        lines.value("version.stepping", vi.map(|v| v.stepping()))?;
+        lines.text("microarch", self.microarchitecture().map(|u| u.name()))?;
+        lines.value("level", self.microarch_level())?;
+
+        #[cfg(feature = "hypervisor")]
+        let hi = self.hypervisor_information().map(|h| (hypervisor(h.hypervisor()), h.signature()));
+        #[cfg(not(feature = "hypervisor"))]
+        let hi: Option<(&str, &str)> = None;
+        lines.text("hypervisor", hi.map(|h| h.0))?;
+        lines.text("hypervisor.signature", hi.map(|h| h.1))?;
+
+        let pas = self.physical_address_size();
+        lines.value("address_sizes.physical", pas.map(|p| p.physical_address_bits()))?;
+        lines.value("address_sizes.linear", self.linear_address_bits())?;
+
+        let core_type = self.native_model_identification().and_then(|n| n.core_type());
+        lines.text("topology.core_type", core_type.map(|c| match c {
+            CoreType::Efficiency => "efficiency",
+            CoreType::Performance => "performance",
+        }))?;
+        lines.value("topology.cores_per_package", self.physical_cores())?;
+        lines.value("topology.threads_per_core", self.threads_per_core())?;
+        #[cfg(feature = "amd")]
+        let ccx = self.core_complex_information().and_then(|c| c.logical_processors_per_ccx());
+        #[cfg(not(feature = "amd"))]
+        let ccx: Option<u32> = None;
+        lines.value("topology.threads_per_ccx", ccx)?;
+
+        #[cfg(feature = "caches")]
+        let caches = self.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]);
+        #[cfg(feature = "caches")]
+        for (i, cache) in caches.iter().enumerate() {
+            let key = |field| CacheKey { index: i, field };
+            lines.value(key("level"), Some(cache.level()))?;
+            lines.text(key("type"), cache.cache_type().map(|t| match t {
+                CacheType::Data => "data",
+                CacheType::Instruction => "instruction",
+                CacheType::Unified => "unified",
+            }))?;
+            lines.value(key("size"), Some(cache.size()))?;
+            lines.value(key("line_size"), Some(cache.line_size()))?;
+            lines.value(key("ways"), Some(cache.ways()))?;
+            lines.value(key("sets"), Some(cache.sets()))?;
+            lines.value(key("shared_by"), Some(cache.max_logical_processors_sharing()))?;
+        }
+
+        // Sorted by finding each next name in turn, as there may be no
+        // allocator to sort with
+        let mut after = "";
+        while let Some(&(name, has)) = Master::FLAGS.iter().filter(|f| f.0 > after).min_by_key(|f| f.0) {
+            after = name;
+            let set = has(self);
+            if set || lines.style == ReportStyle::Full {
+                writeln!(lines.out, "feature.{}={}", name, set)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Decoded {
+    /// Writes the result of each decoder as a `key=value` line, to
+    /// follow those of `Master::write_report`.
+    pub fn write_report<W: fmt::Write + ?Sized>(&self, out: &mut W, style: ReportStyle) -> fmt::Result {
+        let mut lines = Lines { out, style };
+        for (name, value) in self.iter() {
+            lines.text(DecoderKey(name), value)?;
+        }
+        Ok(())
+    }
+}
+
+// Writes the lines of `write_report`, leaving out those without a value
+// in the compact style
+struct Lines<'a, W: 'a + ?Sized> {
+    out: &'a mut W,
+    style: ReportStyle,
+}
+
+impl<'a, W: fmt::Write + ?Sized> Lines<'a, W> {
+    fn value<K: fmt::Display, V: fmt::Display>(&mut self, key: K, value: Option<V>) -> fmt::Result {
+        match value {
+            Some(value) => writeln!(self.out, "{}={}", key, value),
+            None if self.style == ReportStyle::Full => writeln!(self.out, "{}=", key),
+            None => Ok(()),
+        }
+    }
+
+    fn text<K: fmt::Display>(&mut self, key: K, value: Option<&str>) -> fmt::Result {
+        self.value(key, value.map(Escaped))
+    }
+}
+
+#[cfg(feature = "caches")]
+struct CacheKey {
+    index: usize,
+    field: &'static str,
+}
+
+#[cfg(feature = "caches")]
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cache.{}.{}", self.index, self.field)
+    }
+}
+
+#[cfg(feature = "std")]
+struct DecoderKey<'a>(&'a str);
+
+#[cfg(feature = "std")]
+impl<'a> fmt::Display for DecoderKey<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "decoder.{}", self.0)
+    }
+}
+
+// Keeps each value on its own line
+struct Escaped<'a>(&'a str);
+
+impl<'a> fmt::Display for Escaped<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.0.chars() {
+            if c == '\\' || c.is_control() {
+                write!(f, "{}", c.escape_default())?;
+            } else {
+                fmt::Write::write_char(f, c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "hypervisor")]
+fn hypervisor(hypervisor: Hypervisor) -> &'static str {
+    match hypervisor {
+        Hypervisor::Kvm => "kvm",
+        Hypervisor::HyperV => "hyperv",
+        Hypervisor::VMware => "vmware",
+        Hypervisor::Xen => "xen",
+        Hypervisor::Tcg => "tcg",
+        Hypervisor::Bhyve => "bhyve",
+        Hypervisor::VirtualBox => "virtualbox",
+        Hypervisor::Parallels => "parallels",
+        Hypervisor::Acrn => "acrn",
+        Hypervisor::Unknown => "unknown",
+    }
+}
+
+// A table cell may not contain an unescaped pipe
+#[cfg(feature = "std")]
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+// Lists the flags of one leaf under a heading
+fn features(f: &mut fmt::Formatter, heading: &str, names: &mut dyn Iterator<Item = &'static str>) -> fmt::Result {
+    writeln!(f, "  {}:", heading)?;
+    let mut width = 0;
+    for name in names {
+        if width != 0 && width + 1 + name.len() > LINE_WIDTH {
+            writeln!(f)?;
+            width = 0;
+        }
+        if width == 0 {
+            write!(f, "   ")?;
+            width = 3;
+        }
+        write!(f, " {}", name)?;
+        width += 1 + name.len();
+    }
+    writeln!(f)
+}
+
+#[cfg(feature = "caches")]
+fn size(bytes: u64) -> (u64, &'static str) {
+    if bytes >= 1024 * 1024 && bytes.is_multiple_of(1024 * 1024) {
+        (bytes / (1024 * 1024), "MiB")
+    } else {
+        (bytes / 1024, "KiB")
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn display_groups_features_by_leaf() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+        let report = format!("{}", Master::from_dump(&dump));
+
+        assert!(report.starts_with("Vendor:        GenuineIntel\n"));
+        assert!(report.contains("Model:         106 (0x6a)\n"));
+        assert!(report.ends_with("Features:\n  Version information:\n    sse4_2 sse2\n"));
+    }
+
+    #[test]
+    fn markdown_tabulates_identity_features_and_caches() {
+        let mut dump = genuine_intel_dump(4);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+        dump.insert(4, 0, (0x0400_4121, 0x02C0_003F, 0x3F, 0));
+        let markdown = Master::from_dump(&dump).to_markdown();
+
+        assert!(markdown.starts_with("## Processor\n\n| | |\n|---|---|\n| Vendor | GenuineIntel |\n"));
+        assert!(markdown.contains("| Model | 106 (0x6a) |\n"));
+        assert!(markdown.contains("| Version information | `sse4_2` `sse2` |\n"));
+        if cfg!(feature = "caches") {
+            assert!(markdown.ends_with("| L1 | Data | 48 KiB | 12 | 64 | 2 |\n"));
+        }
+    }
+
+    #[test]
+    fn report_lists_keys_in_a_stable_order() {
+        let mut dump = genuine_intel_dump(4);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+        dump.insert(4, 0, (0x0400_4121, 0x02C0_003F, 0x3F, 0));
+        let info = Master::from_dump(&dump);
+
+        let mut full = std::string::String::new();
+        info.write_report(&mut full, ReportStyle::Full).unwrap();
+        assert!(full.starts_with("vendor=GenuineIntel\nbrand="));
+        assert!(full.contains("\nversion.family=6\nversion.model=106\nversion.stepping=6\n"));
+        assert!(full.contains("\nhypervisor=\nhypervisor.signature=\n"));
+        assert!(full.contains("\nfeature.sse3=false\n"));
+        assert_eq!(Master::FLAGS.len(), full.matches("\nfeature.").count());
+        assert!(full.ends_with("=false\n") || full.ends_with("=true\n"));
+
+        let mut compact = std::string::String::new();
+        info.write_report(&mut compact, ReportStyle::Compact).unwrap();
+        assert!(!compact.contains("=\n"));
+        assert!(!compact.contains("=false"));
+        assert!(compact.ends_with("\nfeature.sse2=true\nfeature.sse4_2=true\n"));
+        if cfg!(feature = "caches") {
+            assert!(compact.contains("\ncache.0.level=1\ncache.0.type=data\ncache.0.size=49152\n"));
+        }
+        let lines: std::vec::Vec<_> = compact.lines().collect();
+        assert!(full.lines().filter(|l| lines.contains(l)).eq(lines.iter().cloned()));
+        let features: std::vec::Vec<_> = full.lines()
+            .filter(|l| l.starts_with("feature."))
+            .map(|l| l.split('=').next().unwrap())
+            .collect();
+        assert!(features.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn report_counts_cores_from_the_topology_leaves() {
+        // An i7-8700K, with six cores of two threads
+        let mut dump = genuine_intel_dump(0x16);
+        dump.insert(1, 0, (0x0009_06EA, 0x0C10_0800, 0x7FFA_FBBF, 0xBFEB_FBFF));
+        dump.insert(0xB, 0, (1, 2, 0x100, 0));
+        dump.insert(0xB, 1, (4, 12, 0x201, 0));
+        dump.insert(0x8000_0000, 0, (0x8000_0008, 0, 0, 0));
+        dump.insert(0x8000_0008, 0, (0x3027, 0, 0, 0));
+        let info = Master::from_dump(&dump);
+
+        let mut report = std::string::String::new();
+        info.write_report(&mut report, ReportStyle::Compact).unwrap();
+        assert!(report.contains("\ntopology.cores_per_package=6\ntopology.threads_per_core=2\n"));
+        assert!(format!("{}", info).contains("  Cores per package: 6\n  Threads per core:  2\n"));
+    }
+}
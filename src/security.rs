@@ -0,0 +1,132 @@
+//! A summary of the hardware mitigations for speculative execution
+//! vulnerabilities.
+//!
+//! Only what CPUID enumerates is summarized: whether a mitigation
+//! exists, not whether the operating system has enabled it. Whether a
+//! processor is affected at all is mostly reported through the
+//! `IA32_ARCH_CAPABILITIES` MSR rather than CPUID.
+
+use super::{Feature, Master};
+
+/// Mitigations for branch target injection (Spectre variant 2).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpectreV2 {
+    /// Indirect branch restricted speculation can be enabled.
+    pub ibrs: bool,
+    /// The indirect branch predictor barrier can be issued.
+    pub ibpb: bool,
+    /// Single thread indirect branch predictors can be enabled.
+    pub stibp: bool,
+    /// IBRS only needs to be enabled once, rather than on every entry
+    /// to a more privileged mode.
+    pub ibrs_always_on: bool,
+    /// IBRS is preferred over software mitigations such as retpolines.
+    pub ibrs_preferred: bool,
+}
+
+/// Mitigations for speculative store bypass (Spectre variant 4).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpecStoreBypass {
+    /// Speculative store bypass disable can be enabled.
+    pub ssbd: bool,
+    /// SSBD is controlled through the virtualized `VIRT_SPEC_CTRL` MSR.
+    pub virt_ssbd: bool,
+    /// The processor reports that it is not affected.
+    pub not_affected: bool,
+}
+
+/// Mitigations for L1 terminal fault (Foreshadow).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct L1tf {
+    /// The L1 data cache can be flushed through `IA32_FLUSH_CMD`.
+    pub l1d_flush: bool,
+}
+
+/// Mitigations for microarchitectural data sampling.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mds {
+    /// `VERW` clears the affected microarchitectural buffers.
+    pub md_clear: bool,
+}
+
+/// Mitigations for special register buffer data sampling.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Srbds {
+    /// The microcode mitigation can be controlled through
+    /// `IA32_MCU_OPT_CTRL`.
+    pub srbds_ctrl: bool,
+}
+
+/// The hardware mitigations available for each class of vulnerability,
+/// from `Master::security_summary`. Intel and AMD enumerate the same
+/// mitigations in different places; both are combined here.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SecuritySummary {
+    pub spectre_v2: SpectreV2,
+    pub spec_store_bypass: SpecStoreBypass,
+    pub l1tf: L1tf,
+    pub mds: Mds,
+    pub srbds: Srbds,
+    /// The `IA32_ARCH_CAPABILITIES` MSR exists, so the processor
+    /// reports the classes it is not affected by, such as `RDCL_NO`
+    /// for Meltdown.
+    pub arch_capabilities: bool,
+}
+
+impl Master {
+    pub fn security_summary(&self) -> SecuritySummary {
+        let has = |f| self.supports(f);
+
+        SecuritySummary {
+            spectre_v2: SpectreV2 {
+                ibrs: has(Feature::IbrsIbpb) || has(Feature::AmdIbrs),
+                ibpb: has(Feature::IbrsIbpb) || has(Feature::AmdIbpb),
+                stibp: has(Feature::Stibp) || has(Feature::AmdStibp),
+                ibrs_always_on: has(Feature::IbrsAlwaysOn),
+                ibrs_preferred: has(Feature::IbrsPreferred),
+            },
+            spec_store_bypass: SpecStoreBypass {
+                ssbd: has(Feature::Ssbd) || has(Feature::AmdSsbd),
+                virt_ssbd: has(Feature::VirtSsbd),
+                not_affected: has(Feature::SsbNo),
+            },
+            l1tf: L1tf {
+                l1d_flush: has(Feature::L1dFlush),
+            },
+            mds: Mds {
+                md_clear: has(Feature::MdClear),
+            },
+            srbds: Srbds {
+                srbds_ctrl: has(Feature::SrbdsCtrl),
+            },
+            arch_capabilities: has(Feature::ArchCapabilities),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use authentic_amd_dump;
+
+    #[test]
+    fn security_summary_combines_vendors() {
+        let mut dump = authentic_amd_dump(7);
+        dump.insert(7, 0, (0, 0, 0, 1 << 10));
+        dump.insert(0x8000_0000, 0, (0x8000_0008, 0, 0, 0));
+        dump.insert(0x8000_0008, 0, (0, 1 << 12 | 1 << 14 | 1 << 26, 0, 0));
+        let summary = Master::from_dump(&dump).security_summary();
+
+        assert!(summary.spectre_v2.ibrs && summary.spectre_v2.ibpb);
+        assert!(!summary.spectre_v2.stibp);
+        assert!(summary.spec_store_bypass.not_affected);
+        assert!(summary.mds.md_clear);
+        assert!(!summary.arch_capabilities);
+    }
+}
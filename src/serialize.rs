@@ -0,0 +1,60 @@
+//! The SERIALIZE instruction, for code that modifies itself or hands
+//! freshly written code to another thread.
+//!
+//! `serialize` waits until every earlier instruction has completed and
+//! every buffered write has reached memory, and no later instruction is
+//! fetched until it does. Unlike CPUID, the traditional serializing
+//! instruction, it leaves the registers alone and is not intercepted
+//! by hypervisors.
+//!
+//! `serialize` returns `None` if the processor lacks SERIALIZE, which
+//! is checked once and remembered, as with WAITPKG.
+
+use super::{Register, RequestType};
+use super::detect::{detect_once, Detected};
+
+static SERIALIZE: Detected = Detected::new();
+
+fn has_serialize() -> bool {
+    detect_once(&SERIALIZE, RequestType::StructuredExtendedInformation as u32, 0, Register::Edx, 14)
+}
+
+/// Serializes instruction execution, as described in the module
+/// documentation.
+pub fn serialize() -> Option<()> {
+    if !has_serialize() {
+        return None;
+    }
+
+    arch::serialize();
+    Some(())
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+mod arch {
+    use core::arch::asm;
+
+    pub fn serialize() {
+        unsafe {
+            asm!("serialize", options(nostack, preserves_flags));
+        }
+    }
+}
+
+// Never reached, as SERIALIZE is reported absent
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+mod arch {
+    pub fn serialize() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use master;
+
+    #[test]
+    fn serialize_follows_its_flag() {
+        let present = master().map(|m| m.serialize()).unwrap_or(false);
+        assert_eq!(present, serialize().is_some());
+    }
+}
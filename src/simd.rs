@@ -0,0 +1,157 @@
+//! A coarse SIMD tier, for dispatching between implementations.
+
+use core::fmt;
+
+use super::{master, xcr0, Feature, Master};
+
+// The XCR0 state components each tier needs enabled
+const XCR0_SSE: u64 = 1 << 1;
+const XCR0_AVX: u64 = 1 << 2;
+const XCR0_OPMASK: u64 = 1 << 5;
+const XCR0_ZMM_HI256: u64 = 1 << 6;
+const XCR0_HI16_ZMM: u64 = 1 << 7;
+
+/// A tier of SIMD support. Each tier includes those below it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum SimdLevel {
+    Scalar,
+    Sse2,
+    Sse42,
+    Avx,
+    Avx2Fma,
+    Avx512,
+    Avx10,
+}
+
+impl SimdLevel {
+    pub const ALL: &'static [SimdLevel] = &[
+        SimdLevel::Scalar,
+        SimdLevel::Sse2,
+        SimdLevel::Sse42,
+        SimdLevel::Avx,
+        SimdLevel::Avx2Fma,
+        SimdLevel::Avx512,
+        SimdLevel::Avx10,
+    ];
+
+    /// The features this tier adds to the one below it.
+    pub fn features(self) -> &'static [Feature] {
+        match self {
+            SimdLevel::Scalar => &[],
+            SimdLevel::Sse2 => &[Feature::Sse, Feature::Sse2],
+            SimdLevel::Sse42 => &[
+                Feature::Sse3,
+                Feature::Ssse3,
+                Feature::Sse4_1,
+                Feature::Sse4_2,
+                Feature::Popcnt,
+            ],
+            SimdLevel::Avx => &[Feature::Avx, Feature::Osxsave],
+            SimdLevel::Avx2Fma => &[Feature::Avx2, Feature::Fma],
+            SimdLevel::Avx512 => &[
+                Feature::Avx512f,
+                Feature::Avx512cd,
+                Feature::Avx512bw,
+                Feature::Avx512dq,
+                Feature::Avx512vl,
+            ],
+            SimdLevel::Avx10 => &[Feature::Avx10],
+        }
+    }
+
+    // The register state the operating system must have enabled
+    fn state(self) -> u64 {
+        match self {
+            SimdLevel::Scalar | SimdLevel::Sse2 | SimdLevel::Sse42 => 0,
+            SimdLevel::Avx | SimdLevel::Avx2Fma => XCR0_SSE | XCR0_AVX,
+            SimdLevel::Avx512 | SimdLevel::Avx10 => {
+                XCR0_SSE | XCR0_AVX | XCR0_OPMASK | XCR0_ZMM_HI256 | XCR0_HI16_ZMM
+            }
+        }
+    }
+
+    /// The highest tier whose features `info` supports and whose
+    /// register state is enabled in `xcr0`.
+    pub fn detect(info: &Master, xcr0: u64) -> SimdLevel {
+        SimdLevel::ALL.iter()
+            .cloned()
+            .take_while(|level| {
+                level.features().iter().all(|&f| info.supports(f)) &&
+                    xcr0 & level.state() == level.state()
+            })
+            .last()
+            .unwrap_or(SimdLevel::Scalar)
+    }
+}
+
+impl fmt::Display for SimdLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let level = match *self {
+            SimdLevel::Scalar => "scalar",
+            SimdLevel::Sse2 => "sse2",
+            SimdLevel::Sse42 => "sse4.2",
+            SimdLevel::Avx => "avx",
+            SimdLevel::Avx2Fma => "avx2+fma",
+            SimdLevel::Avx512 => "avx512",
+            SimdLevel::Avx10 => "avx10",
+        };
+        f.write_str(level)
+    }
+}
+
+/// The highest SIMD tier that the processor supports and the operating
+/// system has enabled the register state for.
+pub fn best_simd_level() -> SimdLevel {
+    match master() {
+        Some(info) => SimdLevel::detect(&info, xcr0().unwrap_or(0)),
+        None => SimdLevel::Scalar,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dispatch;
+    #[cfg(feature = "std")]
+    use genuine_intel_dump;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn simd_level_requires_enabled_state() {
+        let mut dump = genuine_intel_dump(7);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 | 1 << 9 | 1 << 12 | 1 << 19 | 1 << 20 | 1 << 23 | 1 << 27 | 1 << 28,
+                           1 << 25 | 1 << 26));
+        dump.insert(7, 0, (0, 1 << 5, 0, 0));
+        let info = Master::from_dump(&dump);
+
+        assert_eq!(SimdLevel::Avx2Fma, SimdLevel::detect(&info, 0b111));
+        assert_eq!(SimdLevel::Sse42, SimdLevel::detect(&info, 0b011));
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    fn dispatch_chooses_the_highest_supported_tier_once() {
+        dispatch! {
+            fn tier(offset: u32) -> (SimdLevel, u32);
+            avx10 => |offset| (SimdLevel::Avx10, offset),
+            sse2 => |offset| (SimdLevel::Sse2, offset),
+            scalar => |offset| (SimdLevel::Scalar, offset),
+        }
+
+        let expected = if best_simd_level() >= SimdLevel::Avx10 {
+            SimdLevel::Avx10
+        } else {
+            SimdLevel::Sse2
+        };
+        assert_eq!((expected, 1), tier(1));
+        assert_eq!((expected, 2), tier(2));
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    fn best_simd_level_includes_sse2() {
+        assert!(best_simd_level() >= SimdLevel::Sse2);
+    }
+}
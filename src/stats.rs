@@ -0,0 +1,100 @@
+//! How many of the features the crate knows about a processor has.
+//!
+//! A recent processor sets a larger share of the flags in each leaf, so
+//! the totals make a rough score of how modern it is. A leaf with far
+//! fewer flags set than its neighbours, or none where the processor
+//! should have some, suggests a hypervisor is masking it.
+
+use super::Master;
+
+/// The flags of one leaf: how many the processor sets, out of those
+/// the crate decodes.
+// Only serialized, as the leaf names are borrowed from the crate
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LeafStats {
+    /// The `Master` accessor for the leaf, such as
+    /// "structured_extended_information".
+    pub leaf: &'static str,
+    pub enabled: usize,
+    pub known: usize,
+}
+
+impl LeafStats {
+    /// The share of the known flags that are enabled, from 0 to 1.
+    pub fn coverage(&self) -> f64 {
+        if self.known == 0 {
+            0.0
+        } else {
+            self.enabled as f64 / self.known as f64
+        }
+    }
+}
+
+/// The flag counts of every leaf with flags, in the order of
+/// `Master::flags`. Leaves the processor does not report count as
+/// having no flags enabled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FeatureStats {
+    leaves: [LeafStats; Master::FLAG_LEAVES.len()],
+}
+
+impl FeatureStats {
+    pub fn leaves(&self) -> &[LeafStats] {
+        &self.leaves
+    }
+
+    pub fn leaf(&self, leaf: &str) -> Option<LeafStats> {
+        self.leaves.iter().cloned().find(|l| l.leaf == leaf)
+    }
+
+    pub fn enabled(&self) -> usize {
+        self.leaves.iter().map(|l| l.enabled).sum()
+    }
+
+    pub fn known(&self) -> usize {
+        self.leaves.iter().map(|l| l.known).sum()
+    }
+
+    /// The share of every known flag that is enabled, from 0 to 1.
+    pub fn coverage(&self) -> f64 {
+        LeafStats { leaf: "", enabled: self.enabled(), known: self.known() }.coverage()
+    }
+}
+
+impl Master {
+    /// Counts the enabled and known flags of each leaf.
+    pub fn stats(&self) -> FeatureStats {
+        let empty = LeafStats { leaf: "", enabled: 0, known: 0 };
+        let mut stats = FeatureStats { leaves: [empty; Master::FLAG_LEAVES.len()] };
+
+        let mut flags = Master::FLAGS.iter();
+        for (stats, &(leaf, known)) in stats.leaves.iter_mut().zip(Master::FLAG_LEAVES) {
+            let enabled = flags.by_ref().take(known).filter(|&&(_, has)| has(self)).count();
+            *stats = LeafStats { leaf, enabled, known };
+        }
+        stats
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use {genuine_intel_dump, VersionInformation};
+
+    #[test]
+    fn stats_count_flags_per_leaf() {
+        let mut dump = genuine_intel_dump(7);
+        dump.insert(1, 0, (0x0009_06EA, 0, (1 << 20) | (1 << 19), 1 << 26));
+        let stats = Master::from_dump(&dump).stats();
+
+        let vi = stats.leaf("version_information").unwrap();
+        assert_eq!(3, vi.enabled);
+        assert_eq!(VersionInformation::FLAGS.len(), vi.known);
+        assert_eq!(0, stats.leaf("structured_extended_information").unwrap().enabled);
+        assert_eq!(Master::FLAGS.len(), stats.known());
+        assert_eq!(3, stats.enabled());
+        assert!(stats.coverage() > 0.0 && stats.coverage() < vi.coverage());
+    }
+}
@@ -0,0 +1,271 @@
+//! Flags for compiling code that targets the processor with rustc.
+//!
+//! Unlike `-C target-cpu=native`, the flags can be recorded and reused
+//! to build the same code later, or on another machine.
+
+use core::fmt;
+
+use super::{Feature, FeatureSet, Master, MissingFeatures};
+
+impl Feature {
+    /// The name of the feature in `-C target-feature`, such as "sse4.2"
+    /// for `Sse4_2`. Some names, such as "movdiri", are known to LLVM
+    /// but not to rustc, which passes them on with a warning and does
+    /// not accept them in `#[target_feature]`.
+    pub fn target_feature(self) -> Option<&'static str> {
+        Feature::TARGET_FEATURES.iter().find(|n| n.0 == self).map(|n| n.1)
+    }
+
+    /// Looks up a feature by its `-C target-feature` name, such as
+    /// "avx2".
+    pub fn from_target_feature(name: &str) -> Option<Feature> {
+        Feature::TARGET_FEATURES.iter().find(|n| n.1 == name).map(|n| n.0)
+    }
+}
+
+impl FeatureSet {
+    /// The features with the given `-C target-feature` names, or the
+    /// first name that the crate does not know.
+    pub fn from_target_features<'a, I>(names: I) -> Result<FeatureSet, UnknownTargetFeature<'a>>
+        where I: IntoIterator<Item = &'a str>
+    {
+        names.into_iter()
+            .map(|name| Feature::from_target_feature(name).ok_or(UnknownTargetFeature(name)))
+            .collect()
+    }
+}
+
+/// The error from a target feature name that the crate does not know.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct UnknownTargetFeature<'a>(pub &'a str);
+
+impl<'a> fmt::Display for UnknownTargetFeature<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown target feature '{}'", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> std::error::Error for UnknownTargetFeature<'a> {}
+
+/// The error from `Master::requires_target_features`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TargetFeatureError<'a> {
+    /// A name that the crate does not know, so cannot check.
+    Unknown(UnknownTargetFeature<'a>),
+    /// The required features that the processor lacks.
+    Missing(MissingFeatures),
+}
+
+impl<'a> fmt::Display for TargetFeatureError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TargetFeatureError::Unknown(ref e) => e.fmt(f),
+            TargetFeatureError::Missing(ref e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> std::error::Error for TargetFeatureError<'a> {}
+
+/// The features that the calling crate is compiled to assume, from
+/// `cfg!(target_feature = "...")` where the macro is used, as set by
+/// `-C target-feature` or `-C target-cpu`. Any processor the binary
+/// runs on has them, so dispatchers need not check them at runtime.
+///
+/// ```
+/// let assumed = cupid::statically_known!();
+/// if cfg!(target_arch = "x86_64") {
+///     assert!(assumed.contains(cupid::Feature::Sse2));
+/// }
+/// ```
+#[macro_export]
+macro_rules! statically_known {
+    () => {
+        $crate::__feature_table!($crate::__statically_known)
+    };
+}
+
+// Checks each name that rustc knows in the feature table, where the
+// macro is used
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __statically_known {
+    ({$($(#[cfg($($gate:tt)*)])? $item:ident $location:tt => {$($id:literal: $name:ident => $variant:ident
+                          $(($target:literal))? $((llvm $llvm:literal))?),+}),+}) => {
+        [$($($(($crate::Feature::$variant, cfg!(target_feature = $target)),)?)+)+]
+            .iter()
+            .filter(|known| known.1)
+            .map(|known| known.0)
+            .collect::<$crate::FeatureSet>()
+    };
+}
+
+impl Master {
+    /// Checks that the processor supports every feature a binary was
+    /// built for, given by `-C target-feature` name, such as from a
+    /// manifest. Names that the crate does not know cannot be checked,
+    /// so are an error.
+    ///
+    /// ```
+    /// if let Some(info) = cupid::master() {
+    ///     let built_for = ["sse4.2", "popcnt", "avx2"];
+    ///     if let Err(e) = info.requires_target_features(built_for.iter().cloned()) {
+    ///         eprintln!("{}", e);
+    ///     }
+    /// }
+    /// ```
+    pub fn requires_target_features<'a, I>(&self, names: I) -> Result<(), TargetFeatureError<'a>>
+        where I: IntoIterator<Item = &'a str>
+    {
+        let required = FeatureSet::from_target_features(names).map_err(TargetFeatureError::Unknown)?;
+        let missing = required.difference(&FeatureSet::from(self));
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(TargetFeatureError::Missing(MissingFeatures(missing)))
+        }
+    }
+
+    /// The features that may be executed as a rustc flag, such as
+    /// "-C target-feature=+sse3,+ssse3,+avx2", given `xcr0` as for
+    /// `enabled_features`. Code built for AVX is of no use where the
+    /// operating system has not enabled the AVX state. Features whose
+    /// names only LLVM knows are left out, as rustc warns about them.
+    #[cfg(feature = "std")]
+    pub fn target_feature_flag(&self, xcr0: Option<u64>) -> std::string::String {
+        let enabled = self.enabled_features(xcr0);
+        let features: std::vec::Vec<_> = Feature::TARGET_FEATURES.iter()
+            .filter(|&&(f, _, rustc_knows)| rustc_knows && enabled.contains(f))
+            .map(|&(_, name, _)| format!("+{}", name))
+            .collect();
+        format!("-C target-feature={}", features.join(","))
+    }
+
+    /// The LLVM processor name closest to the processor, for
+    /// `-C target-cpu`, if the microarchitecture is known.
+    pub fn target_cpu(&self) -> Option<&'static str> {
+        use super::Microarchitecture::*;
+
+        let avx512 = self.supports(Feature::Avx512f);
+        let amx = self.supports(Feature::AmxTile);
+
+        self.microarchitecture().map(|uarch| match uarch {
+            P6 => "pentiumpro",
+            NetBurst => "nocona",
+            Core => "core2",
+            Penryn => "penryn",
+            Nehalem => "nehalem",
+            Westmere => "westmere",
+            SandyBridge => "sandybridge",
+            IvyBridge => "ivybridge",
+            Haswell => "haswell",
+            Broadwell => "broadwell",
+            Skylake if avx512 => "skylake-avx512",
+            Skylake => "skylake",
+            CascadeLake => "cascadelake",
+            CooperLake => "cooperlake",
+            PalmCove => "cannonlake",
+            SunnyCove => "icelake-client",
+            WillowCove => "tigerlake",
+            CypressCove => "rocketlake",
+            GoldenCove if amx => "sapphirerapids",
+            GoldenCove => "alderlake",
+            RaptorCove if amx => "emeraldrapids",
+            RaptorCove => "raptorlake",
+            RedwoodCove if amx => "graniterapids",
+            RedwoodCove => "meteorlake",
+            LionCove => "arrowlake",
+            Bonnell => "bonnell",
+            Silvermont | Airmont => "silvermont",
+            Goldmont => "goldmont",
+            GoldmontPlus => "goldmont-plus",
+            Tremont => "tremont",
+            Gracemont => "gracemont",
+            Crestmont => "sierraforest",
+            KnightsLanding => "knl",
+            KnightsMill => "knm",
+            K8 => "k8",
+            K10 => "amdfam10",
+            Bobcat => "btver1",
+            Bulldozer => "bdver1",
+            Piledriver => "bdver2",
+            Steamroller => "bdver3",
+            Excavator => "bdver4",
+            Jaguar => "btver2",
+            Zen | ZenPlus => "znver1",
+            Zen2 => "znver2",
+            Zen3 => "znver3",
+            Zen4 => "znver4",
+            Zen5 => "znver5",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {effective_features, master};
+    #[cfg(feature = "std")]
+    use genuine_intel_dump;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn target_flags_use_rustc_names() {
+        let mut dump = genuine_intel_dump(7);
+        dump.insert(1, 0, (0x0003_06C3, 0, 1 << 20, 1 << 26));
+        dump.insert(7, 0, (0, 1 << 5, 0, 0));
+        let info = Master::from_dump(&dump);
+
+        assert_eq!("-C target-feature=+sse4.2,+sse2,+avx2", info.target_feature_flag(Some(0b111)));
+        assert_eq!("-C target-feature=+sse4.2,+sse2", info.target_feature_flag(None));
+        assert_eq!(Some("haswell"), info.target_cpu());
+        assert_eq!(Some("aes"), Feature::Aesni.target_feature());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn target_flags_leave_out_names_only_llvm_knows() {
+        let mut dump = genuine_intel_dump(7);
+        dump.insert(1, 0, (0x0006_06A6, 0, 0, 1 << 26));
+        dump.insert(7, 0, (0, 0, (1 << 8) | (1 << 27), 0));
+        let info = Master::from_dump(&dump);
+
+        assert_eq!("-C target-feature=+sse2,+gfni", info.target_feature_flag(None));
+        assert_eq!(Some(Feature::Movdiri), Feature::from_target_feature("movdiri"));
+        assert_eq!(Some("shstk"), Feature::CetSs.target_feature());
+        let names = ["avx512vbmi2", "gfni", "vaes", "vpclmulqdq", "xsavec", "lahfsahf", "movdir64b", "rdpid"];
+        assert_eq!(Ok(8), FeatureSet::from_target_features(names.iter().cloned()).map(|s| s.len()));
+    }
+
+    #[test]
+    fn statically_known_features_are_supported() {
+        let assumed = statically_known!();
+        if cfg!(target_arch = "x86_64") {
+            assert!(assumed.contains(Feature::Sse2));
+        }
+        if let Some(info) = master() {
+            assert!(assumed.is_subset(&FeatureSet::from(&info)));
+        }
+        assert!(assumed.is_subset(&effective_features!()));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn target_features_report_missing() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 << 20, 1 << 26));
+        let info = Master::from_dump(&dump);
+
+        assert_eq!(Ok(()), info.requires_target_features(vec!["sse2", "sse4.2"]));
+        assert_eq!(Err(TargetFeatureError::Unknown(UnknownTargetFeature("unknown"))),
+                   info.requires_target_features(vec!["sse2", "unknown"]));
+        let missing = match info.requires_target_features(vec!["sse4.2", "avx2", "fma"]) {
+            Err(TargetFeatureError::Missing(missing)) => missing,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(vec![Feature::Fma, Feature::Avx2], missing.features().iter().collect::<std::vec::Vec<_>>());
+    }
+}
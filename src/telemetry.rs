@@ -0,0 +1,236 @@
+//! A compact encoding of the processor's signature and features, for
+//! collecting from many machines.
+//!
+//! The encoding is URL-safe base64, without padding, of:
+//!
+//! * a version byte, currently 1;
+//! * the processor signature, EAX of leaf 1, in little-endian order;
+//! * one bit per feature, in the order fixed for that version, with
+//!   the first feature in the lowest bit of the first byte.
+//!
+//! Each version's order never changes, so an encoding decodes the same
+//! with every later release of the crate. Features added since are
+//! only encoded by later versions.
+
+use core::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+use super::{Feature, FeatureSet, Master};
+
+const VERSION: u8 = 1;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// The features of version 1, in order
+const V1: &[Feature] = &[
+    Feature::Sse3, Feature::Pclmulqdq, Feature::Dtes64, Feature::Monitor, Feature::DsCpl,
+    Feature::Vmx, Feature::Smx, Feature::Eist, Feature::Tm2, Feature::Ssse3, Feature::CnxtId,
+    Feature::Sdbg, Feature::Fma, Feature::Cmpxchg16b, Feature::XtprUpdateControl, Feature::Pdcm,
+    Feature::Pcid, Feature::Dca, Feature::Sse4_1, Feature::Sse4_2, Feature::X2apic, Feature::Movbe,
+    Feature::Popcnt, Feature::TscDeadline, Feature::Aesni, Feature::Xsave, Feature::Osxsave,
+    Feature::Avx, Feature::F16c, Feature::Rdrand, Feature::Hypervisor, Feature::Fpu, Feature::Vme,
+    Feature::De, Feature::Pse, Feature::Tsc, Feature::Msr, Feature::Pae, Feature::Mce,
+    Feature::Cx8, Feature::Apic, Feature::Sep, Feature::Mtrr, Feature::Pge, Feature::Mca,
+    Feature::Cmov, Feature::Pat, Feature::Pse36, Feature::Psn, Feature::Clfsh, Feature::Ds,
+    Feature::Acpi, Feature::Mmx, Feature::Fxsr, Feature::Sse, Feature::Sse2, Feature::Ss,
+    Feature::Htt, Feature::Tm, Feature::Pbe, Feature::DigitalTemperatureSensor,
+    Feature::IntelTurboBoost, Feature::Arat, Feature::Pln, Feature::Ecmd, Feature::Ptm,
+    Feature::Hwp, Feature::HwpNotification, Feature::HwpActivityWindow,
+    Feature::HwpEnergyPerformancePreference, Feature::Hdc, Feature::HardwareCoordinationFeedback,
+    Feature::PerformanceEnergyBias, Feature::Fsgsbase, Feature::Ia32TscAdjustMsr, Feature::Bmi1,
+    Feature::Hle, Feature::Avx2, Feature::Smep, Feature::Bmi2, Feature::EnhancedRepMovsbStosb,
+    Feature::Invpcid, Feature::Rtm, Feature::Pqm, Feature::DeprecatesFpuCsDs, Feature::Pqe,
+    Feature::Avx512f, Feature::Avx512dq, Feature::Rdseed, Feature::Adx, Feature::Smap,
+    Feature::Avx512Ifma, Feature::Clflushopt, Feature::Clwb, Feature::IntelProcessorTrace,
+    Feature::Avx512pf, Feature::Avx512er, Feature::Avx512cd, Feature::Sha, Feature::Avx512bw,
+    Feature::Avx512vl, Feature::Prefetchwt1, Feature::Kl, Feature::SrbdsCtrl, Feature::MdClear,
+    Feature::Hybrid, Feature::Pconfig, Feature::ArchLbr, Feature::AmxTile, Feature::IbrsIbpb,
+    Feature::Stibp, Feature::L1dFlush, Feature::ArchCapabilities, Feature::CoreCapabilities,
+    Feature::Ssbd, Feature::Sha512, Feature::Sm3, Feature::Sm4, Feature::RaoInt, Feature::AvxVnni,
+    Feature::Avx512Bf16, Feature::Lass, Feature::Cmpccxadd, Feature::ArchPerfmonExt,
+    Feature::Fzlrm, Feature::Fsrs, Feature::Fsrcs, Feature::Fred, Feature::Lkgs, Feature::Wrmsrns,
+    Feature::AmxFp16, Feature::Hreset, Feature::AvxIfma, Feature::Lam, Feature::Msrlist,
+    Feature::AvxVnniInt8, Feature::AvxNeConvert, Feature::AmxComplex, Feature::AvxVnniInt16,
+    Feature::Prefetchi, Feature::UiretUifFromRflags, Feature::CetSss, Feature::Avx10,
+    Feature::ApxF, Feature::L3Monitoring, Feature::L3OccupancyMonitoring,
+    Feature::L3TotalBandwidthMonitoring, Feature::L3LocalBandwidthMonitoring, Feature::L3Cat,
+    Feature::L2Cat, Feature::Mba, Feature::KlCpl0OnlyRestriction, Feature::KlNoEncryptRestriction,
+    Feature::KlNoDecryptRestriction, Feature::Aeskle, Feature::WideKl, Feature::IwkeyBackup,
+    Feature::LoadiwkeyNoBackup, Feature::IwkeyRandomization, Feature::Mktme,
+    Feature::DeepCStateReset, Feature::IpValuesContainLip, Feature::CplFiltering,
+    Feature::BranchFiltering, Feature::CallStackMode, Feature::MispredictBit, Feature::TimedLbrs,
+    Feature::BranchTypeField, Feature::Kvmclock, Feature::NopIoDelay, Feature::MmuOp,
+    Feature::Kvmclock2, Feature::AsyncPf, Feature::StealTime, Feature::PvEoi, Feature::PvUnhalt,
+    Feature::PvTlbFlush, Feature::AsyncPfVmexit, Feature::PvSendIpi, Feature::PollControl,
+    Feature::PvSchedYield, Feature::AsyncPfInt, Feature::MsiExtDestId, Feature::HcMapGpaRange,
+    Feature::MigrationControl, Feature::KvmclockStable, Feature::RealtimeHint,
+    Feature::LahfSahfIn64Bit, Feature::CmpLegacy, Feature::Svm, Feature::ExtApicSpace,
+    Feature::AltMovCr8, Feature::Lzcnt, Feature::Sse4a, Feature::MisalignedSse, Feature::Prefetchw,
+    Feature::Osvw, Feature::Ibs, Feature::Xop, Feature::Skinit, Feature::Wdt, Feature::Lwp,
+    Feature::Fma4, Feature::Tce, Feature::Tbm, Feature::TopologyExtensions,
+    Feature::PerfCtrExtCore, Feature::PerfCtrExtNb, Feature::DataBreakpointExtension,
+    Feature::PerfTsc, Feature::PerfCtrExtLlc, Feature::Monitorx, Feature::AddrMaskExt,
+    Feature::SyscallSysretIn64Bit, Feature::ExecuteDisable, Feature::Mmxext, Feature::Ffxsr,
+    Feature::GigabytePages, Feature::RdtscpAndIa32TscAux, Feature::Intel64BitArchitecture,
+    Feature::ThreeDnowExt, Feature::ThreeDnow, Feature::Clzero, Feature::InstRetCntMsr,
+    Feature::RstrFpErrPtrs, Feature::Invlpgb, Feature::Rdpru, Feature::Mbe, Feature::Mcommit,
+    Feature::Wbnoinvd, Feature::AmdIbpb, Feature::IntWbinvd, Feature::AmdIbrs, Feature::AmdStibp,
+    Feature::IbrsAlwaysOn, Feature::StibpAlwaysOn, Feature::IbrsPreferred, Feature::IbrsSameMode,
+    Feature::EferLmsleUnsupported, Feature::InvlpgbNestedPages, Feature::Ppin, Feature::AmdSsbd,
+    Feature::VirtSsbd, Feature::SsbNo, Feature::Cppc, Feature::Psfd, Feature::BtcNo,
+    Feature::IbpbRet, Feature::NestedPaging, Feature::LbrVirtualization, Feature::SvmLock,
+    Feature::NripSave, Feature::TscRateMsr, Feature::VmcbClean, Feature::FlushByAsid,
+    Feature::DecodeAssists, Feature::PmcVirtualization, Feature::PauseFilter,
+    Feature::PauseFilterThreshold, Feature::Avic, Feature::VmsaveVirtualization, Feature::Vgif,
+    Feature::Gmet, Feature::X2avic, Feature::SssCheck, Feature::SpecCtrl, Feature::Rogpt,
+    Feature::HostMceOverride, Feature::TlbiCtl, Feature::Vnmi, Feature::IbsVirtualization,
+    Feature::ExtLvtAvicAccessChg, Feature::NestedVirtVmcbAddrChk, Feature::BusLockThreshold,
+    Feature::Fp128, Feature::Movu, Feature::Fp256, Feature::IbsFeatureFlagsValid,
+    Feature::FetchSampling, Feature::OpSampling, Feature::ReadWriteOpCounter, Feature::OpCounting,
+    Feature::BranchTargetAddress, Feature::OpCounterExtended, Feature::RipInvalidCheck,
+    Feature::OpBranchFuse, Feature::FetchControlExtended, Feature::OpData4,
+    Feature::L3MissFiltering, Feature::Sme, Feature::Sev, Feature::PageFlushMsr, Feature::SevEs,
+    Feature::SevSnp, Feature::Vmpl, Feature::Rmpquery, Feature::VmplSupervisorShadowStack,
+    Feature::SecureTsc, Feature::TscAuxVirtualization, Feature::HardwareCacheCoherency,
+    Feature::Sev64BitHost, Feature::RestrictedInjection, Feature::AlternateInjection,
+    Feature::DebugSwap, Feature::PreventHostIbs, Feature::Vte, Feature::VmgexitParameter,
+    Feature::VirtualTomMsr, Feature::IbsVirtualGuestControl, Feature::VmsaRegisterProtection,
+    Feature::SmtProtection, Feature::SvsmCommunicationPageMsr, Feature::NestedVirtualSnpMsr,
+    Feature::NoNestedDataBreakpoints, Feature::FsGsBaseNonSerializing,
+    Feature::LfenceAlwaysSerializing, Feature::SmmPageConfigLock, Feature::NullSelectClearsBase,
+    Feature::UpperAddressIgnore, Feature::AutomaticIbrs, Feature::NoSmmCtlMsr,
+    Feature::FastShortRepStosb, Feature::FastShortRepeCmpsb, Feature::PrefetchCtlMsr,
+    Feature::CpuidUserDisable, Feature::Epsf, Feature::Sbpb, Feature::IbpbBrtype, Feature::SrsoNo,
+    Feature::SrsoUserKernelNo, Feature::SrsoMsrFix, Feature::TemperatureSensor,
+    Feature::FrequencyIdControl, Feature::VoltageIdControl, Feature::ThermalTrip,
+    Feature::HardwareThermalControl, Feature::OneHundredMhzSteps, Feature::HardwarePstate,
+    Feature::InvariantTsc, Feature::CorePerformanceBoost, Feature::EffectiveFrequencyReadOnly,
+    Feature::ProcessorFeedbackInterface, Feature::ProcessorPowerReporting,
+    Feature::ConnectedStandby, Feature::Rapl,
+];
+
+/// A decoded encoding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Telemetry {
+    /// EAX of leaf 1, holding the family, model and stepping.
+    pub signature: u32,
+    pub features: FeatureSet,
+}
+
+/// The error from decoding a malformed or unknown encoding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TelemetryError {
+    /// A character outside the base64 alphabet, or a truncated encoding.
+    Malformed,
+    /// A version newer than this release of the crate knows.
+    UnknownVersion(u8),
+}
+
+impl fmt::Display for TelemetryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TelemetryError::Malformed => f.write_str("malformed telemetry encoding"),
+            TelemetryError::UnknownVersion(v) => write!(f, "unknown telemetry version {}", v),
+        }
+    }
+}
+
+impl std::error::Error for TelemetryError {}
+
+impl Master {
+    /// Encodes the signature and supported features, in a few dozen
+    /// characters.
+    pub fn to_telemetry(&self) -> String {
+        let signature = self.version_information().map(|vi| vi.eax()).unwrap_or(0);
+
+        let mut bytes = Vec::with_capacity(5 + V1.len().div_ceil(8));
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&signature.to_le_bytes());
+        for chunk in V1.chunks(8) {
+            let byte = chunk.iter()
+                .enumerate()
+                .filter(|&(_, &f)| self.supports(f))
+                .fold(0u8, |byte, (i, _)| byte | 1 << i);
+            bytes.push(byte);
+        }
+
+        encode(&bytes)
+    }
+}
+
+/// Decodes the output of `Master::to_telemetry`.
+pub fn decode(s: &str) -> Result<Telemetry, TelemetryError> {
+    let bytes = decode_base64(s).ok_or(TelemetryError::Malformed)?;
+    match bytes.first() {
+        Some(&VERSION) => {},
+        Some(&v) => return Err(TelemetryError::UnknownVersion(v)),
+        None => return Err(TelemetryError::Malformed),
+    }
+    if bytes.len() != 5 + V1.len().div_ceil(8) {
+        return Err(TelemetryError::Malformed);
+    }
+
+    let signature = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+    let features = V1.iter()
+        .cloned()
+        .enumerate()
+        .filter(|&(i, _)| bytes[5 + i / 8] & (1 << (i % 8)) != 0)
+        .map(|(_, f)| f)
+        .collect();
+
+    Ok(Telemetry { signature, features })
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..chunk.len() + 1 {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+        }
+    }
+    out
+}
+
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    for chunk in s.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = ALPHABET.iter().position(|&a| a == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn telemetry_round_trips() {
+        let mut dump = genuine_intel_dump(7);
+        dump.insert(1, 0, (0x0006_06A6, 0, 1 | 1 << 20, 1 << 26));
+        dump.insert(7, 0, (0, 1 << 5, 0, 0));
+        let info = Master::from_dump(&dump);
+
+        let encoded = info.to_telemetry();
+        assert!(encoded.len() < 80);
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(0x0006_06A6, decoded.signature);
+        assert_eq!(FeatureSet::from(&info), decoded.features);
+
+        assert_eq!(Err(TelemetryError::UnknownVersion(2)), decode("Ag"));
+        assert_eq!(Err(TelemetryError::Malformed), decode("A*"));
+    }
+}
@@ -0,0 +1,19 @@
+//! Fixtures shared by the tests of every module.
+
+use super::RawDump;
+
+/// A dump of a GenuineIntel processor whose highest basic leaf is
+/// `max_leaf`, holding only leaf 0, for a test to add the leaves it
+/// decodes.
+pub fn genuine_intel_dump(max_leaf: u32) -> RawDump {
+    let mut dump = RawDump::new();
+    dump.insert(0, 0, (max_leaf, 0x756E_6547, 0x6C65_746E, 0x4965_6E69));
+    dump
+}
+
+/// As `genuine_intel_dump`, for an AuthenticAMD processor.
+pub fn authentic_amd_dump(max_leaf: u32) -> RawDump {
+    let mut dump = RawDump::new();
+    dump.insert(0, 0, (max_leaf, 0x6874_7541, 0x444D_4163, 0x6974_6E65));
+    dump
+}
@@ -0,0 +1,63 @@
+//! Standing in a recorded processor for the real one.
+//!
+//! With the `testing` feature, setting `CUPID_DUMP` to the path of a
+//! dump makes every query read from it instead of the processor, so
+//! that dispatch logic can be tested against many processors on one
+//! machine. The dump may be the output of `RawDump::to_bytes` or of
+//! `cpuid -r`; it is read once, on the first query.
+
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+
+use super::{CpuidSource, DumpError, RawDump, RequestType};
+
+/// The environment variable naming the dump to read.
+pub const DUMP_VARIABLE: &str = "CUPID_DUMP";
+
+/// The dump named by `CUPID_DUMP`, or `None` if it is not set.
+///
+/// # Panics
+///
+/// If the dump cannot be read or parsed. Falling back to the real
+/// processor would let tests pass against the wrong one.
+pub fn fake_dump() -> Option<&'static RawDump> {
+    static DUMP: OnceLock<Option<RawDump>> = OnceLock::new();
+    DUMP.get_or_init(|| {
+        let path = env::var_os(DUMP_VARIABLE)?;
+        let bytes = fs::read(&path)
+            .unwrap_or_else(|e| panic!("cannot read {} ({:?}): {}", DUMP_VARIABLE, path, e));
+        let dump = parse(&bytes)
+            .unwrap_or_else(|e| panic!("{} ({:?}) is not a CPUID dump: {}", DUMP_VARIABLE, path, e));
+        Some(dump)
+    }).as_ref()
+}
+
+/// Parses a dump in either of the formats `CUPID_DUMP` accepts.
+pub fn parse(bytes: &[u8]) -> Result<RawDump, DumpError> {
+    RawDump::parse(bytes)
+}
+
+/// The XCR0 to report for a dump. XCR0 is not part of a dump, so the
+/// operating system is taken to have enabled every state component the
+/// processor supports.
+pub fn xcr0(dump: &RawDump) -> u64 {
+    let (eax, _, _, edx) = dump.cpuid(RequestType::ExtendedState as u32, 0);
+    (edx as u64) << 32 | eax as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn parses_binary_and_text_dumps() {
+        let mut dump = genuine_intel_dump(0xD);
+        dump.insert(0xD, 0, (0x2E7, 0, 0, 0));
+
+        assert_eq!(Ok(dump.clone()), parse(&dump.to_bytes()));
+        assert_eq!(Ok(dump.clone()), parse(dump.to_cpuid_r().as_bytes()));
+        assert_eq!(0x2E7, xcr0(&dump));
+    }
+}
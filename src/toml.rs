@@ -0,0 +1,107 @@
+//! A TOML rendering of the processor description.
+//!
+//! The document has the same keys as the [`json`](../json/index.html)
+//! rendering. TOML has no null, so values the processor does not report
+//! are left out instead.
+//!
+//! ```text
+//! schema = 1
+//! vendor = "GenuineIntel"
+//! brand = "Intel(R) Core(TM) i7-8700K CPU @ 3.70GHz"
+//!
+//! [version]
+//! family = 6
+//! model = 158
+//! stepping = 10
+//!
+//! [features]
+//! sse3 = true
+//! ...
+//!
+//! [[caches]]
+//! level = 1
+//! type = "data"
+//! size = 32768
+//! ...
+//!
+//! [address_sizes]
+//! physical = 39
+//! linear = 48
+//! ```
+
+use std::string::String;
+
+use super::json::{string, SCHEMA_VERSION};
+#[cfg(feature = "caches")]
+use super::CacheType;
+use super::Master;
+
+impl Master {
+    /// Renders the processor description as a TOML document.
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str(&format!("schema = {}\nvendor = ", SCHEMA_VERSION));
+        string(&mut out, self.vendor_id());
+        out.push('\n');
+
+        if let Some(brand) = self.brand_string() {
+            out.push_str("brand = ");
+            string(&mut out, brand);
+            out.push('\n');
+        }
+
+        if let Some(vi) = self.version_information() {
+            out.push_str(&format!("\n[version]\nfamily = {}\nmodel = {}\nstepping = {}\n",
+                                  vi.family_id(), vi.model_id(), vi.stepping()));
+        }
+
+        out.push_str("\n[features]\n");
+        for &(name, has) in Master::FLAGS {
+            out.push_str(&format!("{} = {}\n", name, has(self)));
+        }
+
+        #[cfg(feature = "caches")]
+        for cache in self.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]) {
+            out.push_str(&format!("\n[[caches]]\nlevel = {}\n", cache.level()));
+            let cache_type = match cache.cache_type() {
+                Some(CacheType::Data) => Some("data"),
+                Some(CacheType::Instruction) => Some("instruction"),
+                Some(CacheType::Unified) => Some("unified"),
+                None => None,
+            };
+            if let Some(cache_type) = cache_type {
+                out.push_str(&format!("type = \"{}\"\n", cache_type));
+            }
+            out.push_str(&format!("size = {}\nline_size = {}\nways = {}\nsets = {}\nshared_by = {}\n",
+                                  cache.size(), cache.line_size(), cache.ways(), cache.sets(),
+                                  cache.max_logical_processors_sharing()));
+        }
+
+        if let (Some(pas), Some(linear)) = (self.physical_address_size(), self.linear_address_bits()) {
+            out.push_str(&format!("\n[address_sizes]\nphysical = {}\nlinear = {}\n",
+                                  pas.physical_address_bits(), linear));
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn toml_leaves_out_what_is_not_reported() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0009_06EA, 0, 0, 1 << 26));
+
+        let toml = Master::from_dump(&dump).to_toml();
+        assert!(toml.starts_with("schema = 1\nvendor = \"GenuineIntel\"\n\
+                                  \n[version]\nfamily = 6\n"));
+        assert!(toml.contains("\n[features]\nsse3 = false\n"));
+        assert!(toml.contains("\nsse2 = true\n"));
+        assert!(!toml.contains("[address_sizes]"));
+    }
+}
@@ -0,0 +1,101 @@
+//! Reading the time stamp counter, when the processor has one.
+//!
+//! Whether the instructions exist is checked once and remembered, as
+//! CPUID is far slower than the instructions themselves. The check
+//! always asks the processor, even when the `testing` feature stands a
+//! dump in for it.
+
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), not(target_env = "msvc")))]
+use core::arch::asm;
+
+use super::{Register, RequestType};
+use super::detect::{detect_once, Detected};
+#[cfg(all(target_env = "msvc", any(target_arch = "x86_64", target_arch = "x86")))]
+use super::msvc::{rdtsc, rdtscp};
+
+static RDTSC: Detected = Detected::new();
+static RDTSCP: Detected = Detected::new();
+
+fn has_rdtsc() -> bool {
+    detect_once(&RDTSC, RequestType::VersionInformation as u32, 0, Register::Edx, 4)
+}
+
+fn has_rdtscp() -> bool {
+    detect_once(&RDTSCP, RequestType::ExtendedProcessorSignature as u32, 0, Register::Edx, 27)
+}
+
+/// Reads the time stamp counter with RDTSC, or `None` if the processor
+/// has no time stamp counter.
+pub fn read_tsc() -> Option<u64> {
+    if !has_rdtsc() {
+        return None;
+    }
+
+    Some(rdtsc())
+}
+
+/// Reads the time stamp counter with RDTSCP, which waits for earlier
+/// instructions to finish, along with the contents of `IA32_TSC_AUX`.
+/// Operating systems commonly store the processor number there.
+/// Returns `None` if the processor lacks RDTSCP.
+pub fn read_tscp() -> Option<(u64, u32)> {
+    if !has_rdtscp() {
+        return None;
+    }
+
+    Some(rdtscp())
+}
+
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), not(target_env = "msvc")))]
+fn rdtsc() -> u64 {
+    let low: u32;
+    let high: u32;
+    unsafe {
+        asm!("rdtsc",
+             out("eax") low,
+             out("edx") high,
+             options(nomem, nostack, preserves_flags));
+    }
+    (high as u64) << 32 | low as u64
+}
+
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), not(target_env = "msvc")))]
+fn rdtscp() -> (u64, u32) {
+    let low: u32;
+    let high: u32;
+    let aux: u32;
+    unsafe {
+        asm!("rdtscp",
+             out("eax") low,
+             out("edx") high,
+             out("ecx") aux,
+             options(nomem, nostack, preserves_flags));
+    }
+    ((high as u64) << 32 | low as u64, aux)
+}
+
+// Never reached, as other architectures report no CPUID
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+fn rdtsc() -> u64 {
+    0
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+fn rdtscp() -> (u64, u32) {
+    (0, 0)
+}
+
+#[cfg(all(test, any(target_arch = "x86_64", target_arch = "x86")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tsc_advances() {
+        if let (Some(first), Some(second)) = (read_tsc(), read_tsc()) {
+            assert!(second >= first);
+        }
+        if let (Some(first), Some((second, _))) = (read_tsc(), read_tscp()) {
+            assert!(second >= first);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Whether Transactional Synchronization Extensions can actually be
+//! used.
+//!
+//! Since TSX Asynchronous Abort, microcode updates have disabled TSX on
+//! many processors that implement it. Some keep the RTM flag but make
+//! every transaction abort, reporting RTM_ALWAYS_ABORT; others let the
+//! operating system disable RTM through `IA32_TSX_CTRL`, which can also
+//! hide the flags from CPUID. Lock elision keyed on the RTM flag alone
+//! then falls back on every attempt.
+
+use super::{master, Master};
+
+/// What happens to a transaction started with `XBEGIN`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum TsxStatus {
+    /// The processor does not implement RTM, as far as CPUID shows.
+    Unsupported,
+    /// RTM is implemented but `IA32_TSX_CTRL` disables it, so that
+    /// every transaction aborts.
+    Disabled,
+    /// Microcode forces every transaction to abort.
+    AlwaysAbort,
+    /// Transactions may commit.
+    Usable,
+}
+
+impl TsxStatus {
+    /// Whether a transaction can commit, so that lock elision is worth
+    /// attempting.
+    pub fn is_usable(self) -> bool {
+        self == TsxStatus::Usable
+    }
+}
+
+impl Master {
+    /// The TSX status as far as CPUID reveals it. Only `tsx_status`
+    /// with the `msr` feature can tell whether `IA32_TSX_CTRL` has
+    /// disabled RTM; CPUID alone reports it as usable, or as
+    /// unsupported if the flags are hidden too.
+    pub fn tsx_status(&self) -> TsxStatus {
+        let sei = self.structured_extended_information();
+        if sei.is_some_and(|s| s.rtm_always_abort()) {
+            TsxStatus::AlwaysAbort
+        } else if sei.is_some_and(|s| s.rtm()) {
+            TsxStatus::Usable
+        } else {
+            TsxStatus::Unsupported
+        }
+    }
+}
+
+/// The TSX status of the running processor. With the `msr` feature on
+/// Linux, `IA32_TSX_CTRL` is read as well, which usually requires root,
+/// to tell whether the operating system has disabled RTM.
+pub fn tsx_status() -> TsxStatus {
+    let status = master().map_or(TsxStatus::Unsupported, |m| m.tsx_status());
+    if rtm_disabled() {
+        TsxStatus::Disabled
+    } else {
+        status
+    }
+}
+
+// Whether the operating system has disabled RTM through IA32_TSX_CTRL,
+// which only exists on processors implementing TSX
+#[cfg(all(feature = "msr", target_os = "linux"))]
+fn rtm_disabled() -> bool {
+    super::MsrInformation::read(0).ok()
+        .and_then(|msrs| msrs.tsx_ctrl().map(|c| c.rtm_disable()))
+        .unwrap_or(false)
+}
+
+#[cfg(not(all(feature = "msr", target_os = "linux")))]
+fn rtm_disabled() -> bool {
+    false
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn tsx_status_follows_rtm_always_abort() {
+        let mut dump = genuine_intel_dump(7);
+        assert_eq!(TsxStatus::Unsupported, Master::from_dump(&dump).tsx_status());
+
+        dump.insert(7, 0, (0, 1 << 11, 0, 0));
+        assert_eq!(TsxStatus::Usable, Master::from_dump(&dump).tsx_status());
+
+        dump.insert(7, 0, (0, 1 << 11, 0, 1 << 11));
+        let status = Master::from_dump(&dump).tsx_status();
+        assert_eq!(TsxStatus::AlwaysAbort, status);
+        assert!(!status.is_usable());
+    }
+}
@@ -0,0 +1,147 @@
+//! The user-mode wait instructions of WAITPKG, for spin loops that
+//! would rather let the core rest than burn power.
+//!
+//! `umonitor` arms address monitoring; `umwait` then waits until the
+//! monitored cache line is written or a deadline passes, and `tpause`
+//! waits for the deadline alone. Deadlines are values of the time stamp
+//! counter. The operating system caps how long any wait may last,
+//! through `IA32_UMWAIT_CONTROL`, and can forbid the deeper state.
+//!
+//! Each function returns `None` if the processor lacks WAITPKG, which
+//! is checked once and remembered, as with the time stamp counter.
+
+use super::{Register, RequestType};
+use super::detect::{detect_once, Detected};
+
+static WAITPKG: Detected = Detected::new();
+
+/// How deeply the core may rest while waiting. The deeper state saves
+/// more power but takes longer to wake from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WaitState {
+    /// C0.1, the lighter state.
+    Light,
+    /// C0.2, the deeper state.
+    Deep,
+}
+
+impl WaitState {
+    // Bit 0 of the control operand selects C0.1
+    fn control(self) -> u32 {
+        match self {
+            WaitState::Light => 1,
+            WaitState::Deep => 0,
+        }
+    }
+}
+
+fn has_waitpkg() -> bool {
+    detect_once(&WAITPKG, RequestType::StructuredExtendedInformation as u32, 0, Register::Ecx, 5)
+}
+
+/// Starts monitoring the cache line holding `address` for writes, for
+/// a following `umwait`.
+pub fn umonitor<T>(address: &T) -> Option<()> {
+    if !has_waitpkg() {
+        return None;
+    }
+
+    arch::umonitor(address as *const T as *const u8);
+    Some(())
+}
+
+/// Waits until the line armed by `umonitor` is written, the time stamp
+/// counter reaches `deadline`, or an interrupt arrives. Returns whether
+/// the operating system's limit cut the wait short.
+pub fn umwait(deadline: u64, state: WaitState) -> Option<bool> {
+    if !has_waitpkg() {
+        return None;
+    }
+
+    Some(arch::umwait(state.control(), deadline))
+}
+
+/// Waits until the time stamp counter reaches `deadline` or an
+/// interrupt arrives. Returns whether the operating system's limit cut
+/// the wait short.
+pub fn tpause(deadline: u64, state: WaitState) -> Option<bool> {
+    if !has_waitpkg() {
+        return None;
+    }
+
+    Some(arch::tpause(state.control(), deadline))
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+mod arch {
+    use core::arch::asm;
+
+    pub fn umonitor(address: *const u8) {
+        unsafe {
+            asm!("umonitor {0}",
+                 in(reg) address,
+                 options(nostack, preserves_flags));
+        }
+    }
+
+    // The carry flag is set if the operating system's limit expired
+    pub fn umwait(control: u32, deadline: u64) -> bool {
+        let low = deadline as u32;
+        let high = (deadline >> 32) as u32;
+        let expired: u8;
+        unsafe {
+            asm!("umwait {control:e}",
+                 "setc {expired}",
+                 control = in(reg) control,
+                 expired = out(reg_byte) expired,
+                 in("eax") low,
+                 in("edx") high,
+                 options(nostack));
+        }
+        expired != 0
+    }
+
+    pub fn tpause(control: u32, deadline: u64) -> bool {
+        let low = deadline as u32;
+        let high = (deadline >> 32) as u32;
+        let expired: u8;
+        unsafe {
+            asm!("tpause {control:e}",
+                 "setc {expired}",
+                 control = in(reg) control,
+                 expired = out(reg_byte) expired,
+                 in("eax") low,
+                 in("edx") high,
+                 options(nostack));
+        }
+        expired != 0
+    }
+}
+
+// Never reached, as WAITPKG is reported absent
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+mod arch {
+    pub fn umonitor(_address: *const u8) {}
+
+    pub fn umwait(_control: u32, _deadline: u64) -> bool {
+        false
+    }
+
+    pub fn tpause(_control: u32, _deadline: u64) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use master;
+
+    #[test]
+    fn wait_instructions_follow_waitpkg() {
+        let waitpkg = master().map(|m| m.waitpkg()).unwrap_or(false);
+        assert_eq!(waitpkg, tpause(0, WaitState::Light).is_some());
+        assert_eq!(waitpkg, umonitor(&0u64).is_some());
+    }
+}
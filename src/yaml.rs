@@ -0,0 +1,119 @@
+//! A YAML rendering of the processor description.
+//!
+//! The document has the same keys as the [`json`](../json/index.html)
+//! rendering, and values the processor does not report are likewise
+//! `null`. Strings are always double-quoted, so that brand strings
+//! such as "AMD Ryzen 9 7950X 16-Core Processor" stay strings.
+//!
+//! ```text
+//! schema: 1
+//! vendor: "GenuineIntel"
+//! brand: "Intel(R) Core(TM) i7-8700K CPU @ 3.70GHz"
+//! version:
+//!   family: 6
+//!   model: 158
+//!   stepping: 10
+//! features:
+//!   sse3: true
+//!   ...
+//! caches:
+//!   - level: 1
+//!     type: "data"
+//!     size: 32768
+//!     ...
+//! address_sizes:
+//!   physical: 39
+//!   linear: 48
+//! ```
+
+use std::string::String;
+
+use super::json::{string, SCHEMA_VERSION};
+#[cfg(feature = "caches")]
+use super::CacheType;
+use super::Master;
+
+impl Master {
+    /// Renders the processor description as a YAML document.
+    pub fn to_yaml(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str(&format!("schema: {}\nvendor: ", SCHEMA_VERSION));
+        string(&mut out, self.vendor_id());
+
+        out.push_str("\nbrand: ");
+        match self.brand_string() {
+            Some(brand) => string(&mut out, brand),
+            None => out.push_str("null"),
+        }
+
+        out.push_str("\nversion:");
+        match self.version_information() {
+            Some(vi) => {
+                out.push_str(&format!("\n  family: {}\n  model: {}\n  stepping: {}",
+                                      vi.family_id(), vi.model_id(), vi.stepping()));
+            },
+            None => out.push_str(" null"),
+        }
+
+        out.push_str("\nfeatures:");
+        for &(name, has) in Master::FLAGS {
+            out.push_str(&format!("\n  {}: {}", name, has(self)));
+        }
+
+        out.push_str("\ncaches:");
+        #[cfg(not(feature = "caches"))]
+        out.push_str(" []");
+        #[cfg(feature = "caches")]
+        {
+            let caches = self.deterministic_cache_parameters().map(|p| p.caches()).unwrap_or(&[]);
+            if caches.is_empty() {
+                out.push_str(" []");
+            }
+            for cache in caches {
+                let cache_type = match cache.cache_type() {
+                    Some(CacheType::Data) => "\"data\"",
+                    Some(CacheType::Instruction) => "\"instruction\"",
+                    Some(CacheType::Unified) => "\"unified\"",
+                    None => "null",
+                };
+                out.push_str(&format!("\n  - level: {}\n    type: {}\n    size: {}\n    line_size: {}\
+                                       \n    ways: {}\n    sets: {}\n    shared_by: {}",
+                                      cache.level(), cache_type, cache.size(), cache.line_size(),
+                                      cache.ways(), cache.sets(),
+                                      cache.max_logical_processors_sharing()));
+            }
+        }
+
+        out.push_str("\naddress_sizes:");
+        match (self.physical_address_size(), self.linear_address_bits()) {
+            (Some(pas), Some(linear)) => {
+                out.push_str(&format!("\n  physical: {}\n  linear: {}",
+                                      pas.physical_address_bits(), linear));
+            },
+            _ => out.push_str(" null"),
+        }
+
+        out.push('\n');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genuine_intel_dump;
+
+    #[test]
+    fn yaml_nests_the_json_layout() {
+        let mut dump = genuine_intel_dump(1);
+        dump.insert(1, 0, (0x0009_06EA, 0, 0, 1 << 26));
+
+        let yaml = Master::from_dump(&dump).to_yaml();
+        assert!(yaml.starts_with("schema: 1\nvendor: \"GenuineIntel\"\nbrand: null\n\
+                                  version:\n  family: 6\n"));
+        assert!(yaml.contains("\nfeatures:\n  sse3: false\n"));
+        assert!(yaml.contains("\n  sse2: true\n"));
+        assert!(yaml.ends_with("\ncaches: []\naddress_sizes: null\n"));
+    }
+}